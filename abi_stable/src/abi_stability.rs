@@ -8,7 +8,12 @@ pub mod get_static_equivalent;
 pub mod stable_abi_trait;
 
 pub use self::{
-    abi_checking::exported_check_layout_compatibility as check_layout_compatibility,
+    abi_checking::check_layout_or_snapshot_compatibility as check_layout_compatibility,
+    abi_checking::{
+        collect_layout_incompatibilities, compare_layouts, AbiIncompatibility,
+        AbiIncompatibilityKind, AbiIncompatibilityReport, LayoutComparison, LayoutExtension,
+        LayoutExtensionKind,
+    },
     const_generics::ConstGeneric,
     get_static_equivalent::{GetStaticEquivalent, GetStaticEquivalent_},
     stable_abi_trait::{AbiConsts, PrefixStableAbi, StableAbi},
@@ -20,24 +20,41 @@ use crate::{
     },
     prefix_type::{FieldAccessibility, FieldConditionality},
     sabi_types::{CmpIgnored, ParseVersionError, VersionStrings},
-    std_types::{RArc, RBox, RBoxError, RErr, RNone, ROk, RResult, RSome, RStr, RVec, UTypeId},
+    std_types::{RArc, RBox, RBoxError, RErr, RNone, ROk, RResult, RSome, RVec, UTypeId},
     traits::IntoReprC,
     type_layout::{
-        tagging::TagErrors, FmtFullType, IncompatibleWithNonExhaustive, IsExhaustive, ReprAttr,
-        TLData, TLDataDiscriminant, TLDiscriminant, TLEnum, TLField, TLFieldOrFunction, TLFunction,
-        TLNonExhaustive, TLPrimitive, TypeLayout,
+        tagging::TagErrors, FingerprintedLayout, FmtFullType, IncompatibleWithNonExhaustive,
+        IsExhaustive, LayoutOrSnapshot, ReprAttr, TLData, TLDataDiscriminant, TLDiscriminant,
+        TLEnum, TLField, TLFieldOrFunction, TLFunction, TLNonExhaustive, TLPrefixType, TLPrimitive,
+        TypeLayout,
     },
     type_level::downcasting::TD_Opaque,
     utils::{max_by, min_max_by},
 };
 
 mod errors;
+mod layout_view;
+mod report;
+mod snapshots;
 
 pub use self::errors::{
     AbiInstability, AbiInstability as AI, AbiInstabilityError, AbiInstabilityErrors,
     ExtraCheckError,
 };
 
+pub use self::layout_view::{
+    DataView, EnumView, FieldOrFunctionView, FieldView, FunctionView, LayoutView, PrefixView,
+};
+
+pub use self::report::{
+    collect_layout_incompatibilities, compare_layouts, AbiIncompatibility, AbiIncompatibilityKind,
+    AbiIncompatibilityReport, LayoutComparison, LayoutExtension, LayoutExtensionKind,
+};
+
+use self::report::CheckedExtension;
+
+pub(crate) use self::report::into_report_error;
+
 ////////////////////////////////////////////////////////////////////////////////
 
 /// What is AbiChecker::check_fields being called with.
@@ -51,22 +68,6 @@ enum FieldContext {
 
 //////
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Ord, PartialOrd, Hash)]
-#[repr(C)]
-struct CheckingUTypeId {
-    type_id: UTypeId,
-}
-
-impl CheckingUTypeId {
-    fn new(this: &'static TypeLayout) -> Self {
-        Self {
-            type_id: this.get_utypeid(),
-        }
-    }
-}
-
-//////
-
 #[derive(Debug, PartialEq, Eq, Ord, PartialOrd, Hash)]
 pub enum CheckingState {
     Checking { layer: u32 },
@@ -164,15 +165,15 @@ pub struct CheckedNonExhaustiveEnums {
 
 ///////////////////////////////////////////////
 
-struct AbiChecker {
-    stack_trace: RVec<ExpectedFound<TLFieldOrFunction>>,
+struct AbiChecker<V: LayoutView> {
+    stack_trace: RVec<ExpectedFound<V::FieldOrFunction>>,
     checked_prefix_types: RVec<CheckedPrefixTypes>,
     checked_nonexhaustive_enums: RVec<CheckedNonExhaustiveEnums>,
     checked_extra_checks: RVec<ExtraChecksBoxWithContext>,
 
-    visited: HashMap<(CheckingUTypeId, CheckingUTypeId), CheckingState>,
+    visited: HashMap<(V::Id, V::Id), CheckingState>,
 
-    errors: RVec<AbiInstabilityError>,
+    errors: RVec<AbiInstabilityError<V>>,
 
     /// Layer 0 is checking a type layout,
     ///
@@ -188,11 +189,62 @@ struct AbiChecker {
     current_layer: u32,
 
     error_index: usize,
+
+    /// Whether to keep checking types after finding errors that
+    /// usually stop the checking of a type (eg: mismatched names).
+    collect_all: bool,
+
+    /// The compatible extensions of prefix types and nonexhaustive enums,
+    /// only recorded for `compare_layouts`.
+    extensions: Option<RVec<CheckedExtension<V>>>,
+}
+
+/// The checks that are done differently for every kind of layout,
+/// because they run code from the `TypeLayout`,
+/// or combine the checked types with the ones from previous calls to the layout checker.
+trait CheckLayoutView: LayoutView {
+    /// Checks that the const parameters of both types are equal.
+    fn check_const_params(
+        checker: &mut AbiChecker<Self>,
+        errs: &mut RVec<AbiInstability<Self>>,
+        this: Self,
+        other: Self,
+    );
+
+    /// Checks that the `ExtraChecks` of both types are compatible.
+    fn check_extra_checks(
+        checker: &mut AbiChecker<Self>,
+        errs: &mut RVec<AbiInstability<Self>>,
+        top_level_errs: &mut RVec<AbiInstabilityError<Self>>,
+        this: Self,
+        other: Self,
+    );
+
+    /// Records a pair of checked enums,to combine the nonexhaustive ones in `final_checks`.
+    fn record_enums(
+        checker: &mut AbiChecker<Self>,
+        this: Self,
+        t_enum: Self::Enum,
+        other: Self,
+        o_enum: Self::Enum,
+    );
+
+    /// Records a pair of checked prefix types,to combine them in `final_checks`.
+    fn record_prefix_types(
+        checker: &mut AbiChecker<Self>,
+        this: Self,
+        t_prefix: Self::Prefix,
+        other: Self,
+        o_prefix: Self::Prefix,
+    );
+
+    /// Combines the recorded types with the ones from previously checked layouts.
+    fn final_checks(checker: &mut AbiChecker<Self>, globals: &CheckingGlobals);
 }
 
 ///////////////////////////////////////////////
 
-impl AbiChecker {
+impl<V: CheckLayoutView> AbiChecker<V> {
     fn new() -> Self {
         Self {
             stack_trace: RVec::new(),
@@ -204,21 +256,36 @@ impl AbiChecker {
             errors: RVec::new(),
             current_layer: 0,
             error_index: 0,
+            collect_all: false,
+            extensions: None,
+        }
+    }
+
+    /// Records that `added` fields/variants were added to the type being checked.
+    fn record_extension(&mut self, kind: LayoutExtensionKind, added: Vec<String>) {
+        if let Some(extensions) = &mut self.extensions {
+            if !added.is_empty() {
+                extensions.push(CheckedExtension {
+                    kind,
+                    stack_trace: self.stack_trace.clone(),
+                    added,
+                });
+            }
         }
     }
 
     #[inline]
     fn check_fields<I, F>(
         &mut self,
-        errs: &mut RVec<AbiInstability>,
-        t_lay: &'static TypeLayout,
-        o_lay: &'static TypeLayout,
+        errs: &mut RVec<AbiInstability<V>>,
+        t_lay: V,
+        o_lay: V,
         ctx: FieldContext,
         t_fields: I,
         o_fields: I,
     ) where
         I: ExactSizeIterator<Item = F>,
-        F: Borrow<TLField>,
+        F: Borrow<V::Field>,
     {
         if t_fields.len() == 0 && o_fields.len() == 0 {
             return;
@@ -227,8 +294,8 @@ impl AbiChecker {
         let t_data = t_lay.data();
 
         let is_prefix = match &t_data {
-            TLData::PrefixType { .. } => true,
-            TLData::Enum(enum_) => !enum_.exhaustiveness.is_exhaustive(),
+            DataView::PrefixType { .. } => true,
+            DataView::Enum(enum_) => !enum_.is_exhaustive(),
             _ => false,
         };
         match (t_fields.len().cmp(&o_fields.len()), is_prefix) {
@@ -246,9 +313,14 @@ impl AbiChecker {
 
         let acc_fields: Option<(FieldAccessibility, FieldAccessibility)> =
             match (&t_data, &o_lay.data()) {
-                (TLData::PrefixType(t_prefix), TLData::PrefixType(o_prefix)) => {
-                    Some((t_prefix.accessible_fields, o_prefix.accessible_fields))
-                }
+                (
+                    DataView::PrefixType {
+                        prefix: t_prefix, ..
+                    },
+                    DataView::PrefixType {
+                        prefix: o_prefix, ..
+                    },
+                ) => Some((t_prefix.accessible_fields(), o_prefix.accessible_fields())),
                 _ => None,
             };
 
@@ -257,7 +329,9 @@ impl AbiChecker {
             let other_f = other_f.borrow();
             if this_f.name() != other_f.name() {
                 push_err(errs, this_f, other_f, |x| *x, AI::UnexpectedField);
-                continue;
+                if !self.collect_all {
+                    continue;
+                }
             }
 
             let t_field_abi = this_f.layout();
@@ -282,11 +356,11 @@ impl AbiChecker {
 
                 let sf_ctx = FieldContext::Subfields;
 
-                let func_ranges = this_f.function_range().iter().zip(other_f.function_range());
+                let func_ranges = this_f.functions().zip(other_f.functions());
                 for (t_func, o_func) in func_ranges {
                     self.error_index += 1;
                     let errs_index = self.error_index;
-                    let mut errs_ = RVec::<AbiInstability>::new();
+                    let mut errs_ = RVec::<AbiInstability<V>>::new();
                     let errs = &mut errs_;
 
                     self.stack_trace.push(ExpectedFound {
@@ -294,7 +368,7 @@ impl AbiChecker {
                         found: o_func.into(),
                     });
 
-                    if t_func.paramret_lifetime_indices != o_func.paramret_lifetime_indices {
+                    if t_func.lifetime_indices() != o_func.lifetime_indices() {
                         push_err(errs, t_func, o_func, |x| x, AI::FnLifetimeMismatch);
                     }
 
@@ -307,8 +381,8 @@ impl AbiChecker {
                         t_lay,
                         o_lay,
                         sf_ctx,
-                        t_func.get_params_ret_iter(),
-                        o_func.get_params_ret_iter(),
+                        t_func.params_ret(),
+                        o_func.params_ret(),
                     );
 
                     if !errs_.is_empty() {
@@ -350,19 +424,13 @@ impl AbiChecker {
         }
     }
 
-    fn check_inner(
-        &mut self,
-        this: &'static TypeLayout,
-        other: &'static TypeLayout,
-    ) -> Result<(), ()> {
-        let t_cuti = CheckingUTypeId::new(this);
-        let o_cuti = CheckingUTypeId::new(other);
-        let cuti_pair = (t_cuti, o_cuti);
+    fn check_inner(&mut self, this: V, other: V) -> Result<(), ()> {
+        let cuti_pair = (this.id(), other.id());
 
         self.error_index += 1;
         let errs_index = self.error_index;
-        let mut errs_ = RVec::<AbiInstability>::new();
-        let mut top_level_errs_ = RVec::<AbiInstabilityError>::new();
+        let mut errs_ = RVec::<AbiInstability<V>>::new();
+        let mut top_level_errs_ = RVec::<AbiInstabilityError<V>>::new();
         let t_lay = &this;
         let o_lay = &other;
 
@@ -398,18 +466,21 @@ impl AbiChecker {
             }
         }
 
+        let collect_all = self.collect_all;
         (|| {
             let errs = &mut errs_;
             let top_level_errs = &mut top_level_errs_;
             if t_lay.name() != o_lay.name() {
                 push_err(errs, t_lay, o_lay, |x| x.full_type(), AI::Name);
-                return;
+                if !collect_all {
+                    return;
+                }
             }
-            let (t_package, t_ver_str) = t_lay.package_and_version();
-            let (o_package, o_ver_str) = o_lay.package_and_version();
-            if t_package != o_package {
+            if t_lay.package() != o_lay.package() {
                 push_err(errs, t_lay, o_lay, |x| x.package(), AI::Package);
-                return;
+                if !collect_all {
+                    return;
+                }
             }
 
             if this.is_nonzero() != other.is_nonzero() {
@@ -422,8 +493,8 @@ impl AbiChecker {
 
             {
                 let x = (|| {
-                    let l = t_ver_str.parsed()?;
-                    let r = o_ver_str.parsed()?;
+                    let l = t_lay.parse_version()?;
+                    let r = o_lay.parse_version()?;
                     Ok(l.is_loosely_compatible(r))
                 })();
                 match x {
@@ -439,31 +510,20 @@ impl AbiChecker {
                     Ok(true) => {}
                     Err(parse_error) => {
                         errs.push(AI::PackageVersionParseError(parse_error));
-                        return;
+                        if !collect_all {
+                            return;
+                        }
                     }
                 }
             }
             {
-                let t_gens = t_lay.generics();
-                let o_gens = o_lay.generics();
-
-                let t_consts = t_gens.const_params();
-                let o_consts = o_gens.const_params();
-                if t_gens.lifetime_count() != o_gens.lifetime_count()
-                    || t_gens.const_params().len() != o_gens.const_params().len()
+                if t_lay.lifetime_count() != o_lay.lifetime_count()
+                    || t_lay.const_param_count() != o_lay.const_param_count()
                 {
                     push_err(errs, t_lay, o_lay, |x| x.full_type(), AI::GenericParamCount);
                 }
 
-                let mut ty_checker = TypeCheckerMut::from_ptr(&mut *self, TD_Opaque);
-                for (l, r) in t_consts.iter().zip(o_consts.iter()) {
-                    match l.is_equal(r, ty_checker.sabi_reborrow_mut()) {
-                        Ok(false) | Err(_) => {
-                            push_err(errs, l, r, |x| *x, AI::MismatchedConstParam);
-                        }
-                        Ok(true) => {}
-                    }
-                }
+                V::check_const_params(self, errs, this, other);
             }
 
             // Checking phantom fields
@@ -472,8 +532,8 @@ impl AbiChecker {
                 this,
                 other,
                 FieldContext::PhantomFields,
-                this.phantom_fields().iter(),
-                other.phantom_fields().iter(),
+                this.phantom_fields(),
+                other.phantom_fields(),
             );
 
             match (t_lay.size().cmp(&o_lay.size()), this.is_prefix_kind()) {
@@ -495,53 +555,18 @@ impl AbiChecker {
                 }));
             }
 
-            let t_tag = t_lay.tag().to_checkable();
-            let o_tag = o_lay.tag().to_checkable();
-            if let Err(tag_err) = t_tag.check_compatible(&o_tag) {
+            if let Err(tag_err) = t_lay.check_tag(*o_lay) {
                 errs.push(AI::TagError { err: tag_err });
             }
 
-            match (t_lay.extra_checks(), o_lay.extra_checks()) {
-                (None, _) => {}
-                (Some(_), None) => {
-                    errs.push(AI::NoneExtraChecks);
-                }
-                (Some(t_extra_checks), Some(o_extra_checks)) => {
-                    let mut ty_checker = TypeCheckerMut::from_ptr(&mut *self, TD_Opaque);
-
-                    let res = handle_extra_checks_ret(
-                        t_extra_checks.clone(),
-                        o_extra_checks.clone(),
-                        errs,
-                        top_level_errs,
-                        move || {
-                            let ty_checker_ = ty_checker.sabi_reborrow_mut();
-                            rtry!(t_extra_checks.check_compatibility(t_lay, o_lay, ty_checker_));
-
-                            let ty_checker_ = ty_checker.sabi_reborrow_mut();
-                            let opt = rtry!(t_extra_checks.combine(o_extra_checks, ty_checker_));
-
-                            opt.map(|combined| ExtraChecksBoxWithContext {
-                                t_lay,
-                                o_lay,
-                                extra_checks: combined,
-                            })
-                            .piped(ROk)
-                        },
-                    );
-
-                    if let Ok(RSome(x)) = res {
-                        self.checked_extra_checks.push(x);
-                    }
-                }
-            }
+            V::check_extra_checks(self, errs, top_level_errs, this, other);
 
             match (t_lay.data(), o_lay.data()) {
-                (TLData::Opaque { .. }, _) => {
+                (DataView::Opaque, _) => {
                     // No checks are necessary
                 }
 
-                (TLData::Primitive(t_prim), TLData::Primitive(o_prim)) => {
+                (DataView::Primitive(t_prim), DataView::Primitive(o_prim)) => {
                     if t_prim != o_prim {
                         errs.push(AI::MismatchedPrimitive(ExpectedFound {
                             expected: t_prim,
@@ -549,68 +574,62 @@ impl AbiChecker {
                         }));
                     }
                 }
-                (TLData::Primitive { .. }, _) => {}
+                (DataView::Primitive { .. }, _) => {}
 
-                (TLData::Struct { fields: t_fields }, TLData::Struct { fields: o_fields }) => {
-                    self.check_fields(
-                        errs,
-                        this,
-                        other,
-                        FieldContext::Fields,
-                        t_fields.iter(),
-                        o_fields.iter(),
-                    );
+                (DataView::Struct { fields: t_fields }, DataView::Struct { fields: o_fields }) => {
+                    self.check_fields(errs, this, other, FieldContext::Fields, t_fields, o_fields);
                 }
-                (TLData::Struct { .. }, _) => {}
+                (DataView::Struct { .. }, _) => {}
 
-                (TLData::Union { fields: t_fields }, TLData::Union { fields: o_fields }) => {
-                    self.check_fields(
-                        errs,
-                        this,
-                        other,
-                        FieldContext::Fields,
-                        t_fields.iter(),
-                        o_fields.iter(),
-                    );
+                (DataView::Union { fields: t_fields }, DataView::Union { fields: o_fields }) => {
+                    self.check_fields(errs, this, other, FieldContext::Fields, t_fields, o_fields);
                 }
-                (TLData::Union { .. }, _) => {}
+                (DataView::Union { .. }, _) => {}
+
+                (DataView::Enum(t_enum), DataView::Enum(o_enum)) => {
+                    if self.extensions.is_some() && !t_enum.is_exhaustive() {
+                        let t_count = t_enum.field_counts().len();
+                        let o_count = o_enum.field_counts().len();
+                        let added = o_enum
+                            .variant_names()
+                            .take(o_count)
+                            .skip(t_count)
+                            .map(|x| x.to_string())
+                            .collect();
+                        self.record_extension(LayoutExtensionKind::NonExhaustiveVariants, added);
+                    }
 
-                (TLData::Enum(t_enum), TLData::Enum(o_enum)) => {
                     self.check_enum(errs, this, other, t_enum, o_enum);
-                    let t_as_ne = t_enum.exhaustiveness.as_nonexhaustive();
-                    let o_as_ne = o_enum.exhaustiveness.as_nonexhaustive();
-                    if let (Some(this_ne), Some(other_ne)) = (t_as_ne, o_as_ne) {
-                        self.checked_nonexhaustive_enums
-                            .push(CheckedNonExhaustiveEnums {
-                                this: NonExhaustiveEnumWithContext {
-                                    layout: this,
-                                    enum_: t_enum,
-                                    nonexhaustive: this_ne,
-                                },
-                                other: NonExhaustiveEnumWithContext {
-                                    layout: other,
-                                    enum_: o_enum,
-                                    nonexhaustive: other_ne,
-                                },
-                            });
-                    }
+                    V::record_enums(self, this, t_enum, other, o_enum);
                 }
-                (TLData::Enum { .. }, _) => {}
-
-                (TLData::PrefixType(t_prefix), TLData::PrefixType(o_prefix)) => {
-                    let this_prefix = __PrefixTypeMetadata::with_prefix_layout(t_prefix, t_lay);
-                    let other_prefix = __PrefixTypeMetadata::with_prefix_layout(o_prefix, o_lay);
+                (DataView::Enum { .. }, _) => {}
+
+                (
+                    DataView::PrefixType {
+                        prefix: t_prefix,
+                        fields: t_fields,
+                    },
+                    DataView::PrefixType {
+                        prefix: o_prefix,
+                        fields: o_fields,
+                    },
+                ) => {
+                    if self.extensions.is_some() {
+                        let added = o_fields
+                            .clone()
+                            .skip(t_fields.len())
+                            .map(|x| x.name().to_string())
+                            .collect();
+                        self.record_extension(LayoutExtensionKind::PrefixFields, added);
+                    }
 
-                    self.check_prefix_types(errs, &this_prefix, &other_prefix);
+                    self.check_prefix_types(
+                        errs, this, other, &t_prefix, &o_prefix, t_fields, o_fields,
+                    );
 
-                    self.checked_prefix_types.push(CheckedPrefixTypes {
-                        this,
-                        this_prefix,
-                        other,
-                        other_prefix,
-                    })
+                    V::record_prefix_types(self, this, t_prefix, other, o_prefix);
                 }
-                (TLData::PrefixType { .. }, _) => {}
+                (DataView::PrefixType { .. }, _) => {}
             }
         })();
 
@@ -639,62 +658,62 @@ impl AbiChecker {
 
     fn check_enum(
         &mut self,
-        errs: &mut RVec<AbiInstability>,
-        this: &'static TypeLayout,
-        other: &'static TypeLayout,
-        t_enum: TLEnum,
-        o_enum: TLEnum,
+        errs: &mut RVec<AbiInstability<V>>,
+        this: V,
+        other: V,
+        t_enum: V::Enum,
+        o_enum: V::Enum,
     ) {
-        let TLEnum {
-            fields: t_fields, ..
-        } = t_enum;
-        let TLEnum {
-            fields: o_fields, ..
-        } = o_enum;
-
-        let t_fcount = t_enum.field_count.as_slice();
-        let o_fcount = o_enum.field_count.as_slice();
-
-        let t_exhaus = t_enum.exhaustiveness;
-        let o_exhaus = o_enum.exhaustiveness;
-
-        match (t_exhaus.as_nonexhaustive(), o_exhaus.as_nonexhaustive()) {
-            (Some(this_ne), Some(other_ne)) => {
-                if let Err(e) = this_ne.check_compatible(this) {
+        let t_fields = t_enum.fields();
+        let o_fields = o_enum.fields();
+
+        let t_fcount = t_enum.field_counts();
+        let o_fcount = o_enum.field_counts();
+
+        let t_exhaustive = t_enum.is_exhaustive();
+        let o_exhaustive = o_enum.is_exhaustive();
+
+        match (t_exhaustive, o_exhaustive) {
+            (false, false) => {
+                if let Err(e) = t_enum.check_nonexhaustive(this) {
                     errs.push(AI::IncompatibleWithNonExhaustive(e))
                 }
-                if let Err(e) = other_ne.check_compatible(other) {
+                if let Err(e) = o_enum.check_nonexhaustive(other) {
                     errs.push(AI::IncompatibleWithNonExhaustive(e))
                 }
             }
-            (Some(_), None) | (None, Some(_)) => {
+            (false, true) | (true, false) => {
                 push_err(
                     errs,
                     t_enum,
                     o_enum,
-                    |x| x.exhaustiveness,
+                    |x| x.exhaustiveness(),
                     AI::MismatchedExhaustiveness,
                 );
             }
-            (None, None) => {}
+            (true, true) => {}
         }
 
-        if t_exhaus.is_exhaustive() && t_fcount.len() != o_fcount.len()
-            || t_exhaus.is_nonexhaustive() && t_fcount.len() > o_fcount.len()
+        if t_exhaustive && t_fcount.len() != o_fcount.len()
+            || !t_exhaustive && t_fcount.len() > o_fcount.len()
         {
             push_err(errs, t_fcount, o_fcount, |x| x.len(), AI::TooManyVariants);
         }
 
-        if let Err(d_errs) = t_enum.discriminants.compare(&o_enum.discriminants) {
+        if let Err(d_errs) = t_enum.compare_discriminants(&o_enum) {
             errs.extend(d_errs);
         }
 
-        let mut t_names = t_enum.variant_names.as_str().split(';');
-        let mut o_names = o_enum.variant_names.as_str().split(';');
+        let mut t_names = t_enum.variant_names();
+        let mut o_names = o_enum.variant_names();
         let mut total_field_count = 0;
         for (t_field_count, o_field_count) in t_fcount.iter().zip(o_fcount) {
-            let t_name = t_names.next().unwrap_or("<this unavailable>");
-            let o_name = o_names.next().unwrap_or("<other unavailable>");
+            let t_name = t_names
+                .next()
+                .unwrap_or_else(|| "<this unavailable>".into());
+            let o_name = o_names
+                .next()
+                .unwrap_or_else(|| "<other unavailable>".into());
 
             total_field_count += usize::from(*t_field_count);
 
@@ -709,7 +728,7 @@ impl AbiChecker {
             }
 
             if t_name != o_name {
-                push_err(errs, t_name, o_name, RStr::from_str, AI::UnexpectedVariant);
+                push_err(errs, t_name, o_name, |x| x, AI::UnexpectedVariant);
                 continue;
             }
         }
@@ -725,52 +744,168 @@ impl AbiChecker {
             );
         }
 
-        self.check_fields(
-            errs,
-            this,
-            other,
-            FieldContext::Fields,
-            t_fields.iter(),
-            o_fields.iter(),
-        );
+        self.check_fields(errs, this, other, FieldContext::Fields, t_fields, o_fields);
     }
 
-    fn check_prefix_types(
+    #[allow(clippy::too_many_arguments)]
+    fn check_prefix_types<P, I, F>(
         &mut self,
-        errs: &mut RVec<AbiInstability>,
-        this: &__PrefixTypeMetadata,
-        other: &__PrefixTypeMetadata,
-    ) {
-        if this.prefix_field_count != other.prefix_field_count {
+        errs: &mut RVec<AbiInstability<V>>,
+        this: V,
+        other: V,
+        this_prefix: &P,
+        other_prefix: &P,
+        t_fields: I,
+        o_fields: I,
+    ) where
+        P: PrefixView,
+        I: ExactSizeIterator<Item = F>,
+        F: Borrow<V::Field>,
+    {
+        if this_prefix.prefix_field_count() != other_prefix.prefix_field_count() {
             push_err(
                 errs,
-                this,
-                other,
-                |x| x.prefix_field_count,
+                this_prefix,
+                other_prefix,
+                |x| x.prefix_field_count(),
                 AI::MismatchedPrefixSize,
             );
         }
 
-        if this.conditional_prefix_fields != other.conditional_prefix_fields {
+        if this_prefix.conditional_prefix_fields() != other_prefix.conditional_prefix_fields() {
             push_err(
                 errs,
-                this,
-                other,
-                |x| x.conditional_prefix_fields,
+                this_prefix,
+                other_prefix,
+                |x| x.conditional_prefix_fields(),
                 AI::MismatchedPrefixConditionality,
             );
         }
 
-        self.check_fields(
-            errs,
-            this.layout,
-            other.layout,
-            FieldContext::Fields,
-            this.fields.iter(),
-            other.fields.iter(),
-        );
+        self.check_fields(errs, this, other, FieldContext::Fields, t_fields, o_fields);
+    }
+}
+
+impl CheckLayoutView for &'static TypeLayout {
+    fn check_const_params(
+        checker: &mut AbiChecker<Self>,
+        errs: &mut RVec<AbiInstability>,
+        this: Self,
+        other: Self,
+    ) {
+        let t_consts = this.generics().const_params();
+        let o_consts = other.generics().const_params();
+
+        let mut ty_checker = TypeCheckerMut::from_ptr(checker, TD_Opaque);
+        for (l, r) in t_consts.iter().zip(o_consts.iter()) {
+            match l.is_equal(r, ty_checker.sabi_reborrow_mut()) {
+                Ok(false) | Err(_) => {
+                    push_err(errs, l, r, |x| *x, AI::MismatchedConstParam);
+                }
+                Ok(true) => {}
+            }
+        }
+    }
+
+    fn check_extra_checks(
+        checker: &mut AbiChecker<Self>,
+        errs: &mut RVec<AbiInstability>,
+        top_level_errs: &mut RVec<AbiInstabilityError>,
+        t_lay: Self,
+        o_lay: Self,
+    ) {
+        match (t_lay.extra_checks(), o_lay.extra_checks()) {
+            (None, _) => {}
+            (Some(_), None) => {
+                errs.push(AI::NoneExtraChecks);
+            }
+            (Some(t_extra_checks), Some(o_extra_checks)) => {
+                let mut ty_checker = TypeCheckerMut::from_ptr(&mut *checker, TD_Opaque);
+
+                let res = handle_extra_checks_ret(
+                    t_extra_checks.clone(),
+                    o_extra_checks.clone(),
+                    errs,
+                    top_level_errs,
+                    move || {
+                        let ty_checker_ = ty_checker.sabi_reborrow_mut();
+                        rtry!(t_extra_checks.check_compatibility(t_lay, o_lay, ty_checker_));
+
+                        let ty_checker_ = ty_checker.sabi_reborrow_mut();
+                        let opt = rtry!(t_extra_checks.combine(o_extra_checks, ty_checker_));
+
+                        opt.map(|combined| ExtraChecksBoxWithContext {
+                            t_lay,
+                            o_lay,
+                            extra_checks: combined,
+                        })
+                        .piped(ROk)
+                    },
+                );
+
+                if let Ok(RSome(x)) = res {
+                    checker.checked_extra_checks.push(x);
+                }
+            }
+        }
+    }
+
+    fn record_enums(
+        checker: &mut AbiChecker<Self>,
+        this: Self,
+        t_enum: TLEnum,
+        other: Self,
+        o_enum: TLEnum,
+    ) {
+        let t_as_ne = t_enum.exhaustiveness.as_nonexhaustive();
+        let o_as_ne = o_enum.exhaustiveness.as_nonexhaustive();
+        if let (Some(this_ne), Some(other_ne)) = (t_as_ne, o_as_ne) {
+            checker
+                .checked_nonexhaustive_enums
+                .push(CheckedNonExhaustiveEnums {
+                    this: NonExhaustiveEnumWithContext {
+                        layout: this,
+                        enum_: t_enum,
+                        nonexhaustive: this_ne,
+                    },
+                    other: NonExhaustiveEnumWithContext {
+                        layout: other,
+                        enum_: o_enum,
+                        nonexhaustive: other_ne,
+                    },
+                });
+        }
+    }
+
+    fn record_prefix_types(
+        checker: &mut AbiChecker<Self>,
+        this: Self,
+        t_prefix: TLPrefixType,
+        other: Self,
+        o_prefix: TLPrefixType,
+    ) {
+        checker.checked_prefix_types.push(CheckedPrefixTypes {
+            this,
+            this_prefix: __PrefixTypeMetadata::with_prefix_layout(t_prefix, this),
+            other,
+            other_prefix: __PrefixTypeMetadata::with_prefix_layout(o_prefix, other),
+        })
+    }
+
+    fn final_checks(checker: &mut AbiChecker<Self>, globals: &CheckingGlobals) {
+        if let Err(e) = checker.final_prefix_type_checks(globals) {
+            checker.errors.push(e);
+        }
+        if let Err(e) = checker.final_non_exhaustive_enum_checks(globals) {
+            checker.errors.push(e);
+        }
+        if let Err(e) = checker.final_extra_checks(globals) {
+            checker.errors.extend(e);
+        }
     }
+}
 
+impl AbiChecker<&'static TypeLayout> {
     /// Combines the prefix types into a global map of prefix types.
     fn final_prefix_type_checks(
         &mut self,
@@ -785,6 +920,7 @@ impl AbiChecker {
         for pair in mem::take(&mut self.checked_prefix_types) {
             // let t_lay=pair.this_prefix;
             let errors_before = self.errors.len();
+            let errs_before = errs.len();
             let t_utid = pair.this.get_utypeid();
             let o_utid = pair.other.get_utypeid();
             // let t_fields=pair.this_prefix.fields;
@@ -818,8 +954,19 @@ impl AbiChecker {
                     let (min_prefix, max_prefix) =
                         min_max_by(im_prefix, &mut max_prefix, |x| x.fields.len());
 
-                    self.check_prefix_types(errs, min_prefix, max_prefix);
-                    if !errs.is_empty() || errors_before != self.errors.len() {
+                    self.check_prefix_types(
+                        errs,
+                        min_prefix.layout,
+                        max_prefix.layout,
+                        &*min_prefix,
+                        &*max_prefix,
+                        min_prefix.fields.iter(),
+                        max_prefix.fields.iter(),
+                    );
+                    if errs.len() != errs_before || errors_before != self.errors.len() {
+                        if self.collect_all {
+                            continue;
+                        }
                         break;
                     }
 
@@ -846,8 +993,19 @@ impl AbiChecker {
 
                     let (min_prefix, max_prefix) =
                         min_max_by(l_prefix, r_prefix, |x| x.fields.len());
-                    self.check_prefix_types(errs, min_prefix, max_prefix);
-                    if !errs.is_empty() || errors_before != self.errors.len() {
+                    self.check_prefix_types(
+                        errs,
+                        min_prefix.layout,
+                        max_prefix.layout,
+                        &*min_prefix,
+                        &*max_prefix,
+                        min_prefix.fields.iter(),
+                        max_prefix.fields.iter(),
+                    );
+                    if errs.len() != errs_before || errors_before != self.errors.len() {
+                        if self.collect_all {
+                            continue;
+                        }
                         break;
                     }
 
@@ -884,6 +1042,7 @@ impl AbiChecker {
         for pair in mem::take(&mut self.checked_nonexhaustive_enums) {
             let CheckedNonExhaustiveEnums { this, other } = pair;
             let errors_before = self.errors.len();
+            let errs_before = errs.len();
 
             let t_utid = this.layout.get_utypeid();
             let o_utid = other.layout.get_utypeid();
@@ -921,7 +1080,10 @@ impl AbiChecker {
                         max_nonexh.enum_,
                     );
 
-                    if !errs.is_empty() || errors_before != self.errors.len() {
+                    if errs.len() != errs_before || errors_before != self.errors.len() {
+                        if self.collect_all {
+                            continue;
+                        }
                         break;
                     }
 
@@ -956,7 +1118,10 @@ impl AbiChecker {
                         max_nonexh.enum_,
                     );
 
-                    if !errs.is_empty() || errors_before != self.errors.len() {
+                    if errs.len() != errs_before || errors_before != self.errors.len() {
+                        if self.collect_all {
+                            continue;
+                        }
                         break;
                     }
 
@@ -1000,6 +1165,7 @@ impl AbiChecker {
             } = with_context;
 
             let errors_before = self.errors.len();
+            let errs_before = errs.len();
             let type_checker = TypeCheckerMut::from_ptr(&mut *self, TD_Opaque);
             let t_utid = t_lay.get_utypeid();
             let o_utid = o_lay.get_utypeid();
@@ -1030,7 +1196,10 @@ impl AbiChecker {
                         &[extra_checks.sabi_reborrow()],
                     );
 
-                    if !errs.is_empty() || errors_before != self.errors.len() {
+                    if errs.len() != errs_before || errors_before != self.errors.len() {
+                        if self.collect_all {
+                            continue;
+                        }
                         break;
                     }
 
@@ -1051,7 +1220,10 @@ impl AbiChecker {
                         &[r_extra_checks.sabi_reborrow(), extra_checks.sabi_reborrow()],
                     );
 
-                    if !errs.is_empty() || errors_before != self.errors.len() {
+                    if errs.len() != errs_before || errors_before != self.errors.len() {
+                        if self.collect_all {
+                            continue;
+                        }
                         break;
                     }
 
@@ -1095,7 +1267,19 @@ pub fn check_layout_compatibility_with_globals(
     implementation: &'static TypeLayout,
     globals: &CheckingGlobals,
 ) -> Result<(), AbiInstabilityErrors> {
-    let mut errors: RVec<AbiInstabilityError>;
+    check_layout_compatibility_inner(interface, implementation, globals, false, None)
+}
+
+/// Checks that the layout of `interface` is compatible with `implementation`,
+/// appending the compatible extensions of their types to `extensions` if it's `Some`.
+fn check_layout_compatibility_inner<V: CheckLayoutView>(
+    interface: V,
+    implementation: V,
+    globals: &CheckingGlobals,
+    collect_all: bool,
+    extensions: Option<&mut Vec<LayoutExtension>>,
+) -> Result<(), AbiInstabilityErrors<V>> {
+    let mut errors: RVec<AbiInstabilityError<V>>;
 
     if interface.is_prefix_kind() || implementation.is_prefix_kind() {
         let mut errs = RVec::with_capacity(1);
@@ -1114,18 +1298,21 @@ pub fn check_layout_compatibility_with_globals(
         }]
         .into();
     } else {
-        let mut checker = AbiChecker::new();
+        let mut checker = AbiChecker::<V>::new();
+        checker.collect_all = collect_all;
+        if extensions.is_some() {
+            checker.extensions = Some(RVec::new());
+        }
         let _ = checker.check_inner(interface, implementation);
-        if checker.errors.is_empty() {
-            if let Err(e) = checker.final_prefix_type_checks(globals) {
-                checker.errors.push(e);
-            }
-            if let Err(e) = checker.final_non_exhaustive_enum_checks(globals) {
-                checker.errors.push(e);
-            }
-            if let Err(e) = checker.final_extra_checks(globals) {
-                checker.errors.extend(e);
-            }
+        if checker.errors.is_empty() || collect_all {
+            V::final_checks(&mut checker, globals);
+        }
+        if let (Some(extensions), Some(checked)) = (extensions, checker.extensions) {
+            extensions.extend(
+                checked
+                    .into_iter()
+                    .map(|x| LayoutExtension::new(interface, x)),
+            );
         }
         errors = checker.errors;
     }
@@ -1149,27 +1336,115 @@ pub(crate) extern "C" fn check_layout_compatibility_for_ffi(
     implementation: &'static TypeLayout,
 ) -> RResult<(), RBoxError> {
     extern_fn_panic_handling! {
-        let mut is_already_inside=false;
-        INSIDE_LAYOUT_CHECKER.with(|inside|{
-            is_already_inside=inside.get();
-            inside.set(true);
-        });
-        let _guard=LayoutCheckerGuard;
-
-        if is_already_inside {
-            let errors =
-                vec![AbiInstabilityError {
-                    stack_trace: vec![].into(),
-                    errs:vec![AbiInstability::ReentrantLayoutCheckingCall].into(),
-                    index: 0,
-                    _priv:(),
-                }].into_c();
-
-            Err(AbiInstabilityErrors{ interface, implementation, errors, _priv:() })
-        }else{
-            check_layout_compatibility(interface,implementation)
-        }.map_err(RBoxError::new)
-         .into_c()
+        outside_layout_checker(interface, implementation, || {
+            check_layout_compatibility(interface, implementation)
+        })
+        .map_err(RBoxError::new)
+        .into_c()
+    }
+}
+
+/// Checks that the layout of `interface` is compatible with the
+/// `implementation` layout of the root module of a library,
+/// skipping the comparison of their layouts if they have the same `LayoutFingerprint`.
+///
+/// When the fingerprints are the same,this only does the checks that the fingerprint
+/// doesn't cover:
+/// comparing the values of const parameters and `ExtraChecks`,
+/// checking that nonexhaustive enums fit in their storage,
+/// and combining the prefix types,nonexhaustive enums,and `ExtraChecks`
+/// with the ones from previously checked layouts.
+///
+/// This must only be called when [`is_global_layout_checker`] returns true,
+/// since it uses the `CheckingGlobals` of this binary.
+pub(crate) fn check_fingerprinted_layout_compatibility(
+    interface: &'static TypeLayout,
+    implementation: &'static FingerprintedLayout,
+) -> Result<(), AbiInstabilityErrors> {
+    let globals = get_checking_globals();
+    let interface_fp = globals.fingerprinted_layout(interface);
+
+    outside_layout_checker(interface, implementation.layout, || {
+        if interface_fp.fingerprint != implementation.fingerprint {
+            return check_layout_compatibility_with_globals(
+                interface,
+                implementation.layout,
+                globals,
+            );
+        }
+
+        let mut checker = AbiChecker::<&'static TypeLayout>::new();
+        let checked_types = interface_fp
+            .checked_types
+            .iter()
+            .zip(&implementation.checked_types);
+        for (&this, &other) in checked_types {
+            checker.check_fingerprinted_type(this, other);
+        }
+        if checker.errors.is_empty() {
+            CheckLayoutView::final_checks(&mut checker, globals);
+        }
+
+        let mut errors = checker.errors;
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            errors.sort_by_key(|x| x.index);
+            Err(AbiInstabilityErrors {
+                interface,
+                implementation: implementation.layout,
+                errors,
+                _priv: (),
+            })
+        }
+    })
+}
+
+/// Whether the layout checker that every library uses
+/// (the one in the `Globals` of the executable) is the one in this binary.
+pub(crate) fn is_global_layout_checker() -> bool {
+    type LayoutChecking =
+        extern "C" fn(&'static TypeLayout, &'static TypeLayout) -> RResult<(), RBoxError>;
+
+    let this_binary: LayoutChecking = check_layout_compatibility_for_ffi;
+    let layout_checking = crate::globals::initialized_globals().layout_checking;
+    layout_checking as usize == this_binary as usize
+}
+
+/// Calls `f` unless this is called inside of the layout checker,
+/// in which case it returns a `ReentrantLayoutCheckingCall` error.
+fn outside_layout_checker<F>(
+    interface: &'static TypeLayout,
+    implementation: &'static TypeLayout,
+    f: F,
+) -> Result<(), AbiInstabilityErrors>
+where
+    F: FnOnce() -> Result<(), AbiInstabilityErrors>,
+{
+    let mut is_already_inside = false;
+    INSIDE_LAYOUT_CHECKER.with(|inside| {
+        is_already_inside = inside.get();
+        inside.set(true);
+    });
+    let _guard = LayoutCheckerGuard;
+
+    if is_already_inside {
+        let errors = vec![AbiInstabilityError {
+            stack_trace: vec![].into(),
+            errs: vec![AbiInstability::ReentrantLayoutCheckingCall].into(),
+            index: 0,
+            _priv: (),
+        }]
+        .into_c();
+
+        Err(AbiInstabilityErrors {
+            interface,
+            implementation,
+            errors,
+            _priv: (),
+        })
+    } else {
+        f()
     }
 }
 
@@ -1199,7 +1474,141 @@ pub extern "C" fn exported_check_layout_compatibility(
     }
 }
 
-impl AbiChecker {
+/// Checks that the layout of `interface` is compatible with `implementation`,
+/// where either one can be a `&'static TypeLayout` or a `&LayoutSnapshot`.
+///
+/// When both are `TypeLayout`s this is the same as
+/// [`exported_check_layout_compatibility`](./fn.exported_check_layout_compatibility.html).
+///
+/// When either one is a snapshot,both are checked as snapshots by the same layout checker,
+/// returning an error that contains an `AbiIncompatibilityReport`,
+/// with these differences:
+///
+/// - `ExtraChecks` can only be run when both are `TypeLayout`s,
+/// so the check fails if the `ExtraChecks` of the types are different.
+///
+/// - The prefix types and nonexhaustive enums aren't combined with the ones from
+/// previously checked layouts.
+///
+/// # Warning
+///
+/// This function is not symmetric,
+/// the first parameter must be the expected layout,
+/// and the second must be actual layout.
+///
+/// # Example
+///
+/// ```rust
+/// use abi_stable::{
+///     abi_stability::{
+///         check_layout_compatibility, AbiIncompatibilityKind, AbiIncompatibilityReport,
+///     },
+///     type_layout::LayoutSnapshot,
+///     StableAbi,
+/// };
+///
+/// mod v1 {
+///     #[repr(C)]
+///     #[derive(abi_stable::StableAbi)]
+///     pub struct Point {
+///         pub x: u32,
+///         pub y: u32,
+///     }
+/// }
+///
+/// mod v2 {
+///     #[repr(C)]
+///     #[derive(abi_stable::StableAbi)]
+///     pub struct Point {
+///         pub x: u32,
+///         pub y: u64,
+///     }
+/// }
+///
+/// let json = serde_json::to_string(&LayoutSnapshot::new(v1::Point::LAYOUT)).unwrap();
+/// let released: LayoutSnapshot = serde_json::from_str(&json).unwrap();
+///
+/// check_layout_compatibility(&released, v1::Point::LAYOUT).unwrap();
+///
+/// let err = check_layout_compatibility(&released, v2::Point::LAYOUT).unwrap_err();
+/// let report = err.downcast::<AbiIncompatibilityReport>().unwrap();
+/// assert!(report
+///     .incompatibilities()
+///     .iter()
+///     .any(|x| x.kind() == AbiIncompatibilityKind::Size));
+///
+/// ```
+///
+pub fn check_layout_or_snapshot_compatibility<'a, 'b, I, O>(
+    interface: I,
+    implementation: O,
+) -> RResult<(), RBoxError>
+where
+    I: Into<LayoutOrSnapshot<'a>>,
+    O: Into<LayoutOrSnapshot<'b>>,
+{
+    match (interface.into(), implementation.into()) {
+        (LayoutOrSnapshot::Layout(interface), LayoutOrSnapshot::Layout(implementation)) => {
+            exported_check_layout_compatibility(interface, implementation)
+        }
+        (interface, implementation) => {
+            snapshots::check_snapshot_compatibility(interface, implementation, false, None)
+                .map_err(RBoxError::new)
+                .into_c()
+        }
+    }
+}
+
+impl AbiChecker<&'static TypeLayout> {
+    /// Does the checks of `check_inner` that a `LayoutFingerprint` doesn't cover,
+    /// for a pair of types at the same position in layouts with the same fingerprint.
+    fn check_fingerprinted_type(&mut self, this: &'static TypeLayout, other: &'static TypeLayout) {
+        self.error_index += 1;
+        let errs_index = self.error_index;
+        let mut errs = RVec::<AbiInstability>::new();
+        let mut top_level_errs = RVec::<AbiInstabilityError>::new();
+
+        self.visited.insert(
+            (this.id(), other.id()),
+            CheckingState::Checking {
+                layer: self.current_layer,
+            },
+        );
+
+        CheckLayoutView::check_const_params(self, &mut errs, this, other);
+        CheckLayoutView::check_extra_checks(self, &mut errs, &mut top_level_errs, this, other);
+
+        match (this.data(), other.data()) {
+            (TLData::Enum(t_enum), TLData::Enum(o_enum)) => {
+                for (layout, enum_) in [(this, t_enum), (other, o_enum)] {
+                    if let Err(e) = EnumView::check_nonexhaustive(&enum_, layout) {
+                        errs.push(AI::IncompatibleWithNonExhaustive(e));
+                    }
+                }
+                CheckLayoutView::record_enums(self, this, t_enum, other, o_enum);
+            }
+            (TLData::PrefixType(t_prefix), TLData::PrefixType(o_prefix)) => {
+                CheckLayoutView::record_prefix_types(self, this, t_prefix, other, o_prefix);
+            }
+            _ => {}
+        }
+
+        self.errors.extend(top_level_errs);
+
+        let state = if errs.is_empty() {
+            CheckingState::Compatible
+        } else {
+            self.errors.push(AbiInstabilityError {
+                stack_trace: self.stack_trace.clone(),
+                errs,
+                index: errs_index,
+                _priv: (),
+            });
+            CheckingState::Error
+        };
+        self.visited.insert((this.id(), other.id()), state);
+    }
+
     fn check_compatibility_inner(
         &mut self,
         interface: &'static TypeLayout,
@@ -1221,7 +1630,7 @@ impl AbiChecker {
     }
 }
 
-unsafe impl TypeChecker for AbiChecker {
+unsafe impl TypeChecker for AbiChecker<&'static TypeLayout> {
     fn check_compatibility(
         &mut self,
         interface: &'static TypeLayout,
@@ -1289,6 +1698,9 @@ pub struct CheckingGlobals {
     pub prefix_type_map: Mutex<MultiKeyMap<UTypeId, __PrefixTypeMetadata>>,
     pub nonexhaustive_map: Mutex<MultiKeyMap<UTypeId, NonExhaustiveEnumWithContext>>,
     pub extra_checker_map: Mutex<MultiKeyMap<UTypeId, ExtraChecksBox>>,
+    /// The fingerprints of the expected layouts of root modules,
+    /// keyed by the address of the layout.
+    fingerprinted_layouts: Mutex<HashMap<usize, &'static FingerprintedLayout>>,
 }
 
 #[allow(clippy::new_without_default)]
@@ -1298,8 +1710,18 @@ impl CheckingGlobals {
             prefix_type_map: MultiKeyMap::new().piped(Mutex::new),
             nonexhaustive_map: MultiKeyMap::new().piped(Mutex::new),
             extra_checker_map: MultiKeyMap::new().piped(Mutex::new),
+            fingerprinted_layouts: HashMap::new().piped(Mutex::new),
         }
     }
+
+    /// Gets the fingerprint of `layout`,computing it the first time.
+    fn fingerprinted_layout(&self, layout: &'static TypeLayout) -> &'static FingerprintedLayout {
+        self.fingerprinted_layouts
+            .lock()
+            .unwrap()
+            .entry(layout as *const TypeLayout as usize)
+            .or_insert_with(|| leak_value(FingerprintedLayout::new(layout)))
+    }
 }
 
 static CHECKING_GLOBALS: LateStaticRef<&CheckingGlobals> = LateStaticRef::new();
@@ -1310,15 +1732,16 @@ pub fn get_checking_globals() -> &'static CheckingGlobals {
 
 ///////////////////////////////////////////////
 
-pub(crate) fn push_err<O, U, FG, VC>(
-    errs: &mut RVec<AbiInstability>,
+pub(crate) fn push_err<V, O, U, FG, VC>(
+    errs: &mut RVec<AbiInstability<V>>,
     this: O,
     other: O,
     field_getter: FG,
     mut variant_constructor: VC,
 ) where
+    V: LayoutView,
     FG: FnMut(O) -> U,
-    VC: FnMut(ExpectedFound<U>) -> AbiInstability,
+    VC: FnMut(ExpectedFound<U>) -> AbiInstability<V>,
 {
     let x = ExpectedFound::new(this, other, field_getter);
     let x = variant_constructor(x);
@@ -3,42 +3,40 @@ use super::*;
 use core_extensions::StringExt;
 
 /// An individual error from checking the layout of some type.
+///
+/// `V` is the kind of layout that was checked,
+/// which is `&'static TypeLayout` unless a `LayoutSnapshot` was checked.
 #[derive(Debug, PartialEq, Clone)]
-pub enum AbiInstability {
+pub enum AbiInstability<V: LayoutView = &'static TypeLayout> {
     ReentrantLayoutCheckingCall,
-    CyclicTypeChecking {
-        interface: &'static TypeLayout,
-        implementation: &'static TypeLayout,
-    },
+    CyclicTypeChecking { interface: V, implementation: V },
     NonZeroness(ExpectedFound<bool>),
-    Name(ExpectedFound<FmtFullType>),
-    Package(ExpectedFound<RStr<'static>>),
-    PackageVersionParseError(ParseVersionError),
-    PackageVersion(ExpectedFound<VersionStrings>),
+    Name(ExpectedFound<V::FullType>),
+    Package(ExpectedFound<V::Str>),
+    PackageVersionParseError(V::ParseVersionError),
+    PackageVersion(ExpectedFound<V::VersionStrings>),
     MismatchedPrefixSize(ExpectedFound<u8>),
     Size(ExpectedFound<usize>),
     Alignment(ExpectedFound<usize>),
-    GenericParamCount(ExpectedFound<FmtFullType>),
+    GenericParamCount(ExpectedFound<V::FullType>),
     TLDataDiscriminant(ExpectedFound<TLDataDiscriminant>),
-    MismatchedPrimitive(ExpectedFound<TLPrimitive>),
+    MismatchedPrimitive(ExpectedFound<V::Primitive>),
     FieldCountMismatch(ExpectedFound<usize>),
-    FieldLifetimeMismatch(ExpectedFound<TLField>),
-    FnLifetimeMismatch(ExpectedFound<TLFunction>),
-    FnQualifierMismatch(ExpectedFound<TLFunction>),
-    UnexpectedField(ExpectedFound<TLField>),
+    FieldLifetimeMismatch(ExpectedFound<V::Field>),
+    FnLifetimeMismatch(ExpectedFound<V::Function>),
+    FnQualifierMismatch(ExpectedFound<V::Function>),
+    UnexpectedField(ExpectedFound<V::Field>),
     TooManyVariants(ExpectedFound<usize>),
     MismatchedPrefixConditionality(ExpectedFound<FieldConditionality>),
-    MismatchedExhaustiveness(ExpectedFound<IsExhaustive>),
-    MismatchedConstParam(ExpectedFound<ConstGeneric>),
-    UnexpectedVariant(ExpectedFound<RStr<'static>>),
-    ReprAttr(ExpectedFound<ReprAttr>),
-    EnumDiscriminant(ExpectedFound<TLDiscriminant>),
-    IncompatibleWithNonExhaustive(IncompatibleWithNonExhaustive),
+    MismatchedExhaustiveness(ExpectedFound<V::Exhaustiveness>),
+    MismatchedConstParam(ExpectedFound<V::ConstParam>),
+    UnexpectedVariant(ExpectedFound<V::Str>),
+    ReprAttr(ExpectedFound<V::ReprAttr>),
+    EnumDiscriminant(ExpectedFound<V::Discriminant>),
+    IncompatibleWithNonExhaustive(V::IncompatibleWithNonExhaustive),
     NoneExtraChecks,
     ExtraCheckError(CmpIgnored<ExtraCheckError>),
-    TagError {
-        err: TagErrors,
-    },
+    TagError { err: V::TagErrors },
 }
 
 #[derive(Debug, Clone)]
@@ -50,26 +48,26 @@ pub struct ExtraCheckError {
 use self::AbiInstability as AI;
 
 #[allow(dead_code)]
-impl AbiInstabilityErrors {
+impl<V: LayoutView> AbiInstabilityErrors<V> {
     #[cfg(feature = "testing")]
-    pub fn flatten_errors(&self) -> RVec<AbiInstability> {
-        self.flattened_errors().collect::<RVec<AbiInstability>>()
+    pub fn flatten_errors(&self) -> RVec<AbiInstability<V>> {
+        self.flattened_errors().collect::<RVec<AbiInstability<V>>>()
     }
 
     #[cfg(feature = "testing")]
-    pub fn flattened_errors(&self) -> impl Iterator<Item = AbiInstability> + '_ {
+    pub fn flattened_errors(&self) -> impl Iterator<Item = AbiInstability<V>> + '_ {
         self.errors.iter().flat_map(|x| &x.errs).cloned()
     }
 }
 
-impl std::error::Error for AbiInstabilityErrors {}
+impl<V: LayoutView> std::error::Error for AbiInstabilityErrors<V> {}
 
-impl fmt::Debug for AbiInstabilityErrors {
+impl<V: LayoutView> fmt::Debug for AbiInstabilityErrors<V> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Display::fmt(self, f)
     }
 }
-impl fmt::Display for AbiInstabilityErrors {
+impl<V: LayoutView> fmt::Display for AbiInstabilityErrors<V> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(
             f,
@@ -84,7 +82,113 @@ impl fmt::Display for AbiInstabilityErrors {
     }
 }
 
-impl fmt::Display for AbiInstabilityError {
+/// The human readable description of an `AbiInstability`.
+pub(super) struct ErrorDescription {
+    pub(super) message: &'static str,
+    pub(super) expected_found: Option<ExpectedFound<String>>,
+    pub(super) extra: Option<String>,
+}
+
+impl<V: LayoutView> AbiInstability<V> {
+    pub(super) fn describe(&self) -> ErrorDescription {
+        let mut extra_err = None::<String>;
+
+        let pair = match self {
+            AI::ReentrantLayoutCheckingCall => ("reentrant layout checking call", None),
+            AI::CyclicTypeChecking { interface, .. } => {
+                extra_err = Some(format!("The type:\n{}", interface));
+
+                (
+                    "Attempted to check the layout of a type while checking the layout \
+                     of one of it's const parameters/extra_checks\
+                     (not necessarily a direct one).",
+                    None,
+                )
+            }
+            AI::NonZeroness(v) => ("mismatched non-zeroness", v.display_str()),
+            AI::Name(v) => ("mismatched type", v.display_str()),
+            AI::Package(v) => ("mismatched package", v.display_str()),
+            AI::PackageVersionParseError(v) => {
+                let expected = "a valid version string".to_string();
+                let found = format!("{:#?}", v);
+
+                (
+                    "could not parse version string",
+                    Some(ExpectedFound { expected, found }),
+                )
+            }
+            AI::PackageVersion(v) => ("incompatible package versions", v.display_str()),
+            AI::MismatchedPrefixSize(v) => {
+                ("prefix-types have a different prefix", v.display_str())
+            }
+            AI::Size(v) => ("incompatible type size", v.display_str()),
+            AI::Alignment(v) => ("incompatible type alignment", v.display_str()),
+            AI::GenericParamCount(v) => {
+                ("incompatible amount of generic parameters", v.display_str())
+            }
+
+            AI::TLDataDiscriminant(v) => ("incompatible data ", v.debug_str()),
+            AI::MismatchedPrimitive(v) => ("incompatible primitive", v.debug_str()),
+            AI::FieldCountMismatch(v) => ("too many fields", v.display_str()),
+            AI::FnLifetimeMismatch(v) => (
+                "function pointers reference different lifetimes",
+                v.display_str(),
+            ),
+            AI::FnQualifierMismatch(v) => (
+                "function pointers have different qualifiers (`unsafe`, etc.)",
+                v.display_str(),
+            ),
+            AI::FieldLifetimeMismatch(v) => {
+                ("field references different lifetimes", v.display_str())
+            }
+            AI::UnexpectedField(v) => ("unexpected field", v.display_str()),
+            AI::TooManyVariants(v) => ("too many variants", v.display_str()),
+            AI::MismatchedPrefixConditionality(v) => (
+                "prefix fields differ in whether they are conditional",
+                v.debug_str(),
+            ),
+            AI::MismatchedExhaustiveness(v) => {
+                ("enums differ in whether they are exhaustive", v.debug_str())
+            }
+            AI::MismatchedConstParam(v) => ("The cconst parameters are different", v.debug_str()),
+            AI::UnexpectedVariant(v) => ("unexpected variant", v.debug_str()),
+            AI::ReprAttr(v) => ("incompatible repr attributes", v.debug_str()),
+            AI::EnumDiscriminant(v) => ("different discriminants", v.debug_str()),
+            AI::IncompatibleWithNonExhaustive(e) => {
+                extra_err = Some(e.to_string());
+
+                ("", None)
+            }
+            AI::NoneExtraChecks => {
+                let msg = "\
+                    Interface contains a value in `extra_checks` \
+                    while the implementation does not.\
+                ";
+                (msg, None)
+            }
+            AI::ExtraCheckError(ec_error) => {
+                let ExtraCheckError { err, expected_err } = &**ec_error;
+                extra_err = Some((**err).to_string());
+
+                ("", expected_err.display_str())
+            }
+            AI::TagError { err } => {
+                extra_err = Some(err.to_string());
+
+                ("", None)
+            }
+        };
+
+        let (message, expected_found) = pair;
+        ErrorDescription {
+            message,
+            expected_found,
+            extra: extra_err,
+        }
+    }
+}
+
+impl<V: LayoutView> fmt::Display for AbiInstabilityError<V> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut extra_err = None::<String>;
 
@@ -109,95 +213,14 @@ impl fmt::Display for AbiInstabilityError {
         writeln!(f)?;
 
         for err in &self.errs {
-            let pair = match err {
-                AI::ReentrantLayoutCheckingCall => ("reentrant layout checking call", None),
-                AI::CyclicTypeChecking { interface, .. } => {
-                    extra_err = Some(format!("The type:\n{}", interface));
-
-                    (
-                        "Attempted to check the layout of a type while checking the layout \
-                         of one of it's const parameters/extra_checks\
-                         (not necessarily a direct one).",
-                        None,
-                    )
-                }
-                AI::NonZeroness(v) => ("mismatched non-zeroness", v.display_str()),
-                AI::Name(v) => ("mismatched type", v.display_str()),
-                AI::Package(v) => ("mismatched package", v.display_str()),
-                AI::PackageVersionParseError(v) => {
-                    let expected = "a valid version string".to_string();
-                    let found = format!("{:#?}", v);
-
-                    (
-                        "could not parse version string",
-                        Some(ExpectedFound { expected, found }),
-                    )
-                }
-                AI::PackageVersion(v) => ("incompatible package versions", v.display_str()),
-                AI::MismatchedPrefixSize(v) => {
-                    ("prefix-types have a different prefix", v.display_str())
-                }
-                AI::Size(v) => ("incompatible type size", v.display_str()),
-                AI::Alignment(v) => ("incompatible type alignment", v.display_str()),
-                AI::GenericParamCount(v) => {
-                    ("incompatible amount of generic parameters", v.display_str())
-                }
-
-                AI::TLDataDiscriminant(v) => ("incompatible data ", v.debug_str()),
-                AI::MismatchedPrimitive(v) => ("incompatible primitive", v.debug_str()),
-                AI::FieldCountMismatch(v) => ("too many fields", v.display_str()),
-                AI::FnLifetimeMismatch(v) => (
-                    "function pointers reference different lifetimes",
-                    v.display_str(),
-                ),
-                AI::FnQualifierMismatch(v) => (
-                    "function pointers have different qualifiers (`unsafe`, etc.)",
-                    v.display_str(),
-                ),
-                AI::FieldLifetimeMismatch(v) => {
-                    ("field references different lifetimes", v.display_str())
-                }
-                AI::UnexpectedField(v) => ("unexpected field", v.display_str()),
-                AI::TooManyVariants(v) => ("too many variants", v.display_str()),
-                AI::MismatchedPrefixConditionality(v) => (
-                    "prefix fields differ in whether they are conditional",
-                    v.debug_str(),
-                ),
-                AI::MismatchedExhaustiveness(v) => {
-                    ("enums differ in whether they are exhaustive", v.debug_str())
-                }
-                AI::MismatchedConstParam(v) => {
-                    ("The cconst parameters are different", v.debug_str())
-                }
-                AI::UnexpectedVariant(v) => ("unexpected variant", v.debug_str()),
-                AI::ReprAttr(v) => ("incompatible repr attributes", v.debug_str()),
-                AI::EnumDiscriminant(v) => ("different discriminants", v.debug_str()),
-                AI::IncompatibleWithNonExhaustive(e) => {
-                    extra_err = Some(e.to_string());
-
-                    ("", None)
-                }
-                AI::NoneExtraChecks => {
-                    let msg = "\
-                        Interface contains a value in `extra_checks` \
-                        while the implementation does not.\
-                    ";
-                    (msg, None)
-                }
-                AI::ExtraCheckError(ec_error) => {
-                    let ExtraCheckError { err, expected_err } = &**ec_error;
-                    extra_err = Some((**err).to_string());
-
-                    ("", expected_err.display_str())
-                }
-                AI::TagError { err } => {
-                    extra_err = Some(err.to_string());
-
-                    ("", None)
-                }
-            };
-
-            let (error_msg, expected_err): (&'static str, Option<ExpectedFound<String>>) = pair;
+            let ErrorDescription {
+                message: error_msg,
+                expected_found: expected_err,
+                extra,
+            } = err.describe();
+            if extra.is_some() {
+                extra_err = extra;
+            }
 
             if let Some(expected_err) = expected_err {
                 writeln!(
@@ -219,10 +242,10 @@ impl fmt::Display for AbiInstabilityError {
 /// All the errors from checking the layout of every nested type in TypeLayout.
 #[derive(Clone, PartialEq)]
 #[repr(C)]
-pub struct AbiInstabilityErrors {
-    pub interface: &'static TypeLayout,
-    pub implementation: &'static TypeLayout,
-    pub errors: RVec<AbiInstabilityError>,
+pub struct AbiInstabilityErrors<V: LayoutView = &'static TypeLayout> {
+    pub interface: V,
+    pub implementation: V,
+    pub errors: RVec<AbiInstabilityError<V>>,
     pub(super) _priv: (),
 }
 
@@ -232,9 +255,9 @@ pub struct AbiInstabilityErrors {
 ///  `AbiInstabilityError`s.
 #[derive(Debug, Clone, PartialEq)]
 #[repr(C)]
-pub struct AbiInstabilityError {
-    pub stack_trace: RVec<ExpectedFound<TLFieldOrFunction>>,
-    pub errs: RVec<AbiInstability>,
+pub struct AbiInstabilityError<V: LayoutView = &'static TypeLayout> {
+    pub stack_trace: RVec<ExpectedFound<V::FieldOrFunction>>,
+    pub errs: RVec<AbiInstability<V>>,
     pub index: usize,
     pub(super) _priv: (),
 }
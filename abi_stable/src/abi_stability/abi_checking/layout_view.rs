@@ -0,0 +1,405 @@
+//! The views of type layouts that the layout checker can compare.
+
+use super::*;
+
+use crate::{
+    prefix_type::__PrefixTypeMetadata,
+    sabi_types::VersionNumber,
+    std_types::RStr,
+    type_layout::{
+        LifetimeArrayOrSlice, TLFieldsIterator, TLFunctionIter, TLFunctionQualifiers, TLPrefixType,
+    },
+};
+
+use std::{fmt::Debug, fmt::Display, hash::Hash, str::Split};
+
+/// A type layout that the layout checker can compare with another one of the same kind.
+///
+/// This is implemented for `&'static TypeLayout`,
+/// and for the types in a `LayoutSnapshot`,
+/// so that the same checks are done on both.
+///
+/// The associated types are the values that are stored in an `AbiInstability`
+/// when the layouts are incompatible.
+pub trait LayoutView: Copy + Debug + Display + PartialEq {
+    /// Identifies a type,used to check every pair of types once.
+    type Id: Copy + Eq + Hash + Debug;
+    /// The name of the type with its generic parameters.
+    type FullType: Clone + Debug + Display + PartialEq;
+    /// The package name and the variant names.
+    type Str: Clone + Debug + Display + PartialEq + From<&'static str>;
+    /// The version of the package.
+    type VersionStrings: Clone + Debug + Display + PartialEq;
+    /// The error from parsing the version of the package.
+    type ParseVersionError: Clone + Debug + PartialEq;
+    /// The representation attribute of the type.
+    type ReprAttr: Clone + Debug + PartialEq;
+    /// The primitive that the type is.
+    type Primitive: Clone + Debug + PartialEq;
+    /// A const parameter of the type.
+    type ConstParam: Clone + Debug + PartialEq;
+    /// Whether an enum is exhaustive.
+    type Exhaustiveness: Clone + Debug + PartialEq;
+    /// The discriminant of an enum variant.
+    type Discriminant: Clone + Debug + PartialEq;
+    /// The error from checking that a nonexhaustive enum fits in its storage.
+    type IncompatibleWithNonExhaustive: Clone + Debug + Display + PartialEq;
+    /// The error from checking that the tags of the types are compatible.
+    type TagErrors: Clone + Debug + Display + PartialEq;
+
+    /// A field of the type.
+    type Field: FieldView<Self>;
+    /// A function pointer in the type of a field.
+    type Function: FunctionView<Self>;
+    /// Either a field or a function pointer,
+    /// used to record where an error happened.
+    type FieldOrFunction: FieldOrFunctionView + From<Self::Field> + From<Self::Function>;
+    /// An iterator over fields.
+    type Fields: ExactSizeIterator<Item = Self::Field> + Clone;
+    /// The contents of an enum.
+    type Enum: EnumView<Self>;
+    /// The contents of a prefix type.
+    type Prefix: PrefixView;
+
+    /// Identifies the type.
+    fn id(self) -> Self::Id;
+    /// The name of the type.
+    fn name(&self) -> &str;
+    /// The name of the type with its generic parameters.
+    fn full_type(self) -> Self::FullType;
+    /// The package where the type was declared.
+    fn package(self) -> Self::Str;
+    /// The version of the package where the type was declared.
+    fn package_version(self) -> Self::VersionStrings;
+    /// Parses the version of the package where the type was declared.
+    fn parse_version(self) -> Result<VersionNumber, Self::ParseVersionError>;
+    /// Whether the type uses non-zero value optimization.
+    fn is_nonzero(self) -> bool;
+    /// The representation attribute of the type.
+    fn repr_attr(self) -> Self::ReprAttr;
+    /// The size of the type.
+    fn size(self) -> usize;
+    /// The alignment of the type.
+    fn alignment(self) -> usize;
+    /// Whether this is a prefix-type(module or vtable).
+    fn is_prefix_kind(self) -> bool;
+    /// The amount of lifetime parameters of the type.
+    fn lifetime_count(self) -> usize;
+    /// The amount of const parameters of the type.
+    fn const_param_count(self) -> usize;
+    /// The virtual fields that are checked as part of the type.
+    fn phantom_fields(self) -> Self::Fields;
+    /// Checks that the tag of this type is compatible with the one of `other`.
+    fn check_tag(self, other: Self) -> Result<(), Self::TagErrors>;
+    /// Describes whether the type is a primitive/enum/struct/union.
+    fn data_discriminant(self) -> TLDataDiscriminant;
+    /// The contents of the type.
+    fn data(self) -> DataView<Self>;
+}
+
+/// The contents of a type,
+/// every variant corresponds to a `TLData` variant of the same name.
+pub enum DataView<V: LayoutView> {
+    /// Types defined in the compiler.
+    Primitive(V::Primitive),
+    /// A type that can't be inspected.
+    Opaque,
+    /// For structs.
+    Struct {
+        ///
+        fields: V::Fields,
+    },
+    /// For unions.
+    Union {
+        ///
+        fields: V::Fields,
+    },
+    /// For enums.
+    Enum(V::Enum),
+    /// vtables and modules that can be extended in minor versions.
+    PrefixType {
+        ///
+        prefix: V::Prefix,
+        ///
+        fields: V::Fields,
+    },
+}
+
+/// A field (or function parameter) of a type.
+pub trait FieldView<V: LayoutView>: Copy + Debug + Display + PartialEq {
+    /// The lifetimes that the field references.
+    type LifetimeIndices: PartialEq;
+    /// An iterator over the function pointers in the type of the field.
+    type Functions: Iterator<Item = V::Function>;
+
+    /// The name of the field.
+    fn name(&self) -> &str;
+    /// The lifetimes that the field references.
+    fn lifetime_indices(&self) -> Self::LifetimeIndices;
+    /// The type of the field.
+    fn layout(&self) -> V;
+    /// The function pointers in the type of the field.
+    fn functions(&self) -> Self::Functions;
+}
+
+/// A function pointer in the type of a field.
+pub trait FunctionView<V: LayoutView>: Copy + Debug + Display + PartialEq {
+    /// The lifetimes that the parameters and return type reference.
+    type LifetimeIndices: PartialEq;
+    /// The qualifiers of the function (`unsafe`,etc).
+    type Qualifiers: PartialEq;
+    /// An iterator over the parameters and return type.
+    type ParamsRet: ExactSizeIterator<Item = V::Field>;
+
+    /// The lifetimes that the parameters and return type reference.
+    fn lifetime_indices(&self) -> Self::LifetimeIndices;
+    /// The qualifiers of the function (`unsafe`,etc).
+    fn qualifiers(&self) -> Self::Qualifiers;
+    /// The parameters,followed by the return type as a field named `__returns`.
+    fn params_ret(&self) -> Self::ParamsRet;
+}
+
+/// Either a field or a function pointer,
+/// which the layout checker was checking when it found an error.
+pub trait FieldOrFunctionView: Clone + Debug + Display + PartialEq {
+    /// Outputs the layout of the type of the field,
+    /// or the function pointer,into a String.
+    fn formatted_layout(&self) -> String;
+    /// The name of the field,None for function pointers.
+    fn field_name(&self) -> Option<&str>;
+    /// The name of the type of the field,or the signature of the function pointer.
+    fn type_name(&self) -> String;
+}
+
+/// The contents of an enum.
+pub trait EnumView<V: LayoutView>: Copy {
+    /// An iterator over the names of the variants.
+    type VariantNames: Iterator<Item = V::Str>;
+
+    /// Whether the enum is exhaustive.
+    fn is_exhaustive(&self) -> bool;
+    /// Whether the enum is exhaustive,to report that it differs.
+    fn exhaustiveness(&self) -> V::Exhaustiveness;
+    /// Checks that `layout` fits in the storage of a nonexhaustive enum.
+    ///
+    /// This is always Ok for exhaustive enums.
+    fn check_nonexhaustive(&self, layout: V) -> Result<(), V::IncompatibleWithNonExhaustive>;
+    /// The amount of fields of each variant.
+    fn field_counts(&self) -> &[u8];
+    /// The names of the variants.
+    fn variant_names(&self) -> Self::VariantNames;
+    /// Compares the discriminants of the variants of both enums.
+    fn compare_discriminants(&self, other: &Self) -> Result<(), RVec<AbiInstability<V>>>;
+    /// The fields of all the variants.
+    fn fields(&self) -> V::Fields;
+}
+
+/// The contents of a prefix type.
+pub trait PrefixView {
+    /// The amount of fields in the prefix.
+    fn prefix_field_count(&self) -> u8;
+    /// Which fields are accessible.
+    fn accessible_fields(&self) -> FieldAccessibility;
+    /// Which fields in the prefix are conditional.
+    fn conditional_prefix_fields(&self) -> FieldConditionality;
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl LayoutView for &'static TypeLayout {
+    type Id = UTypeId;
+    type FullType = FmtFullType;
+    type Str = RStr<'static>;
+    type VersionStrings = VersionStrings;
+    type ParseVersionError = ParseVersionError;
+    type ReprAttr = ReprAttr;
+    type Primitive = TLPrimitive;
+    type ConstParam = ConstGeneric;
+    type Exhaustiveness = IsExhaustive;
+    type Discriminant = TLDiscriminant;
+    type IncompatibleWithNonExhaustive = IncompatibleWithNonExhaustive;
+    type TagErrors = TagErrors;
+
+    type Field = TLField;
+    type Function = TLFunction;
+    type FieldOrFunction = TLFieldOrFunction;
+    type Fields = TLFieldsIterator;
+    type Enum = TLEnum;
+    type Prefix = TLPrefixType;
+
+    fn id(self) -> UTypeId {
+        self.get_utypeid()
+    }
+    fn name(&self) -> &str {
+        TypeLayout::name(self)
+    }
+    fn full_type(self) -> FmtFullType {
+        TypeLayout::full_type(self)
+    }
+    fn package(self) -> RStr<'static> {
+        TypeLayout::package(self)
+    }
+    fn package_version(self) -> VersionStrings {
+        TypeLayout::package_version(self)
+    }
+    fn parse_version(self) -> Result<VersionNumber, ParseVersionError> {
+        TypeLayout::package_version(self).parsed()
+    }
+    fn is_nonzero(self) -> bool {
+        TypeLayout::is_nonzero(self)
+    }
+    fn repr_attr(self) -> ReprAttr {
+        TypeLayout::repr_attr(self)
+    }
+    fn size(self) -> usize {
+        TypeLayout::size(self)
+    }
+    fn alignment(self) -> usize {
+        TypeLayout::alignment(self)
+    }
+    fn is_prefix_kind(self) -> bool {
+        TypeLayout::is_prefix_kind(self)
+    }
+    fn lifetime_count(self) -> usize {
+        self.generics().lifetime_count()
+    }
+    fn const_param_count(self) -> usize {
+        self.generics().const_params().len()
+    }
+    fn phantom_fields(self) -> TLFieldsIterator {
+        TypeLayout::phantom_fields(self).iter()
+    }
+    fn check_tag(self, other: Self) -> Result<(), TagErrors> {
+        self.tag()
+            .to_checkable()
+            .check_compatible(&other.tag().to_checkable())
+    }
+    fn data_discriminant(self) -> TLDataDiscriminant {
+        TypeLayout::data_discriminant(self)
+    }
+    fn data(self) -> DataView<Self> {
+        match TypeLayout::data(self) {
+            TLData::Primitive(prim) => DataView::Primitive(prim),
+            TLData::Opaque => DataView::Opaque,
+            TLData::Struct { fields } => DataView::Struct {
+                fields: fields.iter(),
+            },
+            TLData::Union { fields } => DataView::Union {
+                fields: fields.iter(),
+            },
+            TLData::Enum(enum_) => DataView::Enum(enum_),
+            TLData::PrefixType(prefix) => DataView::PrefixType {
+                prefix,
+                fields: prefix.fields.iter(),
+            },
+        }
+    }
+}
+
+impl FieldView<&'static TypeLayout> for TLField {
+    type LifetimeIndices = LifetimeArrayOrSlice<'static>;
+    type Functions = TLFunctionIter;
+
+    fn name(&self) -> &str {
+        TLField::name(self)
+    }
+    fn lifetime_indices(&self) -> LifetimeArrayOrSlice<'static> {
+        TLField::lifetime_indices(self)
+    }
+    fn layout(&self) -> &'static TypeLayout {
+        TLField::layout(self)
+    }
+    fn functions(&self) -> TLFunctionIter {
+        self.function_range().iter()
+    }
+}
+
+impl FunctionView<&'static TypeLayout> for TLFunction {
+    type LifetimeIndices = LifetimeArrayOrSlice<'static>;
+    type Qualifiers = TLFunctionQualifiers;
+    type ParamsRet = std::vec::IntoIter<TLField>;
+
+    fn lifetime_indices(&self) -> LifetimeArrayOrSlice<'static> {
+        self.paramret_lifetime_indices
+    }
+    fn qualifiers(&self) -> TLFunctionQualifiers {
+        TLFunction::qualifiers(self)
+    }
+    fn params_ret(&self) -> std::vec::IntoIter<TLField> {
+        self.get_params_ret_vec().into_vec().into_iter()
+    }
+}
+
+impl FieldOrFunctionView for TLFieldOrFunction {
+    fn formatted_layout(&self) -> String {
+        TLFieldOrFunction::formatted_layout(self)
+    }
+    fn field_name(&self) -> Option<&str> {
+        match self {
+            TLFieldOrFunction::Field(field) => Some(field.name()),
+            TLFieldOrFunction::Function(_) => None,
+        }
+    }
+    fn type_name(&self) -> String {
+        match self {
+            TLFieldOrFunction::Field(field) => field.full_type().to_string(),
+            TLFieldOrFunction::Function(function) => function.to_string(),
+        }
+    }
+}
+
+impl EnumView<&'static TypeLayout> for TLEnum {
+    type VariantNames = std::iter::Map<Split<'static, char>, fn(&'static str) -> RStr<'static>>;
+
+    fn is_exhaustive(&self) -> bool {
+        self.exhaustiveness.is_exhaustive()
+    }
+    fn exhaustiveness(&self) -> IsExhaustive {
+        self.exhaustiveness
+    }
+    fn check_nonexhaustive(
+        &self,
+        layout: &'static TypeLayout,
+    ) -> Result<(), IncompatibleWithNonExhaustive> {
+        match self.exhaustiveness.as_nonexhaustive() {
+            Some(nonexhaustive) => nonexhaustive.check_compatible(layout),
+            None => Ok(()),
+        }
+    }
+    fn field_counts(&self) -> &[u8] {
+        self.field_count.as_slice()
+    }
+    fn variant_names(&self) -> Self::VariantNames {
+        self.variant_names.as_str().split(';').map(RStr::from_str)
+    }
+    fn compare_discriminants(&self, other: &Self) -> Result<(), RVec<AbiInstability>> {
+        self.discriminants.compare(&other.discriminants)
+    }
+    fn fields(&self) -> TLFieldsIterator {
+        self.fields.iter()
+    }
+}
+
+impl PrefixView for TLPrefixType {
+    fn prefix_field_count(&self) -> u8 {
+        self.first_suffix_field
+    }
+    fn accessible_fields(&self) -> FieldAccessibility {
+        self.accessible_fields
+    }
+    fn conditional_prefix_fields(&self) -> FieldConditionality {
+        self.conditional_prefix_fields
+    }
+}
+
+impl PrefixView for __PrefixTypeMetadata {
+    fn prefix_field_count(&self) -> u8 {
+        self.prefix_field_count
+    }
+    fn accessible_fields(&self) -> FieldAccessibility {
+        self.accessible_fields
+    }
+    fn conditional_prefix_fields(&self) -> FieldConditionality {
+        self.conditional_prefix_fields
+    }
+}
@@ -0,0 +1,587 @@
+use super::{errors::ErrorDescription, *};
+
+use core_extensions::StringExt;
+
+use serde::{Deserialize, Serialize};
+
+/// A report of the incompatibilities found by checking the layout of a type,
+/// which can be inspected and (de)serialized with serde.
+///
+/// Unlike the `AbiInstabilityErrors` returned by the layout checker,this doesn't reference the type layouts that were
+/// checked,so it can outlive the dynamic library that they came from.
+///
+/// The `LibraryError::AbiInstability` errors that the executable returns when
+/// loading a library contain one of these,
+/// which can be accessed with [`LibraryError::abi_incompatibility_report`].
+///
+/// # Example
+///
+/// ```rust
+/// use abi_stable::{
+///     abi_stability::{collect_layout_incompatibilities, AbiIncompatibilityKind},
+///     StableAbi,
+/// };
+///
+/// mod v1 {
+///     #[repr(C)]
+///     #[derive(abi_stable::StableAbi)]
+///     pub struct Point {
+///         pub x: u32,
+///         pub y: u32,
+///     }
+/// }
+///
+/// mod v2 {
+///     #[repr(C)]
+///     #[derive(abi_stable::StableAbi)]
+///     pub struct Point {
+///         pub x: u64,
+///         pub z: u32,
+///     }
+/// }
+///
+/// let report =
+///     collect_layout_incompatibilities(v1::Point::LAYOUT, v2::Point::LAYOUT).unwrap_err();
+///
+/// assert!(report
+///     .incompatibilities()
+///     .iter()
+///     .any(|x| x.kind() == AbiIncompatibilityKind::UnexpectedField));
+///
+/// // The type of the `x` field is reported as having a different name and size,
+/// // while `check_layout_compatibility` stops checking it after the name.
+/// let x_field = report
+///     .incompatibilities()
+///     .iter()
+///     .filter(|x| x.field_path() == ["x"])
+///     .collect::<Vec<_>>();
+/// assert_eq!(x_field[0].kind(), AbiIncompatibilityKind::Name);
+/// assert_eq!(x_field[0].expected(), Some("u32"));
+/// assert_eq!(x_field[0].found(), Some("u64"));
+/// assert!(x_field
+///     .iter()
+///     .any(|x| x.kind() == AbiIncompatibilityKind::Size));
+///
+/// let json = serde_json::to_string(&report).unwrap();
+/// assert!(json.contains("UnexpectedField"));
+///
+/// ```
+///
+/// [`LibraryError::abi_incompatibility_report`]:
+/// ../library/enum.LibraryError.html#method.abi_incompatibility_report
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbiIncompatibilityReport {
+    interface: String,
+    implementation: String,
+    incompatibilities: Vec<AbiIncompatibility>,
+    /// The `Display` output of the errors that this was constructed from.
+    #[serde(skip)]
+    formatted: String,
+}
+
+/// An individual incompatibility in an [`AbiIncompatibilityReport`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbiIncompatibility {
+    kind: AbiIncompatibilityKind,
+    type_path: Vec<String>,
+    field_path: Vec<String>,
+    message: String,
+    expected: Option<String>,
+    found: Option<String>,
+    extra: Option<String>,
+}
+
+/// A compatible change between the layouts compared by [`compare_layouts`],
+/// where fields were added to a prefix type,
+/// or variants were added to a nonexhaustive enum.
+///
+/// [`compare_layouts`]: ./fn.compare_layouts.html
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayoutExtension {
+    kind: LayoutExtensionKind,
+    type_path: Vec<String>,
+    field_path: Vec<String>,
+    added: Vec<String>,
+}
+
+/// The kind of a [`LayoutExtension`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum LayoutExtensionKind {
+    /// Fields were added to a prefix type (a module or vtable).
+    PrefixFields,
+    /// Variants were added to a nonexhaustive enum.
+    NonExhaustiveVariants,
+}
+
+/// The result of comparing two layouts with [`compare_layouts`].
+///
+/// [`compare_layouts`]: ./fn.compare_layouts.html
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayoutComparison {
+    extensions: Vec<LayoutExtension>,
+    incompatibilities: Option<AbiIncompatibilityReport>,
+}
+
+/// A `LayoutExtension` recorded by the layout checker,
+/// before the type and field paths are computed.
+pub(super) struct CheckedExtension<V: LayoutView> {
+    pub(super) kind: LayoutExtensionKind,
+    pub(super) stack_trace: RVec<ExpectedFound<V::FieldOrFunction>>,
+    pub(super) added: Vec<String>,
+}
+
+/// The kind of an [`AbiIncompatibility`],
+/// named after the variant of `AbiInstability` that it was constructed from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum AbiIncompatibilityKind {
+    ///
+    ReentrantLayoutCheckingCall,
+    ///
+    CyclicTypeChecking,
+    ///
+    NonZeroness,
+    ///
+    Name,
+    ///
+    Package,
+    ///
+    PackageVersionParseError,
+    ///
+    PackageVersion,
+    ///
+    MismatchedPrefixSize,
+    ///
+    Size,
+    ///
+    Alignment,
+    ///
+    GenericParamCount,
+    ///
+    TLDataDiscriminant,
+    ///
+    MismatchedPrimitive,
+    ///
+    FieldCountMismatch,
+    ///
+    FieldLifetimeMismatch,
+    ///
+    FnLifetimeMismatch,
+    ///
+    FnQualifierMismatch,
+    ///
+    UnexpectedField,
+    ///
+    TooManyVariants,
+    ///
+    MismatchedPrefixConditionality,
+    ///
+    MismatchedExhaustiveness,
+    ///
+    MismatchedConstParam,
+    ///
+    UnexpectedVariant,
+    ///
+    ReprAttr,
+    ///
+    EnumDiscriminant,
+    ///
+    IncompatibleWithNonExhaustive,
+    ///
+    NoneExtraChecks,
+    ///
+    ExtraCheckError,
+    ///
+    TagError,
+}
+
+impl<V: LayoutView> AbiInstability<V> {
+    /// The kind of this error in an [`AbiIncompatibilityReport`].
+    pub const fn kind(&self) -> AbiIncompatibilityKind {
+        use self::AbiIncompatibilityKind as K;
+
+        match self {
+            AI::ReentrantLayoutCheckingCall => K::ReentrantLayoutCheckingCall,
+            AI::CyclicTypeChecking { .. } => K::CyclicTypeChecking,
+            AI::NonZeroness(_) => K::NonZeroness,
+            AI::Name(_) => K::Name,
+            AI::Package(_) => K::Package,
+            AI::PackageVersionParseError(_) => K::PackageVersionParseError,
+            AI::PackageVersion(_) => K::PackageVersion,
+            AI::MismatchedPrefixSize(_) => K::MismatchedPrefixSize,
+            AI::Size(_) => K::Size,
+            AI::Alignment(_) => K::Alignment,
+            AI::GenericParamCount(_) => K::GenericParamCount,
+            AI::TLDataDiscriminant(_) => K::TLDataDiscriminant,
+            AI::MismatchedPrimitive(_) => K::MismatchedPrimitive,
+            AI::FieldCountMismatch(_) => K::FieldCountMismatch,
+            AI::FieldLifetimeMismatch(_) => K::FieldLifetimeMismatch,
+            AI::FnLifetimeMismatch(_) => K::FnLifetimeMismatch,
+            AI::FnQualifierMismatch(_) => K::FnQualifierMismatch,
+            AI::UnexpectedField(_) => K::UnexpectedField,
+            AI::TooManyVariants(_) => K::TooManyVariants,
+            AI::MismatchedPrefixConditionality(_) => K::MismatchedPrefixConditionality,
+            AI::MismatchedExhaustiveness(_) => K::MismatchedExhaustiveness,
+            AI::MismatchedConstParam(_) => K::MismatchedConstParam,
+            AI::UnexpectedVariant(_) => K::UnexpectedVariant,
+            AI::ReprAttr(_) => K::ReprAttr,
+            AI::EnumDiscriminant(_) => K::EnumDiscriminant,
+            AI::IncompatibleWithNonExhaustive(_) => K::IncompatibleWithNonExhaustive,
+            AI::NoneExtraChecks => K::NoneExtraChecks,
+            AI::ExtraCheckError(_) => K::ExtraCheckError,
+            AI::TagError { .. } => K::TagError,
+        }
+    }
+}
+
+impl AbiIncompatibilityReport {
+    /// Constructs the report of `errors`.
+    pub fn new<V: LayoutView>(errors: &AbiInstabilityErrors<V>) -> Self {
+        let root_type = errors.interface.full_type().to_string();
+
+        let incompatibilities = errors
+            .errors
+            .iter()
+            .flat_map(|error| {
+                let (type_path, field_path) = paths(root_type.clone(), &error.stack_trace);
+
+                error.errs.iter().map(move |err| {
+                    let ErrorDescription {
+                        message,
+                        expected_found,
+                        extra,
+                    } = err.describe();
+                    let (expected, found) = match expected_found {
+                        Some(ExpectedFound { expected, found }) => (Some(expected), Some(found)),
+                        None => (None, None),
+                    };
+
+                    AbiIncompatibility {
+                        kind: err.kind(),
+                        type_path: type_path.clone(),
+                        field_path: field_path.clone(),
+                        message: message.to_string(),
+                        expected,
+                        found,
+                        extra,
+                    }
+                })
+            })
+            .collect();
+
+        Self {
+            interface: root_type,
+            implementation: errors.implementation.full_type().to_string(),
+            incompatibilities,
+            formatted: errors.to_string(),
+        }
+    }
+
+    /// The full name of the expected type.
+    pub fn interface(&self) -> &str {
+        &self.interface
+    }
+
+    /// The full name of the type that was found.
+    pub fn implementation(&self) -> &str {
+        &self.implementation
+    }
+
+    /// The incompatibilities,in the order that they were found.
+    pub fn incompatibilities(&self) -> &[AbiIncompatibility] {
+        &self.incompatibilities
+    }
+}
+
+impl fmt::Display for AbiIncompatibilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.formatted.is_empty() {
+            return f.write_str(&self.formatted);
+        }
+
+        // Deserialized reports don't have the original output
+        writeln!(
+            f,
+            "Compared <this>:\n    {}\nTo <other>:\n    {}\n",
+            self.interface, self.implementation,
+        )?;
+        for incompatibility in &self.incompatibilities {
+            fmt::Display::fmt(incompatibility, f)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for AbiIncompatibilityReport {}
+
+impl AbiIncompatibility {
+    /// The kind of incompatibility.
+    pub const fn kind(&self) -> AbiIncompatibilityKind {
+        self.kind
+    }
+
+    /// The types that contain the incompatible type,
+    /// starting with the type that was checked,
+    /// and including the function pointer types that contain it.
+    ///
+    /// The last element is the incompatible type.
+    pub fn type_path(&self) -> &[String] {
+        &self.type_path
+    }
+
+    /// The names of the fields that contain the incompatible type,
+    /// starting with a field of the type that was checked.
+    pub fn field_path(&self) -> &[String] {
+        &self.field_path
+    }
+
+    /// A description of the incompatibility,
+    /// which is empty for the kinds that are only described by [`extra`](#method.extra).
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The expected value,if the incompatibility is between two values.
+    pub fn expected(&self) -> Option<&str> {
+        self.expected.as_deref()
+    }
+
+    /// The value that was found,if the incompatibility is between two values.
+    pub fn found(&self) -> Option<&str> {
+        self.found.as_deref()
+    }
+
+    /// More information about the incompatibility.
+    pub fn extra(&self) -> Option<&str> {
+        self.extra.as_deref()
+    }
+}
+
+impl LayoutExtension {
+    pub(super) fn new<V: LayoutView>(root: V, checked: CheckedExtension<V>) -> Self {
+        let (type_path, field_path) = paths(root.full_type().to_string(), &checked.stack_trace);
+        Self {
+            kind: checked.kind,
+            type_path,
+            field_path,
+            added: checked.added,
+        }
+    }
+
+    /// The kind of extension.
+    pub const fn kind(&self) -> LayoutExtensionKind {
+        self.kind
+    }
+
+    /// The types that contain the extended type,
+    /// starting with the type that was checked,
+    /// and including the function pointer types that contain it.
+    ///
+    /// The last element is the extended type.
+    pub fn type_path(&self) -> &[String] {
+        &self.type_path
+    }
+
+    /// The names of the fields that contain the extended type,
+    /// starting with a field of the type that was checked.
+    pub fn field_path(&self) -> &[String] {
+        &self.field_path
+    }
+
+    /// The names of the fields or variants that were added.
+    pub fn added(&self) -> &[String] {
+        &self.added
+    }
+}
+
+impl LayoutComparison {
+    /// The compatible extensions,in the order that they were found.
+    pub fn extensions(&self) -> &[LayoutExtension] {
+        &self.extensions
+    }
+
+    /// The incompatibilities,None if the layouts are compatible.
+    pub const fn incompatibilities(&self) -> Option<&AbiIncompatibilityReport> {
+        self.incompatibilities.as_ref()
+    }
+
+    /// Whether the layouts are compatible.
+    pub const fn is_compatible(&self) -> bool {
+        self.incompatibilities.is_none()
+    }
+}
+
+/// Gets the type path and field path of a type inside the `root` type.
+fn paths<F: FieldOrFunctionView>(
+    root: String,
+    stack_trace: &[ExpectedFound<F>],
+) -> (Vec<String>, Vec<String>) {
+    let mut type_path = vec![root];
+    let mut field_path = Vec::new();
+    for ExpectedFound { expected, .. } in stack_trace {
+        type_path.push(expected.type_name());
+        if let Some(name) = expected.field_name() {
+            field_path.push(name.to_string());
+        }
+    }
+    (type_path, field_path)
+}
+
+impl fmt::Display for AbiIncompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:?} at {}", self.kind, self.type_path.join(" > "))?;
+        if !self.message.is_empty() {
+            writeln!(f, "Error:{}", self.message)?;
+        }
+        if let (Some(expected), Some(found)) = (&self.expected, &self.found) {
+            writeln!(
+                f,
+                "Expected:\n{}\nFound:\n{}",
+                expected.left_padder(4),
+                found.left_padder(4),
+            )?;
+        }
+        if let Some(extra) = &self.extra {
+            writeln!(f, "Extra:\n{}", extra.left_padder(4))?;
+        }
+        writeln!(f)
+    }
+}
+
+/// Checks that the layout of `interface` is compatible with `implementation`,
+/// collecting every incompatibility in both type layouts into a report.
+///
+/// Either side can be a `&'static TypeLayout` or a `&LayoutSnapshot`,
+/// if either one is a snapshot,both are checked as snapshots.
+///
+/// Unlike [`check_layout_compatibility`],this doesn't stop checking a type after
+/// finding that it has a different name or package,or that a field was renamed,
+/// and it doesn't record the checked types in the global layout checking state.
+///
+/// # Warning
+///
+/// This function is not symmetric,
+/// the first parameter must be the expected layout,
+/// and the second must be actual layout.
+///
+/// [`check_layout_compatibility`]: ./fn.check_layout_compatibility.html
+pub fn collect_layout_incompatibilities<'a, 'b, I, O>(
+    interface: I,
+    implementation: O,
+) -> Result<(), AbiIncompatibilityReport>
+where
+    I: Into<LayoutOrSnapshot<'a>>,
+    O: Into<LayoutOrSnapshot<'b>>,
+{
+    collect_layout_changes(interface.into(), implementation.into(), None)
+}
+
+/// Compares the layouts of `interface` and `implementation`,
+/// collecting both the compatible extensions of their types
+/// and every incompatibility,
+/// like [`collect_layout_incompatibilities`] does.
+///
+/// Either side can be a `&'static TypeLayout` or a `&LayoutSnapshot`,
+/// if either one is a snapshot,both are checked as snapshots.
+///
+/// # Warning
+///
+/// This function is not symmetric,
+/// the first parameter must be the expected layout,
+/// and the second must be actual layout.
+///
+/// # Example
+///
+/// ```rust
+/// use abi_stable::{
+///     abi_stability::{compare_layouts, LayoutExtensionKind},
+///     StableAbi,
+/// };
+///
+/// mod v1 {
+///     #[repr(C)]
+///     #[derive(abi_stable::StableAbi)]
+///     #[sabi(kind(Prefix))]
+///     pub struct Module {
+///         #[sabi(last_prefix_field)]
+///         pub first: u32,
+///     }
+/// }
+///
+/// mod v2 {
+///     #[repr(C)]
+///     #[derive(abi_stable::StableAbi)]
+///     #[sabi(kind(Prefix))]
+///     pub struct Module {
+///         #[sabi(last_prefix_field)]
+///         pub first: u32,
+///         pub second: u64,
+///     }
+/// }
+///
+/// let comparison = compare_layouts(
+///     <v1::Module_Ref as StableAbi>::LAYOUT,
+///     <v2::Module_Ref as StableAbi>::LAYOUT,
+/// );
+///
+/// assert!(comparison.is_compatible());
+///
+/// let extension = &comparison.extensions()[0];
+/// assert_eq!(extension.kind(), LayoutExtensionKind::PrefixFields);
+/// assert_eq!(extension.added(), ["second"]);
+///
+/// ```
+///
+/// [`collect_layout_incompatibilities`]: ./fn.collect_layout_incompatibilities.html
+pub fn compare_layouts<'a, 'b, I, O>(interface: I, implementation: O) -> LayoutComparison
+where
+    I: Into<LayoutOrSnapshot<'a>>,
+    O: Into<LayoutOrSnapshot<'b>>,
+{
+    let mut extensions = Vec::new();
+    let incompatibilities = collect_layout_changes(
+        interface.into(),
+        implementation.into(),
+        Some(&mut extensions),
+    )
+    .err();
+
+    LayoutComparison {
+        extensions,
+        incompatibilities,
+    }
+}
+
+fn collect_layout_changes(
+    interface: LayoutOrSnapshot<'_>,
+    implementation: LayoutOrSnapshot<'_>,
+    extensions: Option<&mut Vec<LayoutExtension>>,
+) -> Result<(), AbiIncompatibilityReport> {
+    match (interface, implementation) {
+        (LayoutOrSnapshot::Layout(interface), LayoutOrSnapshot::Layout(implementation)) => {
+            check_layout_compatibility_inner(
+                interface,
+                implementation,
+                &CheckingGlobals::new(),
+                true,
+                extensions,
+            )
+            .map_err(|e| AbiIncompatibilityReport::new(&e))
+        }
+        (interface, implementation) => {
+            snapshots::check_snapshot_compatibility(interface, implementation, true, extensions)
+        }
+    }
+}
+
+/// Converts the error returned by the layout checker into an error that doesn't
+/// reference the checked type layouts,
+/// containing an `AbiIncompatibilityReport` if the error was created by
+/// the layout checker of this executable/library.
+pub(crate) fn into_report_error(e: RBoxError) -> RBoxError {
+    match e.downcast_ref::<AbiInstabilityErrors>() {
+        Some(errors) => RBoxError::new(AbiIncompatibilityReport::new(errors)),
+        None => e.to_formatted_error(),
+    }
+}
@@ -0,0 +1,775 @@
+//! Checks `LayoutSnapshot`s with the layout checker,
+//! by implementing `LayoutView` for the types in a snapshot.
+
+use super::*;
+
+use crate::{
+    prefix_type::{IsAccessible, IsConditional},
+    sabi_types::VersionNumber,
+    type_layout::{
+        DataSnapshot, EnumSnapshot, FieldSnapshot, FunctionSnapshot, LayoutSnapshot,
+        PrefixTypeSnapshot, TagSnapshot, TypeSnapshot,
+    },
+};
+
+use core_extensions::StringExt;
+
+use std::{borrow::Cow, iter::Map, slice};
+
+/// Checks that the layout of `interface` is compatible with `implementation`,
+/// by checking snapshots of both of them with the layout checker,
+/// appending the compatible extensions of their types to `extensions` if it's `Some`.
+///
+/// The prefix types and nonexhaustive enums aren't combined with the ones from
+/// previously checked layouts,
+/// and `ExtraChecks` can't be run,
+/// so the check fails if the `ExtraChecks` of the types are different.
+pub(super) fn check_snapshot_compatibility(
+    interface: LayoutOrSnapshot<'_>,
+    implementation: LayoutOrSnapshot<'_>,
+    collect_all: bool,
+    extensions: Option<&mut Vec<LayoutExtension>>,
+) -> Result<(), AbiIncompatibilityReport> {
+    let interface = interface.to_snapshot();
+    let implementation = implementation.to_snapshot();
+
+    check_layout_compatibility_inner(
+        SnapshotType::root(&interface),
+        SnapshotType::root(&implementation),
+        &CheckingGlobals::new(),
+        collect_all,
+        extensions,
+    )
+    .map_err(|e| AbiIncompatibilityReport::new(&e))
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// A string from a snapshot,
+/// which is used for the values that are `Debug`-formatted in a `TypeLayout`.
+#[derive(Clone, PartialEq, Eq)]
+pub(super) struct SnapshotValue<'a>(Cow<'a, str>);
+
+impl<'a> SnapshotValue<'a> {
+    fn borrowed(s: &'a str) -> Self {
+        SnapshotValue(Cow::Borrowed(s))
+    }
+}
+
+impl From<&'static str> for SnapshotValue<'_> {
+    fn from(s: &'static str) -> Self {
+        SnapshotValue(Cow::Borrowed(s))
+    }
+}
+
+impl From<String> for SnapshotValue<'_> {
+    fn from(s: String) -> Self {
+        SnapshotValue(Cow::Owned(s))
+    }
+}
+
+impl fmt::Debug for SnapshotValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Display for SnapshotValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// A type in a `LayoutSnapshot`.
+#[derive(Copy, Clone)]
+pub(super) struct SnapshotType<'a> {
+    snapshot: &'a LayoutSnapshot,
+    index: usize,
+}
+
+/// A field (or function parameter) of a type in a `LayoutSnapshot`.
+#[derive(Copy, Clone)]
+pub(super) struct SnapshotField<'a> {
+    snapshot: &'a LayoutSnapshot,
+    field: &'a FieldSnapshot,
+}
+
+/// A function pointer in the type of a field in a `LayoutSnapshot`.
+#[derive(Copy, Clone)]
+pub(super) struct SnapshotFunction<'a> {
+    snapshot: &'a LayoutSnapshot,
+    function: &'a FunctionSnapshot,
+}
+
+/// Either a field or a function pointer in a `LayoutSnapshot`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(super) enum SnapshotFieldOrFunction<'a> {
+    Field(SnapshotField<'a>),
+    Function(SnapshotFunction<'a>),
+}
+
+/// An enum in a `LayoutSnapshot`.
+#[derive(Copy, Clone)]
+pub(super) struct SnapshotEnum<'a> {
+    snapshot: &'a LayoutSnapshot,
+    enum_: &'a EnumSnapshot,
+}
+
+/// The contents of a prefix type in a `LayoutSnapshot`,
+/// in the same form as in a `TLPrefixType`.
+pub(super) struct SnapshotPrefix {
+    prefix_field_count: u8,
+    accessible_fields: FieldAccessibility,
+    conditional_prefix_fields: FieldConditionality,
+}
+
+/// An iterator over the fields or function pointers in a `LayoutSnapshot`.
+#[derive(Clone)]
+pub(super) struct SnapshotIter<'a, T> {
+    snapshot: &'a LayoutSnapshot,
+    iter: slice::Iter<'a, T>,
+}
+
+impl<'a> SnapshotType<'a> {
+    fn root(snapshot: &'a LayoutSnapshot) -> Self {
+        Self { snapshot, index: 0 }
+    }
+
+    fn ty(self) -> &'a TypeSnapshot {
+        self.snapshot.type_at(self.index)
+    }
+
+    fn fields(self, fields: &'a [FieldSnapshot]) -> SnapshotIter<'a, FieldSnapshot> {
+        SnapshotIter {
+            snapshot: self.snapshot,
+            iter: fields.iter(),
+        }
+    }
+}
+
+impl<'a> SnapshotField<'a> {
+    fn ty(self) -> &'a TypeSnapshot {
+        self.snapshot.type_at(self.field.type_index)
+    }
+}
+
+impl fmt::Debug for SnapshotType<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.ty(), f)
+    }
+}
+
+impl fmt::Debug for SnapshotField<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.field, f)
+    }
+}
+
+impl fmt::Debug for SnapshotFunction<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.function, f)
+    }
+}
+
+impl PartialEq for SnapshotType<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ty() == other.ty()
+    }
+}
+
+impl PartialEq for SnapshotField<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.field == other.field
+    }
+}
+
+impl PartialEq for SnapshotFunction<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.function == other.function
+    }
+}
+
+/// Formats the type like the header of the `Display` impl of `TypeLayout`.
+impl fmt::Display for SnapshotType<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ty = self.ty();
+        writeln!(
+            f,
+            "--- Type Layout ---\n\
+             type:{ty}\n\
+             size:{size} align:{align}\n\
+             package:'{package}' version:'{version}'\n\
+             line:{line} mod:{mod_path}",
+            ty = ty.full_type,
+            size = ty.size,
+            align = ty.alignment,
+            package = ty.package,
+            version = ty.package_version,
+            line = ty.line,
+            mod_path = ty.mod_path,
+        )
+    }
+}
+
+impl fmt::Display for SnapshotField<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ty = self.ty();
+        writeln!(
+            f,
+            "field_name:{name}\n\
+             type:{ty}\n\
+             size:{size} align:{align}\n\
+             package:'{package}' version:'{version}'",
+            name = self.field.name,
+            ty = ty.full_type,
+            size = ty.size,
+            align = ty.alignment,
+            package = ty.package,
+            version = ty.package_version,
+        )?;
+
+        if !self.field.functions.is_empty() {
+            writeln!(f, "fn pointer(s):")?;
+            for function in FieldView::functions(self) {
+                writeln!(f, "{}", function.to_string().left_padder(4))?;
+            }
+        }
+
+        if !self.field.lifetime_indices.is_empty() {
+            writeln!(f, "lifetime indices:{:?}", self.field.lifetime_indices)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for SnapshotFunction<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&function_signature(self.snapshot, self.function))?;
+
+        if !self.function.lifetime_indices.is_empty() {
+            writeln!(f, "\nlifetime indices:{:?}", self.function.lifetime_indices)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for SnapshotFieldOrFunction<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotFieldOrFunction::Field(x) => fmt::Display::fmt(x, f),
+            SnapshotFieldOrFunction::Function(x) => fmt::Display::fmt(x, f),
+        }
+    }
+}
+
+impl<'a> From<SnapshotField<'a>> for SnapshotFieldOrFunction<'a> {
+    fn from(x: SnapshotField<'a>) -> Self {
+        SnapshotFieldOrFunction::Field(x)
+    }
+}
+
+impl<'a> From<SnapshotFunction<'a>> for SnapshotFieldOrFunction<'a> {
+    fn from(x: SnapshotFunction<'a>) -> Self {
+        SnapshotFieldOrFunction::Function(x)
+    }
+}
+
+impl<'a> Iterator for SnapshotIter<'a, FieldSnapshot> {
+    type Item = SnapshotField<'a>;
+
+    fn next(&mut self) -> Option<SnapshotField<'a>> {
+        let snapshot = self.snapshot;
+        self.iter
+            .next()
+            .map(|field| SnapshotField { snapshot, field })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl ExactSizeIterator for SnapshotIter<'_, FieldSnapshot> {}
+
+impl<'a> Iterator for SnapshotIter<'a, FunctionSnapshot> {
+    type Item = SnapshotFunction<'a>;
+
+    fn next(&mut self) -> Option<SnapshotFunction<'a>> {
+        let snapshot = self.snapshot;
+        self.iter
+            .next()
+            .map(|function| SnapshotFunction { snapshot, function })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl SnapshotPrefix {
+    fn new(prefix: &PrefixTypeSnapshot) -> Self {
+        let accessible_fields = prefix
+            .accessible_fields
+            .iter()
+            .take(64)
+            .enumerate()
+            .fold(FieldAccessibility::empty(), |acc, (i, &is_accessible)| {
+                acc.set(i, IsAccessible::new(is_accessible))
+            });
+        let conditional_prefix_fields = prefix
+            .conditional_prefix_fields
+            .iter()
+            .take(64)
+            .enumerate()
+            .fold(FieldConditionality::empty(), |acc, (i, &is_conditional)| {
+                acc.set(i, IsConditional::new(is_conditional))
+            });
+
+        Self {
+            prefix_field_count: prefix.prefix_field_count,
+            accessible_fields,
+            conditional_prefix_fields,
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+impl<'a> LayoutView for SnapshotType<'a> {
+    type Id = usize;
+    type FullType = SnapshotValue<'a>;
+    type Str = SnapshotValue<'a>;
+    type VersionStrings = SnapshotValue<'a>;
+    type ParseVersionError = SnapshotValue<'a>;
+    type ReprAttr = SnapshotValue<'a>;
+    type Primitive = SnapshotValue<'a>;
+    type ConstParam = SnapshotValue<'a>;
+    type Exhaustiveness = SnapshotValue<'a>;
+    type Discriminant = i128;
+    type IncompatibleWithNonExhaustive = SnapshotValue<'a>;
+    type TagErrors = SnapshotValue<'a>;
+
+    type Field = SnapshotField<'a>;
+    type Function = SnapshotFunction<'a>;
+    type FieldOrFunction = SnapshotFieldOrFunction<'a>;
+    type Fields = SnapshotIter<'a, FieldSnapshot>;
+    type Enum = SnapshotEnum<'a>;
+    type Prefix = SnapshotPrefix;
+
+    fn id(self) -> usize {
+        self.index
+    }
+    fn name(&self) -> &str {
+        &self.ty().name
+    }
+    fn full_type(self) -> SnapshotValue<'a> {
+        SnapshotValue::borrowed(&self.ty().full_type)
+    }
+    fn package(self) -> SnapshotValue<'a> {
+        SnapshotValue::borrowed(&self.ty().package)
+    }
+    fn package_version(self) -> SnapshotValue<'a> {
+        SnapshotValue::borrowed(&self.ty().package_version)
+    }
+    fn parse_version(self) -> Result<VersionNumber, SnapshotValue<'a>> {
+        VersionNumber::parse_str(&self.ty().package_version)
+            .map_err(|e| SnapshotValue::from(format!("{:#?}", e)))
+    }
+    fn is_nonzero(self) -> bool {
+        self.ty().is_nonzero
+    }
+    fn repr_attr(self) -> SnapshotValue<'a> {
+        SnapshotValue::borrowed(&self.ty().repr_attr)
+    }
+    fn size(self) -> usize {
+        self.ty().size
+    }
+    fn alignment(self) -> usize {
+        self.ty().alignment
+    }
+    fn is_prefix_kind(self) -> bool {
+        self.ty().is_prefix_kind()
+    }
+    fn lifetime_count(self) -> usize {
+        self.ty().generics.lifetimes.len()
+    }
+    fn const_param_count(self) -> usize {
+        self.ty().generics.const_params.len()
+    }
+    fn phantom_fields(self) -> SnapshotIter<'a, FieldSnapshot> {
+        self.fields(&self.ty().phantom_fields)
+    }
+    fn check_tag(self, other: Self) -> Result<(), SnapshotValue<'a>> {
+        check_tag(&self.ty().tag, &other.ty().tag).map_err(SnapshotValue::from)
+    }
+    fn data_discriminant(self) -> TLDataDiscriminant {
+        self.ty().data.discriminant()
+    }
+    fn data(self) -> DataView<Self> {
+        match &self.ty().data {
+            DataSnapshot::Primitive(prim) => DataView::Primitive(SnapshotValue::borrowed(prim)),
+            DataSnapshot::Opaque => DataView::Opaque,
+            DataSnapshot::Struct { fields } => DataView::Struct {
+                fields: self.fields(fields),
+            },
+            DataSnapshot::Union { fields } => DataView::Union {
+                fields: self.fields(fields),
+            },
+            DataSnapshot::Enum(enum_) => DataView::Enum(SnapshotEnum {
+                snapshot: self.snapshot,
+                enum_,
+            }),
+            DataSnapshot::PrefixType(prefix) => DataView::PrefixType {
+                prefix: SnapshotPrefix::new(prefix),
+                fields: self.fields(&prefix.fields),
+            },
+        }
+    }
+}
+
+impl<'a> FieldView<SnapshotType<'a>> for SnapshotField<'a> {
+    type LifetimeIndices = &'a [String];
+    type Functions = SnapshotIter<'a, FunctionSnapshot>;
+
+    fn name(&self) -> &str {
+        &self.field.name
+    }
+    fn lifetime_indices(&self) -> &'a [String] {
+        &self.field.lifetime_indices
+    }
+    fn layout(&self) -> SnapshotType<'a> {
+        SnapshotType {
+            snapshot: self.snapshot,
+            index: self.field.type_index,
+        }
+    }
+    fn functions(&self) -> SnapshotIter<'a, FunctionSnapshot> {
+        SnapshotIter {
+            snapshot: self.snapshot,
+            iter: self.field.functions.iter(),
+        }
+    }
+}
+
+impl<'a> FunctionView<SnapshotType<'a>> for SnapshotFunction<'a> {
+    type LifetimeIndices = &'a [String];
+    type Qualifiers = bool;
+    type ParamsRet = std::vec::IntoIter<SnapshotField<'a>>;
+
+    fn lifetime_indices(&self) -> &'a [String] {
+        &self.function.lifetime_indices
+    }
+    fn qualifiers(&self) -> bool {
+        self.function.is_unsafe
+    }
+    fn params_ret(&self) -> std::vec::IntoIter<SnapshotField<'a>> {
+        let snapshot = self.snapshot;
+        self.function
+            .params
+            .iter()
+            .chain(Some(&self.function.returns))
+            .map(|field| SnapshotField { snapshot, field })
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl FieldOrFunctionView for SnapshotFieldOrFunction<'_> {
+    fn formatted_layout(&self) -> String {
+        match self {
+            SnapshotFieldOrFunction::Field(x) => x.layout().to_string(),
+            SnapshotFieldOrFunction::Function(x) => x.to_string(),
+        }
+    }
+    fn field_name(&self) -> Option<&str> {
+        match self {
+            SnapshotFieldOrFunction::Field(x) => Some(&x.field.name),
+            SnapshotFieldOrFunction::Function(_) => None,
+        }
+    }
+    fn type_name(&self) -> String {
+        match self {
+            SnapshotFieldOrFunction::Field(x) => x.ty().full_type.clone(),
+            SnapshotFieldOrFunction::Function(x) => x.to_string(),
+        }
+    }
+}
+
+impl<'a> EnumView<SnapshotType<'a>> for SnapshotEnum<'a> {
+    type VariantNames = Map<slice::Iter<'a, String>, fn(&'a String) -> SnapshotValue<'a>>;
+
+    fn is_exhaustive(&self) -> bool {
+        self.enum_.nonexhaustive.is_none()
+    }
+    fn exhaustiveness(&self) -> SnapshotValue<'a> {
+        if self.is_exhaustive() {
+            "exhaustive".into()
+        } else {
+            "nonexhaustive".into()
+        }
+    }
+    fn check_nonexhaustive(&self, layout: SnapshotType<'a>) -> Result<(), SnapshotValue<'a>> {
+        let ne = match &self.enum_.nonexhaustive {
+            Some(ne) => ne,
+            None => return Ok(()),
+        };
+        let ty = layout.ty();
+
+        if ty.size < ne.original_size || ty.alignment < ne.original_alignment {
+            Err(format!(
+                "Type '{ty}' has an incompatible layout for the storage.\n\
+                 Type    size:{t_size} alignment:{t_align}\n\
+                 Storage size:{s_size} alignment:{s_align}\n\
+                 module_path:{mod_}\n",
+                ty = ty.full_type,
+                t_size = ne.original_size,
+                t_align = ne.original_alignment,
+                s_size = ty.size,
+                s_align = ty.alignment,
+                mod_ = ty.mod_path,
+            )
+            .into())
+        } else {
+            Ok(())
+        }
+    }
+    fn field_counts(&self) -> &[u8] {
+        &self.enum_.field_counts
+    }
+    fn variant_names(&self) -> Self::VariantNames {
+        let borrowed: fn(&'a String) -> SnapshotValue<'a> = |x| SnapshotValue::borrowed(x);
+        self.enum_.variant_names.iter().map(borrowed)
+    }
+    fn compare_discriminants(
+        &self,
+        other: &Self,
+    ) -> Result<(), RVec<AbiInstability<SnapshotType<'a>>>> {
+        let mut errs = RVec::new();
+        if self.enum_.discriminant_repr != other.enum_.discriminant_repr {
+            push_err(
+                &mut errs,
+                self.enum_,
+                other.enum_,
+                |x| SnapshotValue::borrowed(&x.discriminant_repr),
+                AI::ReprAttr,
+            );
+        } else {
+            let t_discrs = &self.enum_.discriminants;
+            let o_discrs = &other.enum_.discriminants;
+            for (&t_discr, &o_discr) in t_discrs.iter().zip(o_discrs) {
+                if t_discr != o_discr {
+                    push_err(&mut errs, t_discr, o_discr, |x| x, AI::EnumDiscriminant);
+                }
+            }
+        }
+
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+    fn fields(&self) -> SnapshotIter<'a, FieldSnapshot> {
+        SnapshotIter {
+            snapshot: self.snapshot,
+            iter: self.enum_.fields.iter(),
+        }
+    }
+}
+
+impl PrefixView for SnapshotPrefix {
+    fn prefix_field_count(&self) -> u8 {
+        self.prefix_field_count
+    }
+    fn accessible_fields(&self) -> FieldAccessibility {
+        self.accessible_fields
+    }
+    fn conditional_prefix_fields(&self) -> FieldConditionality {
+        self.conditional_prefix_fields
+    }
+}
+
+impl<'a> CheckLayoutView for SnapshotType<'a> {
+    fn check_const_params(
+        _checker: &mut AbiChecker<Self>,
+        errs: &mut RVec<AbiInstability<Self>>,
+        this: Self,
+        other: Self,
+    ) {
+        let t_consts = &this.ty().generics.const_params;
+        let o_consts = &other.ty().generics.const_params;
+        for (l, r) in t_consts.iter().zip(o_consts) {
+            if l != r {
+                push_err(
+                    errs,
+                    l,
+                    r,
+                    |x| SnapshotValue::borrowed(x),
+                    AI::MismatchedConstParam,
+                );
+            }
+        }
+    }
+
+    fn check_extra_checks(
+        _checker: &mut AbiChecker<Self>,
+        errs: &mut RVec<AbiInstability<Self>>,
+        _top_level_errs: &mut RVec<AbiInstabilityError<Self>>,
+        this: Self,
+        other: Self,
+    ) {
+        match (&this.ty().extra_checks, &other.ty().extra_checks) {
+            (None, _) => {}
+            (Some(_), None) => {
+                errs.push(AI::NoneExtraChecks);
+            }
+            (Some(t_extra_checks), Some(o_extra_checks)) => {
+                if t_extra_checks != o_extra_checks {
+                    let err = "\
+                        The `ExtraChecks` of the types are different,\
+                        and they can only be run when neither type is a snapshot.\
+                    ";
+                    let expected_err = ExpectedFound {
+                        expected: RArc::new(RBoxError::from_fmt(t_extra_checks)),
+                        found: RArc::new(RBoxError::from_fmt(o_extra_checks)),
+                    };
+                    ExtraCheckError {
+                        err: RArc::new(RBoxError::from_fmt(&err)),
+                        expected_err,
+                    }
+                    .piped(CmpIgnored::new)
+                    .piped(AI::ExtraCheckError)
+                    .piped(|e| errs.push(e));
+                }
+            }
+        }
+    }
+
+    fn record_enums(
+        _checker: &mut AbiChecker<Self>,
+        _this: Self,
+        _t_enum: SnapshotEnum<'a>,
+        _other: Self,
+        _o_enum: SnapshotEnum<'a>,
+    ) {
+    }
+
+    fn record_prefix_types(
+        _checker: &mut AbiChecker<Self>,
+        _this: Self,
+        _t_prefix: SnapshotPrefix,
+        _other: Self,
+        _o_prefix: SnapshotPrefix,
+    ) {
+    }
+
+    fn final_checks(_checker: &mut AbiChecker<Self>, _globals: &CheckingGlobals) {}
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+/// Formats a function pointer like the `Display` impl of `TLFunction`.
+fn function_signature(snapshot: &LayoutSnapshot, func: &FunctionSnapshot) -> String {
+    use std::fmt::Write;
+
+    let mut out = String::new();
+    if func.is_unsafe {
+        out.push_str("unsafe ");
+    }
+    out.push_str("fn(");
+    for (i, param) in func.params.iter().enumerate() {
+        if i != 0 {
+            out.push_str(", ");
+        }
+        let ty = snapshot.type_at(param.type_index);
+        let _ = write!(out, "{}: {}", param.name, ty.full_type);
+    }
+    let returns = snapshot.type_at(func.returns.type_index);
+    let _ = write!(out, ")->{}", returns.full_type);
+    out
+}
+
+/// Checks that the `this` tag is compatible with `other`,
+/// following the same rules as `CheckableTag::check_compatible`.
+fn check_tag(this: &TagSnapshot, other: &TagSnapshot) -> Result<(), String> {
+    use self::TagSnapshot as T;
+
+    let error = |what: &str| {
+        Err(format!(
+            "{}\nExpected:\n    {}\nFound:\n    {}",
+            what, this, other,
+        ))
+    };
+
+    match (this, other) {
+        (T::Null, _) => Ok(()),
+        (T::Ignored(_), T::Ignored(_)) => Ok(()),
+        (T::Bool(l), T::Bool(r)) if l == r => Ok(()),
+        (T::Int(l), T::Int(r)) if l == r => Ok(()),
+        (T::UInt(l), T::UInt(r)) if l == r => Ok(()),
+        (T::String(l), T::String(r)) if l == r => Ok(()),
+        (T::Bool(_), T::Bool(_))
+        | (T::Int(_), T::Int(_))
+        | (T::UInt(_), T::UInt(_))
+        | (T::String(_), T::String(_)) => error("Mismatched value."),
+        (T::Array(l_arr), T::Array(r_arr)) => {
+            if l_arr.len() != r_arr.len() {
+                return error(&format!(
+                    "Mismatched length  expected:{}  found:{}",
+                    l_arr.len(),
+                    r_arr.len(),
+                ));
+            }
+            l_arr
+                .iter()
+                .zip(r_arr)
+                .try_for_each(|(l_elem, r_elem)| check_tag(l_elem, r_elem))
+        }
+        (T::Set(l_set), T::Set(r_set)) => {
+            let as_entry = |x| (x, &T::Null);
+            check_tag_entries(
+                l_set.iter().map(as_entry).collect(),
+                r_set.iter().map(as_entry).collect(),
+            )
+            .or_else(|e| error(&e))
+        }
+        (T::Map(l_map), T::Map(r_map)) => check_tag_entries(
+            l_map.iter().map(|(k, v)| (k, v)).collect(),
+            r_map.iter().map(|(k, v)| (k, v)).collect(),
+        )
+        .or_else(|e| error(&e)),
+        _ => error("Mismatched Tag variant."),
+    }
+}
+
+/// Checks that every entry in `this` has a compatible entry in `other`,
+/// both of which are sorted.
+fn check_tag_entries(
+    this: Vec<(&TagSnapshot, &TagSnapshot)>,
+    other: Vec<(&TagSnapshot, &TagSnapshot)>,
+) -> Result<(), String> {
+    if this.len() > other.len() {
+        return Err(format!(
+            "Mismatched length  expected at least:{}  found:{}",
+            this.len(),
+            other.len(),
+        ));
+    }
+
+    let mut r_iter = other.into_iter();
+
+    'outer: for (l_key, l_value) in this {
+        for (r_key, r_value) in &mut r_iter {
+            let res = check_tag(l_key, r_key).and_then(|_| check_tag(l_value, r_value));
+            if res.is_ok() {
+                continue 'outer;
+            }
+        }
+        return Err(format!("Missing entry:\n    {}:{}", l_key, l_value));
+    }
+    Ok(())
+}
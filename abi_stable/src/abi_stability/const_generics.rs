@@ -47,6 +47,11 @@ impl ConstGeneric {
         }
     }
 
+    /// The type layout of the constant.
+    pub(crate) fn layout(&self) -> &'static TypeLayout {
+        self.vtable.layout()
+    }
+
     /// Compares this to another `ConstGeneric` for equality,
     /// returning an error if the type layout of `self` and `other` is not compatible.
     pub fn is_equal(
@@ -7,12 +7,16 @@
 #[cfg_attr(feature = "docsrs", doc(cfg(feature = "channels")))]
 pub mod crossbeam_channel;
 
+pub mod log;
+
 pub mod parking_lot;
 
 #[cfg(feature = "serde_json")]
 #[cfg_attr(feature = "docsrs", doc(cfg(feature = "serde_json")))]
 pub mod serde_json;
 
+pub use self::log::{RLevel, RLogger, RMetadata, RRecord};
+
 pub use self::parking_lot::{RMutex, ROnce, RRwLock};
 
 #[cfg(feature = "serde_json")]
@@ -0,0 +1,488 @@
+//! Ffi-safe equivalents of the types in the `log` crate,
+//! for forwarding the log records of dynamic libraries to the executable.
+//!
+//! Every dynamic library has its own copy of the logger static of the `log` crate,
+//! so the records logged in a library are dropped unless the library sets its own logger.
+//!
+//! With the "log" feature enabled,
+//! the logger that the executable sets with `log::set_logger`
+//! is shared with every library that the executable loads a root module from,
+//! and those libraries forward all their records to it
+//! (if they also enable the "log" feature).
+//!
+//! # Example
+//!
+//! This is what a library does with the logger of the executable,
+//! which is passed to it as an [`RLogger`].
+//!
+#![cfg_attr(feature = "log", doc = "```rust")]
+#![cfg_attr(not(feature = "log"), doc = "```ignore")]
+//! use abi_stable::{
+//!     external_types::log::{RLevel, RLogger, RMetadata, RRecord},
+//!     std_types::RStr,
+//! };
+//!
+//! let logger: RLogger = RLogger::GLOBAL;
+//!
+//! let metadata = RMetadata::new(RLevel::Info, RStr::from_str("plugin"));
+//!
+//! if logger.enabled(&metadata) {
+//!     logger.log(&RRecord::new(metadata, "hello from a plugin".into()).with_line(Some(10)));
+//! }
+//!
+//! ```
+
+use crate::std_types::{ROption, RStr, RString};
+
+use std::fmt::{self, Debug, Display};
+
+/// Ffi-safe equivalent of `log::Level`.
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, StableAbi)]
+pub enum RLevel {
+    /// Designates very serious errors.
+    Error = 1,
+    /// Designates hazardous situations.
+    Warn,
+    /// Designates useful information.
+    Info,
+    /// Designates lower priority information.
+    Debug,
+    /// Designates very low priority,often extremely verbose,information.
+    Trace,
+}
+
+impl Display for RLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            RLevel::Error => "ERROR",
+            RLevel::Warn => "WARN",
+            RLevel::Info => "INFO",
+            RLevel::Debug => "DEBUG",
+            RLevel::Trace => "TRACE",
+        })
+    }
+}
+
+#[cfg(feature = "log")]
+impl From<log::Level> for RLevel {
+    fn from(level: log::Level) -> Self {
+        match level {
+            log::Level::Error => RLevel::Error,
+            log::Level::Warn => RLevel::Warn,
+            log::Level::Info => RLevel::Info,
+            log::Level::Debug => RLevel::Debug,
+            log::Level::Trace => RLevel::Trace,
+        }
+    }
+}
+
+#[cfg(feature = "log")]
+impl From<RLevel> for log::Level {
+    fn from(level: RLevel) -> Self {
+        match level {
+            RLevel::Error => log::Level::Error,
+            RLevel::Warn => log::Level::Warn,
+            RLevel::Info => log::Level::Info,
+            RLevel::Debug => log::Level::Debug,
+            RLevel::Trace => log::Level::Trace,
+        }
+    }
+}
+
+//////////////////////////////////////////////////////////////////////
+
+/// Ffi-safe equivalent of `log::Metadata`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, StableAbi)]
+pub struct RMetadata<'a> {
+    level: RLevel,
+    target: RStr<'a>,
+}
+
+impl<'a> RMetadata<'a> {
+    /// Constructs an `RMetadata`.
+    pub const fn new(level: RLevel, target: RStr<'a>) -> Self {
+        Self { level, target }
+    }
+
+    /// The verbosity level of the record.
+    pub const fn level(&self) -> RLevel {
+        self.level
+    }
+
+    /// The name of the target of the record.
+    pub const fn target(&self) -> RStr<'a> {
+        self.target
+    }
+}
+
+#[cfg(feature = "log")]
+impl<'a> From<&log::Metadata<'a>> for RMetadata<'a> {
+    fn from(metadata: &log::Metadata<'a>) -> Self {
+        Self::new(metadata.level().into(), RStr::from_str(metadata.target()))
+    }
+}
+
+#[cfg(feature = "log")]
+impl<'a> From<RMetadata<'a>> for log::Metadata<'a> {
+    fn from(metadata: RMetadata<'a>) -> Self {
+        log::Metadata::builder()
+            .level(metadata.level.into())
+            .target(metadata.target.as_str())
+            .build()
+    }
+}
+
+//////////////////////////////////////////////////////////////////////
+
+/// Ffi-safe equivalent of `log::Record`,
+/// with the message already formatted.
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, StableAbi)]
+pub struct RRecord<'a> {
+    metadata: RMetadata<'a>,
+    args: RString,
+    module_path: ROption<RStr<'a>>,
+    file: ROption<RStr<'a>>,
+    line: ROption<u32>,
+}
+
+impl<'a> RRecord<'a> {
+    /// Constructs an `RRecord` without a module path,file,or line.
+    pub const fn new(metadata: RMetadata<'a>, args: RString) -> Self {
+        Self {
+            metadata,
+            args,
+            module_path: ROption::RNone,
+            file: ROption::RNone,
+            line: ROption::RNone,
+        }
+    }
+
+    /// Sets the module path that the record was logged in.
+    pub fn with_module_path(mut self, module_path: Option<RStr<'a>>) -> Self {
+        self.module_path = module_path.into();
+        self
+    }
+
+    /// Sets the source file that the record was logged in.
+    pub fn with_file(mut self, file: Option<RStr<'a>>) -> Self {
+        self.file = file.into();
+        self
+    }
+
+    /// Sets the line that the record was logged at.
+    pub fn with_line(mut self, line: Option<u32>) -> Self {
+        self.line = line.into();
+        self
+    }
+
+    /// The metadata of the record.
+    pub const fn metadata(&self) -> &RMetadata<'a> {
+        &self.metadata
+    }
+
+    /// The verbosity level of the record.
+    pub const fn level(&self) -> RLevel {
+        self.metadata.level
+    }
+
+    /// The name of the target of the record.
+    pub const fn target(&self) -> RStr<'a> {
+        self.metadata.target
+    }
+
+    /// The formatted message of the record.
+    pub fn args(&self) -> RStr<'_> {
+        self.args.as_rstr()
+    }
+
+    /// The module path that the record was logged in.
+    pub fn module_path(&self) -> Option<RStr<'a>> {
+        self.module_path.into_option()
+    }
+
+    /// The source file that the record was logged in.
+    pub fn file(&self) -> Option<RStr<'a>> {
+        self.file.into_option()
+    }
+
+    /// The line that the record was logged at.
+    pub fn line(&self) -> Option<u32> {
+        self.line.into_option()
+    }
+}
+
+#[cfg(feature = "log")]
+impl<'a> From<&log::Record<'a>> for RRecord<'a> {
+    fn from(record: &log::Record<'a>) -> Self {
+        let args = match record.args().as_str() {
+            Some(args) => RString::from(args),
+            None => RString::from(record.args().to_string()),
+        };
+
+        RRecord::new(record.metadata().into(), args)
+            .with_module_path(record.module_path().map(RStr::from_str))
+            .with_file(record.file().map(RStr::from_str))
+            .with_line(record.line())
+    }
+}
+
+//////////////////////////////////////////////////////////////////////
+
+/// An ffi-safe logger,made of the functions that implement `log::Log`.
+///
+/// The logger of the executable is passed to the libraries it loads as an `RLogger`,
+/// which they forward their records to.
+#[repr(C)]
+#[derive(Copy, Clone, StableAbi)]
+pub struct RLogger {
+    enabled: extern "C" fn(&RMetadata<'_>) -> bool,
+    log: extern "C" fn(&RRecord<'_>),
+    flush: extern "C" fn(),
+}
+
+impl RLogger {
+    /// Constructs an `RLogger` from the functions that implement it.
+    pub const fn new(
+        enabled: extern "C" fn(&RMetadata<'_>) -> bool,
+        log: extern "C" fn(&RRecord<'_>),
+        flush: extern "C" fn(),
+    ) -> Self {
+        Self {
+            enabled,
+            log,
+            flush,
+        }
+    }
+
+    /// A logger that ignores every record.
+    pub const NOOP: Self = {
+        extern "C" fn enabled(_: &RMetadata<'_>) -> bool {
+            false
+        }
+        extern "C" fn log(_: &RRecord<'_>) {}
+        extern "C" fn flush() {}
+
+        Self::new(enabled, log, flush)
+    };
+
+    /// A logger that forwards to the logger set with `log::set_logger`
+    /// in the binary that uses this constant,
+    /// ignoring the records above the level set with `log::set_max_level`.
+    #[cfg(feature = "log")]
+    #[cfg_attr(feature = "docsrs", doc(cfg(feature = "log")))]
+    pub const GLOBAL: Self = Self::new(global_enabled, global_log, global_flush);
+
+    /// Whether a record with this metadata would be logged.
+    pub fn enabled(&self, metadata: &RMetadata<'_>) -> bool {
+        (self.enabled)(metadata)
+    }
+
+    /// Logs the record.
+    pub fn log(&self, record: &RRecord<'_>) {
+        (self.log)(record)
+    }
+
+    /// Flushes any buffered records.
+    pub fn flush(&self) {
+        (self.flush)()
+    }
+}
+
+impl Debug for RLogger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RLogger").finish_non_exhaustive()
+    }
+}
+
+/// The logger that the executable shares with the libraries it loads.
+pub(crate) const EXECUTABLE_LOGGER: RLogger = {
+    #[cfg(feature = "log")]
+    {
+        RLogger::GLOBAL
+    }
+    #[cfg(not(feature = "log"))]
+    {
+        RLogger::NOOP
+    }
+};
+
+#[cfg(feature = "log")]
+extern "C" fn global_enabled(metadata: &RMetadata<'_>) -> bool {
+    extern_fn_panic_handling! {
+        log::Level::from(metadata.level) <= log::max_level()
+            && log::logger().enabled(&(*metadata).into())
+    }
+}
+
+#[cfg(feature = "log")]
+extern "C" fn global_log(record: &RRecord<'_>) {
+    extern_fn_panic_handling! {
+        if log::Level::from(record.level()) <= log::max_level() {
+            log::logger().log(
+                &log::Record::builder()
+                    .metadata(record.metadata.into())
+                    .args(format_args!("{}", record.args))
+                    .module_path(record.module_path().map(|x| x.as_str()))
+                    .file(record.file().map(|x| x.as_str()))
+                    .line(record.line())
+                    .build(),
+            );
+        }
+    }
+}
+
+#[cfg(feature = "log")]
+extern "C" fn global_flush() {
+    extern_fn_panic_handling! {
+        log::logger().flush();
+    }
+}
+
+//////////////////////////////////////////////////////////////////////
+
+/// The `log::Log` that libraries set as their logger,
+/// forwarding to the logger of the executable.
+#[cfg(feature = "log")]
+struct ForwardingLogger(&'static RLogger);
+
+#[cfg(feature = "log")]
+impl log::Log for ForwardingLogger {
+    fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+        self.0.enabled(&metadata.into())
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        // Checking this before formatting the message,
+        // since the max level of this library allows every record.
+        if self.0.enabled(&record.metadata().into()) {
+            self.0.log(&record.into());
+        }
+    }
+
+    fn flush(&self) {
+        self.0.flush()
+    }
+}
+
+/// Sets the logger of this library to one that forwards to `logger`,
+/// if the library didn't set a logger already.
+#[cfg(feature = "log")]
+pub(crate) fn install_forwarding_logger(logger: &'static RLogger) {
+    let forwarding = crate::utils::leak_value(ForwardingLogger(logger));
+    if log::set_logger(forwarding).is_ok() {
+        log::set_max_level(log::LevelFilter::Trace);
+    }
+}
+
+#[cfg(all(test, feature = "log"))]
+mod tests {
+    use super::*;
+
+    use parking_lot::{const_mutex, Mutex};
+
+    static RECORDS: Mutex<Vec<String>> = const_mutex(Vec::new());
+
+    struct TestLogger;
+
+    impl log::Log for TestLogger {
+        fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+            metadata.target() != "ignored"
+        }
+
+        fn log(&self, record: &log::Record<'_>) {
+            if self.enabled(record.metadata()) {
+                RECORDS.lock().push(format!(
+                    "{} {} {:?} {:?}: {}",
+                    record.level(),
+                    record.target(),
+                    record.file(),
+                    record.line(),
+                    record.args()
+                ));
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[test]
+    fn forwarding_test() {
+        static GLOBAL: RLogger = RLogger::GLOBAL;
+
+        log::set_logger(&TestLogger).unwrap();
+        log::set_max_level(log::LevelFilter::Info);
+
+        let forwarding = ForwardingLogger(&GLOBAL);
+        let log_with = |level: log::Level, target: &str, message: &str| {
+            log::Log::log(
+                &forwarding,
+                &log::Record::builder()
+                    .level(level)
+                    .target(target)
+                    .args(format_args!("{}", message))
+                    .file(Some("plugin.rs"))
+                    .line(Some(3))
+                    .build(),
+            );
+        };
+
+        log_with(log::Level::Warn, "plugin", "hello");
+        log_with(log::Level::Debug, "plugin", "too verbose");
+        log_with(log::Level::Error, "ignored", "not enabled");
+
+        assert!(log::Log::enabled(
+            &forwarding,
+            &log::Metadata::builder()
+                .level(log::Level::Info)
+                .target("plugin")
+                .build()
+        ));
+        assert!(!GLOBAL.enabled(&RMetadata::new(RLevel::Trace, "plugin".into())));
+        assert!(!RLogger::NOOP.enabled(&RMetadata::new(RLevel::Error, "plugin".into())));
+
+        assert_eq!(
+            *RECORDS.lock(),
+            ["WARN plugin Some(\"plugin.rs\") Some(3): hello"]
+        );
+    }
+
+    #[test]
+    fn conversion_test() {
+        for level in [
+            log::Level::Error,
+            log::Level::Warn,
+            log::Level::Info,
+            log::Level::Debug,
+            log::Level::Trace,
+        ] {
+            let rlevel = RLevel::from(level);
+            assert_eq!(log::Level::from(rlevel), level);
+            assert_eq!(rlevel.to_string(), level.to_string());
+        }
+
+        let check_record = |record: &log::Record<'_>| {
+            let rrecord = RRecord::from(record);
+            assert_eq!(rrecord.level(), RLevel::Info);
+            assert_eq!(rrecord.target(), "plugin");
+            assert_eq!(rrecord.args(), "hello 3");
+            assert_eq!(
+                rrecord.module_path(),
+                Some(RStr::from_str("plugin::module"))
+            );
+            assert_eq!(rrecord.file(), None);
+            assert_eq!(rrecord.line(), None);
+        };
+        let number = 3;
+        check_record(
+            &log::Record::builder()
+                .level(log::Level::Info)
+                .target("plugin")
+                .args(format_args!("hello {}", number))
+                .module_path(Some("plugin::module"))
+                .build(),
+        );
+    }
+}
@@ -85,6 +85,28 @@ enabling the features you need in the `features` array.
 
 ### Manually enabled
 
+These are crate features that enable optional crates:
+
+- "log":
+    Depends on `log`,
+    forwarding the `log` records of dynamic libraries to the logger of the executable,
+    with the ffi-safe equivalents of its types in `abi_stable::external_types::log` .
+
+These are crate features that enable optional functionality:
+
+- "allocation_tracking":
+    Counts the bytes and allocations of the `RVec`s,`RBox`es,and `RString`s
+    that each executable/library allocated,
+    readable with `abi_stable::library::LibHeader::allocation_stats`.
+    Libraries loaded with `RootModule::load_reloadable_from` are not unloaded
+    while they still own tracked allocations.
+
+- "out_of_process":
+    Depends on `serde_json`,
+    loading root modules in child processes with `abi_stable::library::OutOfProcess`,
+    so that libraries that crash return an error instead of crashing the executable.
+    This is only available on Unix.
+
 These are crate features to manually enable support for newer language features:
 
 - "rust_1_64": Turns many functions for converting types to slices into const fns.
@@ -219,6 +241,7 @@ extern crate self as abi_stable;
 
 include! {"./proc_macro_reexports/get_static_equivalent.rs"}
 include! {"./proc_macro_reexports/export_root_module.rs"}
+include! {"./proc_macro_reexports/export_item.rs"}
 include! {"./proc_macro_reexports/sabi_extern_fn.rs"}
 include! {"./proc_macro_reexports/sabi_trait_attribute.rs"}
 include! {"./proc_macro_reexports/stable_abi_derive.rs"}
@@ -332,6 +355,8 @@ pub use crate::{
 pub mod globals {
     use crate::{
         abi_stability::abi_checking::check_layout_compatibility_for_ffi,
+        external_types::log::{RLogger, EXECUTABLE_LOGGER},
+        library::{forward_to_plugin_panic_hook, PanicHookInfo},
         sabi_types::LateStaticRef,
         std_types::{RBoxError, RResult},
         type_layout::TypeLayout,
@@ -344,12 +369,18 @@ pub mod globals {
     pub struct Globals {
         pub layout_checking:
             extern "C" fn(&'static TypeLayout, &'static TypeLayout) -> RResult<(), RBoxError>,
+        // Older versions of abi_stable don't have the fields below,
+        // so libraries only read them when `Globals` is passed to them by a `GlobalsInstaller`.
+        pub panic_hook: extern "C" fn(&PanicHookInfo<'_>),
+        pub logger: RLogger,
     }
 
     impl Globals {
         pub fn new() -> &'static Self {
             leak_value(Globals {
                 layout_checking: check_layout_compatibility_for_ffi,
+                panic_hook: forward_to_plugin_panic_hook,
+                logger: EXECUTABLE_LOGGER,
             })
         }
     }
@@ -20,15 +20,125 @@
 //!
 //! All steps can return errors.
 //!
+//! [`RootModule::load_from_with_options`] loads the [`RawLibrary`] with [`LoadOptions`],
+//! which control the flags passed to `dlopen` on unix platforms.
+//!
+//! # Loading many libraries
+//!
+//! [`RootModule`]`::load_from*` can only load one library for every root module type,
+//! [`PluginSet`] can load any number of libraries that export the same root module type.
+//! [`load_all`] loads many of those libraries in parallel,
+//! reporting how long each one took to load.
+//!
+//! A library can export several root modules with
+//! [`#[export_root_module(name = "...")]`](../attr.export_root_module.html#name),
+//! each one is loaded by a [`RootModule`] with that name as its [`RootModule::EXPORT_NAME`].
+//!
+//! Libraries can export several versions of an interface as separate root modules,
+//! listed in an [`InterfaceVersions`] table in the [`LibHeader`],
+//! and [`VersionNegotiator`] loads the highest version that the executable also supports.
+//!
+//! The function that exports a root module can take a
+//! [host context](../attr.export_root_module.html#host-context),
+//! which is passed to it by [`RootModule::load_from_with_context`].
+//!
+//! # Finding libraries
+//!
+//! [`discover_libraries`] and [`discover_root_modules`] find the libraries in directories,
+//! reading the name and version of the root module that each one exports.
+//!
+//! [`LibraryInfo`] describes a library and the root module that it exports
+//! without initializing the library nor constructing the root module.
+//!
+//! [`LibraryPath::SearchPaths`] loads a library from the first of a list of
+//! [`SearchPaths`] directories that contains a compatible library.
+//!
+//! # Dependencies
+//!
+//! Root modules can declare the other root modules that they depend on in
+//! [`RootModule::DEPENDENCIES`],
+//! which [`RootModule::load_with_dependencies_from`] loads before the root module itself.
+//!
+//! # Panics and logging
+//!
+//! Libraries that export a root module forward their panics to the hook set with
+//! [`set_plugin_panic_hook`],
+//! since `std::panic::set_hook` in the executable doesn't affect them.
+//!
+//! With the "log" feature,they also forward their `log` records to the logger of
+//! the executable,as described in [`external_types::log`].
+//!
+//! # Allocation tracking
+//!
+//! With the "allocation_tracking" feature,
+//! every executable/library counts the `RVec`s,`RBox`es,and `RString`s that it allocated
+//! (including the ones that were passed to other libraries),
+//! the counts of a library can be read with [`LibHeader::allocation_stats`],
+//! and the counts of the executable with [`local_allocation_stats`].
+//!
+//! # Shutting down
+//!
+//! Root modules can be exported with a shutdown hook,
+//! with [`#[export_root_module(shutdown = "...")]`](../attr.export_root_module.html#shutdown),
+//! which is called by [`RootModule::shutdown`] or [`shutdown_all`].
+//!
+//! # Running libraries in a child process
+//!
+//! With the "out_of_process" feature on Unix,
+//! [`OutOfProcess`] loads a root module in a child process,
+//! calling its functions by passing their serialized arguments and return values
+//! through a Unix socket,
+//! so that a library that crashes returns an error instead of crashing the executable.
+//!
+//! # Unloading
+//!
+//! Libraries loaded with [`RootModule`]`::load_from*` are never unloaded.
+//!
+//! [`RootModule::load_reloadable_from`] loads the library as a [`ReloadableModule`],
+//! which can be reloaded,
+//! and whose libraries are unloaded once no [`LibraryHandle`] refers to them,
+//! unless they still own allocations that are tracked with the "allocation_tracking" feature.
+//!
 //! [`RawLibrary`]: ./struct.RawLibrary.html
 //! [`AbiHeaderRef`]: ./struct.AbiHeaderRef.html
 //! [`RootModule`]: ./trait.RootModule.html
 //! [`RootModule::initialize`]: ./trait.RootModule.html#method.initialization
+//! [`RootModule::load_reloadable_from`]:
+//! ./trait.RootModule.html#method.load_reloadable_from
+//! [`ReloadableModule`]: ./struct.ReloadableModule.html
+//! [`LibraryHandle`]: ./struct.LibraryHandle.html
+//! [`LibHeader::allocation_stats`]: ./struct.LibHeader.html#method.allocation_stats
+//! [`local_allocation_stats`]: ./fn.local_allocation_stats.html
+//! [`PluginSet`]: ./struct.PluginSet.html
+//! [`load_all`]: ./fn.load_all.html
+//! [`OutOfProcess`]: ./struct.OutOfProcess.html
+//! [`InterfaceVersions`]: ./struct.InterfaceVersions.html
+//! [`LibHeader`]: ./struct.LibHeader.html
+//! [`VersionNegotiator`]: ./struct.VersionNegotiator.html
+//! [`discover_libraries`]: ./fn.discover_libraries.html
+//! [`discover_root_modules`]: ./fn.discover_root_modules.html
+//! [`LibraryPath::SearchPaths`]: ./enum.LibraryPath.html#variant.SearchPaths
+//! [`SearchPaths`]: ./struct.SearchPaths.html
+//! [`LibraryInfo`]: ./struct.LibraryInfo.html
+//! [`RootModule::DEPENDENCIES`]: ./trait.RootModule.html#associatedconstant.DEPENDENCIES
+//! [`RootModule::load_with_dependencies_from`]:
+//! ./trait.RootModule.html#method.load_with_dependencies_from
 //! [`&'static LibHeader`]: ./struct.LibHeader.html
+//! [`RootModule::load_from_with_options`]:
+//! ./trait.RootModule.html#method.load_from_with_options
+//! [`LoadOptions`]: ./struct.LoadOptions.html
+//! [`RootModule::shutdown`]: ./trait.RootModule.html#method.shutdown
+//! [`shutdown_all`]: ./fn.shutdown_all.html
+//! [`set_plugin_panic_hook`]: ./fn.set_plugin_panic_hook.html
+//! [`external_types::log`]: ../external_types/log/index.html
+//! [`RootModule::EXPORT_NAME`]: ./trait.RootModule.html#associatedconstant.EXPORT_NAME
+//! [`RootModule::load_from_with_context`]:
+//! ./trait.RootModule.html#method.load_from_with_context
 
 use std::{
     convert::Infallible,
     mem,
+    panic::AssertUnwindSafe,
     path::{Path, PathBuf},
     sync::atomic,
 };
@@ -43,35 +153,90 @@ use crate::{
     globals::{self, Globals},
     marker_type::ErasedPrefix,
     prefix_type::{PrefixRef, PrefixRefTrait},
-    sabi_types::{LateStaticRef, NulStr, VersionNumber, VersionStrings},
-    std_types::{RResult, RStr},
+    sabi_types::{LateStaticRef, NulStr, VersionNumber, VersionRequirement, VersionStrings},
+    std_types::{RResult, RSome, RStr},
     type_layout::TypeLayout,
+    utils,
 };
 
+mod allocation_tracking;
 pub mod c_abi_testing;
+mod context_loader;
+mod dependencies;
 pub mod development_utils;
+mod discovery;
 mod errors;
+mod exported_item;
+mod globals_installer;
 mod lib_header;
+mod library_info;
+mod load_all;
 
 #[cfg(test)]
 mod library_tests;
 
+mod load_options;
+
+#[cfg(all(feature = "out_of_process", unix))]
+mod out_of_process;
+
+mod panic_hook;
+mod plugin_set;
 mod raw_library;
+mod reloadable;
 mod root_mod_trait;
+mod search_paths;
+mod shutdown;
+mod version_negotiation;
 
 #[doc(no_inline)]
 pub use self::c_abi_testing::{CAbiTestingFns, C_ABI_TESTING_FNS};
 
 pub use self::{
+    allocation_tracking::{local_allocation_stats, AllocationStats, AllocationTracker},
+    context_loader::{ContextLoader, ContextLoaderFn},
+    dependencies::{dependency_load_order, RootModuleDependency},
+    discovery::{discover_libraries, discover_root_modules, Compatibility, LibraryCandidate},
     errors::{IntoRootModuleResult, LibraryError, RootModuleError},
+    exported_item::ExportedItem,
+    globals_installer::GlobalsInstaller,
     lib_header::{AbiHeader, AbiHeaderRef, LibHeader},
+    library_info::LibraryInfo,
+    load_all::{load_all, LibraryLoadReport, LoadReport},
+    load_options::{LoadOptions, SymbolBinding, SymbolScope},
+    panic_hook::{
+        set_plugin_panic_hook, take_plugin_panic_hook, PanicHookFn, PanicHookInfo, PanicLocation,
+        PluginPanicHook,
+    },
+    plugin_set::{LoadedPlugin, PluginSet},
     raw_library::RawLibrary,
+    reloadable::{LibraryBound, LibraryHandle, ReloadableModule, WeakLibraryHandle},
     root_mod_trait::{
-        abi_header_from_path, abi_header_from_raw_library, lib_header_from_path,
-        lib_header_from_raw_library, RootModule, RootModuleConsts,
+        abi_header_from_path, abi_header_from_raw_library, abi_header_from_raw_library_named,
+        lib_header_from_path, lib_header_from_raw_library, lib_header_from_raw_library_named,
+        RootModule, RootModuleConsts,
+    },
+    search_paths::{SearchPaths, PLUGIN_PATH_ENV_VAR},
+    shutdown::{shutdown_all, IntoShutdownResult, ShutdownFn},
+    version_negotiation::{
+        InterfaceVersion, InterfaceVersions, NegotiatedModule, VersionNegotiator,
     },
 };
 
+pub(crate) use self::{
+    allocation_tracking::record_reallocation, panic_hook::forward_to_plugin_panic_hook,
+};
+
+#[cfg(all(feature = "out_of_process", unix))]
+#[cfg_attr(feature = "docsrs", doc(cfg(all(feature = "out_of_process", unix))))]
+pub use self::out_of_process::{OutOfProcess, OutOfProcessModule, RemoteFn};
+
+#[doc(hidden)]
+pub use self::{
+    context_loader::{__call_root_module_loader_with_context, __root_module_requires_context},
+    shutdown::__call_root_module_shutdown,
+};
+
 ///////////////////////////////////////////////////////////////////////////////
 
 /// What naming convention to expect when loading a library from a directory.
@@ -87,12 +252,18 @@ pub enum LibrarySuffix {
 //////////////////////////////////////////////////////////////////////
 
 /// The path a library is loaded from.
+///
+/// This enum is `#[non_exhaustive]`,so that more ways to find a library can be added.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Ord, PartialOrd, Hash)]
+#[non_exhaustive]
 pub enum LibraryPath<'a> {
     /// The full path to the dynamic library.
     FullPath(&'a Path),
     /// The path to the directory that contains the dynamic library.
     Directory(&'a Path),
+    /// A list of directories,the library is loaded from the first one
+    /// that contains a compatible library.
+    SearchPaths(&'a SearchPaths),
 }
 
 //////////////////////////////////////////////////////////////////////
@@ -144,6 +315,7 @@ pub type RootModuleResult = RResult<PrefixRef<ErasedPrefix>, RootModuleError>;
 pub struct RootModuleStatics<M> {
     root_mod: LateStaticRef<M>,
     raw_lib: LateStaticRef<&'static RawLibrary>,
+    lib_header: LateStaticRef<&'static LibHeader>,
 }
 
 impl<M> RootModuleStatics<M> {
@@ -157,6 +329,7 @@ impl<M> RootModuleStatics<M> {
         Self {
             root_mod: LateStaticRef::new(),
             raw_lib: LateStaticRef::new(),
+            lib_header: LateStaticRef::new(),
         }
     }
 }
@@ -294,15 +467,27 @@ pub const ROOT_MODULE_LOADER_NAME_NULSTR: NulStr<'_> =
 pub fn __call_root_module_loader<T>(function: fn() -> T) -> RootModuleResult
 where
     T: IntoRootModuleResult,
+{
+    call_root_module_loader_with(function)
+}
+
+fn call_root_module_loader_with<F, T>(function: F) -> RootModuleResult
+where
+    F: FnOnce() -> T,
+    T: IntoRootModuleResult,
 {
     type TheResult = Result<PrefixRef<ErasedPrefix>, RootModuleError>;
-    let res = ::std::panic::catch_unwind(|| -> TheResult {
+    // The state that `function` captured is discarded if it panics.
+    let res = ::std::panic::catch_unwind(AssertUnwindSafe(move || -> TheResult {
         let ret: T::Module = function().into_root_module_result()?;
 
         let _ = <T::Module as RootModule>::load_module_with(|| Ok::<_, Infallible>(ret));
         unsafe { ret.to_prefix_ref().cast::<ErasedPrefix>().piped(Ok) }
-    });
+    }));
     // We turn an unwinding panic into an error value
-    let flattened: TheResult = res.unwrap_or(Err(RootModuleError::Unwound));
+    let flattened: TheResult = res.unwrap_or_else(|payload| {
+        let message = utils::panic_payload_message(&*payload);
+        Err(RootModuleError::Unwound(RSome(message)))
+    });
     RootModuleResult::from(flattened)
 }
@@ -0,0 +1,248 @@
+use super::*;
+
+use crate::std_types::ROption;
+
+use abi_stable_shared::mangled_allocation_tracker_name;
+
+use parking_lot::{const_rwlock, RwLock};
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Statistics about the `RVec`s,`RBox`es,and `RString`s
+/// allocated by an executable or dynamic library,
+/// which are still tracked after being passed to other dynamic libraries.
+///
+/// Allocations are only tracked with the "allocation_tracking" feature,
+/// which must be enabled in the binary that allocates them.
+///
+/// The fields are read separately,
+/// so they may be slightly inconsistent with each other while other threads allocate.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, StableAbi)]
+pub struct AllocationStats {
+    live_bytes: usize,
+    live_allocations: usize,
+    total_allocations: usize,
+}
+
+impl AllocationStats {
+    /// How many bytes are currently allocated.
+    pub const fn live_bytes(&self) -> usize {
+        self.live_bytes
+    }
+
+    /// How many allocations haven't been deallocated yet.
+    pub const fn live_allocations(&self) -> usize {
+        self.live_allocations
+    }
+
+    /// How many allocations were made,including the deallocated ones.
+    ///
+    /// Reallocating a buffer (eg: when an `RVec` grows) doesn't count as a new allocation.
+    pub const fn total_allocations(&self) -> usize {
+        self.total_allocations
+    }
+}
+
+//////////////////////////////////////////////////////////////////////
+
+#[cfg_attr(not(feature = "allocation_tracking"), allow(dead_code))]
+pub(super) struct AllocationCounter {
+    live_bytes: AtomicUsize,
+    live_allocations: AtomicUsize,
+    total_allocations: AtomicUsize,
+}
+
+#[cfg_attr(not(feature = "allocation_tracking"), allow(dead_code))]
+impl AllocationCounter {
+    pub(super) const fn new() -> Self {
+        Self {
+            live_bytes: AtomicUsize::new(0),
+            live_allocations: AtomicUsize::new(0),
+            total_allocations: AtomicUsize::new(0),
+        }
+    }
+
+    /// Records that an allocation of `old_bytes` bytes was resized to `new_bytes` bytes,
+    /// where `0` bytes means that there is no allocation.
+    pub(super) fn record_reallocation(&self, old_bytes: usize, new_bytes: usize) {
+        match (old_bytes, new_bytes) {
+            (0, 0) => return,
+            (0, _) => {
+                self.live_allocations.fetch_add(1, Ordering::Relaxed);
+                self.total_allocations.fetch_add(1, Ordering::Relaxed);
+            }
+            (_, 0) => {
+                self.live_allocations.fetch_sub(1, Ordering::Relaxed);
+            }
+            (_, _) => {}
+        }
+
+        if new_bytes > old_bytes {
+            self.live_bytes
+                .fetch_add(new_bytes - old_bytes, Ordering::Relaxed);
+        } else {
+            self.live_bytes
+                .fetch_sub(old_bytes - new_bytes, Ordering::Relaxed);
+        }
+    }
+
+    pub(super) fn stats(&self) -> AllocationStats {
+        AllocationStats {
+            live_bytes: self.live_bytes.load(Ordering::Relaxed),
+            live_allocations: self.live_allocations.load(Ordering::Relaxed),
+            total_allocations: self.total_allocations.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(feature = "allocation_tracking")]
+static LOCAL_ALLOCATIONS: AllocationCounter = AllocationCounter::new();
+
+/// Records that an allocation owned by this executable/library of `old_bytes` bytes
+/// was resized to `new_bytes` bytes,
+/// where `0` bytes means that there is no allocation.
+///
+/// This must only be called by the functions that (de)allocate with the
+/// global allocator of this executable/library.
+#[inline]
+pub(crate) fn record_reallocation(old_bytes: usize, new_bytes: usize) {
+    #[cfg(feature = "allocation_tracking")]
+    LOCAL_ALLOCATIONS.record_reallocation(old_bytes, new_bytes);
+
+    #[cfg(not(feature = "allocation_tracking"))]
+    let _ = (old_bytes, new_bytes);
+}
+
+/// Gets the statistics of the allocations made by this executable/library,
+/// returning None if the "allocation_tracking" feature is disabled.
+///
+/// # Example
+///
+/// ```rust
+/// use abi_stable::{library::local_allocation_stats, std_types::RVec};
+///
+/// let list = RVec::<u64>::with_capacity(1024);
+///
+/// if let Some(stats) = local_allocation_stats() {
+///     assert!(stats.live_bytes() >= 1024 * 8);
+///     assert_ne!(stats.live_allocations(), 0);
+/// }
+/// # drop(list);
+///
+/// ```
+pub fn local_allocation_stats() -> Option<AllocationStats> {
+    #[cfg(feature = "allocation_tracking")]
+    return Some(LOCAL_ALLOCATIONS.stats());
+
+    #[cfg(not(feature = "allocation_tracking"))]
+    return None;
+}
+
+//////////////////////////////////////////////////////////////////////
+
+/// The static that `#[export_root_module]` exports alongside the root module,
+/// which the executable uses to get the [`AllocationStats`] of the library.
+///
+/// [`AllocationStats`]: ./struct.AllocationStats.html
+#[repr(C)]
+pub struct AllocationTracker {
+    abi_header: AbiHeader,
+    stats: extern "C" fn() -> ROption<AllocationStats>,
+}
+
+impl AllocationTracker {
+    /// Constructs the `AllocationTracker` of the library.
+    ///
+    /// This is used by the [`#[export_root_module]`](../attr.export_root_module.html) attribute.
+    pub const fn new() -> Self {
+        Self {
+            abi_header: AbiHeader::VALUE,
+            stats: get_local_allocation_stats,
+        }
+    }
+
+    fn stats(&self) -> Option<AllocationStats> {
+        (self.stats)().into_option()
+    }
+}
+
+impl Default for AllocationTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+extern "C" fn get_local_allocation_stats() -> ROption<AllocationStats> {
+    local_allocation_stats().into()
+}
+
+/// The `AllocationTracker`s of the loaded libraries,
+/// keyed by the address of the `LibHeader` of each root module.
+static ALLOCATION_TRACKERS: RwLock<Vec<(usize, &'static AllocationTracker)>> =
+    const_rwlock(Vec::new());
+
+fn find_tracker(
+    trackers: &[(usize, &'static AllocationTracker)],
+    lib_header: &LibHeader,
+) -> Option<usize> {
+    let address = lib_header as *const LibHeader as usize;
+    trackers.iter().position(|(header, _)| *header == address)
+}
+
+/// Remembers the `AllocationTracker` of the root module exported with `export_name`,
+/// so that [`LibHeader::allocation_stats`] can find it.
+///
+/// # Safety
+///
+/// `raw_library` must be an abi_stable library whose `AbiHeader` was checked,
+/// and `lib_header` must be the header of the root module exported with `export_name`.
+pub(super) unsafe fn register_allocation_tracker(
+    raw_library: &RawLibrary,
+    export_name: Option<&str>,
+    lib_header: &'static LibHeader,
+) -> Result<(), LibraryError> {
+    let mut mangled = mangled_allocation_tracker_name(export_name);
+    mangled.push('\0');
+
+    let tracker = match unsafe { raw_library.get::<&'static AllocationTracker>(mangled.as_bytes()) }
+    {
+        Ok(tracker) => Some(*tracker),
+        Err(_) => None,
+    };
+
+    if let Some(tracker) = tracker {
+        if !tracker.abi_header.is_valid() {
+            return Err(LibraryError::InvalidAbiHeader(tracker.abi_header));
+        }
+    }
+
+    let mut trackers = ALLOCATION_TRACKERS.write();
+    // A previously unloaded library could have had its header at the same address.
+    if let Some(i) = find_tracker(&trackers, lib_header) {
+        trackers.swap_remove(i);
+    }
+    if let Some(tracker) = tracker {
+        trackers.push((lib_header as *const LibHeader as usize, tracker));
+    }
+    Ok(())
+}
+
+/// Forgets the `AllocationTracker` of the root module,
+/// called right before its library is unloaded.
+pub(super) fn unregister_allocation_tracker(lib_header: &LibHeader) {
+    let mut trackers = ALLOCATION_TRACKERS.write();
+    if let Some(i) = find_tracker(&trackers, lib_header) {
+        trackers.swap_remove(i);
+    }
+}
+
+/// Gets the allocation statistics of the library that `lib_header` was loaded from.
+pub(super) fn library_allocation_stats(lib_header: &LibHeader) -> Option<AllocationStats> {
+    let tracker = {
+        let trackers = ALLOCATION_TRACKERS.read();
+        let i = find_tracker(&trackers, lib_header)?;
+        trackers[i].1
+    };
+    tracker.stats()
+}
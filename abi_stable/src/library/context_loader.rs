@@ -0,0 +1,142 @@
+use super::*;
+
+use crate::{abi_stability::abi_checking::into_report_error, std_types::RBoxError};
+
+use abi_stable_shared::mangled_context_loader_name;
+
+use std::mem::ManuallyDrop;
+
+/// The type of the function that a [`ContextLoader`] wraps,
+/// which takes ownership of the type-erased host context that the pointer points to.
+pub type ContextLoaderFn = unsafe extern "C" fn(*mut ()) -> RootModuleResult;
+
+/// The static that `#[export_root_module]` exports alongside the root module
+/// when the function it's applied to takes a host context,
+/// which contains the type layout of the context,
+/// and the function that constructs the root module from it.
+///
+/// The root module is constructed from a context with
+/// [`RootModule::load_from_with_context`].
+///
+/// [`RootModule::load_from_with_context`]:
+/// ./trait.RootModule.html#method.load_from_with_context
+#[repr(C)]
+pub struct ContextLoader {
+    abi_header: AbiHeader,
+    context_layout: &'static TypeLayout,
+    loader: ContextLoaderFn,
+}
+
+impl ContextLoader {
+    /// Constructs a `ContextLoader` from the function that constructs the root module.
+    ///
+    /// This is used by the [`#[export_root_module]`](../attr.export_root_module.html) attribute.
+    ///
+    /// # Safety
+    ///
+    /// `loader` must take ownership of the `C` that its parameter points to.
+    pub const unsafe fn new<C>(loader: ContextLoaderFn) -> Self
+    where
+        C: StableAbi,
+    {
+        Self {
+            abi_header: AbiHeader::VALUE,
+            context_layout: C::LAYOUT,
+            loader,
+        }
+    }
+
+    /// Checks that the layout of the context that the root module loader takes
+    /// is compatible with `C`.
+    ///
+    /// # Errors
+    ///
+    /// This returns these errors:
+    ///
+    /// - `LibraryError::InvalidAbiHeader`:
+    /// If the abi_stable version used by the library is not compatible.
+    ///
+    /// - `LibraryError::AbiInstability`:
+    /// If the layout of the context is not compatible with the layout of `C`.
+    ///
+    pub(super) fn ensure_context_layout<C>(&self) -> Result<(), LibraryError>
+    where
+        C: StableAbi,
+    {
+        if !self.abi_header.is_valid() {
+            return Err(LibraryError::InvalidAbiHeader(self.abi_header));
+        }
+
+        // Using the layout checker of the executable for the same reasons as
+        // `LibHeader::ensure_layout`.
+        (globals::initialized_globals().layout_checking)(C::LAYOUT, self.context_layout)
+            .into_result()
+            .map_err(|e| LibraryError::AbiInstability(into_report_error(e)))?;
+
+        atomic::compiler_fence(atomic::Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Calls the root module loader,passing ownership of `context` to it.
+    ///
+    /// # Safety
+    ///
+    /// The layout of `C` must have been checked with `ensure_context_layout`.
+    pub(super) unsafe fn call<C>(
+        &self,
+        context: C,
+    ) -> Result<PrefixRef<ErasedPrefix>, RootModuleError>
+    where
+        C: StableAbi,
+    {
+        let mut context = ManuallyDrop::new(context);
+        let context = &mut *context as *mut C as *mut ();
+        unsafe { (self.loader)(context) }.into_result()
+    }
+}
+
+//////////////////////////////////////////////////////////////////////
+
+#[doc(hidden)]
+pub unsafe fn __call_root_module_loader_with_context<C, T>(
+    context: *mut (),
+    function: fn(C) -> T,
+) -> RootModuleResult
+where
+    T: IntoRootModuleResult,
+{
+    // safety: the caller passes ownership of the context,
+    // whose layout was checked before calling the `ContextLoader`.
+    let context = unsafe { (context as *mut C).read() };
+    call_root_module_loader_with(move || function(context))
+}
+
+#[doc(hidden)]
+pub fn __root_module_requires_context() -> RootModuleResult {
+    let err = RBoxError::from_fmt(
+        "This root module takes a host context,\
+         it must be loaded with `RootModule::load_from_with_context`",
+    );
+    RResult::RErr(RootModuleError::Returned(err))
+}
+
+//////////////////////////////////////////////////////////////////////
+
+/// Gets the context loader of the root module exported with `export_name` as its name,
+/// returning None if the function that exports the root module doesn't take a context.
+///
+/// # Safety
+///
+/// The ContextLoader is implicitly tied to the lifetime of the library,
+/// it will contain dangling `'static` references if the library is dropped before it does.
+pub(super) unsafe fn context_loader_from_raw_library(
+    raw_library: &RawLibrary,
+    export_name: Option<&str>,
+) -> Option<&'static ContextLoader> {
+    let mut mangled = mangled_context_loader_name(export_name);
+    mangled.push('\0');
+
+    unsafe { raw_library.get::<&'static ContextLoader>(mangled.as_bytes()) }
+        .ok()
+        .map(|loader| *loader)
+}
@@ -0,0 +1,329 @@
+use super::*;
+
+use crate::std_types::RSlice;
+
+use parking_lot::{const_mutex, Mutex};
+
+use std::fmt;
+
+/// A dependency of a root module on another root module,
+/// which must be loaded before it.
+///
+/// These are declared in [`RootModule::DEPENDENCIES`],
+/// and loaded by [`RootModule::load_with_dependencies_from`].
+///
+/// The dependencies are also stored in the [`LibHeader`] of the library that
+/// exports the root module,
+/// so that the dependencies that the library was compiled with must be loaded before it,
+/// regardless of the ones that the loading executable declares.
+///
+/// # Example
+///
+/// ```rust
+/// use abi_stable::{
+///     library::{RootModule, RootModuleDependency},
+///     for_examples::Module_Ref,
+///     sabi_types::{VersionRequirement, VersionStrings},
+///     StableAbi,
+/// };
+///
+/// #[repr(C)]
+/// #[derive(StableAbi)]
+/// #[sabi(kind(Prefix(prefix_ref = PluginMod_Ref)))]
+/// pub struct PluginMod {
+///     #[sabi(last_prefix_field)]
+///     pub value: u32,
+/// }
+///
+/// impl RootModule for PluginMod_Ref {
+///     abi_stable::declare_root_module_statics! {PluginMod_Ref}
+///     const BASE_NAME: &'static str = "plugin";
+///     const NAME: &'static str = "plugin";
+///     const VERSION_STRINGS: VersionStrings = abi_stable::package_version_strings!();
+///
+///     // Requires a 0.1.* version of the `Module_Ref` library,from 0.1.2 onwards.
+///     const DEPENDENCIES: &'static [RootModuleDependency] = &[
+///         RootModuleDependency::new::<Module_Ref>(VersionRequirement::new(">=0.1.2, <0.2")),
+///     ];
+/// }
+///
+/// ```
+///
+/// [`RootModule::DEPENDENCIES`]: ./trait.RootModule.html#associatedconstant.DEPENDENCIES
+/// [`RootModule::load_with_dependencies_from`]:
+/// ./trait.RootModule.html#method.load_with_dependencies_from
+/// [`LibHeader`]: ./struct.LibHeader.html
+#[repr(C)]
+#[derive(StableAbi, Copy, Clone)]
+pub struct RootModuleDependency {
+    name: RStr<'static>,
+    requirement: RStr<'static>,
+    // This is only called on the dependencies that the loading executable declares,
+    // never on the ones in the `LibHeader` of a library,
+    // since the types it takes and returns are only known to the executable.
+    #[sabi(unsafe_opaque_field)]
+    load: LoadDependencyFn,
+    // This is a function so that cyclic dependencies can be reported as errors,
+    // instead of causing a cycle when evaluating the `DEPENDENCIES` constants.
+    dependencies: extern "C" fn() -> RSlice<'static, RootModuleDependency>,
+}
+
+// Writes the result into the `Option`,
+// because `LibraryError` can't be returned from an `extern "C"` function.
+type LoadDependencyFn = for<'a, 'b> extern "C" fn(
+    &LibraryPath<'a>,
+    &'b mut Option<Result<VersionStrings, LibraryError>>,
+);
+
+impl RootModuleDependency {
+    /// Constructs a dependency on the `M` root module,
+    /// requiring the loaded library to have a version that satisfies `requirement`.
+    pub const fn new<M>(requirement: VersionRequirement) -> Self
+    where
+        M: RootModule,
+    {
+        Self {
+            name: RStr::from_str(M::NAME),
+            requirement: RStr::from_str(requirement.as_str()),
+            load: load_dependency::<M>,
+            dependencies: dependencies_of::<M>,
+        }
+    }
+
+    /// The `RootModule::NAME` of the dependency.
+    pub fn name(&self) -> &'static str {
+        self.name.as_str()
+    }
+
+    /// The requirement on the version of the loaded library.
+    pub fn requirement(&self) -> VersionRequirement {
+        VersionRequirement::new(self.requirement.as_str())
+    }
+
+    /// The dependencies of this dependency.
+    pub fn dependencies(&self) -> &'static [RootModuleDependency] {
+        (self.dependencies)().as_slice()
+    }
+
+    fn load(&self, where_: LibraryPath<'_>) -> Result<VersionStrings, LibraryError> {
+        let mut ret = None;
+        (self.load)(&where_, &mut ret);
+        ret.expect("BUG: the function that loads a dependency didn't write its result")
+    }
+}
+
+impl fmt::Debug for RootModuleDependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RootModuleDependency")
+            .field("name", &self.name())
+            .field("requirement", &self.requirement())
+            .finish()
+    }
+}
+
+extern "C" fn load_dependency<M>(
+    where_: &LibraryPath<'_>,
+    ret: &mut Option<Result<VersionStrings, LibraryError>>,
+) where
+    M: RootModule,
+{
+    extern_fn_panic_handling! {
+        *ret = Some(load_dependency_inner::<M>(*where_));
+    }
+}
+
+fn load_dependency_inner<M>(where_: LibraryPath<'_>) -> Result<VersionStrings, LibraryError>
+where
+    M: RootModule,
+{
+    M::load_from(where_)?;
+
+    // The root module could have been loaded without a library,
+    // with `RootModule::load_module_with`.
+    let version = M::root_module_statics()
+        .lib_header
+        .get()
+        .map_or(M::VERSION_STRINGS, LibHeader::version_strings);
+
+    register_loaded_root_module(M::NAME, version);
+
+    Ok(version)
+}
+
+extern "C" fn dependencies_of<M>() -> RSlice<'static, RootModuleDependency>
+where
+    M: RootModule,
+{
+    RSlice::from_slice(M::DEPENDENCIES)
+}
+
+//////////////////////////////////////////////////////////////////////
+
+/// Gets the (transitive) dependencies of `M` in the order that they must be loaded,
+/// with every dependency after all of its own dependencies.
+///
+/// # Errors
+///
+/// This returns a `LibraryError::DependencyCycle` if a root module
+/// (directly or indirectly) depends on itself.
+pub fn dependency_load_order<M>() -> Result<Vec<RootModuleDependency>, LibraryError>
+where
+    M: RootModule,
+{
+    Resolver::resolve::<M>().map(|resolver| resolver.order)
+}
+
+/// Loads the dependencies of `M`,and then `M`,from `where_`.
+pub(super) fn load_with_dependencies<M>(where_: LibraryPath<'_>) -> Result<M, LibraryError>
+where
+    M: RootModule,
+{
+    let resolver = Resolver::resolve::<M>()?;
+
+    for dependency in &resolver.order {
+        let mut dependents = resolver
+            .edges
+            .iter()
+            .filter(|edge| edge.dependency.name == dependency.name)
+            .peekable();
+
+        let found = dependency
+            .load(where_)
+            .map_err(|err| LibraryError::MissingDependency {
+                module_name: dependents.peek().map_or(M::NAME, |edge| edge.dependent),
+                dependency: dependency.name(),
+                err: Box::new(err),
+            })?;
+        let found_number = found.parsed()?;
+
+        for edge in dependents {
+            let required = edge.dependency.requirement();
+            if !required.matches(found_number)? {
+                return Err(LibraryError::IncompatibleDependency {
+                    module_name: edge.dependent,
+                    dependency: dependency.name(),
+                    required,
+                    found,
+                });
+            }
+        }
+    }
+
+    M::load_from(where_)
+}
+
+struct DependencyEdge {
+    dependent: &'static str,
+    dependency: RootModuleDependency,
+}
+
+struct Resolver {
+    stack: Vec<&'static str>,
+    order: Vec<RootModuleDependency>,
+    edges: Vec<DependencyEdge>,
+}
+
+impl Resolver {
+    fn resolve<M>() -> Result<Self, LibraryError>
+    where
+        M: RootModule,
+    {
+        let mut this = Self {
+            stack: Vec::new(),
+            order: Vec::new(),
+            edges: Vec::new(),
+        };
+        this.visit(M::NAME, M::DEPENDENCIES)?;
+        Ok(this)
+    }
+
+    fn visit(
+        &mut self,
+        dependent: &'static str,
+        dependencies: &'static [RootModuleDependency],
+    ) -> Result<(), LibraryError> {
+        self.stack.push(dependent);
+
+        for &dependency in dependencies {
+            self.edges.push(DependencyEdge {
+                dependent,
+                dependency,
+            });
+
+            if let Some(pos) = self.stack.iter().position(|&x| x == dependency.name()) {
+                let mut cycle = self.stack[pos..].to_vec();
+                cycle.push(dependency.name());
+                return Err(LibraryError::DependencyCycle { cycle });
+            }
+
+            if self.order.iter().any(|x| x.name == dependency.name) {
+                continue;
+            }
+
+            self.visit(dependency.name(), dependency.dependencies())?;
+            self.order.push(dependency);
+        }
+
+        self.stack.pop();
+        Ok(())
+    }
+}
+
+//////////////////////////////////////////////////////////////////////
+
+/// The names and versions of the root modules that were loaded from libraries,
+/// used to check the dependencies that libraries declare in their `LibHeader`.
+static LOADED_ROOT_MODULES: Mutex<Vec<(&'static str, VersionStrings)>> = const_mutex(Vec::new());
+
+/// Records that the `module_name` root module was loaded.
+pub(super) fn register_loaded_root_module(module_name: &'static str, version: VersionStrings) {
+    let mut loaded = LOADED_ROOT_MODULES.lock();
+    if !loaded.iter().any(|&(name, _)| name == module_name) {
+        loaded.push((module_name, version));
+    }
+}
+
+/// Checks that the dependencies declared in the `LibHeader` of the
+/// `module_name` root module were loaded,with a compatible version.
+///
+/// # Errors
+///
+/// This returns these errors:
+///
+/// - `LibraryError::DependencyNotLoaded`:
+/// If a dependency wasn't loaded.
+///
+/// - `LibraryError::IncompatibleDependency`:
+/// If the loaded version of a dependency doesn't satisfy the declared requirement.
+///
+/// - `LibraryError::ParseVersionError`/`LibraryError::ParseVersionRequirementError`:
+/// If the loaded version or the declared requirement can't be parsed.
+pub(super) fn check_declared_dependencies(
+    module_name: &'static str,
+    lib_header: &'static LibHeader,
+) -> Result<(), LibraryError> {
+    let loaded = LOADED_ROOT_MODULES.lock().clone();
+
+    for dependency in lib_header.dependencies() {
+        let found = match loaded.iter().find(|&&(name, _)| name == dependency.name()) {
+            Some(&(_, found)) => found,
+            None => {
+                return Err(LibraryError::DependencyNotLoaded {
+                    module_name,
+                    dependency: dependency.name(),
+                })
+            }
+        };
+
+        let required = dependency.requirement();
+        if !required.matches(found.parsed()?)? {
+            return Err(LibraryError::IncompatibleDependency {
+                module_name,
+                dependency: dependency.name(),
+                required,
+                found,
+            });
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,369 @@
+use super::{root_mod_trait::abi_header_from_raw_library_with, *};
+
+use std::{
+    env::consts::{DLL_PREFIX, DLL_SUFFIX},
+    fmt, fs, io,
+};
+
+/// A dynamic library found by [`discover_libraries`],
+/// with information about the abi_stable root module it exports.
+///
+/// The information is copied out of the library,
+/// which is unloaded after its headers are read
+/// (it stays loaded if it was already loaded by something else).
+pub struct LibraryCandidate {
+    path: PathBuf,
+    abi_header: Option<AbiHeader>,
+    root_module: Option<RootModuleSummary>,
+    compatibility: Compatibility,
+}
+
+struct RootModuleSummary {
+    base_name: String,
+    name: String,
+    version: String,
+}
+
+/// Whether a [`LibraryCandidate`] can be loaded by this version of abi_stable.
+#[derive(Debug)]
+pub enum Compatibility {
+    /// The library exports a root module,
+    /// and uses a compatible version of abi_stable.
+    Compatible,
+    /// The library could not be loaded,
+    /// doesn't export a root module,
+    /// or uses an incompatible version of abi_stable.
+    Incompatible(LibraryError),
+}
+
+impl Compatibility {
+    /// Whether this is `Compatibility::Compatible`.
+    pub const fn is_compatible(&self) -> bool {
+        matches!(self, Compatibility::Compatible)
+    }
+
+    /// Converts this into a `Result`,
+    /// with the error from `Compatibility::Incompatible`.
+    pub fn into_result(self) -> Result<(), LibraryError> {
+        match self {
+            Compatibility::Compatible => Ok(()),
+            Compatibility::Incompatible(e) => Err(e),
+        }
+    }
+}
+
+impl LibraryCandidate {
+    /// Opens the library at `path`,reading its headers.
+    ///
+    /// This runs the static initializers of the library,
+    /// but doesn't call any of its functions,
+    /// the library is unloaded before this returns.
+    pub fn from_path(path: &Path) -> Self {
+        Self::from_path_with(path, None, |_| Ok(()))
+    }
+
+    /// Opens the library at `path`,
+    /// reading the headers of the root module exported with `export_name` as its name,
+    /// and passing them to `check` to determine whether the library is compatible.
+    fn from_path_with<F>(path: &Path, export_name: Option<&str>, check: F) -> Self
+    where
+        F: FnOnce(&'static LibHeader) -> Result<(), LibraryError>,
+    {
+        let mut this = Self {
+            path: path.to_owned(),
+            abi_header: None,
+            root_module: None,
+            compatibility: Compatibility::Compatible,
+        };
+
+        let raw_library = match RawLibrary::load_at(path) {
+            Ok(x) => x,
+            Err(e) => {
+                this.compatibility = Compatibility::Incompatible(e);
+                return this;
+            }
+        };
+
+        // The references into the library that these return are not used
+        // after the library is unloaded at the end of this function.
+        let res = unsafe { abi_header_from_raw_library_with(&raw_library, export_name) }
+            .and_then(|abi_header| {
+                this.abi_header = Some(*abi_header);
+                abi_header.peek_lib_header()
+            })
+            .and_then(|lib_header| {
+                let consts = lib_header.root_mod_consts();
+                this.root_module = Some(RootModuleSummary {
+                    base_name: consts.base_name().to_string(),
+                    name: consts.name().to_string(),
+                    version: consts.version_strings().version.to_string(),
+                });
+                check(lib_header)
+            });
+
+        if let Err(e) = res {
+            this.compatibility = Compatibility::Incompatible(e);
+        }
+
+        drop(raw_library);
+
+        this
+    }
+
+    /// The path to the library.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The abi_stable version that the library uses,
+    /// None if the library doesn't export a root module.
+    pub const fn abi_header(&self) -> Option<AbiHeader> {
+        self.abi_header
+    }
+
+    /// The `RootModule::BASE_NAME` of the root module that the library exports,
+    /// None if the library doesn't export a root module,
+    /// or if it uses an incompatible version of abi_stable.
+    pub fn base_name(&self) -> Option<&str> {
+        self.root_module.as_ref().map(|x| &*x.base_name)
+    }
+
+    /// The `RootModule::NAME` of the root module that the library exports,
+    /// None if the library doesn't export a root module,
+    /// or if it uses an incompatible version of abi_stable.
+    pub fn name(&self) -> Option<&str> {
+        self.root_module.as_ref().map(|x| &*x.name)
+    }
+
+    /// The version of the root module that the library exports,
+    /// None if the library doesn't export a root module,
+    /// or if it uses an incompatible version of abi_stable.
+    pub fn version(&self) -> Option<&str> {
+        self.root_module.as_ref().map(|x| &*x.version)
+    }
+
+    /// Whether the library can be loaded by this version of abi_stable.
+    pub const fn compatibility(&self) -> &Compatibility {
+        &self.compatibility
+    }
+
+    /// Whether the library can be loaded by this version of abi_stable.
+    pub const fn is_compatible(&self) -> bool {
+        self.compatibility.is_compatible()
+    }
+
+    /// Opens the library at `path`,reading its headers,
+    /// and checking that it exports an `M` root module that can be loaded.
+    ///
+    /// The headers are those of the root module exported with the `M::EXPORT_NAME` name.
+    ///
+    /// This checks the name,version,and layout of the root module,
+    /// without calling the function that exports the root module,
+    /// the library is unloaded before this returns.
+    ///
+    /// Unlike loading the root module,
+    /// this doesn't register the types of the library in the global layout checking state,
+    /// so prefix types and nonexhaustive enums are only checked for compatibility
+    /// with the types in `M`.
+    ///
+    /// # Incompatibilities
+    ///
+    /// On top of the errors that make the library incompatible in
+    /// [`from_path`](#method.from_path),
+    /// these errors are stored in `Compatibility::Incompatible`:
+    ///
+    /// - `LibraryError::IncompatibleRootModuleName`:
+    /// If the name of the root module isn't `M::NAME`.
+    ///
+    /// - `LibraryError::InvalidVersionString`:
+    /// If the version strings in the library can't be parsed as version numbers.
+    ///
+    /// - `LibraryError::IncompatibleVersionNumber`:
+    /// If the version number of the library is incompatible.
+    ///
+    /// - `LibraryError::AbiInstability`:
+    /// If the layout of the root module is not the expected one.
+    ///
+    pub fn for_root_module<M>(path: &Path) -> Self
+    where
+        M: RootModule,
+    {
+        Self::from_path_with(path, M::EXPORT_NAME, check_root_module::<M>)
+    }
+}
+
+impl fmt::Debug for LibraryCandidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LibraryCandidate")
+            .field("path", &self.path)
+            .field("abi_header", &self.abi_header)
+            .field("name", &self.name())
+            .field("version", &self.version())
+            .field("compatibility", &self.compatibility)
+            .finish()
+    }
+}
+
+fn check_root_module<M>(lib_header: &LibHeader) -> Result<(), LibraryError>
+where
+    M: RootModule,
+{
+    let name = lib_header.root_mod_consts().name().as_str();
+    if name != M::NAME {
+        return Err(LibraryError::IncompatibleRootModuleName {
+            expected: M::NAME,
+            found: name.to_owned(),
+        });
+    }
+
+    // The error can't refer to the version string in the library,
+    // since the library is unloaded afterwards.
+    lib_header
+        .check_version::<M>()
+        .map_err(LibraryError::with_owned_version_string)?;
+    // Not registering the types in the global layout checking state,
+    // since the library is unloaded afterwards.
+    lib_header.ensure_layout_in_isolation::<M>()
+}
+
+/// Finds all the dynamic libraries in `directories`,
+/// reading the abi_stable headers of each one.
+///
+/// This considers every file whose name starts with the platform's `DLL_PREFIX`
+/// and ends with its `DLL_SUFFIX` (eg: `lib*.so`,`*.dll`,`lib*.dylib`) to be a library,
+/// subdirectories are not searched.
+///
+/// # Warning
+///
+/// This loads every library that is found,running their static initializers,
+/// and then unloads them.
+///
+/// # Errors
+///
+/// This returns a `LibraryError::IoError` if a directory could not be read.
+///
+/// Libraries that could not be loaded are returned as
+/// [`Compatibility::Incompatible`] candidates.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use abi_stable::library::discover_libraries;
+///
+/// let candidates = discover_libraries(&["./plugins"]).unwrap();
+///
+/// for candidate in &candidates {
+///     println!(
+///         "{}: {:?} {:?} compatible: {}",
+///         candidate.path().display(),
+///         candidate.name(),
+///         candidate.version(),
+///         candidate.is_compatible(),
+///     );
+/// }
+///
+/// ```
+pub fn discover_libraries<I>(directories: I) -> Result<Vec<LibraryCandidate>, LibraryError>
+where
+    I: IntoIterator,
+    I::Item: AsRef<Path>,
+{
+    discover_with(directories, LibraryCandidate::from_path)
+}
+
+/// Finds all the dynamic libraries in `directories`,
+/// checking whether each one exports an `M` root module that can be loaded.
+///
+/// The libraries are found and loaded the same way as in [`discover_libraries`],
+/// and are checked with [`LibraryCandidate::for_root_module`].
+///
+/// # Errors
+///
+/// This returns a `LibraryError::IoError` if a directory could not be read.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use abi_stable::{
+///     for_examples::Module_Ref,
+///     library::{discover_root_modules, PluginSet},
+/// };
+///
+/// let mut plugins = PluginSet::<Module_Ref>::new();
+///
+/// for candidate in discover_root_modules::<Module_Ref, _>(&["./plugins"]).unwrap() {
+///     if candidate.is_compatible() {
+///         plugins.load_from_file(candidate.path()).unwrap();
+///     }
+/// }
+///
+/// ```
+pub fn discover_root_modules<M, I>(directories: I) -> Result<Vec<LibraryCandidate>, LibraryError>
+where
+    M: RootModule,
+    I: IntoIterator,
+    I::Item: AsRef<Path>,
+{
+    discover_with(directories, LibraryCandidate::for_root_module::<M>)
+}
+
+fn discover_with<I, F>(directories: I, mut f: F) -> Result<Vec<LibraryCandidate>, LibraryError>
+where
+    I: IntoIterator,
+    I::Item: AsRef<Path>,
+    F: FnMut(&Path) -> LibraryCandidate,
+{
+    let mut candidates = Vec::new();
+    for directory in directories {
+        for path in library_files_in(directory.as_ref())? {
+            candidates.push(f(&path));
+        }
+    }
+    Ok(candidates)
+}
+
+/// Gets the paths of all the files in `directory` that are named like dynamic libraries,
+/// sorted by file name.
+pub(super) fn library_files_in(directory: &Path) -> Result<Vec<PathBuf>, LibraryError> {
+    let io_err = |err| LibraryError::IoError {
+        path: directory.to_owned(),
+        err,
+    };
+
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(directory).map_err(io_err)? {
+        let path = entry.map_err(io_err)?.path();
+        if !is_library_file_name(&path) {
+            continue;
+        }
+
+        // Using `fs::metadata` because it follows symlinks,
+        // so that symlinked libraries (eg: `libfoo.so -> libfoo.so.1`) are found.
+        let is_file = match fs::metadata(&path) {
+            Ok(metadata) => metadata.is_file(),
+            // A broken symlink
+            Err(e) if e.kind() == io::ErrorKind::NotFound => false,
+            Err(err) => {
+                return Err(LibraryError::IoError { path, err });
+            }
+        };
+        if is_file {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Whether the file name of `path` has the prefix and suffix of
+/// a dynamic library on this platform.
+pub(super) fn is_library_file_name(path: &Path) -> bool {
+    let file_name = match path.file_name().and_then(|x| x.to_str()) {
+        Some(x) => x,
+        None => return false,
+    };
+
+    file_name.len() > DLL_PREFIX.len() + DLL_SUFFIX.len()
+        && file_name.starts_with(DLL_PREFIX)
+        && file_name.ends_with(DLL_SUFFIX)
+}
@@ -1,15 +1,27 @@
 #![allow(clippy::missing_const_for_fn)]
 
-use super::{lib_header::AbiHeader, root_mod_trait::RootModule};
+use super::{
+    lib_header::AbiHeader, load_options::LoadOptions, reloadable::LibraryHandle,
+    root_mod_trait::RootModule,
+};
 
 use crate::{
-    sabi_types::{ParseVersionError, VersionNumber, VersionStrings},
-    std_types::{RBoxError, RResult, RVec},
+    abi_stability::AbiIncompatibilityReport,
+    sabi_types::{
+        ParseVersionError, ParseVersionRequirementError, VersionNumber, VersionRequirement,
+        VersionStrings,
+    },
+    std_types::{RBoxError, RNone, ROption, RResult, RSome, RString, RVec},
+    utils::FromPanicMessage,
 };
 
 use std::{
     fmt::{self, Display},
+    io,
+    num::ParseIntError,
     path::PathBuf,
+    process::ExitStatus,
+    time::Duration,
 };
 
 #[allow(unused_imports)]
@@ -23,6 +35,8 @@ pub enum LibraryError {
     OpenError {
         /// The path to the library
         path: PathBuf,
+        /// The options that the library was loaded with.
+        options: LoadOptions,
         /// The cause of the error
         err: Box<libloading::Error>,
     },
@@ -37,6 +51,19 @@ pub enum LibraryError {
     },
     /// The version string could not be parsed into a version number.
     ParseVersionError(ParseVersionError),
+    /// The version string of a library could not be parsed into a version number.
+    ///
+    /// This is returned instead of `ParseVersionError` when the library is
+    /// unloaded before the error is returned,
+    /// since `ParseVersionError` refers to the version string in the library.
+    InvalidVersionString {
+        /// The version string of the library.
+        version: String,
+        /// The field of the version string that could not be parsed.
+        field: &'static str,
+        /// The cause of the error
+        err: ParseIntError,
+    },
     /// The version numbers of the library was incompatible.
     IncompatibleVersionNumber {
         ///
@@ -46,6 +73,25 @@ pub enum LibraryError {
         ///
         actual_version: VersionNumber,
     },
+    /// A version requirement could not be parsed.
+    ParseVersionRequirementError(ParseVersionRequirementError),
+    /// The version number of the library doesn't satisfy
+    /// the `RootModule::VERSION_REQUIREMENT` of the root module.
+    UnmetVersionRequirement {
+        ///
+        library_name: &'static str,
+        /// The requirement that the library version doesn't satisfy.
+        requirement: VersionRequirement,
+        ///
+        actual_version: VersionNumber,
+    },
+    /// The name of the root module in the library is not the expected one.
+    IncompatibleRootModuleName {
+        /// The name of the root module that was expected.
+        expected: &'static str,
+        /// The name of the root module in the library.
+        found: String,
+    },
     /// Error returned by the root module
     RootModule {
         /// The error returned by the `#[export_root_module]` function.
@@ -58,6 +104,9 @@ pub enum LibraryError {
     /// The abi is incompatible.
     /// The error is opaque,since the error always comes from the main binary
     /// (dynamic libraries can be loaded from other dynamic libraries).
+    ///
+    /// The structured report of the incompatibilities can be accessed with
+    /// [`abi_incompatibility_report`](#method.abi_incompatibility_report).
     AbiInstability(RBoxError),
     /// The type used to check that this is a compatible abi_stable
     /// is not the same.
@@ -69,24 +118,195 @@ pub enum LibraryError {
         ///
         found: RBoxError,
     },
+    /// When an io operation on the file of a library failed.
+    IoError {
+        /// The path to the library
+        path: PathBuf,
+        /// The cause of the error
+        err: io::Error,
+    },
+    /// An error that happened while loading the library at `path`.
+    AtPath {
+        /// The path to the library
+        path: PathBuf,
+        /// The error
+        err: Box<LibraryError>,
+    },
+    /// An error from checking a library loaded by
+    /// [`RootModule::load_reloadable_from`],
+    /// which keeps the library loaded while this error exists,
+    /// since `err` can refer to data in the library.
+    ///
+    /// [`RootModule::load_reloadable_from`]:
+    /// ./trait.RootModule.html#method.load_reloadable_from
+    ReloadableLibrary {
+        /// A handle to the library that `err` came from.
+        library: LibraryHandle,
+        /// The error
+        err: Box<LibraryError>,
+    },
+    /// When a library could not be loaded from any of the
+    /// directories in a `SearchPaths`.
+    SearchPathsExhausted {
+        /// The base name of the library
+        base_name: &'static str,
+        /// A `LibraryError::AtPath` for every path that was tried,in order.
+        tried: RVec<Self>,
+    },
+    /// When a dependency of a root module could not be loaded.
+    MissingDependency {
+        /// The name of the root module that declared the dependency.
+        module_name: &'static str,
+        /// The name of the root module that could not be loaded.
+        dependency: &'static str,
+        /// The error from loading the dependency.
+        err: Box<LibraryError>,
+    },
+    /// When a dependency that a library declares in its `LibHeader` was not loaded
+    /// before the root module of the library.
+    DependencyNotLoaded {
+        /// The name of the root module that declared the dependency.
+        module_name: &'static str,
+        /// The name of the root module that was not loaded.
+        dependency: &'static str,
+    },
+    /// When the loaded version of a dependency doesn't satisfy
+    /// the version required by a root module.
+    IncompatibleDependency {
+        /// The name of the root module that declared the dependency.
+        module_name: &'static str,
+        /// The name of the dependency.
+        dependency: &'static str,
+        /// The version requirement on the dependency that `module_name` declared.
+        required: VersionRequirement,
+        /// The version of the dependency that was loaded.
+        found: VersionStrings,
+    },
+    /// When a root module depends on itself,directly or indirectly.
+    DependencyCycle {
+        /// The names of the root modules in the cycle,
+        /// where the first and last names are the same.
+        cycle: Vec<&'static str>,
+    },
+    /// When the shutdown hook of a root module returned an error or panicked.
+    RootModuleShutdown {
+        /// The error returned by the shutdown hook.
+        err: RootModuleError,
+        ///
+        module_name: &'static str,
+        ///
+        version: VersionStrings,
+    },
+    /// When a root module is loaded with a host context,
+    /// but the function that exports it doesn't take one.
+    ContextNotAccepted {
+        ///
+        module_name: &'static str,
+    },
+    /// When the name of an exported item or root module is not one that
+    /// `#[export_item]` or `#[export_root_module]` accept.
+    InvalidExportName {
+        /// The name of the item or root module.
+        name: String,
+    },
+    /// When a `VersionNegotiator` could not load any of the versions of an interface
+    /// that the library exports.
+    NoCompatibleInterfaceVersion {
+        /// The name of the interface.
+        interface: &'static str,
+        /// The versions of the interface that the library exports,from the highest.
+        offered: RVec<u32>,
+        /// The errors from loading the versions that the executable supports,
+        /// from the highest version.
+        errors: RVec<Self>,
+    },
+    /// When the child process that an `OutOfProcessModule` loaded the root module in
+    /// reported an error,or could not be communicated with.
+    OutOfProcess {
+        ///
+        module_name: &'static str,
+        /// The path to the library
+        path: PathBuf,
+        /// The error that the child process reported.
+        message: String,
+    },
+    /// When the child process that an `OutOfProcessModule` loaded the root module in
+    /// exited before returning,eg: because the library crashed.
+    ChildProcessExited {
+        ///
+        module_name: &'static str,
+        /// The path to the library
+        path: PathBuf,
+        /// How the child process exited.
+        status: ExitStatus,
+    },
+    /// When the child process that an `OutOfProcessModule` loaded the root module in
+    /// didn't respond before the timeout,which kills it.
+    ChildProcessTimedOut {
+        ///
+        module_name: &'static str,
+        /// The path to the library
+        path: PathBuf,
+        /// How long the executable waited for the child process.
+        timeout: Duration,
+    },
     /// There could have been 0 or more errors in the function.
     Many(RVec<Self>),
 }
 
+impl LibraryError {
+    /// Replaces a `LibraryError::ParseVersionError` with a
+    /// `LibraryError::InvalidVersionString` that owns a copy of the version string,
+    /// so that the error can outlive the library that the version string is in.
+    pub(super) fn with_owned_version_string(self) -> Self {
+        match self {
+            LibraryError::ParseVersionError(e) => LibraryError::InvalidVersionString {
+                version: e.version_strings().version.to_string(),
+                field: e.which_field(),
+                err: e.parse_error().clone(),
+            },
+            e => e,
+        }
+    }
+
+    /// Gets the report of the layout incompatibilities that caused this error,
+    /// looking through `LibraryError::AtPath` and `LibraryError::ReloadableLibrary`.
+    ///
+    /// This returns None if this isn't a `LibraryError::AbiInstability`,
+    /// or if the layout was checked by a layout checker that doesn't create
+    /// an `AbiIncompatibilityReport`
+    /// (the executable's layout checker is used to check libraries loaded by libraries).
+    pub fn abi_incompatibility_report(&self) -> Option<&AbiIncompatibilityReport> {
+        match self {
+            LibraryError::AbiInstability(e) => e.downcast_ref::<AbiIncompatibilityReport>(),
+            LibraryError::AtPath { err, .. } => err.abi_incompatibility_report(),
+            LibraryError::ReloadableLibrary { err, .. } => err.abi_incompatibility_report(),
+            _ => None,
+        }
+    }
+}
+
 impl From<ParseVersionError> for LibraryError {
     fn from(v: ParseVersionError) -> LibraryError {
         LibraryError::ParseVersionError(v)
     }
 }
 
+impl From<ParseVersionRequirementError> for LibraryError {
+    fn from(v: ParseVersionRequirementError) -> LibraryError {
+        LibraryError::ParseVersionRequirementError(v)
+    }
+}
+
 impl Display for LibraryError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str("\n")?;
         match self {
-            LibraryError::OpenError { path, err } => writeln!(
+            LibraryError::OpenError { path, options, err } => writeln!(
                 f,
-                "Could not open library at:\n\t{}\nbecause:\n\t{}",
+                "Could not open library at:\n\t{}\nwith load options:\n\t{}\nbecause:\n\t{}",
                 path.display(),
+                options,
                 err
             ),
             LibraryError::GetSymbolError {
@@ -101,6 +321,15 @@ impl Display for LibraryError {
                 err
             ),
             LibraryError::ParseVersionError(x) => fmt::Display::fmt(x, f),
+            LibraryError::InvalidVersionString {
+                version,
+                field,
+                err,
+            } => writeln!(
+                f,
+                "\nInvalid version string:'{}'\nerror at the {} field:{}",
+                version, field, err,
+            ),
             LibraryError::IncompatibleVersionNumber {
                 library_name,
                 expected_version,
@@ -110,6 +339,21 @@ impl Display for LibraryError {
                 "\n'{}' library version mismatch:\nuser:{}\nlibrary:{}",
                 library_name, expected_version, actual_version,
             ),
+            LibraryError::ParseVersionRequirementError(x) => fmt::Display::fmt(x, f),
+            LibraryError::UnmetVersionRequirement {
+                library_name,
+                requirement,
+                actual_version,
+            } => writeln!(
+                f,
+                "\n'{}' library version mismatch:\nrequirement:{}\nlibrary:{}",
+                library_name, requirement, actual_version,
+            ),
+            LibraryError::IncompatibleRootModuleName { expected, found } => writeln!(
+                f,
+                "\nroot module name mismatch:\nexpected:{}\nfound:{}",
+                expected, found,
+            ),
             LibraryError::RootModule {
                 err,
                 module_name,
@@ -144,6 +388,153 @@ impl Display for LibraryError {
                     expected=expected,
                 }
             }
+            LibraryError::IoError { path, err } => writeln!(
+                f,
+                "Could not access the library file at:\n\t{}\nbecause:\n\t{}",
+                path.display(),
+                err
+            ),
+            LibraryError::AtPath { path, err } => {
+                writeln!(f, "While loading the library at:\n\t{}", path.display())?;
+                Display::fmt(err, f)
+            }
+            LibraryError::ReloadableLibrary { err, .. } => Display::fmt(err, f),
+            LibraryError::SearchPathsExhausted { base_name, tried } => {
+                writeln!(
+                    f,
+                    "Could not load the '{}' library from any of the search paths.",
+                    base_name
+                )?;
+                if tried.is_empty() {
+                    f.write_str("No directories were searched.\n")?;
+                }
+                for e in tried {
+                    Display::fmt(e, f)?;
+                }
+                Ok(())
+            }
+            LibraryError::MissingDependency {
+                module_name,
+                dependency,
+                err,
+            } => {
+                writeln!(
+                    f,
+                    "'{}' depends on '{}',which could not be loaded because:",
+                    module_name, dependency,
+                )?;
+                Display::fmt(err, f)
+            }
+            LibraryError::DependencyNotLoaded {
+                module_name,
+                dependency,
+            } => writeln!(
+                f,
+                "'{}' depends on '{}',which must be loaded before it.",
+                module_name, dependency,
+            ),
+            LibraryError::IncompatibleDependency {
+                module_name,
+                dependency,
+                required,
+                found,
+            } => writeln!(
+                f,
+                "\n'{}' dependency version mismatch in '{}':\nrequired:{}\nloaded:{}",
+                dependency, module_name, required, found,
+            ),
+            LibraryError::DependencyCycle { cycle } => writeln!(
+                f,
+                "These root modules depend on each other:\n\t{}",
+                cycle.join(" -> "),
+            ),
+            LibraryError::RootModuleShutdown {
+                err,
+                module_name,
+                version,
+            } => {
+                writeln!(
+                    f,
+                    "The shutdown hook of the '{}' root module (version {}) failed:",
+                    module_name, version,
+                )?;
+                match err {
+                    RootModuleError::Returned(e) => writeln!(f, "{}", e),
+                    RootModuleError::Unwound(RSome(message)) => {
+                        writeln!(f, "the shutdown hook panicked with:\n{}", message)
+                    }
+                    RootModuleError::Unwound(RNone) => writeln!(f, "the shutdown hook panicked"),
+                }
+            }
+            LibraryError::ContextNotAccepted { module_name } => writeln!(
+                f,
+                "The '{}' root module was loaded with a host context,\n\
+                 but the function that exports it doesn't take one.",
+                module_name,
+            ),
+            LibraryError::InvalidExportName { name } => writeln!(
+                f,
+                "{:?} is not a valid export name,\n\
+                 it must only contain ascii alphanumeric characters,'_','-',and '.'",
+                name,
+            ),
+            LibraryError::NoCompatibleInterfaceVersion {
+                interface,
+                offered,
+                errors,
+            } => {
+                let offered = offered.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+                writeln!(
+                    f,
+                    "Could not load any version of the '{}' interface,\n\
+                     the library exports these versions:\n\t{}",
+                    interface,
+                    offered.join(", "),
+                )?;
+                if errors.is_empty() {
+                    f.write_str("none of which are supported by this executable.\n")?;
+                }
+                for e in errors {
+                    Display::fmt(e, f)?;
+                }
+                Ok(())
+            }
+            LibraryError::OutOfProcess {
+                module_name,
+                path,
+                message,
+            } => writeln!(
+                f,
+                "The child process that loaded the '{}' root module from:\n\t{}\n\
+                 failed with:\n{}",
+                module_name,
+                path.display(),
+                message,
+            ),
+            LibraryError::ChildProcessExited {
+                module_name,
+                path,
+                status,
+            } => writeln!(
+                f,
+                "The child process that loaded the '{}' root module from:\n\t{}\n\
+                 exited with {}",
+                module_name,
+                path.display(),
+                status,
+            ),
+            LibraryError::ChildProcessTimedOut {
+                module_name,
+                path,
+                timeout,
+            } => writeln!(
+                f,
+                "The child process that loaded the '{}' root module from:\n\t{}\n\
+                 didn't respond in {:?},so it was killed",
+                module_name,
+                path.display(),
+                timeout,
+            ),
             LibraryError::Many(list) => {
                 for e in list {
                     Display::fmt(e, f)?;
@@ -166,8 +557,12 @@ impl ::std::error::Error for LibraryError {}
 pub enum RootModuleError {
     /// When the root loader function returned an error normally
     Returned(RBoxError),
-    /// When the root loader function panicked
-    Unwound,
+    /// When the root loader function panicked,
+    /// with the message of the panic.
+    ///
+    /// The message is `RNone` if the error was constructed without one,
+    /// abi_stable always includes the message of panics that it catches.
+    Unwound(ROption<RString>),
 }
 
 impl RootModuleError {
@@ -178,7 +573,10 @@ impl RootModuleError {
             Self::Returned(e) => {
                 *e = e.to_formatted_error();
             }
-            Self::Unwound => {}
+            Self::Unwound(RSome(message)) => {
+                *message = message.as_str().into();
+            }
+            Self::Unwound(RNone) => {}
         }
     }
 
@@ -198,7 +596,10 @@ impl Display for RootModuleError {
         f.write_str("\n")?;
         match self {
             Self::Returned(e) => Display::fmt(e, f)?,
-            Self::Unwound => f.write_str("the root module loader panicked")?,
+            Self::Unwound(RSome(message)) => {
+                write!(f, "the root module loader panicked with:\n{}", message)?
+            }
+            Self::Unwound(RNone) => f.write_str("the root module loader panicked")?,
         }
         f.write_str("\n")?;
         Ok(())
@@ -207,6 +608,12 @@ impl Display for RootModuleError {
 
 impl ::std::error::Error for RootModuleError {}
 
+impl FromPanicMessage for RootModuleError {
+    fn from_panic_message(message: RString) -> Self {
+        Self::Unwound(RSome(message))
+    }
+}
+
 //////////////////////////////////////////////////////////////////////
 
 /// For converting the return value of a `#[export_root_module]` function
@@ -288,7 +695,7 @@ mod tests {
 
             let downcasted = match err.err().unwrap() {
                 RootModuleError::Returned(x) => x.downcast::<FmtError>().unwrap(),
-                RootModuleError::Unwound => unreachable!(),
+                RootModuleError::Unwound(_) => unreachable!(),
             };
             assert_eq!(downcasted, RBox::new(FmtError));
         }
@@ -309,4 +716,35 @@ mod tests {
             test_case(ok.into_root_module_result(), err.into_root_module_result());
         }
     }
+
+    #[test]
+    fn with_owned_version_string_test() {
+        let parse_err = VersionStrings::new("1.x.0").parsed().unwrap_err();
+        let err = LibraryError::ParseVersionError(parse_err.clone()).with_owned_version_string();
+
+        match &err {
+            LibraryError::InvalidVersionString {
+                version,
+                field,
+                err,
+            } => {
+                assert_eq!(version, "1.x.0");
+                assert_eq!(*field, "minor");
+                assert_eq!(err, parse_err.parse_error());
+            }
+            _ => panic!("expected an InvalidVersionString, found: {:?}", err),
+        }
+        assert_eq!(
+            err.to_string(),
+            LibraryError::ParseVersionError(parse_err).to_string()
+        );
+
+        let err = LibraryError::InvalidExportName {
+            name: "foo".to_string(),
+        };
+        assert!(matches!(
+            err.with_owned_version_string(),
+            LibraryError::InvalidExportName { .. }
+        ));
+    }
 }
@@ -0,0 +1,79 @@
+use super::*;
+
+use crate::{
+    abi_stability::abi_checking::{
+        check_layout_compatibility_with_globals, AbiIncompatibilityReport, CheckingGlobals,
+    },
+    std_types::RBoxError,
+};
+
+/// The static that the [`#[export_item]`](../attr.export_item.html) attribute
+/// exports for an item,
+/// which contains the type layout of the item,
+/// so that it can be checked when it's accessed with [`RawLibrary::get_checked`].
+///
+/// [`RawLibrary::get_checked`]: ./struct.RawLibrary.html#method.get_checked
+#[repr(C)]
+pub struct ExportedItem {
+    abi_header: AbiHeader,
+    layout: &'static TypeLayout,
+    item: *const (),
+}
+
+// safety: this is a `&'static T` where `T: Sync`,with the type erased.
+unsafe impl Sync for ExportedItem {}
+
+impl ExportedItem {
+    /// Constructs an `ExportedItem` for the `item` static.
+    ///
+    /// This is used by the [`#[export_item]`](../attr.export_item.html) attribute.
+    pub const fn new<T>(item: &'static T) -> Self
+    where
+        T: StableAbi + Sync,
+    {
+        Self {
+            abi_header: AbiHeader::VALUE,
+            layout: T::LAYOUT,
+            item: item as *const T as *const (),
+        }
+    }
+
+    /// Gets the item,
+    /// checking that the abi_stable version and type layout of the item are compatible.
+    ///
+    /// # Errors
+    ///
+    /// This returns these errors:
+    ///
+    /// - `LibraryError::InvalidAbiHeader`:
+    /// If the abi_stable version used by the library is not compatible.
+    ///
+    /// - `LibraryError::AbiInstability`:
+    /// If the layout of the item is not compatible with the layout of `T`.
+    ///
+    pub(super) fn get<T>(&self) -> Result<&T, LibraryError>
+    where
+        T: StableAbi,
+    {
+        if !self.abi_header.is_valid() {
+            return Err(LibraryError::InvalidAbiHeader(self.abi_header));
+        }
+
+        // Not using the global layout checker,
+        // since `RawLibrary`s can be unloaded,
+        // and the global state keeps references to the type layouts that it checked.
+        let checking_globals = CheckingGlobals::new();
+        check_layout_compatibility_with_globals(T::LAYOUT, self.layout, &checking_globals)
+            .map_err(|e| {
+                // The error must not contain references into the library,
+                // since it can outlive it.
+                let report = AbiIncompatibilityReport::new(&e);
+                LibraryError::AbiInstability(RBoxError::new(report))
+            })?;
+
+        atomic::compiler_fence(atomic::Ordering::SeqCst);
+
+        // safety: the layout of the item was checked to be compatible with `T` above.
+        unsafe { Ok(&*(self.item as *const T)) }
+    }
+}
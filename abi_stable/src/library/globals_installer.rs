@@ -0,0 +1,79 @@
+use super::*;
+
+use abi_stable_shared::mangled_globals_installer_name;
+
+/// The static that `#[export_root_module]` exports alongside the root module,
+/// which the executable uses to pass its `Globals` to the library.
+///
+/// Older versions of abi_stable only pass `Globals` with the fields up to
+/// `layout_checking` through the [`LibHeader`],
+/// so libraries only read the fields after it
+/// (the panic hook forwarder and the logger)
+/// when they're passed `Globals` through this.
+///
+/// Once installed,the library forwards its panics to the hook set with
+/// [`set_plugin_panic_hook`],
+/// and if the "log" feature is enabled,
+/// forwards its `log` records to the logger of the executable.
+///
+/// [`LibHeader`]: ./struct.LibHeader.html
+/// [`set_plugin_panic_hook`]: ./fn.set_plugin_panic_hook.html
+#[repr(C)]
+pub struct GlobalsInstaller {
+    abi_header: AbiHeader,
+    install: extern "C" fn(&'static Globals),
+}
+
+impl GlobalsInstaller {
+    /// Constructs the `GlobalsInstaller` of the library.
+    ///
+    /// This is used by the [`#[export_root_module]`](../attr.export_root_module.html) attribute.
+    pub const fn new() -> Self {
+        Self {
+            abi_header: AbiHeader::VALUE,
+            install: install_globals,
+        }
+    }
+}
+
+impl Default for GlobalsInstaller {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+extern "C" fn install_globals(globals: &'static Globals) {
+    extern_fn_panic_handling! {
+        panic_hook::install_forwarding_hook(globals);
+
+        #[cfg(feature = "log")]
+        crate::external_types::log::install_forwarding_logger(&globals.logger);
+    }
+}
+
+/// Passes the `Globals` of this executable to the library,
+/// if it was built with a version of abi_stable that can receive all of them.
+///
+/// # Safety
+///
+/// `raw_library` must be an abi_stable library whose `AbiHeader` was checked.
+pub(super) unsafe fn install_globals_in_library(
+    raw_library: &RawLibrary,
+    export_name: Option<&str>,
+) -> Result<(), LibraryError> {
+    let mut mangled = mangled_globals_installer_name(export_name);
+    mangled.push('\0');
+
+    let installer =
+        match unsafe { raw_library.get::<&'static GlobalsInstaller>(mangled.as_bytes()) } {
+            Ok(installer) => *installer,
+            Err(_) => return Ok(()),
+        };
+
+    if !installer.abi_header.is_valid() {
+        return Err(LibraryError::InvalidAbiHeader(installer.abi_header));
+    }
+
+    (installer.install)(globals::initialized_globals());
+    Ok(())
+}
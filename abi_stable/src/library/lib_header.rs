@@ -1,8 +1,15 @@
 use super::*;
 
 use crate::{
+    abi_stability::abi_checking::{
+        check_fingerprinted_layout_compatibility, check_layout_compatibility_with_globals,
+        into_report_error, is_global_layout_checker, AbiIncompatibilityReport, CheckingGlobals,
+    },
     prefix_type::{PrefixRef, PrefixRefTrait},
     sabi_types::RRef,
+    std_types::{RBoxError, RNone, ROption, RSlice},
+    type_layout::{FingerprintedLayout, LayoutFingerprint},
+    utils::leak_value,
 };
 
 /// Used to check the layout of modules returned by module-loading functions
@@ -19,6 +26,12 @@ pub struct LibHeader {
     init_globals_with: InitGlobalsWith,
     module: LateStaticRef<PrefixRef<ErasedPrefix>>,
     constructor: extern "C" fn() -> RootModuleResult,
+    // New fields go at the end,
+    // so that the fields above can be read from libraries using other abi_stable versions.
+    dependencies: RSlice<'static, RootModuleDependency>,
+    shutdown_hook: ROption<extern "C" fn() -> RResult<(), RootModuleError>>,
+    interface_versions: ROption<InterfaceVersions>,
+    layout_fingerprint: LateStaticRef<&'static FingerprintedLayout>,
 }
 
 impl LibHeader {
@@ -44,6 +57,33 @@ impl LibHeader {
             init_globals_with: INIT_GLOBALS_WITH,
             module: LateStaticRef::new(),
             constructor,
+            dependencies: RSlice::from_slice(M::DEPENDENCIES),
+            shutdown_hook: RNone,
+            interface_versions: RNone,
+            layout_fingerprint: LateStaticRef::new(),
+        }
+    }
+
+    /// Sets the function that's called when the root module is shut down.
+    ///
+    /// This is used by `#[export_root_module(shutdown = "...")]`.
+    pub const fn with_shutdown_hook(self, hook: ShutdownFn) -> Self {
+        Self {
+            shutdown_hook: RSome(hook),
+            ..self
+        }
+    }
+
+    /// Sets the versions of an interface that the library exports,
+    /// read by [`VersionNegotiator`] to pick the version to load.
+    ///
+    /// This is used by `#[export_root_module(interface_versions = "...")]`.
+    ///
+    /// [`VersionNegotiator`]: ./struct.VersionNegotiator.html
+    pub const fn with_interface_versions(self, versions: InterfaceVersions) -> Self {
+        Self {
+            interface_versions: RSome(versions),
+            ..self
         }
     }
 
@@ -61,9 +101,18 @@ impl LibHeader {
                 LateStaticRef::from_prefixref(erased)
             },
             constructor: GetAbortingConstructor::aborting_constructor,
+            dependencies: RSlice::from_slice(M::DEPENDENCIES),
+            shutdown_hook: RNone,
+            interface_versions: RNone,
+            layout_fingerprint: LateStaticRef::new(),
         }
     }
 
+    /// The header describing the abi_stable version that the library uses.
+    pub const fn abi_header(&self) -> AbiHeader {
+        self.header
+    }
+
     /// All the important constants of a `RootModule` for some erased type.
     pub const fn root_mod_consts(&self) -> &RootModuleConsts {
         &self.root_mod_consts
@@ -74,6 +123,26 @@ impl LibHeader {
         self.root_mod_consts.version_strings()
     }
 
+    /// The function that's called when the root module is shut down.
+    pub(super) fn shutdown_hook(&self) -> Option<ShutdownFn> {
+        self.shutdown_hook.into_option()
+    }
+
+    /// The root modules that the root module depends on,
+    /// declared in `RootModule::DEPENDENCIES` when the library was compiled.
+    ///
+    /// These must be loaded before the root module,
+    /// otherwise loading the root module returns a `LibraryError::DependencyNotLoaded`.
+    pub fn dependencies(&self) -> &'static [RootModuleDependency] {
+        self.dependencies.as_slice()
+    }
+
+    /// The versions of an interface that the library exports,
+    /// if they were declared in this header.
+    pub fn interface_versions(&self) -> Option<InterfaceVersions> {
+        self.interface_versions.into_option()
+    }
+
     /// Gets the layout of the root module.
     ///
     /// This returns a None if the root module layout is not included
@@ -83,18 +152,72 @@ impl LibHeader {
         self.root_mod_consts.layout().into_option()
     }
 
+    /// Gets the [`LayoutFingerprint`] of the root module,
+    /// computing it the first time that this is called or that the library is loaded.
+    ///
+    /// This returns a None if the root module layout is not included,
+    /// like [`layout`](#method.layout) does.
+    ///
+    /// [`LayoutFingerprint`]: ../type_layout/struct.LayoutFingerprint.html
+    pub fn layout_fingerprint(&self) -> Option<LayoutFingerprint> {
+        let layout = self.layout()?;
+        Some(self.fingerprinted_layout(layout).fingerprint)
+    }
+
+    fn fingerprinted_layout(&self, layout: &'static TypeLayout) -> &'static FingerprintedLayout {
+        self.layout_fingerprint
+            .init(|| leak_value(FingerprintedLayout::new(layout)))
+    }
+
+    /// Gets the statistics of the allocations made by the library
+    /// that this header was loaded from.
+    ///
+    /// This returns None if the library was not loaded by this executable,
+    /// or if the library was built without the "allocation_tracking" feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use abi_stable::library::lib_header_from_path;
+    ///
+    /// let header = lib_header_from_path("./plugins/libplugin.so".as_ref()).unwrap();
+    ///
+    /// if let Some(stats) = header.allocation_stats() {
+    ///     println!(
+    ///         "{} bytes in {} allocations",
+    ///         stats.live_bytes(),
+    ///         stats.live_allocations(),
+    ///     );
+    /// }
+    ///
+    /// ```
+    pub fn allocation_stats(&self) -> Option<AllocationStats> {
+        allocation_tracking::library_allocation_stats(self)
+    }
+
     pub(super) fn initialize_library_globals(&self, globals: &'static Globals) {
         (self.init_globals_with.0)(globals);
     }
 
-    fn check_version<M>(&self) -> Result<(), LibraryError>
+    pub(super) fn check_version<M>(&self) -> Result<(), LibraryError>
     where
         M: RootModule,
     {
-        let expected_version = M::VERSION_STRINGS.piped(VersionNumber::new)?;
-
         let actual_version = self.version_strings().piped(VersionNumber::new)?;
 
+        if let Some(requirement) = M::VERSION_REQUIREMENT {
+            if !requirement.matches(actual_version)? {
+                return Err(LibraryError::UnmetVersionRequirement {
+                    library_name: M::NAME,
+                    requirement,
+                    actual_version,
+                });
+            }
+            return Ok(());
+        }
+
+        let expected_version = M::VERSION_STRINGS.piped(VersionNumber::new)?;
+
         if expected_version.major != actual_version.major
             || (expected_version.major == 0) && expected_version.minor > actual_version.minor
         {
@@ -135,6 +258,12 @@ impl LibHeader {
     /// - `LibraryError::IncompatibleVersionNumber`:
     /// If the version number of the library is incompatible.
     ///
+    /// - `LibraryError::ParseVersionRequirementError`:
+    /// If `M::VERSION_REQUIREMENT` is not a valid version requirement.
+    ///
+    /// - `LibraryError::UnmetVersionRequirement`:
+    /// If the version number of the library doesn't satisfy `M::VERSION_REQUIREMENT`.
+    ///
     /// - `LibraryError::AbiInstability`:
     /// If the layout of the root module is not the expected one.
     ///
@@ -180,6 +309,12 @@ impl LibHeader {
     /// - `LibraryError::IncompatibleVersionNumber`:
     /// If the version number of the library is incompatible.
     ///
+    /// - `LibraryError::ParseVersionRequirementError`:
+    /// If `M::VERSION_REQUIREMENT` is not a valid version requirement.
+    ///
+    /// - `LibraryError::UnmetVersionRequirement`:
+    /// If the version number of the library doesn't satisfy `M::VERSION_REQUIREMENT`.
+    ///
     /// - `LibraryError::RootModule` :
     /// If the root module initializer returned an error or panicked.
     ///
@@ -210,6 +345,21 @@ impl LibHeader {
         M: RootModule,
     {
         if let IsLayoutChecked::Yes(root_mod_layout) = self.root_mod_consts.layout() {
+            // When the layout checker of the executable is the one in this binary,
+            // the layouts are only compared if their fingerprints are different.
+            if is_global_layout_checker() {
+                let fingerprinted = self.fingerprinted_layout(root_mod_layout);
+                check_fingerprinted_layout_compatibility(<M>::LAYOUT, fingerprinted).map_err(
+                    |e| {
+                        let report = AbiIncompatibilityReport::new(&e);
+                        LibraryError::AbiInstability(RBoxError::new(report))
+                    },
+                )?;
+
+                atomic::compiler_fence(atomic::Ordering::SeqCst);
+                return Ok(());
+            }
+
             // Using this instead of
             // crate::abi_stability::abi_checking::check_layout_compatibility
             // so that if this is called in a dynamic-library that loads
@@ -227,8 +377,7 @@ impl LibHeader {
                     //
                     // This isn't strictly required anymore because abi_stable doesn't
                     // unload libraries right now.
-                    let formatted = e.to_formatted_error();
-                    LibraryError::AbiInstability(formatted)
+                    LibraryError::AbiInstability(into_report_error(e))
                 })?;
         }
 
@@ -237,6 +386,38 @@ impl LibHeader {
         Ok(())
     }
 
+    /// Checks that the layout of the `M` root module from the dynamic library is
+    /// compatible with the expected layout,
+    /// without registering the library's types in the global layout checking state.
+    ///
+    /// This is what [`ensure_layout`](#method.ensure_layout) does for libraries
+    /// that can be unloaded,
+    /// since the global state keeps references to the type layouts
+    /// of the libraries that it checked.
+    pub(super) fn ensure_layout_in_isolation<M>(&self) -> Result<(), LibraryError>
+    where
+        M: RootModule,
+    {
+        if let IsLayoutChecked::Yes(root_mod_layout) = self.root_mod_consts.layout() {
+            let checking_globals = CheckingGlobals::new();
+            check_layout_compatibility_with_globals(
+                <M>::LAYOUT,
+                root_mod_layout,
+                &checking_globals,
+            )
+            .map_err(|e| {
+                // The error must not contain references into the library,
+                // since it can outlive it.
+                let report = AbiIncompatibilityReport::new(&e);
+                LibraryError::AbiInstability(RBoxError::new(report))
+            })?;
+        }
+
+        atomic::compiler_fence(atomic::Ordering::SeqCst);
+
+        Ok(())
+    }
+
     /// Gets the root module,first
     /// checking that the layout of the `M` from the dynamic library is
     /// compatible with the expected layout.
@@ -282,18 +463,47 @@ impl LibHeader {
     where
         M: PrefixRefTrait,
     {
-        let reff = self
-            .module
-            .try_init(|| (self.constructor)().into_result())
-            .map_err(|mut err| {
-                // Making sure that the error doesn't contain references into
-                // the unloaded library.
-                //
-                // This isn't strictly required anymore because abi_stable doesn't
-                // unload libraries right now.
-                err.reallocate();
-                err
-            })?;
+        unsafe { self.unchecked_layout_with(|| (self.constructor)().into_result()) }
+    }
+
+    /// Checks that the version number of the library is compatible,
+    /// returning the root module constructed by `loader` from `context`.
+    ///
+    /// If the root module was already constructed,
+    /// this returns it without calling `loader`,dropping `context`.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that `M` has the expected layout,
+    /// and that `loader` takes a context with the same layout as `C`.
+    pub(super) unsafe fn init_root_module_with_context<M, C>(
+        &self,
+        loader: &ContextLoader,
+        context: C,
+    ) -> Result<M, LibraryError>
+    where
+        M: RootModule,
+        C: StableAbi,
+    {
+        self.check_version::<M>()?;
+        unsafe { self.unchecked_layout_with(move || loader.call(context)) }
+            .map_err(RootModuleError::into_library_error::<M>)
+    }
+
+    unsafe fn unchecked_layout_with<M, F>(&self, constructor: F) -> Result<M, RootModuleError>
+    where
+        M: PrefixRefTrait,
+        F: FnOnce() -> Result<PrefixRef<ErasedPrefix>, RootModuleError>,
+    {
+        let reff = self.module.try_init(constructor).map_err(|mut err| {
+            // Making sure that the error doesn't contain references into
+            // the unloaded library.
+            //
+            // This isn't strictly required anymore because abi_stable doesn't
+            // unload libraries right now.
+            err.reallocate();
+            err
+        })?;
         unsafe { Ok(M::from_prefix_ref(reff.cast::<M::PrefixFields>())) }
     }
 }
@@ -403,11 +613,7 @@ impl AbiHeaderRef {
     /// - `LibraryError::InvalidCAbi`:
     /// If the C abi used by the library is not compatible.
     pub fn upgrade(self) -> Result<&'static LibHeader, LibraryError> {
-        if !self.is_valid() {
-            return Err(LibraryError::InvalidAbiHeader(*self));
-        }
-
-        let lib_header: &'static LibHeader = unsafe { self.0.transmute_into_ref() };
+        let lib_header = self.peek_lib_header()?;
 
         let c_abi_testing_fns = lib_header.root_mod_consts().c_abi_testing_fns();
         crate::library::c_abi_testing::run_tests(c_abi_testing_fns)?;
@@ -418,4 +624,19 @@ impl AbiHeaderRef {
 
         Ok(lib_header)
     }
+
+    /// Gets the LibHeader of a library,without running any code in the library,
+    /// so it can only be used to read the data in the LibHeader.
+    ///
+    /// # Errors
+    ///
+    /// This returns a `LibraryError::InvalidAbiHeader` if
+    /// the abi_stable used by the library is not compatible.
+    pub(super) fn peek_lib_header(self) -> Result<&'static LibHeader, LibraryError> {
+        if !self.is_valid() {
+            return Err(LibraryError::InvalidAbiHeader(*self));
+        }
+
+        Ok(unsafe { self.0.transmute_into_ref() })
+    }
 }
@@ -0,0 +1,164 @@
+use super::*;
+
+use crate::sabi_types::ParseVersionError;
+
+/// Read-only information about a dynamic library and the root module that it exports,
+/// obtained without calling any code in the library that abi_stable would run to load it.
+///
+/// The [`AbiHeader`] is always available,
+/// while the information about the root module is only available if
+/// the library uses an abi_stable version compatible with this one.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use abi_stable::library::LibraryInfo;
+///
+/// let info = LibraryInfo::from_path("./plugins/libplugin.so".as_ref()).unwrap();
+///
+/// println!(
+///     "uses abi_stable {}.{}",
+///     info.abi_major(),
+///     info.abi_minor(),
+/// );
+///
+/// if let (Some(name), Some(version)) = (info.name(), info.version_strings()) {
+///     println!("{} version {}", name, version);
+/// }
+///
+/// if let Some(layout) = info.layout() {
+///     println!("root module type: {}", layout.full_type());
+/// }
+///
+/// ```
+///
+/// [`AbiHeader`]: ./struct.AbiHeader.html
+#[derive(Debug, Copy, Clone)]
+pub struct LibraryInfo {
+    abi_header: AbiHeader,
+    root_module: Option<RootModuleInfo>,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct RootModuleInfo {
+    base_name: &'static str,
+    name: &'static str,
+    version_strings: VersionStrings,
+    layout: IsLayoutChecked,
+}
+
+impl LibraryInfo {
+    /// Loads the library at `path`,reading the information about its root module.
+    ///
+    /// This leaks the library,
+    /// it runs the static initializers of the library,
+    /// but it does not run the abi_stable initialization of the library
+    /// (the C ABI tests and the initialization of its globals),
+    /// nor does it call the function that constructs the root module.
+    ///
+    /// # Errors
+    ///
+    /// This returns the same errors as [`abi_header_from_path`].
+    ///
+    /// [`abi_header_from_path`]: ./fn.abi_header_from_path.html
+    pub fn from_path(path: &Path) -> Result<Self, LibraryError> {
+        abi_header_from_path(path).map(Self::from_abi_header)
+    }
+
+    /// Reads the information about a library from its [`AbiHeaderRef`],
+    /// without running any code in the library.
+    ///
+    /// The information about the root module is only read if
+    /// the abi_stable version of the library is compatible with this one.
+    ///
+    /// [`AbiHeaderRef`]: ./struct.AbiHeaderRef.html
+    pub fn from_abi_header(abi_header: AbiHeaderRef) -> Self {
+        match abi_header.peek_lib_header() {
+            Ok(lib_header) => Self::from_lib_header(lib_header),
+            Err(_) => Self {
+                abi_header: *abi_header,
+                root_module: None,
+            },
+        }
+    }
+
+    /// Reads the information about the root module from the header of a library.
+    pub fn from_lib_header(lib_header: &'static LibHeader) -> Self {
+        let consts = lib_header.root_mod_consts();
+        Self {
+            abi_header: lib_header.abi_header(),
+            root_module: Some(RootModuleInfo {
+                base_name: consts.base_name().as_str(),
+                name: consts.name().as_str(),
+                version_strings: consts.version_strings(),
+                layout: consts.layout(),
+            }),
+        }
+    }
+
+    /// The header describing the abi_stable version that the library uses.
+    pub const fn abi_header(&self) -> AbiHeader {
+        self.abi_header
+    }
+
+    /// The major abi version of abi_stable that the library uses.
+    pub const fn abi_major(&self) -> u32 {
+        self.abi_header.abi_major
+    }
+
+    /// The minor abi version of abi_stable that the library uses.
+    pub const fn abi_minor(&self) -> u32 {
+        self.abi_header.abi_minor
+    }
+
+    /// Whether the abi_stable version that the library uses is compatible with this one.
+    ///
+    /// When this returns false,
+    /// none of the information about the root module is available.
+    pub const fn is_abi_compatible(&self) -> bool {
+        self.root_module.is_some()
+    }
+
+    /// The `RootModule::BASE_NAME` of the root module,
+    /// the name of the dynamic library without the platform-specific prefix and suffix.
+    ///
+    /// This is None if the abi_stable version of the library is incompatible.
+    pub fn base_name(&self) -> Option<&'static str> {
+        self.root_module.map(|x| x.base_name)
+    }
+
+    /// The `RootModule::NAME` of the root module,used in error messages.
+    ///
+    /// This is None if the abi_stable version of the library is incompatible.
+    pub fn name(&self) -> Option<&'static str> {
+        self.root_module.map(|x| x.name)
+    }
+
+    /// The `RootModule::VERSION_STRINGS` of the root module.
+    ///
+    /// This is None if the abi_stable version of the library is incompatible.
+    pub fn version_strings(&self) -> Option<VersionStrings> {
+        self.root_module.map(|x| x.version_strings)
+    }
+
+    /// Parses the version of the root module into a `VersionNumber`.
+    ///
+    /// This is None if the abi_stable version of the library is incompatible.
+    pub fn version_number(&self) -> Option<Result<VersionNumber, ParseVersionError>> {
+        self.root_module.map(|x| x.version_strings.parsed())
+    }
+
+    /// Whether the layout of the root module is checked when it's loaded.
+    ///
+    /// This is None if the abi_stable version of the library is incompatible.
+    pub fn is_layout_checked(&self) -> Option<IsLayoutChecked> {
+        self.root_module.map(|x| x.layout)
+    }
+
+    /// The layout of the root module,
+    /// None if the layout is not checked when the root module is loaded,
+    /// or if the abi_stable version of the library is incompatible.
+    pub fn layout(&self) -> Option<&'static TypeLayout> {
+        self.root_module.and_then(|x| x.layout.into_option())
+    }
+}
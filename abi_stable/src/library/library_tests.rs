@@ -1,7 +1,39 @@
+use crate::{
+    abi_stability::abi_checking::{get_checking_globals, is_global_layout_checker},
+    for_examples::{Module, Module_Ref},
+    library::{
+        allocation_tracking::AllocationCounter, load_all, local_allocation_stats,
+        panic_hook::forward_test_panic, set_plugin_panic_hook, shutdown::ShutdownHooks,
+        take_plugin_panic_hook, InterfaceVersion, InterfaceVersions, LibHeader, LibraryPath,
+        RootModule, RootModuleError, RootModuleResult, ShutdownFn, VersionNegotiator,
+    },
+    prefix_type::PrefixTypeTrait,
+    sabi_types::VersionStrings,
+    std_types::{RBox, RBoxError, RNone, RResult, RSome, RString, RVec},
+    type_layout::{FingerprintedLayout, LayoutFingerprint, TLData},
+    StableAbi,
+};
+
 use crate::library::{
-    ROOT_MODULE_LOADER_NAME, ROOT_MODULE_LOADER_NAME_NULSTR, ROOT_MODULE_LOADER_NAME_WITH_NUL,
+    discover_libraries, discovery::is_library_file_name, LibraryError, LoadOptions, RawLibrary,
+    SearchPaths, SymbolBinding, SymbolScope, ROOT_MODULE_LOADER_NAME,
+    ROOT_MODULE_LOADER_NAME_NULSTR, ROOT_MODULE_LOADER_NAME_WITH_NUL,
+};
+use abi_stable_shared::{
+    is_valid_export_name, mangled_allocation_tracker_name, mangled_context_loader_name,
+    mangled_exported_item_name, mangled_globals_installer_name,
+    mangled_named_root_module_loader_name, mangled_root_module_loader_name,
+};
+
+use parking_lot::{const_mutex, Mutex};
+
+use std::{
+    env,
+    env::consts::{DLL_PREFIX, DLL_SUFFIX},
+    fs,
+    path::{Path, PathBuf},
+    process,
 };
-use abi_stable_shared::mangled_root_module_loader_name;
 
 #[test]
 fn root_module_loader_name_test() {
@@ -13,3 +45,557 @@ fn root_module_loader_name_test() {
     assert_eq!(ROOT_MODULE_LOADER_NAME_NULSTR.to_str(), name);
     assert_eq!(ROOT_MODULE_LOADER_NAME_NULSTR.to_str_with_nul(), with_nul);
 }
+
+#[test]
+fn export_name_test() {
+    for name in ["codecs", "ui-hooks", "storage_v2", "a.b"] {
+        assert!(is_valid_export_name(name), "{}", name);
+    }
+    for name in ["", "two words", "a/b", "ñ"] {
+        assert!(!is_valid_export_name(name), "{}", name);
+    }
+
+    let codecs = mangled_named_root_module_loader_name("codecs");
+    assert_ne!(codecs, mangled_root_module_loader_name());
+    assert_ne!(codecs, mangled_named_root_module_loader_name("storage"));
+    assert_ne!(codecs, mangled_exported_item_name("codecs"));
+}
+
+#[test]
+fn shutdown_hook_test() {
+    static CALLS: Mutex<Vec<&str>> = const_mutex(Vec::new());
+
+    extern "C" fn first() -> RResult<(), RootModuleError> {
+        crate::library::__call_root_module_shutdown(|| CALLS.lock().push("first"))
+    }
+    extern "C" fn second() -> RResult<(), RootModuleError> {
+        crate::library::__call_root_module_shutdown(|| -> Result<(), RBoxError> {
+            CALLS.lock().push("second");
+            Err(RBoxError::from_fmt(&"could not flush"))
+        })
+    }
+    extern "C" fn third() -> RResult<(), RootModuleError> {
+        crate::library::__call_root_module_shutdown(|| -> Result<(), RBoxError> {
+            CALLS.lock().push("third");
+            panic!("oh no")
+        })
+    }
+    extern "C" fn fourth() -> RResult<(), RootModuleError> {
+        crate::library::__call_root_module_shutdown(|| CALLS.lock().push("fourth"))
+    }
+
+    let header = |hook: Option<ShutdownFn>| -> &'static LibHeader {
+        let module = Module {
+            first: RNone,
+            second: "second".into(),
+            third: 3,
+        }
+        .leak_into_prefix();
+        let header = LibHeader::from_module(module);
+        Box::leak(Box::new(match hook {
+            Some(hook) => header.with_shutdown_hook(hook),
+            None => header,
+        }))
+    };
+    let first = header(Some(first));
+    let second = header(Some(second));
+    let third = header(Some(third));
+    let fourth = header(Some(fourth));
+    let no_hook = header(None);
+
+    // A local registry,so that this test doesn't call the hooks registered by other tests.
+    let hooks = ShutdownHooks::new();
+    hooks.register(first);
+    hooks.register(second);
+    hooks.register(first);
+    hooks.register(no_hook);
+    hooks.register(third);
+    hooks.register(fourth);
+
+    hooks.shutdown(fourth).unwrap();
+    hooks.shutdown(fourth).unwrap();
+    hooks.shutdown(no_hook).unwrap();
+
+    let errors = match hooks.shutdown_all() {
+        Err(LibraryError::Many(errors)) => errors,
+        x => panic!("expected shutdown errors, found: {:?}", x),
+    };
+
+    assert_eq!(*CALLS.lock(), ["fourth", "third", "second", "first"]);
+
+    match &errors[..] {
+        [LibraryError::RootModuleShutdown {
+            err: RootModuleError::Unwound(message),
+            module_name: Module_Ref::NAME,
+            ..
+        }, LibraryError::RootModuleShutdown {
+            err: RootModuleError::Returned(err),
+            module_name: Module_Ref::NAME,
+            ..
+        }] => {
+            assert_eq!(*message, RSome(RString::from("oh no")));
+            assert_eq!(err.to_string(), "could not flush");
+        }
+        x => panic!("unexpected shutdown errors: {:?}", x),
+    }
+
+    hooks.shutdown_all().unwrap();
+    assert_eq!(CALLS.lock().len(), 4);
+}
+
+#[test]
+fn root_module_error_test() {
+    let returned = |message: &str| -> RootModuleResult {
+        let message = message.to_string();
+        crate::library::call_root_module_loader_with(move || -> Result<Module_Ref, RBoxError> {
+            Err(RBoxError::from_fmt(&message))
+        })
+    };
+
+    // Returned errors are never mistaken for panics,whatever their message is.
+    for message in [
+        "hello",
+        "abi_stable::library::RootModuleError::Unwound:hello",
+    ] {
+        match returned(message).into_result() {
+            Err(RootModuleError::Returned(err)) => assert_eq!(err.to_string(), message),
+            x => panic!("expected a returned error, found: {:?}", x),
+        }
+    }
+
+    let mut err = RootModuleError::Unwound(RSome(RString::from("oh no")));
+    err.reallocate();
+    match err {
+        RootModuleError::Unwound(RSome(message)) => assert_eq!(message, "oh no"),
+        x => panic!("expected the panic message to be kept, found: {:?}", x),
+    }
+}
+
+#[test]
+fn context_loader_name_test() {
+    let unnamed = mangled_context_loader_name(None);
+    let codecs = mangled_context_loader_name(Some("codecs"));
+
+    assert_ne!(unnamed, codecs);
+    assert_ne!(unnamed, mangled_root_module_loader_name());
+    assert_ne!(unnamed, mangled_globals_installer_name(None));
+    assert_ne!(codecs, mangled_named_root_module_loader_name("codecs"));
+    assert_ne!(codecs, mangled_globals_installer_name(Some("codecs")));
+    assert_ne!(codecs, mangled_exported_item_name("codecs"));
+}
+
+#[test]
+fn panic_hook_test() {
+    static PANICS: Mutex<Vec<String>> = const_mutex(Vec::new());
+
+    assert_ne!(
+        mangled_globals_installer_name(None),
+        mangled_globals_installer_name(Some("codecs"))
+    );
+    assert_ne!(
+        mangled_globals_installer_name(None),
+        mangled_root_module_loader_name()
+    );
+
+    set_plugin_panic_hook(|info| {
+        let location = info.location().unwrap();
+        assert!(
+            location.file().ends_with("library_tests.rs"),
+            "{}",
+            location
+        );
+        assert_eq!(info.thread_name().map(|x| x.as_str()), Some("plugin"));
+        PANICS.lock().push(info.to_string());
+    });
+
+    let location = std::panic::Location::caller();
+    std::thread::Builder::new()
+        .name("plugin".into())
+        .spawn(move || forward_test_panic("oh no", location))
+        .unwrap()
+        .join()
+        .unwrap();
+
+    assert!(take_plugin_panic_hook().is_some());
+    assert!(take_plugin_panic_hook().is_none());
+
+    let expected = format!("thread 'plugin' panicked at {}:\noh no", location);
+    assert_eq!(*PANICS.lock(), [expected]);
+
+    // The hook isn't called while the hook is locked,so it can replace itself.
+    set_plugin_panic_hook(|_| {
+        set_plugin_panic_hook(|info| PANICS.lock().push(info.message().to_string()));
+    });
+    forward_test_panic("replaced", location);
+    forward_test_panic("forwarded", location);
+    assert!(take_plugin_panic_hook().is_some());
+    assert_eq!(PANICS.lock().last().unwrap(), "forwarded");
+    assert_eq!(PANICS.lock().len(), 2);
+}
+
+#[test]
+fn library_file_name_test() {
+    let lib_name = |name: &str| format!("{}{}{}", DLL_PREFIX, name, DLL_SUFFIX);
+
+    assert!(is_library_file_name(Path::new(&lib_name("foo"))));
+    assert!(is_library_file_name(
+        &Path::new("a/b").join(lib_name("foo"))
+    ));
+    assert!(!is_library_file_name(Path::new(&lib_name(""))));
+    assert!(!is_library_file_name(Path::new("foo.txt")));
+    assert!(!is_library_file_name(Path::new("")));
+}
+
+#[test]
+fn discover_libraries_test() {
+    let dir = std::env::temp_dir().join(format!("abi_stable-discovery-test-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join(format!("{}sub{}", DLL_PREFIX, DLL_SUFFIX))).unwrap();
+
+    let not_a_library = dir.join(format!("{}garbage{}", DLL_PREFIX, DLL_SUFFIX));
+    fs::write(&not_a_library, b"not a library").unwrap();
+    fs::write(dir.join("readme.txt"), b"hello").unwrap();
+
+    // Symlinks are followed,and broken symlinks are skipped.
+    #[cfg(unix)]
+    let symlinked = {
+        let symlinked = dir.join(format!("{}linked{}", DLL_PREFIX, DLL_SUFFIX));
+        std::os::unix::fs::symlink(&not_a_library, &symlinked).unwrap();
+        let broken = dir.join(format!("{}broken{}", DLL_PREFIX, DLL_SUFFIX));
+        std::os::unix::fs::symlink(dir.join("nonexistent"), broken).unwrap();
+        symlinked
+    };
+
+    let candidates = discover_libraries(&[&dir]).unwrap();
+    let paths = candidates.iter().map(|x| x.path()).collect::<Vec<_>>();
+    #[cfg(unix)]
+    assert_eq!(paths, [not_a_library.as_path(), symlinked.as_path()]);
+    #[cfg(not(unix))]
+    assert_eq!(paths, [not_a_library.as_path()]);
+
+    for candidate in &candidates {
+        assert!(!candidate.is_compatible());
+        assert_eq!(candidate.name(), None);
+        assert!(candidate.abi_header().is_none());
+    }
+
+    match discover_libraries(&[dir.join("nonexistent")]) {
+        Err(LibraryError::IoError { .. }) => {}
+        x => panic!("expected an IoError, found: {:?}", x),
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn search_paths_test() {
+    let joined = env::join_paths(["foo", "", "bar/baz"]).unwrap();
+
+    let search_paths = SearchPaths::new()
+        .with_path_list(&joined)
+        .with_env_var("ABI_STABLE_SEARCH_PATHS_TEST_UNSET")
+        .with_directory("qux")
+        .with_target_dirs("target");
+
+    let expected: Vec<PathBuf> = ["foo", "bar/baz", "qux", "target/debug", "target/release"]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+    assert_eq!(search_paths.directories(), &expected[..]);
+
+    let exe_dir = env::current_exe().unwrap().parent().unwrap().to_owned();
+    assert_eq!(
+        SearchPaths::new().with_executable_dir().directories(),
+        &[exe_dir][..]
+    );
+}
+
+#[test]
+fn load_options_test() {
+    let default = LoadOptions::new();
+    assert_eq!(default, LoadOptions::default());
+    assert_eq!(default.scope(), SymbolScope::Local);
+    assert_eq!(default.binding(), SymbolBinding::Lazy);
+    assert!(!default.is_deep_bind());
+    assert!(!default.is_no_delete());
+    assert_eq!(default.to_string(), "RTLD_LAZY | RTLD_LOCAL");
+
+    let options = LoadOptions::new()
+        .global()
+        .now()
+        .deep_bind(true)
+        .no_delete(true);
+    assert_eq!(options.scope(), SymbolScope::Global);
+    assert_eq!(options.binding(), SymbolBinding::Now);
+    assert_eq!(
+        options.to_string(),
+        "RTLD_NOW | RTLD_GLOBAL | RTLD_DEEPBIND | RTLD_NODELETE"
+    );
+    assert_eq!(
+        options.local().lazy().deep_bind(false).no_delete(false),
+        default
+    );
+
+    #[cfg(unix)]
+    {
+        use libloading::os::unix::{RTLD_GLOBAL, RTLD_LAZY, RTLD_LOCAL, RTLD_NOW};
+
+        assert_eq!(default.dlopen_flags(), RTLD_LAZY | RTLD_LOCAL);
+        assert_eq!(
+            options.dlopen_flags() & (RTLD_NOW | RTLD_GLOBAL),
+            RTLD_NOW | RTLD_GLOBAL
+        );
+    }
+
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    assert_eq!(
+        options.dlopen_flags(),
+        libc::RTLD_NOW | libc::RTLD_GLOBAL | libc::RTLD_DEEPBIND | libc::RTLD_NODELETE
+    );
+}
+
+#[test]
+fn open_error_records_load_options_test() {
+    let options = LoadOptions::new().now().global();
+    let path = env::temp_dir().join(format!("abi_stable-nonexistent-{}", process::id()));
+
+    let err = match RawLibrary::load_at_with(&path, options) {
+        Ok(_) => panic!("expected the library to not be loaded"),
+        Err(err) => err,
+    };
+    match &err {
+        LibraryError::OpenError {
+            path: err_path,
+            options: err_options,
+            ..
+        } => {
+            assert_eq!(*err_path, path);
+            assert_eq!(*err_options, options);
+        }
+        x => panic!("expected an OpenError, found: {:?}", x),
+    }
+    assert!(
+        err.to_string().contains("RTLD_NOW | RTLD_GLOBAL"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn allocation_counter_test() {
+    let counter = AllocationCounter::new();
+    let stats = || {
+        let stats = counter.stats();
+        (
+            stats.live_bytes(),
+            stats.live_allocations(),
+            stats.total_allocations(),
+        )
+    };
+
+    counter.record_reallocation(0, 0);
+    assert_eq!(stats(), (0, 0, 0));
+
+    counter.record_reallocation(0, 16);
+    counter.record_reallocation(0, 100);
+    assert_eq!(stats(), (116, 2, 2));
+
+    // Growing and shrinking are not new allocations.
+    counter.record_reallocation(16, 64);
+    assert_eq!(stats(), (164, 2, 2));
+    counter.record_reallocation(64, 8);
+    assert_eq!(stats(), (108, 2, 2));
+
+    counter.record_reallocation(100, 0);
+    assert_eq!(stats(), (8, 1, 2));
+    counter.record_reallocation(8, 0);
+    assert_eq!(stats(), (0, 0, 2));
+}
+
+#[test]
+fn allocation_tracking_test() {
+    assert_ne!(
+        mangled_allocation_tracker_name(None),
+        mangled_allocation_tracker_name(Some("codecs"))
+    );
+    assert_ne!(
+        mangled_allocation_tracker_name(None),
+        mangled_globals_installer_name(None)
+    );
+
+    // Other tests allocate concurrently,so only lower bounds can be checked.
+    const CAP: usize = 1 << 20;
+
+    let before = local_allocation_stats().unwrap();
+
+    let mut list = RVec::<u8>::with_capacity(CAP);
+    let stats = local_allocation_stats().unwrap();
+    assert!(stats.live_bytes() >= CAP, "{:?}", stats);
+    assert!(stats.total_allocations() > before.total_allocations());
+
+    list.extend(std::iter::repeat(0).take(CAP + 1));
+    let capacity = list.capacity();
+    assert!(local_allocation_stats().unwrap().live_bytes() >= capacity);
+
+    let string = RString::from_utf8(list.clone()).unwrap();
+    let boxed = RBox::new([0u8; CAP / 16]);
+    let stats = local_allocation_stats().unwrap();
+    assert!(
+        stats.live_bytes() >= capacity + string.capacity() + CAP / 16,
+        "{:?}",
+        stats
+    );
+    assert!(stats.live_allocations() >= 3, "{:?}", stats);
+
+    drop((list.into_vec(), string, RBox::into_box(boxed)));
+
+    // Headers that weren't loaded from a library don't have allocation stats.
+    let module = Module {
+        first: RNone,
+        second: "second".into(),
+        third: 3,
+    }
+    .leak_into_prefix();
+    let header = LibHeader::from_module(module);
+    assert_eq!(header.allocation_stats(), None);
+}
+
+#[test]
+fn version_negotiation_test() {
+    const VERSIONS: InterfaceVersions = InterfaceVersions::new(
+        "codecs",
+        &[
+            InterfaceVersion::new(2, "codecs_v2"),
+            InterfaceVersion::unnamed(1),
+        ],
+    );
+
+    assert_eq!(VERSIONS.interface(), "codecs");
+    let versions = VERSIONS.versions();
+    assert_eq!(versions.len(), 2);
+    assert_eq!(versions[0].version(), 2);
+    assert_eq!(versions[0].export_name(), Some("codecs_v2"));
+    assert_eq!(versions[1].version(), 1);
+    assert_eq!(versions[1].export_name(), None);
+
+    // The versions are stored in the LibHeader.
+    let module = Module {
+        first: RNone,
+        second: "second".into(),
+        third: 3,
+    }
+    .leak_into_prefix();
+    let header = LibHeader::from_module(module);
+    assert!(header.interface_versions().is_none());
+    let header = header.with_interface_versions(VERSIONS);
+    let stored = header.interface_versions().unwrap();
+    assert_eq!(stored.interface(), "codecs");
+    assert_eq!(stored.versions(), versions);
+
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<VersionNegotiator<()>>();
+
+    // Nothing can be loaded without any supported versions.
+    let err = VersionNegotiator::<()>::new("codecs")
+        .load_from(LibraryPath::FullPath("nonexistent".as_ref()))
+        .err()
+        .unwrap();
+    match &err {
+        LibraryError::NoCompatibleInterfaceVersion {
+            interface,
+            offered,
+            errors,
+        } => {
+            assert_eq!(*interface, "codecs");
+            assert!(offered.is_empty());
+            assert!(errors.is_empty());
+        }
+        x => panic!("expected a NoCompatibleInterfaceVersion, found: {:?}", x),
+    }
+    let formatted = err.to_string();
+    assert!(formatted.contains("'codecs' interface"), "{}", formatted);
+    assert!(formatted.contains("none of which"), "{}", formatted);
+}
+
+#[test]
+fn layout_fingerprint_test() {
+    let module = Module {
+        first: RNone,
+        second: "second".into(),
+        third: 3,
+    }
+    .leak_into_prefix();
+    let header: &'static LibHeader = Box::leak(Box::new(LibHeader::from_module(module)));
+
+    let expected = FingerprintedLayout::new(Module_Ref::LAYOUT);
+    assert_eq!(header.layout_fingerprint(), Some(expected.fingerprint));
+    assert_eq!(
+        header.layout_fingerprint(),
+        Some(LayoutFingerprint::new(Module_Ref::LAYOUT)),
+    );
+
+    let prefix_types = expected
+        .checked_types
+        .iter()
+        .filter(|x| matches!(x.data(), TLData::PrefixType { .. }))
+        .collect::<Vec<_>>();
+    assert!(!prefix_types.is_empty());
+
+    // The fingerprints are the same,
+    // so the layouts aren't compared,but the prefix types are still registered.
+    assert!(is_global_layout_checker());
+    header.check_layout::<Module_Ref>().unwrap();
+
+    let prefix_type_map = get_checking_globals().prefix_type_map.lock().unwrap();
+    for prefix_type in prefix_types {
+        assert!(prefix_type_map
+            .get_index(&prefix_type.get_utypeid())
+            .is_some());
+    }
+}
+
+#[test]
+fn load_all_test() {
+    let dir = env::temp_dir().join("abi_stable_load_all_test_nonexistent");
+    let paths = [dir.join("foo"), dir.join("bar"), dir.join("foo")];
+
+    let (plugins, report) = load_all::<Module_Ref, _>(&paths);
+    assert!(plugins.is_empty());
+    assert!(!report.is_ok());
+
+    // Repeated paths are only loaded once.
+    let libraries = report.libraries();
+    assert_eq!(libraries.len(), 2);
+    for (library, path) in libraries.iter().zip(&paths) {
+        assert_eq!(library.path(), path);
+        assert!(library.elapsed() <= report.elapsed());
+        assert!(
+            matches!(library.error(), Some(LibraryError::OpenError { .. })),
+            "{:?}",
+            library.error()
+        );
+    }
+
+    let formatted = report.to_string();
+    assert!(
+        formatted.contains("Loaded 0 of 2 libraries"),
+        "{}",
+        formatted
+    );
+    assert!(formatted.contains("failed"), "{}", formatted);
+
+    match report.into_result() {
+        Err(LibraryError::Many(errors)) => {
+            assert_eq!(errors.len(), 2);
+            assert!(errors
+                .iter()
+                .all(|e| matches!(e, LibraryError::AtPath { .. })));
+        }
+        x => panic!("expected a LibraryError::Many, found: {:?}", x),
+    }
+
+    let (plugins, report) = load_all::<Module_Ref, _>(Vec::<PathBuf>::new());
+    assert!(plugins.is_empty());
+    assert!(report.is_ok());
+    assert!(report.libraries().is_empty());
+}
@@ -0,0 +1,422 @@
+use super::{
+    plugin_set::{init_plugin, open_plugin, plugin_key},
+    *,
+};
+
+use crate::std_types::RVec;
+
+use parking_lot::Mutex;
+
+use std::{
+    collections::HashSet,
+    fmt,
+    num::NonZeroUsize,
+    panic,
+    sync::{mpsc, Arc},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Loads the `M` root modules of the libraries at `paths` on a pool of threads,
+/// continuing after a library fails to load.
+///
+/// Each library is opened,has its root module checked for compatibility,
+/// and has its root module initialized in one of the threads,
+/// so loading many libraries takes about as long as loading the slowest few of them.
+///
+/// The layout checks of different libraries only wait for each other while
+/// combining the prefix types and nonexhaustive enums that they found into
+/// the global maps,which are protected by locks,
+/// so the libraries are checked as if they were loaded one after the other.
+///
+/// Every library in `paths` exports an `M` root module,
+/// so a library that declares a dependency on `M` could depend on any other library in `paths`.
+/// Those libraries are initialized after all the other libraries were initialized,
+/// so that whether their dependency is found doesn't depend on thread timing.
+///
+/// The root modules are returned in a [`PluginSet`],in the order of `paths`,
+/// skipping the libraries that failed to load and repeated paths.
+/// The shutdown hooks of the root modules are registered in the order that
+/// the libraries finished loading.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use abi_stable::{for_examples::Module_Ref, library::load_all};
+///
+/// let (plugins, report) =
+///     load_all::<Module_Ref, _>(&["plugins/libfoo.so", "plugins/libbar.so"]);
+///
+/// // Prints how long each library took to load,and the errors of the ones that failed.
+/// println!("{}", report);
+///
+/// for plugin in plugins.iter() {
+///     println!("{}: {:?}", plugin.path().display(), plugin.module().third());
+/// }
+///
+/// ```
+///
+/// [`PluginSet`]: ./struct.PluginSet.html
+pub fn load_all<M, I>(paths: I) -> (PluginSet<M>, LoadReport)
+where
+    M: RootModule + Send,
+    I: IntoIterator,
+    I::Item: AsRef<Path>,
+{
+    let started = Instant::now();
+
+    let mut keys = HashSet::new();
+    let jobs = paths
+        .into_iter()
+        .map(|path| path.as_ref().to_owned())
+        .filter_map(|path| {
+            let key = plugin_key(&path);
+            keys.insert(key.clone()).then(|| (path, key))
+        })
+        .collect::<Vec<(PathBuf, PathBuf)>>();
+
+    let results = load_in_two_steps(
+        jobs.clone(),
+        |(path, _)| open_plugin::<M>(path),
+        |&(_, lib_header)| {
+            lib_header
+                .dependencies()
+                .iter()
+                .any(|dependency| dependency.name() == M::NAME)
+        },
+        |(_, key), (raw_library, lib_header)| {
+            init_plugin::<M>(raw_library, lib_header, key.clone())
+        },
+    );
+
+    let mut plugins = PluginSet::new();
+    let mut libraries = Vec::with_capacity(jobs.len());
+    for ((path, _), (elapsed, res)) in jobs.iter().zip(results) {
+        let error = match res {
+            Ok(plugin) => {
+                plugins.insert(plugin);
+                None
+            }
+            Err(e) => Some(e),
+        };
+        libraries.push(LibraryLoadReport {
+            path: path.clone(),
+            elapsed,
+            error,
+        });
+    }
+
+    let report = LoadReport {
+        libraries,
+        elapsed: started.elapsed(),
+    };
+    (plugins, report)
+}
+
+/// Opens every job with `open`,and initializes it with `init`,on a pool of threads.
+///
+/// The jobs for which `is_dependent` returns true are initialized in a second step,
+/// after all the other jobs were initialized.
+///
+/// Returns the result of each job,in the order of `jobs`,
+/// with how long it took to open and initialize it.
+fn load_in_two_steps<J, O, T, FO, FD, FI>(
+    jobs: Vec<J>,
+    open: FO,
+    is_dependent: FD,
+    init: FI,
+) -> Vec<(Duration, Result<T, LibraryError>)>
+where
+    J: Send + Sync + 'static,
+    O: Send + 'static,
+    T: Send + 'static,
+    FO: Fn(&J) -> Result<O, LibraryError> + Send + Sync + 'static,
+    FD: Fn(&O) -> bool + Send + Sync + 'static,
+    FI: Fn(&J, O) -> Result<T, LibraryError> + Send + Sync + 'static,
+{
+    enum Opened<O, T> {
+        Done(Result<T, LibraryError>),
+        Dependent(O),
+    }
+
+    let jobs = Arc::new(jobs);
+    let init = Arc::new(init);
+
+    let first_step = {
+        let jobs = jobs.clone();
+        let init = init.clone();
+        run_on_threads((0..jobs.len()).collect(), move |index| {
+            let job = &jobs[index];
+            match open(job) {
+                Ok(opened) if is_dependent(&opened) => Opened::Dependent(opened),
+                Ok(opened) => Opened::Done(init(job, opened)),
+                Err(e) => Opened::Done(Err(e)),
+            }
+        })
+    };
+
+    let mut results = Vec::with_capacity(jobs.len());
+    let mut dependents = Vec::new();
+    for (index, (elapsed, opened)) in first_step.into_iter().enumerate() {
+        match opened {
+            Opened::Done(res) => results.push(Some((elapsed, res))),
+            Opened::Dependent(opened) => {
+                results.push(None);
+                dependents.push((index, elapsed, opened));
+            }
+        }
+    }
+
+    let indices = dependents
+        .iter()
+        .map(|&(index, elapsed, _)| (index, elapsed))
+        .collect::<Vec<_>>();
+    let second_step = run_on_threads(
+        dependents
+            .into_iter()
+            .map(|(index, _, opened)| (index, opened))
+            .collect(),
+        move |(index, opened)| init(&jobs[index], opened),
+    );
+    for ((index, opened_in), (elapsed, res)) in indices.into_iter().zip(second_step) {
+        results[index] = Some((opened_in + elapsed, res));
+    }
+
+    results
+        .into_iter()
+        .map(|x| x.expect("every job is either done in the first step or in the second one"))
+        .collect()
+}
+
+/// Calls `f` with each of `inputs` on a pool of threads,
+/// returning the results in the order of `inputs`,with how long each call took.
+fn run_on_threads<I, R, F>(inputs: Vec<I>, f: F) -> Vec<(Duration, R)>
+where
+    I: Send + 'static,
+    R: Send + 'static,
+    F: Fn(I) -> R + Send + Sync + 'static,
+{
+    let len = inputs.len();
+    let thread_count = thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(len);
+
+    let inputs = Arc::new(Mutex::new(inputs.into_iter().enumerate()));
+    let f = Arc::new(f);
+    let (sender, receiver) = mpsc::channel();
+
+    let threads = (0..thread_count)
+        .map(|_| {
+            let inputs = inputs.clone();
+            let f = f.clone();
+            let sender = sender.clone();
+            thread::spawn(move || loop {
+                let (index, input) = match inputs.lock().next() {
+                    Some(x) => x,
+                    None => break,
+                };
+                let started = Instant::now();
+                let res = f(input);
+                if sender.send((index, started.elapsed(), res)).is_err() {
+                    break;
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    drop(sender);
+
+    let mut results = (0..len).map(|_| None).collect::<Vec<_>>();
+    for (index, elapsed, res) in receiver {
+        results[index] = Some((elapsed, res));
+    }
+
+    for thread in threads {
+        if let Err(payload) = thread.join() {
+            panic::resume_unwind(payload);
+        }
+    }
+
+    results
+        .into_iter()
+        .map(|x| x.expect("every input is passed to `f` by a thread"))
+        .collect()
+}
+
+//////////////////////////////////////////////////////////////////////
+
+/// How loading each library went in a call to [`load_all`].
+///
+/// Its `Display` impl prints how long each library took to load,
+/// followed by the errors of the libraries that failed to load.
+///
+/// [`load_all`]: ./fn.load_all.html
+#[derive(Debug)]
+pub struct LoadReport {
+    libraries: Vec<LibraryLoadReport>,
+    elapsed: Duration,
+}
+
+/// How loading a library went in a call to [`load_all`].
+///
+/// [`load_all`]: ./fn.load_all.html
+#[derive(Debug)]
+pub struct LibraryLoadReport {
+    path: PathBuf,
+    elapsed: Duration,
+    error: Option<LibraryError>,
+}
+
+impl LoadReport {
+    /// The libraries that were loaded (or failed to load),in the order they were passed.
+    pub fn libraries(&self) -> &[LibraryLoadReport] {
+        &self.libraries
+    }
+
+    /// How long it took to load all the libraries.
+    pub const fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Whether every library was loaded successfully.
+    pub fn is_ok(&self) -> bool {
+        self.libraries.iter().all(|x| x.error.is_none())
+    }
+
+    /// Converts this into the same result that [`PluginSet::load_all`] returns.
+    ///
+    /// # Errors
+    ///
+    /// If any library failed to load,this returns a `LibraryError::Many`,
+    /// with a `LibraryError::AtPath` for each library that failed to load.
+    ///
+    /// [`PluginSet::load_all`]: ./struct.PluginSet.html#method.load_all
+    pub fn into_result(self) -> Result<(), LibraryError> {
+        let errors = self
+            .libraries
+            .into_iter()
+            .filter_map(|library| {
+                let err = library.error?;
+                Some(LibraryError::AtPath {
+                    path: library.path,
+                    err: Box::new(err),
+                })
+            })
+            .collect::<RVec<LibraryError>>();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(LibraryError::Many(errors))
+        }
+    }
+}
+
+impl fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let failed = self.libraries.iter().filter(|x| x.error.is_some()).count();
+        writeln!(
+            f,
+            "Loaded {} of {} libraries in {:?}:",
+            self.libraries.len() - failed,
+            self.libraries.len(),
+            self.elapsed,
+        )?;
+        for library in &self.libraries {
+            let status = if library.error.is_some() {
+                "failed"
+            } else {
+                "loaded"
+            };
+            writeln!(
+                f,
+                "\t{:>12?} {} {}",
+                library.elapsed,
+                status,
+                library.path.display()
+            )?;
+        }
+        for library in &self.libraries {
+            if let Some(err) = &library.error {
+                writeln!(
+                    f,
+                    "\nWhile loading the library at:\n\t{}",
+                    library.path.display()
+                )?;
+                fmt::Display::fmt(err, f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl LibraryLoadReport {
+    /// The path that the library was loaded from,as it was passed to `load_all`.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// How long it took to load the library,
+    /// including checking and initializing its root module.
+    pub const fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// The error from loading the library,None if it was loaded.
+    pub const fn error(&self) -> Option<&LibraryError> {
+        self.error.as_ref()
+    }
+}
+
+//////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use parking_lot::const_mutex;
+
+    #[test]
+    fn dependent_library_is_initialized_after_its_dependency() {
+        static LOADED: Mutex<Vec<&str>> = const_mutex(Vec::new());
+
+        // (name,the name of the library it depends on)
+        let libraries: Vec<(&'static str, Option<&'static str>)> =
+            vec![("dependent", Some("dependency")), ("dependency", None)];
+
+        for _ in 0..16 {
+            LOADED.lock().clear();
+
+            let results = load_in_two_steps(
+                libraries.clone(),
+                |&(name, dependency)| {
+                    if dependency.is_none() {
+                        // Makes the dependency finish after the dependent library
+                        // if they were both initialized in the first step.
+                        thread::sleep(Duration::from_millis(10));
+                    }
+                    Ok((name, dependency))
+                },
+                |&(_, dependency)| dependency.is_some(),
+                |_, (name, dependency)| {
+                    if let Some(dependency) = dependency {
+                        if !LOADED.lock().contains(&dependency) {
+                            return Err(LibraryError::DependencyNotLoaded {
+                                module_name: name,
+                                dependency,
+                            });
+                        }
+                    }
+                    LOADED.lock().push(name);
+                    Ok(name)
+                },
+            );
+
+            let names = results
+                .into_iter()
+                .map(|(_, res)| res.unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(names, ["dependent", "dependency"]);
+            assert_eq!(*LOADED.lock(), ["dependency", "dependent"]);
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::fmt::{self, Display};
+
+/// Options for how a dynamic library is loaded,
+/// which are passed to `dlopen` on unix platforms.
+///
+/// The default options are the ones that [`RawLibrary::load_at`] uses:
+/// `RTLD_LAZY | RTLD_LOCAL`.
+///
+/// On non-unix platforms,these options are ignored.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use abi_stable::{
+///     for_examples::Module_Ref,
+///     library::{LibraryPath, LoadOptions, RootModule},
+/// };
+///
+/// // Prefers the symbols of the library over the global ones,
+/// // in case that multiple libraries statically link different versions
+/// // of the same C library.
+/// let options = LoadOptions::new().now().deep_bind(true);
+///
+/// let module = Module_Ref::load_from_with_options(
+///     LibraryPath::Directory("./plugins".as_ref()),
+///     options,
+/// )
+/// .unwrap();
+///
+/// ```
+///
+/// [`RawLibrary::load_at`]: ./struct.RawLibrary.html#method.load_at
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct LoadOptions {
+    scope: SymbolScope,
+    binding: SymbolBinding,
+    deep_bind: bool,
+    no_delete: bool,
+}
+
+/// Whether the symbols of a library are available to libraries loaded after it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SymbolScope {
+    /// The symbols of the library are not available to other libraries (`RTLD_LOCAL`).
+    Local,
+    /// The symbols of the library are available to other libraries (`RTLD_GLOBAL`).
+    Global,
+}
+
+/// When the undefined symbols of a library are resolved.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SymbolBinding {
+    /// Resolves symbols when they're first used (`RTLD_LAZY`).
+    Lazy,
+    /// Resolves all the symbols when the library is loaded (`RTLD_NOW`).
+    Now,
+}
+
+impl LoadOptions {
+    /// Constructs the default options,`RTLD_LAZY | RTLD_LOCAL`.
+    pub const fn new() -> Self {
+        Self {
+            scope: SymbolScope::Local,
+            binding: SymbolBinding::Lazy,
+            deep_bind: false,
+            no_delete: false,
+        }
+    }
+
+    /// Makes the symbols of the library unavailable to other libraries (`RTLD_LOCAL`).
+    pub const fn local(mut self) -> Self {
+        self.scope = SymbolScope::Local;
+        self
+    }
+
+    /// Makes the symbols of the library available to other libraries (`RTLD_GLOBAL`).
+    pub const fn global(mut self) -> Self {
+        self.scope = SymbolScope::Global;
+        self
+    }
+
+    /// Resolves symbols when they're first used (`RTLD_LAZY`).
+    pub const fn lazy(mut self) -> Self {
+        self.binding = SymbolBinding::Lazy;
+        self
+    }
+
+    /// Resolves all the symbols when the library is loaded (`RTLD_NOW`).
+    pub const fn now(mut self) -> Self {
+        self.binding = SymbolBinding::Now;
+        self
+    }
+
+    /// Sets whether the library prefers its own symbols over global symbols
+    /// with the same name (`RTLD_DEEPBIND`).
+    ///
+    /// This only has an effect on Linux with glibc.
+    pub const fn deep_bind(mut self, deep_bind: bool) -> Self {
+        self.deep_bind = deep_bind;
+        self
+    }
+
+    /// Sets whether the library is kept loaded after it's closed (`RTLD_NODELETE`).
+    ///
+    /// This only has an effect on Linux,Android,macOS,iOS,and FreeBSD.
+    pub const fn no_delete(mut self, no_delete: bool) -> Self {
+        self.no_delete = no_delete;
+        self
+    }
+
+    /// Whether the symbols of the library are available to other libraries.
+    pub const fn scope(&self) -> SymbolScope {
+        self.scope
+    }
+
+    /// When the undefined symbols of the library are resolved.
+    pub const fn binding(&self) -> SymbolBinding {
+        self.binding
+    }
+
+    /// Whether the library prefers its own symbols over global symbols.
+    pub const fn is_deep_bind(&self) -> bool {
+        self.deep_bind
+    }
+
+    /// Whether the library is kept loaded after it's closed.
+    pub const fn is_no_delete(&self) -> bool {
+        self.no_delete
+    }
+
+    /// The flags passed to `dlopen` for these options.
+    ///
+    /// Flags that are not supported by the platform are not included.
+    #[cfg(unix)]
+    #[cfg_attr(feature = "docsrs", doc(cfg(unix)))]
+    pub fn dlopen_flags(&self) -> std::os::raw::c_int {
+        use libloading::os::unix as ll;
+
+        let mut flags = match self.scope {
+            SymbolScope::Local => ll::RTLD_LOCAL,
+            SymbolScope::Global => ll::RTLD_GLOBAL,
+        };
+        flags |= match self.binding {
+            SymbolBinding::Lazy => ll::RTLD_LAZY,
+            SymbolBinding::Now => ll::RTLD_NOW,
+        };
+
+        #[cfg(all(target_os = "linux", target_env = "gnu"))]
+        if self.deep_bind {
+            flags |= libc::RTLD_DEEPBIND;
+        }
+
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "android",
+            target_os = "macos",
+            target_os = "ios",
+            target_os = "freebsd",
+        ))]
+        if self.no_delete {
+            flags |= libc::RTLD_NODELETE;
+        }
+
+        flags
+    }
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Display for LoadOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self.binding {
+            SymbolBinding::Lazy => "RTLD_LAZY",
+            SymbolBinding::Now => "RTLD_NOW",
+        })?;
+        f.write_str(match self.scope {
+            SymbolScope::Local => " | RTLD_LOCAL",
+            SymbolScope::Global => " | RTLD_GLOBAL",
+        })?;
+        if self.deep_bind {
+            f.write_str(" | RTLD_DEEPBIND")?;
+        }
+        if self.no_delete {
+            f.write_str(" | RTLD_NODELETE")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,732 @@
+use super::{root_mod_trait::load_library_with, *};
+
+use std::{
+    any::type_name,
+    collections::hash_map::RandomState,
+    env, fmt, fs,
+    hash::{BuildHasher, Hasher},
+    io::{self, BufRead, BufReader, Write},
+    marker::PhantomData,
+    os::unix::{
+        fs::DirBuilderExt,
+        net::{UnixListener, UnixStream},
+    },
+    process::{self, Child, Command, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+/// The path of the library that the child process loads.
+const LIBRARY_ENV_VAR: &str = "ABI_STABLE_OUT_OF_PROCESS_LIBRARY";
+
+/// The `RootModule::NAME` of the root module that the child process loads.
+const MODULE_ENV_VAR: &str = "ABI_STABLE_OUT_OF_PROCESS_MODULE";
+
+/// The path of the socket that the child process connects to.
+const SOCKET_ENV_VAR: &str = "ABI_STABLE_OUT_OF_PROCESS_SOCKET";
+
+/// How long the executable waits for the child process by default.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often the executable checks whether the child process connected to the socket,
+/// or exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+type FunctionFn<M> = Box<dyn Fn(M, Value) -> Result<Value, String> + Send + Sync>;
+
+#[derive(Serialize, Deserialize)]
+struct Request {
+    function: String,
+    args: Value,
+}
+
+#[derive(Serialize, Deserialize)]
+enum Response {
+    Loaded,
+    Returned(Value),
+    Failed(String),
+}
+
+/// A function pointer in a root module that [`OutOfProcessModule::call`] can call
+/// in a child process,
+/// passing its arguments and return value between the processes by serializing them to JSON.
+///
+/// This is implemented for `extern "C" fn`s with up to 6 parameters,
+/// whose parameter and return types implement `Serialize` and `Deserialize`
+/// (as `RVec`,`RString`,`RHashMap`,`ROption`,etc. do).
+///
+/// [`OutOfProcessModule::call`]: ./struct.OutOfProcessModule.html#method.call
+pub trait RemoteFn: Copy {
+    /// The parameters of the function,as a tuple.
+    type Args: Serialize + DeserializeOwned;
+
+    /// The return type of the function.
+    type Output: Serialize + DeserializeOwned;
+
+    /// Calls the function with the `args` tuple.
+    fn call_with(self, args: Self::Args) -> Self::Output;
+}
+
+macro_rules! impl_remote_fn {
+    ($( ($($param:ident),*) )*) => {
+        $(
+            impl<Ret, $($param,)*> RemoteFn for extern "C" fn($($param),*) -> Ret
+            where
+                Ret: Serialize + DeserializeOwned,
+                $($param: Serialize + DeserializeOwned,)*
+            {
+                type Args = ($($param,)*);
+                type Output = Ret;
+
+                #[allow(non_snake_case)]
+                fn call_with(self, ($($param,)*): Self::Args) -> Ret {
+                    self($($param),*)
+                }
+            }
+        )*
+    };
+}
+
+impl_remote_fn! {
+    ()
+    (A0)
+    (A0, A1)
+    (A0, A1, A2)
+    (A0, A1, A2, A3)
+    (A0, A1, A2, A3, A4)
+    (A0, A1, A2, A3, A4, A5)
+}
+
+/// The name that a function of the root module is identified with between the processes,
+/// both are the same executable so the name is the same in both.
+fn function_key<G, F>() -> String {
+    format!("{} -> {}", type_name::<G>(), type_name::<F>())
+}
+
+/// Describes how to call the functions of the root module `M`
+/// when it's loaded in a child process,
+/// so that a library that crashes doesn't take the executable down with it.
+///
+/// The child process is the executable itself,
+/// which must call [`serve_if_child`](#method.serve_if_child) at the start of `main`,
+/// with an `OutOfProcess<M>` that has the same functions as the one that
+/// [`spawn`](#method.spawn)s the child.
+///
+/// Every function of the root module that is called from the executable is registered
+/// with [`function`](#method.function),
+/// passing the method of the `M` prefix reference that returns the function pointer.
+/// The function must implement [`RemoteFn`],
+/// whose arguments and return value are passed between the processes
+/// through a Unix socket.
+///
+/// The child process inherits the stdout and stderr of the executable,
+/// and its stdin is closed.
+///
+/// This requires the "out_of_process" feature,and is only available on Unix.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use abi_stable::{
+///     library::{LibraryPath, OutOfProcess, RootModule},
+///     package_version_strings,
+///     sabi_types::VersionStrings,
+///     std_types::RString,
+///     StableAbi,
+/// };
+///
+/// #[repr(C)]
+/// #[derive(StableAbi)]
+/// #[sabi(kind(Prefix(prefix_ref = TextMod_Ref)))]
+/// pub struct TextMod {
+///     #[sabi(last_prefix_field)]
+///     pub repeat: extern "C" fn(RString, u32) -> RString,
+/// }
+///
+/// impl RootModule for TextMod_Ref {
+///     abi_stable::declare_root_module_statics! {TextMod_Ref}
+///     const BASE_NAME: &'static str = "text";
+///     const NAME: &'static str = "text";
+///     const VERSION_STRINGS: VersionStrings = package_version_strings!();
+/// }
+///
+/// fn text_module() -> OutOfProcess<TextMod_Ref> {
+///     OutOfProcess::new().function(TextMod_Ref::repeat)
+/// }
+///
+/// fn main() {
+///     // Doesn't return if this is the child process.
+///     text_module().serve_if_child();
+///
+///     let module = text_module()
+///         .spawn(LibraryPath::Directory("./plugins".as_ref()))
+///         .unwrap();
+///
+///     match module.call(TextMod_Ref::repeat, (RString::from("ab"), 3)) {
+///         Ok(repeated) => assert_eq!(repeated, "ababab"),
+///         Err(e) => eprintln!("the library crashed:\n{}", e),
+///     }
+/// }
+///
+/// ```
+///
+/// [`RemoteFn`]: ./trait.RemoteFn.html
+pub struct OutOfProcess<M> {
+    functions: Vec<(String, FunctionFn<M>)>,
+    timeout: Duration,
+}
+
+impl<M> OutOfProcess<M>
+where
+    M: RootModule,
+{
+    /// Constructs an `OutOfProcess` without any functions,
+    /// which waits for the child process for 30 seconds.
+    pub fn new() -> Self {
+        Self {
+            functions: Vec::new(),
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Sets how long the executable waits for the child process
+    /// to load the root module,to return from a call,
+    /// and to exit once the [`OutOfProcessModule`] is dropped,
+    /// killing the child process after that.
+    ///
+    /// [`OutOfProcessModule`]: ./struct.OutOfProcessModule.html
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Registers the function that `getter` returns from the root module,
+    /// so that [`OutOfProcessModule::call`] can call it by passing the same `getter`.
+    ///
+    /// `getter` is meant to be the method of the `M` prefix reference that returns the
+    /// function pointer (eg: `TextMod_Ref::repeat`),
+    /// since functions are told apart by the type of `getter`.
+    ///
+    /// # Panics
+    ///
+    /// This panics if a function was already registered with the same type of `getter`,
+    /// eg: if two closures defined in the same function were passed to this.
+    ///
+    /// [`OutOfProcessModule::call`]: ./struct.OutOfProcessModule.html#method.call
+    pub fn function<G, F>(mut self, getter: G) -> Self
+    where
+        G: Fn(&M) -> F + Send + Sync + 'static,
+        F: RemoteFn,
+    {
+        let key = function_key::<G, F>();
+        assert!(
+            self.functions.iter().all(|(other, _)| *other != key),
+            "the `{}` function was already registered",
+            key,
+        );
+
+        let function = move |module: M, args: Value| {
+            let args = serde_json::from_value::<F::Args>(args)
+                .map_err(|e| format!("could not deserialize the arguments:\n{}", e))?;
+            serde_json::to_value(getter(&module).call_with(args))
+                .map_err(|e| format!("could not serialize the return value:\n{}", e))
+        };
+        self.functions.push((key, Box::new(function)));
+        self
+    }
+
+    /// Launches a child process that loads the root module from the library at `where_`.
+    ///
+    /// The child process runs the current executable,
+    /// inheriting its environment variables,stdout,and stderr.
+    ///
+    /// # Errors
+    ///
+    /// This returns these errors:
+    ///
+    /// - `LibraryError::IoError`:
+    /// If the child process or the socket to communicate with it could not be created.
+    ///
+    /// - `LibraryError::OutOfProcess`:
+    /// If the child process could not load the root module,
+    /// with the error that it reported.
+    ///
+    /// - `LibraryError::ChildProcessExited`:
+    /// If the child process exited before loading the root module,
+    /// eg: because the executable doesn't call `serve_if_child`.
+    ///
+    /// - `LibraryError::ChildProcessTimedOut`:
+    /// If the child process didn't load the root module before the [timeout](#method.timeout).
+    pub fn spawn(&self, where_: LibraryPath<'_>) -> Result<OutOfProcessModule<M>, LibraryError> {
+        load_library_with::<M, _, _>(where_, |path| self.spawn_at(path)).map(|(_, x)| x)
+    }
+
+    fn spawn_at(&self, path: &Path) -> Result<OutOfProcessModule<M>, LibraryError> {
+        let io_error = |err| LibraryError::IoError {
+            path: path.to_owned(),
+            err,
+        };
+
+        let socket = SocketDir::new().map_err(io_error)?;
+        let listener = UnixListener::bind(&socket.socket).map_err(io_error)?;
+        listener.set_nonblocking(true).map_err(io_error)?;
+
+        let child = Command::new(env::current_exe().map_err(io_error)?)
+            .env(LIBRARY_ENV_VAR, path)
+            .env(MODULE_ENV_VAR, M::NAME)
+            .env(SOCKET_ENV_VAR, &socket.socket)
+            .stdin(Stdio::null())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(io_error)?;
+
+        let mut process = ChildProcess {
+            child,
+            stream: None,
+            timeout: self.timeout,
+        };
+
+        let stream = process.accept::<M>(path, &listener)?;
+        drop((listener, socket));
+        let configured = stream
+            .set_nonblocking(false)
+            .and_then(|_| stream.set_read_timeout(Some(self.timeout)))
+            .and_then(|_| stream.set_write_timeout(Some(self.timeout)));
+        if let Err(e) = configured {
+            process.kill();
+            return Err(io_error(e));
+        }
+        process.stream = Some(BufReader::new(stream));
+
+        let module = OutOfProcessModule {
+            path: path.to_owned(),
+            process: Mutex::new(process),
+            _marker: PhantomData,
+        };
+
+        match module.process.lock().receive::<M>(path)? {
+            Response::Loaded => {}
+            Response::Failed(message) => return Err(out_of_process_error::<M>(path, message)),
+            Response::Returned(_) => {
+                let message = "the child process returned before loading the library".into();
+                return Err(out_of_process_error::<M>(path, message));
+            }
+        }
+        Ok(module)
+    }
+
+    /// Loads the root module and serves the calls from the executable,
+    /// if this is a child process launched by [`spawn`](#method.spawn) for `M`,
+    /// exiting the process once the executable drops the [`OutOfProcessModule`].
+    ///
+    /// Otherwise this returns immediately.
+    ///
+    /// [`OutOfProcessModule`]: ./struct.OutOfProcessModule.html
+    pub fn serve_if_child(&self) {
+        let (path, socket) = match (env::var_os(LIBRARY_ENV_VAR), env::var_os(SOCKET_ENV_VAR)) {
+            (Some(path), Some(socket))
+                if env::var(MODULE_ENV_VAR).map_or(false, |name| name == M::NAME) =>
+            {
+                (PathBuf::from(path), PathBuf::from(socket))
+            }
+            _ => return,
+        };
+        // So that processes launched by the library aren't mistaken for child processes.
+        env::remove_var(LIBRARY_ENV_VAR);
+        env::remove_var(MODULE_ENV_VAR);
+        env::remove_var(SOCKET_ENV_VAR);
+
+        let code = match self.serve(&path, &socket) {
+            Ok(()) => 0,
+            Err(_) => 1,
+        };
+        std::process::exit(code)
+    }
+
+    fn serve(&self, path: &Path, socket: &Path) -> io::Result<()> {
+        let mut stream = UnixStream::connect(socket)?;
+
+        let module = match M::load_from_file(path) {
+            Ok(module) => {
+                respond(&mut stream, &Response::Loaded)?;
+                module
+            }
+            Err(e) => return respond(&mut stream, &Response::Failed(e.to_string())),
+        };
+
+        // Stops once the executable closes the socket.
+        for line in BufReader::new(stream.try_clone()?).lines() {
+            let response = match serde_json::from_str::<Request>(&line?) {
+                Ok(request) => self.call_function(module, request),
+                Err(e) => Response::Failed(format!("could not deserialize the request:\n{}", e)),
+            };
+            respond(&mut stream, &response)?;
+        }
+        Ok(())
+    }
+
+    fn call_function(&self, module: M, request: Request) -> Response {
+        let function = self
+            .functions
+            .iter()
+            .find(|(key, _)| *key == request.function);
+
+        match function {
+            Some((_, function)) => match function(module, request.args) {
+                Ok(ret) => Response::Returned(ret),
+                Err(e) => Response::Failed(format!("'{}': {}", request.function, e)),
+            },
+            None => Response::Failed(format!(
+                "the `{}` function is not registered in the child process",
+                request.function
+            )),
+        }
+    }
+}
+
+impl<M> Default for OutOfProcess<M>
+where
+    M: RootModule,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M> fmt::Debug for OutOfProcess<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OutOfProcess")
+            .field(
+                "functions",
+                &self
+                    .functions
+                    .iter()
+                    .map(|(key, _)| key)
+                    .collect::<Vec<_>>(),
+            )
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+fn respond(stream: &mut UnixStream, response: &Response) -> io::Result<()> {
+    let json = serde_json::to_string(response).map_err(io::Error::from)?;
+    writeln!(stream, "{}", json)?;
+    stream.flush()
+}
+
+fn out_of_process_error<M: RootModule>(path: &Path, message: String) -> LibraryError {
+    LibraryError::OutOfProcess {
+        module_name: M::NAME,
+        path: path.to_owned(),
+        message,
+    }
+}
+
+/// A directory that only the current user can access,
+/// containing the socket that the executable listens on,
+/// which are removed once the child process connects to it,or fails to.
+///
+/// The socket is in a new directory with a random name,
+/// so that other users can't create a file at the path of the socket before it is bound.
+struct SocketDir {
+    dir: PathBuf,
+    socket: PathBuf,
+}
+
+impl SocketDir {
+    fn new() -> io::Result<Self> {
+        const ATTEMPTS: u32 = 16;
+
+        let temp_dir = env::temp_dir();
+        let mut attempt = 0;
+        loop {
+            let dir_name = format!("abi_stable-{}-{:016x}", process::id(), random_u64());
+            let dir = temp_dir.join(dir_name);
+            match fs::DirBuilder::new().mode(0o700).create(&dir) {
+                Ok(()) => {
+                    let socket = dir.join("socket");
+                    return Ok(Self { dir, socket });
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists && attempt < ATTEMPTS => {
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl Drop for SocketDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.socket);
+        let _ = fs::remove_dir(&self.dir);
+    }
+}
+
+/// A random number,from the randomly seeded keys of `RandomState`.
+fn random_u64() -> u64 {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_usize(COUNTER.fetch_add(1, Ordering::Relaxed));
+    hasher.finish()
+}
+
+//////////////////////////////////////////////////////////////////////
+
+/// A root module loaded in a child process by [`OutOfProcess::spawn`],
+/// whose functions are called with [`call`](#method.call).
+///
+/// Dropping this closes the socket to the child process and waits for it to exit,
+/// killing it if it doesn't exit before the [timeout](./struct.OutOfProcess.html#method.timeout).
+///
+/// [`OutOfProcess::spawn`]: ./struct.OutOfProcess.html#method.spawn
+pub struct OutOfProcessModule<M> {
+    path: PathBuf,
+    process: Mutex<ChildProcess>,
+    _marker: PhantomData<fn() -> M>,
+}
+
+struct ChildProcess {
+    child: Child,
+    /// None once the child process exited or was killed.
+    stream: Option<BufReader<UnixStream>>,
+    timeout: Duration,
+}
+
+impl<M> OutOfProcessModule<M>
+where
+    M: RootModule,
+{
+    /// The path of the library that the child process loaded.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Calls the function that `getter` returns from the root module in the child process,
+    /// waiting for it to return.
+    ///
+    /// `getter` must be registered with [`OutOfProcess::function`].
+    ///
+    /// # Errors
+    ///
+    /// This returns these errors:
+    ///
+    /// - `LibraryError::OutOfProcess`:
+    /// If the function isn't registered,
+    /// the arguments or return value could not be (de)serialized,
+    /// or the child process could not be communicated with.
+    ///
+    /// - `LibraryError::ChildProcessExited`:
+    /// If the child process exited (eg: because the library crashed) before returning,
+    /// every later call returns this error too.
+    ///
+    /// - `LibraryError::ChildProcessTimedOut`:
+    /// If the function didn't return before the
+    /// [timeout](./struct.OutOfProcess.html#method.timeout),
+    /// which kills the child process,
+    /// every later call returns a `LibraryError::ChildProcessExited`.
+    ///
+    /// [`OutOfProcess::function`]: ./struct.OutOfProcess.html#method.function
+    pub fn call<G, F>(&self, getter: G, args: F::Args) -> Result<F::Output, LibraryError>
+    where
+        G: Fn(&M) -> F,
+        F: RemoteFn,
+    {
+        // The getter is only used for its type,the function is called by the child process.
+        let _ = getter;
+
+        let function = function_key::<G, F>();
+        let args = serde_json::to_value(args).map_err(|e| {
+            let message = format!(
+                "could not serialize the arguments of `{}`:\n{}",
+                function, e
+            );
+            out_of_process_error::<M>(&self.path, message)
+        })?;
+        let request = Request { function, args };
+
+        let mut process = self.process.lock();
+        process.send::<M>(&self.path, &request)?;
+
+        match process.receive::<M>(&self.path)? {
+            Response::Returned(ret) => serde_json::from_value::<F::Output>(ret).map_err(|e| {
+                let message = format!(
+                    "could not deserialize the return value of `{}`:\n{}",
+                    request.function, e
+                );
+                out_of_process_error::<M>(&self.path, message)
+            }),
+            Response::Failed(message) => Err(out_of_process_error::<M>(&self.path, message)),
+            Response::Loaded => {
+                let message = "the child process loaded the library again".into();
+                Err(out_of_process_error::<M>(&self.path, message))
+            }
+        }
+    }
+}
+
+impl ChildProcess {
+    /// Waits for the child process to connect to the socket.
+    fn accept<M: RootModule>(
+        &mut self,
+        path: &Path,
+        listener: &UnixListener,
+    ) -> Result<UnixStream, LibraryError> {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            match listener.accept() {
+                Ok((stream, _)) => return Ok(stream),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(e) => {
+                    self.kill();
+                    return Err(out_of_process_error::<M>(path, e.to_string()));
+                }
+            }
+
+            match self.child.try_wait() {
+                Ok(Some(status)) => {
+                    return Err(LibraryError::ChildProcessExited {
+                        module_name: M::NAME,
+                        path: path.to_owned(),
+                        status,
+                    })
+                }
+                Ok(None) if Instant::now() < deadline => thread::sleep(POLL_INTERVAL),
+                Ok(None) => return Err(self.timed_out::<M>(path)),
+                Err(e) => {
+                    self.kill();
+                    return Err(out_of_process_error::<M>(path, e.to_string()));
+                }
+            }
+        }
+    }
+
+    fn send<M: RootModule>(&mut self, path: &Path, request: &Request) -> Result<(), LibraryError> {
+        let json = serde_json::to_string(request).map_err(io::Error::from);
+        let written = match (&mut self.stream, json) {
+            (Some(stream), Ok(json)) => {
+                let stream = stream.get_mut();
+                writeln!(stream, "{}", json).and_then(|_| stream.flush())
+            }
+            (None, _) => Err(io::ErrorKind::BrokenPipe.into()),
+            (_, Err(e)) => Err(e),
+        };
+        match written {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Err(self.exited::<M>(path)),
+            Err(e) if is_timeout(&e) => Err(self.timed_out::<M>(path)),
+            Err(e) => Err(out_of_process_error::<M>(path, e.to_string())),
+        }
+    }
+
+    fn receive<M: RootModule>(&mut self, path: &Path) -> Result<Response, LibraryError> {
+        let mut line = String::new();
+        let read = match &mut self.stream {
+            Some(stream) => stream.read_line(&mut line),
+            None => Ok(0),
+        };
+        match read {
+            Ok(0) => Err(self.exited::<M>(path)),
+            Ok(_) => serde_json::from_str::<Response>(&line).map_err(|e| {
+                let message = format!("could not deserialize the response:\n{}", e);
+                out_of_process_error::<M>(path, message)
+            }),
+            Err(e) if is_timeout(&e) => Err(self.timed_out::<M>(path)),
+            Err(e) => Err(out_of_process_error::<M>(path, e.to_string())),
+        }
+    }
+
+    fn exited<M: RootModule>(&mut self, path: &Path) -> LibraryError {
+        self.stream = None;
+        match self.child.wait() {
+            Ok(status) => LibraryError::ChildProcessExited {
+                module_name: M::NAME,
+                path: path.to_owned(),
+                status,
+            },
+            Err(e) => out_of_process_error::<M>(path, e.to_string()),
+        }
+    }
+
+    fn timed_out<M: RootModule>(&mut self, path: &Path) -> LibraryError {
+        self.kill();
+        LibraryError::ChildProcessTimedOut {
+            module_name: M::NAME,
+            path: path.to_owned(),
+            timeout: self.timeout,
+        }
+    }
+
+    fn kill(&mut self) {
+        self.stream = None;
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+impl<M> Drop for OutOfProcessModule<M> {
+    fn drop(&mut self) {
+        let process = self.process.get_mut();
+        // Closing the socket makes the child process exit.
+        process.stream = None;
+
+        let deadline = Instant::now() + process.timeout;
+        while let Ok(None) = process.child.try_wait() {
+            if Instant::now() >= deadline {
+                process.kill();
+                return;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+impl<M> fmt::Debug for OutOfProcessModule<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OutOfProcessModule")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn socket_dir_test() {
+        let first = SocketDir::new().unwrap();
+        let second = SocketDir::new().unwrap();
+        assert_ne!(first.dir, second.dir);
+        assert_eq!(first.socket.parent(), Some(&*first.dir));
+
+        let mode = fs::metadata(&first.dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+
+        let listener = UnixListener::bind(&first.socket).unwrap();
+        UnixStream::connect(&first.socket).unwrap();
+        drop(listener);
+
+        let dir = first.dir.clone();
+        drop(first);
+        assert!(!dir.exists());
+    }
+}
@@ -0,0 +1,220 @@
+use super::*;
+
+use crate::std_types::{RNone, ROption};
+
+use parking_lot::{const_rwlock, RwLock};
+
+use std::{
+    any::Any,
+    fmt::{self, Display},
+    panic::{self, Location},
+    sync::{Arc, Once},
+    thread,
+};
+
+/// The type of the function that libraries forward their panics to,
+/// stored in the `Globals` that the executable shares with every library.
+pub type PanicHookFn = extern "C" fn(&PanicHookInfo<'_>);
+
+/// The type of the hook that receives the panics of dynamic libraries,
+/// set with [`set_plugin_panic_hook`].
+pub type PluginPanicHook = Arc<dyn Fn(&PanicHookInfo<'_>) + Send + Sync + 'static>;
+
+/// An ffi-safe description of a panic in a dynamic library,
+/// passed to the hook set with [`set_plugin_panic_hook`].
+///
+/// This is displayed in the same format as the default panic hook.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, StableAbi)]
+pub struct PanicHookInfo<'a> {
+    message: RStr<'a>,
+    location: ROption<PanicLocation<'a>>,
+    thread_name: ROption<RStr<'a>>,
+}
+
+impl<'a> PanicHookInfo<'a> {
+    /// The message of the panic,
+    /// which is `"Box<dyn Any>"` if the panic payload isn't a string.
+    pub const fn message(&self) -> RStr<'a> {
+        self.message
+    }
+
+    /// The location of the panic,if it's known.
+    pub fn location(&self) -> Option<PanicLocation<'a>> {
+        self.location.into_option()
+    }
+
+    /// The name of the thread that panicked,
+    /// `None` if the thread is unnamed.
+    pub fn thread_name(&self) -> Option<RStr<'a>> {
+        self.thread_name.into_option()
+    }
+}
+
+impl Display for PanicHookInfo<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let thread_name = self.thread_name.map_or("<unnamed>", |name| name.as_str());
+        write!(f, "thread '{}' panicked", thread_name)?;
+        if let Some(location) = self.location() {
+            write!(f, " at {}", location)?;
+        }
+        write!(f, ":\n{}", self.message)
+    }
+}
+
+/// The source location of a panic in a dynamic library.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, StableAbi)]
+pub struct PanicLocation<'a> {
+    file: RStr<'a>,
+    line: u32,
+    column: u32,
+}
+
+impl<'a> PanicLocation<'a> {
+    /// The path of the source file that the panic happened in.
+    pub const fn file(&self) -> RStr<'a> {
+        self.file
+    }
+
+    /// The line that the panic happened at.
+    pub const fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// The column that the panic happened at.
+    pub const fn column(&self) -> u32 {
+        self.column
+    }
+}
+
+impl<'a> From<&'a Location<'a>> for PanicLocation<'a> {
+    fn from(location: &'a Location<'a>) -> Self {
+        Self {
+            file: RStr::from_str(location.file()),
+            line: location.line(),
+            column: location.column(),
+        }
+    }
+}
+
+impl Display for PanicLocation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+//////////////////////////////////////////////////////////////////////
+
+/// Sets the panic hook of this library to one that forwards every panic to
+/// the `panic_hook` of `globals`.
+///
+/// This replaces any panic hook that the library set before its root module was loaded,
+/// and a hook that the library sets afterwards stops its panics from being forwarded.
+pub(super) fn install_forwarding_hook(globals: &'static Globals) {
+    static INSTALL: Once = Once::new();
+
+    INSTALL.call_once(|| {
+        let forward = globals.panic_hook;
+        panic::set_hook(Box::new(move |info| {
+            forward_panic(forward, info.payload(), info.location());
+        }));
+    });
+}
+
+fn forward_panic(
+    forward: PanicHookFn,
+    payload: &(dyn Any + Send),
+    location: Option<&Location<'_>>,
+) {
+    let message = utils::panic_payload_message(payload);
+    let thread = thread::current();
+
+    forward(&PanicHookInfo {
+        message: message.as_rstr(),
+        location: location.map(PanicLocation::from).into(),
+        thread_name: thread.name().map(RStr::from_str).into(),
+    });
+}
+
+//////////////////////////////////////////////////////////////////////
+
+static PLUGIN_PANIC_HOOK: RwLock<Option<PluginPanicHook>> = const_rwlock(None);
+
+/// Sets the hook that receives the panics of the dynamic libraries that this
+/// executable loads,replacing the previous one.
+///
+/// Each dynamic library has its own copy of the panic hook state of `std`,
+/// so `std::panic::set_hook` in the executable doesn't affect panics in libraries.
+/// Libraries that export a root module with
+/// [`#[export_root_module]`](../attr.export_root_module.html)
+/// forward their panics to this hook once their root module is loaded.
+///
+/// Forwarding the panics of a library replaces the panic hook that the library set
+/// with `std::panic::set_hook` before its root module was loaded,without any warning.
+/// If the library sets a panic hook after that,its panics are no longer forwarded.
+///
+/// Until a hook is set,forwarded panics are printed to stderr in
+/// the same format as the default panic hook.
+///
+/// The hook is not called while the hook is locked,
+/// so it can call `set_plugin_panic_hook` and `take_plugin_panic_hook`.
+///
+/// # Example
+///
+/// ```rust
+/// use abi_stable::library::set_plugin_panic_hook;
+///
+/// set_plugin_panic_hook(|info| {
+///     let location = info.location().map(|l| l.to_string());
+///     eprintln!("plugin panic at {:?}: {}", location, info.message());
+/// });
+///
+/// ```
+pub fn set_plugin_panic_hook<F>(hook: F)
+where
+    F: Fn(&PanicHookInfo<'_>) + Send + Sync + 'static,
+{
+    *PLUGIN_PANIC_HOOK.write() = Some(Arc::new(hook));
+}
+
+/// Removes the hook set with [`set_plugin_panic_hook`],returning it.
+pub fn take_plugin_panic_hook() -> Option<PluginPanicHook> {
+    PLUGIN_PANIC_HOOK.write().take()
+}
+
+/// The `panic_hook` of the `Globals` of the executable.
+pub(crate) extern "C" fn forward_to_plugin_panic_hook(info: &PanicHookInfo<'_>) {
+    extern_fn_panic_handling! {
+        // The copy of `std` in the library only knows the names of
+        // the threads that it spawned,so the executable fills in the name
+        // of the threads that it spawned.
+        let thread = thread::current();
+        let info = PanicHookInfo {
+            thread_name: match info.thread_name {
+                RSome(name) => RSome(name),
+                RNone => thread.name().map(RStr::from_str).into(),
+            },
+            ..*info
+        };
+
+        // Cloning the hook so that it's called after the lock is released,
+        // otherwise setting the hook inside the hook would deadlock.
+        let hook = PLUGIN_PANIC_HOOK.read().clone();
+        match hook {
+            Some(hook) => hook(&info),
+            None => eprintln!("{}", info),
+        }
+    }
+}
+
+/// Calls the panic hook of the executable with a panic that didn't happen,
+/// for testing.
+#[cfg(test)]
+pub(super) fn forward_test_panic(message: &str, location: &Location<'_>) {
+    forward_panic(
+        forward_to_plugin_panic_hook,
+        &message.to_owned(),
+        Some(location),
+    );
+}
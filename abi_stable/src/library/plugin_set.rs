@@ -0,0 +1,332 @@
+use super::{
+    dependencies::{check_declared_dependencies, register_loaded_root_module},
+    root_mod_trait::{
+        install_library_globals, load_library_with, root_module_header_from_raw_library,
+    },
+    shutdown::{register_shutdown_hook, shutdown_registered},
+    *,
+};
+
+use crate::{std_types::RVec, utils::leak_value};
+
+use std::{collections::HashMap, fmt};
+
+/// A set of root modules of the same type,loaded from different dynamic libraries.
+///
+/// Unlike [`RootModule::load_from`],which can only load a single library
+/// for every root module type,
+/// this loads any number of libraries that export an `M` root module.
+///
+/// Loaded libraries are keyed by their path,
+/// loading the same path again returns the already loaded root module.
+/// They are also indexed by the name of the root module that each library declares,
+/// which can be used to look them up with [`get_by_name`](#method.get_by_name).
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use abi_stable::{
+///     for_examples::Module_Ref,
+///     library::{LibraryError, PluginSet},
+/// };
+///
+/// let mut plugins = PluginSet::<Module_Ref>::new();
+///
+/// let res = plugins.load_all(&["plugins/libfoo.so", "plugins/libbar.so"]);
+///
+/// // Failing to load a library doesn't prevent the other ones from being loaded.
+/// if let Err(LibraryError::Many(errors)) = res {
+///     for err in errors {
+///         eprintln!("{}", err);
+///     }
+/// }
+///
+/// for plugin in plugins.iter() {
+///     println!("{}: {:?}", plugin.path().display(), plugin.module().third());
+/// }
+///
+/// ```
+pub struct PluginSet<M> {
+    plugins: Vec<LoadedPlugin<M>>,
+    indices: HashMap<PathBuf, usize>,
+    name_indices: HashMap<&'static str, Vec<usize>>,
+}
+
+/// A root module in a [`PluginSet`],
+/// with metadata about the library it was loaded from.
+#[derive(Clone)]
+pub struct LoadedPlugin<M> {
+    path: PathBuf,
+    lib_header: &'static LibHeader,
+    module: M,
+}
+
+enum Loaded<M> {
+    Existing(usize),
+    New(LoadedPlugin<M>),
+}
+
+impl<M> LoadedPlugin<M> {
+    /// The path that the library was loaded from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The name of the root module,as declared by the library.
+    pub fn name(&self) -> &'static str {
+        self.lib_header.root_mod_consts().name().as_str()
+    }
+
+    /// The version of the root module,as declared by the library.
+    pub const fn version_strings(&self) -> VersionStrings {
+        self.lib_header.version_strings()
+    }
+
+    /// The header of the library that the root module was loaded from.
+    pub const fn lib_header(&self) -> &'static LibHeader {
+        self.lib_header
+    }
+
+    /// Gets the statistics of the allocations made by the library,
+    /// as described in [`LibHeader::allocation_stats`].
+    ///
+    /// [`LibHeader::allocation_stats`]: ./struct.LibHeader.html#method.allocation_stats
+    pub fn allocation_stats(&self) -> Option<AllocationStats> {
+        self.lib_header.allocation_stats()
+    }
+
+    /// The root module.
+    pub fn module(&self) -> M
+    where
+        M: Copy,
+    {
+        self.module
+    }
+
+    /// Calls the shutdown hook of the root module,
+    /// the same way that [`RootModule::shutdown`] does.
+    ///
+    /// [`RootModule::shutdown`]: ./trait.RootModule.html#method.shutdown
+    pub fn shutdown(&self) -> Result<(), LibraryError> {
+        shutdown_registered(self.lib_header)
+    }
+}
+
+impl<M> fmt::Debug for LoadedPlugin<M>
+where
+    M: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoadedPlugin")
+            .field("path", &self.path)
+            .field("name", &self.name())
+            .field("version_strings", &self.version_strings())
+            .field("module", &self.module)
+            .finish()
+    }
+}
+
+impl<M> PluginSet<M>
+where
+    M: RootModule,
+{
+    /// Constructs an empty `PluginSet`.
+    pub fn new() -> Self {
+        Self {
+            plugins: Vec::new(),
+            indices: HashMap::new(),
+            name_indices: HashMap::new(),
+        }
+    }
+
+    /// Loads the root module of the library at `where_`,
+    /// returning the already loaded one if the library is in this set.
+    ///
+    /// Like [`RootModule::load_from`],this leaks the library.
+    ///
+    /// # Errors
+    ///
+    /// This returns the same errors as [`RootModule::load_from`].
+    pub fn load_from(&mut self, where_: LibraryPath<'_>) -> Result<&LoadedPlugin<M>, LibraryError> {
+        let indices = &self.indices;
+        let (_, loaded) = load_library_with::<M, _, _>(where_, |path| {
+            let key = plugin_key(path);
+
+            if let Some(&index) = indices.get(&key) {
+                return Ok(Loaded::Existing(index));
+            }
+
+            load_plugin(path, key).map(Loaded::New)
+        })?;
+
+        let index = match loaded {
+            Loaded::Existing(index) => index,
+            Loaded::New(plugin) => self.insert(plugin),
+        };
+
+        Ok(&self.plugins[index])
+    }
+
+    /// Adds a plugin that isn't in this set yet,returning its index.
+    pub(super) fn insert(&mut self, plugin: LoadedPlugin<M>) -> usize {
+        let index = self.plugins.len();
+        self.indices.insert(plugin.path.clone(), index);
+        self.name_indices
+            .entry(plugin.name())
+            .or_default()
+            .push(index);
+        self.plugins.push(plugin);
+        index
+    }
+
+    /// Loads the root module of the library at `path`,
+    /// returning the already loaded one if the library is in this set.
+    ///
+    /// Errors are detailed in [`load_from`](#method.load_from).
+    pub fn load_from_file(&mut self, path: &Path) -> Result<&LoadedPlugin<M>, LibraryError> {
+        self.load_from(LibraryPath::FullPath(path))
+    }
+
+    /// Loads the root modules of all the libraries at `paths`,
+    /// continuing after a library fails to load.
+    ///
+    /// # Errors
+    ///
+    /// If any library failed to load,this returns a `LibraryError::Many`,
+    /// with a `LibraryError::AtPath` for each library that failed to load.
+    pub fn load_all<I>(&mut self, paths: I) -> Result<(), LibraryError>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<Path>,
+    {
+        let mut errors = RVec::new();
+
+        for path in paths {
+            let path = path.as_ref();
+            if let Err(err) = self.load_from_file(path) {
+                errors.push(LibraryError::AtPath {
+                    path: path.to_owned(),
+                    err: Box::new(err),
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(LibraryError::Many(errors))
+        }
+    }
+
+    /// Gets the root module loaded from the library at `path`.
+    ///
+    /// `path` is canonicalized the same way as when the library was loaded,
+    /// falling back to `path` itself if it could not be canonicalized.
+    pub fn get(&self, path: &Path) -> Option<&LoadedPlugin<M>> {
+        let key = plugin_key(path);
+        self.indices.get(&key).map(|&i| &self.plugins[i])
+    }
+
+    /// Iterates over the root modules whose library declares `name` as
+    /// the name of the root module,in the order that they were loaded.
+    pub fn get_by_name<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a LoadedPlugin<M>> {
+        self.name_indices
+            .get(name)
+            .map_or(&[][..], |indices| &indices[..])
+            .iter()
+            .map(move |&i| &self.plugins[i])
+    }
+
+    /// Iterates over the loaded root modules,in the order that they were loaded.
+    pub fn iter(&self) -> std::slice::Iter<'_, LoadedPlugin<M>> {
+        self.plugins.iter()
+    }
+
+    /// How many root modules are loaded.
+    pub fn len(&self) -> usize {
+        self.plugins.len()
+    }
+
+    /// Whether no root module is loaded.
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+}
+
+/// The path that a library is keyed by in a `PluginSet`.
+pub(super) fn plugin_key(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_owned())
+}
+
+/// Loads the root module of the library at `path`,keyed by `key`.
+pub(super) fn load_plugin<M>(path: &Path, key: PathBuf) -> Result<LoadedPlugin<M>, LibraryError>
+where
+    M: RootModule,
+{
+    let (raw_library, lib_header) = open_plugin::<M>(path)?;
+    init_plugin::<M>(raw_library, lib_header, key)
+}
+
+/// Opens the library at `path`,and gets the header of its `M` root module.
+///
+/// This leaks the library,because the root module refers to it.
+pub(super) fn open_plugin<M>(
+    path: &Path,
+) -> Result<(&'static RawLibrary, &'static LibHeader), LibraryError>
+where
+    M: RootModule,
+{
+    let raw_library = RawLibrary::load_at(path)?;
+    let lib_header = unsafe { root_module_header_from_raw_library::<M>(&raw_library)? };
+    Ok((leak_value(raw_library), lib_header))
+}
+
+/// Initializes the `M` root module of a library opened with `open_plugin`,keyed by `key`.
+pub(super) fn init_plugin<M>(
+    raw_library: &'static RawLibrary,
+    lib_header: &'static LibHeader,
+    key: PathBuf,
+) -> Result<LoadedPlugin<M>, LibraryError>
+where
+    M: RootModule,
+{
+    check_declared_dependencies(M::NAME, lib_header)?;
+    unsafe { install_library_globals(raw_library, M::EXPORT_NAME, lib_header)? };
+    let module = lib_header.init_root_module::<M>()?.initialization()?;
+    register_loaded_root_module(M::NAME, lib_header.version_strings());
+
+    register_shutdown_hook(lib_header);
+
+    Ok(LoadedPlugin {
+        path: key,
+        lib_header,
+        module,
+    })
+}
+
+impl<M> Default for PluginSet<M>
+where
+    M: RootModule,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, M> IntoIterator for &'a PluginSet<M> {
+    type Item = &'a LoadedPlugin<M>;
+    type IntoIter = std::slice::Iter<'a, LoadedPlugin<M>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.plugins.iter()
+    }
+}
+
+impl<M> fmt::Debug for PluginSet<M>
+where
+    M: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(&self.plugins).finish()
+    }
+}
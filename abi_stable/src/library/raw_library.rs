@@ -1,12 +1,24 @@
 use super::*;
 
+use abi_stable_shared::{is_valid_export_name, mangled_exported_item_name};
+
 use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
 
+#[cfg(target_os = "linux")]
+use std::{fs, io};
+
 /// A handle to any dynamically loaded library,
 /// not necessarily ones that export abi_stable compatible modules.
 pub struct RawLibrary {
     path: PathBuf,
     library: LibLoadingLibrary,
+    // The in-memory file that the library was loaded from,
+    // which is kept open while the library is loaded so that
+    // its `/proc/self/fd/*` path isn't reused by another file.
+    //
+    // This must be declared after `library` so that it's closed after the library is unloaded.
+    #[cfg(target_os = "linux")]
+    memfd: Option<fs::File>,
 }
 
 impl RawLibrary {
@@ -32,19 +44,81 @@ impl RawLibrary {
 
     /// Loads the dynamic library at the `full_path` path.
     pub fn load_at(full_path: &Path) -> Result<Self, LibraryError> {
+        Self::load_at_with(full_path, LoadOptions::new())
+    }
+
+    /// Loads the dynamic library at the `full_path` path,
+    /// passing the flags from `options` to `dlopen` on unix platforms.
+    ///
+    /// On non-unix platforms `options` is ignored.
+    pub fn load_at_with(full_path: &Path, options: LoadOptions) -> Result<Self, LibraryError> {
         // safety: not my problem if libraries have problematic static initializers
-        match unsafe { LibLoadingLibrary::new(full_path) } {
+        #[cfg(unix)]
+        let res = unsafe {
+            libloading::os::unix::Library::open(Some(full_path), options.dlopen_flags())
+                .map(LibLoadingLibrary::from)
+        };
+        #[cfg(not(unix))]
+        let res = unsafe { LibLoadingLibrary::new(full_path) };
+
+        match res {
             Ok(library) => Ok(Self {
                 path: full_path.to_owned(),
                 library,
+                #[cfg(target_os = "linux")]
+                memfd: None,
             }),
             Err(err) => Err(LibraryError::OpenError {
                 path: full_path.to_owned(),
+                options,
                 err: Box::new(err),
             }),
         }
     }
 
+    /// Loads a dynamic library from its contents in memory,
+    /// without writing it to the filesystem.
+    ///
+    /// This copies `bytes` into an anonymous in-memory file
+    /// (created with `memfd_create`),
+    /// and loads the library from its `/proc/self/fd/*` path.
+    ///
+    /// # Errors
+    ///
+    /// This returns these errors:
+    ///
+    /// - `LibraryError::IoError`:
+    /// If the in-memory file could not be created or written to.
+    ///
+    /// - `LibraryError::OpenError`:
+    /// If the library could not be loaded.
+    ///
+    #[cfg(target_os = "linux")]
+    #[cfg_attr(feature = "docsrs", doc(cfg(target_os = "linux")))]
+    pub fn load_from_bytes(bytes: &[u8]) -> Result<Self, LibraryError> {
+        Self::load_from_bytes_with(bytes, LoadOptions::new())
+    }
+
+    /// Loads a dynamic library from its contents in memory,
+    /// passing the flags from `options` to `dlopen`.
+    ///
+    /// Errors are detailed in [`load_from_bytes`](#method.load_from_bytes).
+    #[cfg(target_os = "linux")]
+    #[cfg_attr(feature = "docsrs", doc(cfg(target_os = "linux")))]
+    pub fn load_from_bytes_with(bytes: &[u8], options: LoadOptions) -> Result<Self, LibraryError> {
+        use std::os::unix::io::AsRawFd;
+
+        let memfd = memfd_with_contents(bytes).map_err(|err| LibraryError::IoError {
+            path: PathBuf::from("memfd:abi_stable_library"),
+            err,
+        })?;
+
+        let path = PathBuf::from(format!("/proc/self/fd/{}", memfd.as_raw_fd()));
+        let mut this = Self::load_at_with(&path, options)?;
+        this.memfd = Some(memfd);
+        Ok(this)
+    }
+
     /// Gets access to a static/function declared by the library.
     ///
     /// # Safety
@@ -67,4 +141,77 @@ impl RawLibrary {
             }
         }
     }
+
+    /// Gets a reference to the item that the library exported with
+    /// [`#[export_item(name = "...")]`](../attr.export_item.html),
+    /// checking that its type layout is compatible with the layout of `T`.
+    ///
+    /// # Errors
+    ///
+    /// This returns these errors:
+    ///
+    /// - `LibraryError::InvalidExportName`:
+    /// If `name` can't be the name of an exported item.
+    ///
+    /// - `LibraryError::GetSymbolError`:
+    /// If the library doesn't export an item named `name`.
+    ///
+    /// - `LibraryError::InvalidAbiHeader`:
+    /// If the abi_stable version used by the library is not compatible.
+    ///
+    /// - `LibraryError::AbiInstability`:
+    /// If the layout of the item is not compatible with the layout of `T`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use abi_stable::{library::RawLibrary, std_types::RStr};
+    ///
+    /// let library = RawLibrary::load_at("./plugins/libplugin.so".as_ref()).unwrap();
+    ///
+    /// // The library declares the item like this:
+    /// // ```
+    /// // #[abi_stable::export_item(name = "greeting")]
+    /// // static GREETING: RStr<'static> = RStr::from_str("hello");
+    /// // ```
+    /// let greeting = library.get_checked::<RStr<'static>>("greeting").unwrap();
+    ///
+    /// println!("{}", greeting);
+    ///
+    /// ```
+    pub fn get_checked<T>(&self, name: &str) -> Result<&T, LibraryError>
+    where
+        T: StableAbi,
+    {
+        if !is_valid_export_name(name) {
+            return Err(LibraryError::InvalidExportName {
+                name: name.to_owned(),
+            });
+        }
+
+        let mut symbol = mangled_exported_item_name(name);
+        symbol.push('\0');
+
+        // safety: the symbols that `mangled_exported_item_name` returns
+        // are always `ExportedItem` statics.
+        let item: &ExportedItem = unsafe { *self.get::<&ExportedItem>(symbol.as_bytes())? };
+
+        item.get::<T>()
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn memfd_with_contents(bytes: &[u8]) -> io::Result<fs::File> {
+    use std::{io::Write, os::unix::io::FromRawFd};
+
+    let name = b"abi_stable_library\0";
+    let fd = unsafe { libc::memfd_create(name.as_ptr().cast(), libc::MFD_CLOEXEC) };
+    if fd == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // safety: `fd` is a newly created file descriptor that nothing else owns.
+    let mut file = unsafe { fs::File::from_raw_fd(fd) };
+    file.write_all(bytes)?;
+    Ok(file)
 }
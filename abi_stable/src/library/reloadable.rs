@@ -0,0 +1,559 @@
+use super::{
+    allocation_tracking::unregister_allocation_tracker,
+    dependencies::check_declared_dependencies,
+    root_mod_trait::{
+        install_library_globals, lib_header_from_raw_library_with, load_library_with,
+    },
+    shutdown::run_shutdown_hook,
+    *,
+};
+
+use std::{
+    env, fmt, fs, process,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, RwLock, Weak,
+    },
+};
+
+/// A reference-counted handle to a dynamic library that can be unloaded,
+/// which is unloaded once every handle to it is dropped.
+///
+/// Libraries with this handle are loaded from a copy of the library file
+/// (which is deleted when the library is unloaded),
+/// so that the library can be replaced while a previous version is still loaded.
+///
+/// Values whose vtables or function pointers point into the library
+/// (eg: `RBox`/`RArc` from the library, `DynTrait`, `RObject`, function pointers)
+/// must not outlive the library,
+/// which is ensured by only accessing the root module with [`LibraryBound::with`],
+/// since it ties everything returned by the closure to the library.
+/// Other values can be tied to the library with the [`bind`](#method.bind) method.
+///
+/// # Safety concerns
+///
+/// Unloading a library is only sound if nothing refers to it,
+/// this includes values that are not tracked by `LibraryHandle`s,
+/// like threads spawned by the library,
+/// or `'static` references that the library handed out.
+///
+/// # Allocation tracking
+///
+/// If the library was built with the "allocation_tracking" feature,
+/// it's not unloaded while it still owns allocations
+/// (eg: an `RVec` that the library returned and the executable didn't drop,
+/// or one that the library stores in a static),
+/// since dropping them calls functions in the library.
+/// In that case the library is leaked instead,
+/// even though the `LibraryHandle`s to it were dropped.
+#[derive(Clone)]
+pub struct LibraryHandle {
+    inner: Arc<LibraryHandleInner>,
+}
+
+struct LibraryHandleInner {
+    path: PathBuf,
+    shadow_copy: PathBuf,
+    // This is an Option so that the library is unloaded before the
+    // shadow copy is deleted.
+    raw_library: Option<RawLibrary>,
+    lib_header: &'static LibHeader,
+    // Only set once the root module is loaded,
+    // so that the shutdown hook isn't called for root modules that failed to load.
+    root_module_loaded: AtomicBool,
+}
+
+/// A weak reference to a [`LibraryHandle`],
+/// which can be used to tell whether the library was unloaded.
+#[derive(Clone)]
+pub struct WeakLibraryHandle {
+    inner: Weak<LibraryHandleInner>,
+}
+
+/// A value that refers to a dynamic library,
+/// which keeps the library loaded while the value is alive.
+///
+/// The value can only be accessed inside the closures passed to
+/// [`with`](#method.with) and [`with_mut`](#method.with_mut),
+/// which tie the values they return to the same library,
+/// so that copies of the value (eg: of a root module,or of a function pointer)
+/// can't outlive the library.
+///
+/// Constructed with [`LibraryHandle::bind`].
+pub struct LibraryBound<T> {
+    // The value must be declared before the library,
+    // so that it is dropped before the library is.
+    value: T,
+    library: LibraryHandle,
+}
+
+/// The root module of a dynamic library that can be reloaded.
+///
+/// Constructed with [`RootModule::load_reloadable_from`] or
+/// [`ReloadableModule::load_from`].
+///
+/// # Reloading
+///
+/// [`reload`](#method.reload) loads the current version of the library,
+/// checks that it is compatible the same way that [`RootModule::load_from`] does,
+/// and then replaces the root module with the new one.
+///
+/// The previous library is unloaded once nothing refers to it anymore,
+/// which requires all the [`LibraryBound`]s and [`LibraryHandle`]s
+/// to it to be dropped.
+///
+/// # Differences with [`RootModule::load_from`]
+///
+/// - The root module is not stored in the
+/// [`RootModuleStatics`](./struct.RootModuleStatics.html) of `M`,
+/// so `M::get_module()` doesn't return it.
+///
+/// - The layout of the root module is checked without registering
+/// the types of the library in the global layout checking state,
+/// so prefix types and nonexhaustive enums are only checked for compatibility
+/// with the types in the loader.
+///
+/// - The [shutdown hook](./fn.shutdown_all.html) of the root module is called
+/// right before its library is unloaded,
+/// instead of by `RootModule::shutdown` or `shutdown_all`,
+/// ignoring any error that it returns.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use abi_stable::{
+///     library::{LibraryPath, RootModule},
+///     for_examples::Module_Ref,
+/// };
+///
+/// // safety: the values that point into the library are only used inside `with` closures,
+/// // or in the values that those closures return.
+/// let module = unsafe {
+///     Module_Ref::load_reloadable_from(LibraryPath::Directory("./plugins".as_ref()))
+/// }
+/// .unwrap();
+///
+/// {
+///     // Keeps the current version of the library loaded while `second` is alive,
+///     // because `with` ties the returned string (which points into the library)
+///     // to the library.
+///     let second = module.with(|module| module.second());
+///     second.with(|second| println!("{}", second));
+/// }
+///
+/// let previous = module.reload().unwrap();
+///
+/// // The previous version of the library is unloaded,
+/// // because nothing refers to it anymore.
+/// assert!(previous.is_unloaded());
+///
+/// ```
+pub struct ReloadableModule<M> {
+    path: PathBuf,
+    current: RwLock<LibraryBound<M>>,
+    generation: AtomicUsize,
+}
+
+//////////////////////////////////////////////////////////////////////
+
+impl LibraryHandle {
+    fn load(path: &Path, export_name: Option<&str>) -> Result<Self, LibraryError> {
+        let shadow_copy = shadow_copy_path(path);
+
+        fs::copy(path, &shadow_copy).map_err(|err| LibraryError::IoError {
+            path: path.to_owned(),
+            err,
+        })?;
+
+        let raw_library = match RawLibrary::load_at(&shadow_copy) {
+            Ok(x) => x,
+            Err(e) => {
+                let _ = fs::remove_file(&shadow_copy);
+                return Err(e);
+            }
+        };
+
+        let lib_header = unsafe { lib_header_from_raw_library_with(&raw_library, export_name) };
+        let lib_header = match lib_header {
+            Ok(x) => x,
+            Err(e) => {
+                drop(raw_library);
+                let _ = fs::remove_file(&shadow_copy);
+                return Err(e);
+            }
+        };
+
+        Ok(Self {
+            inner: Arc::new(LibraryHandleInner {
+                path: path.to_owned(),
+                shadow_copy,
+                raw_library: Some(raw_library),
+                lib_header,
+                root_module_loaded: AtomicBool::new(false),
+            }),
+        })
+    }
+
+    /// The path that the library was loaded from.
+    pub fn path(&self) -> &Path {
+        &self.inner.path
+    }
+
+    /// Gets the `RawLibrary` of the loaded copy of the library.
+    pub fn raw_library(&self) -> &RawLibrary {
+        self.inner
+            .raw_library
+            .as_ref()
+            .expect("BUG: the library was unloaded")
+    }
+
+    /// Gets the `LibHeader` of the library.
+    ///
+    /// The returned reference is only valid while the library is loaded.
+    pub fn lib_header(&self) -> &LibHeader {
+        self.inner.lib_header
+    }
+
+    /// Gets the statistics of the allocations made by the library,
+    /// as described in [`LibHeader::allocation_stats`].
+    ///
+    /// [`LibHeader::allocation_stats`]: ./struct.LibHeader.html#method.allocation_stats
+    pub fn allocation_stats(&self) -> Option<AllocationStats> {
+        self.inner.lib_header.allocation_stats()
+    }
+
+    /// How many `LibraryHandle`s (including the ones in `LibraryBound`s)
+    /// refer to this library.
+    pub fn reference_count(&self) -> usize {
+        Arc::strong_count(&self.inner)
+    }
+
+    /// Whether both handles refer to the same loaded library.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    /// Creates a weak reference to this library,
+    /// which doesn't keep it loaded.
+    pub fn downgrade(&self) -> WeakLibraryHandle {
+        WeakLibraryHandle {
+            inner: Arc::downgrade(&self.inner),
+        }
+    }
+
+    /// Ties `value` to this library,
+    /// keeping the library loaded until the returned `LibraryBound` is dropped.
+    ///
+    /// This is how values that point into the library
+    /// (eg: function pointers, `DynTrait`, `RObject`)
+    /// and were not returned by [`LibraryBound::with`]
+    /// are prevented from outliving it.
+    pub fn bind<T>(&self, value: T) -> LibraryBound<T> {
+        LibraryBound {
+            value,
+            library: self.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for LibraryHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LibraryHandle")
+            .field("path", &self.inner.path)
+            .field("shadow_copy", &self.inner.shadow_copy)
+            .field("reference_count", &self.reference_count())
+            .finish()
+    }
+}
+
+impl Drop for LibraryHandleInner {
+    fn drop(&mut self) {
+        if *self.root_module_loaded.get_mut() {
+            // There's nothing to report the error to.
+            let _ = run_shutdown_hook(self.lib_header);
+        }
+
+        let owns_allocations = self
+            .lib_header
+            .allocation_stats()
+            .map_or(false, |stats| stats.live_allocations() != 0);
+
+        if owns_allocations {
+            // Values allocated by the library would call into the unloaded library when dropped.
+            std::mem::forget(self.raw_library.take());
+        } else {
+            unregister_allocation_tracker(self.lib_header);
+            drop(self.raw_library.take());
+        }
+        let _ = fs::remove_file(&self.shadow_copy);
+    }
+}
+
+impl WeakLibraryHandle {
+    /// Gets a strong handle to the library,
+    /// returning None if the library was unloaded.
+    pub fn upgrade(&self) -> Option<LibraryHandle> {
+        self.inner.upgrade().map(|inner| LibraryHandle { inner })
+    }
+
+    /// Whether the library was unloaded.
+    pub fn is_unloaded(&self) -> bool {
+        self.inner.strong_count() == 0
+    }
+}
+
+impl fmt::Debug for WeakLibraryHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WeakLibraryHandle")
+            .field("is_unloaded", &self.is_unloaded())
+            .finish()
+    }
+}
+
+//////////////////////////////////////////////////////////////////////
+
+impl<T> LibraryBound<T> {
+    /// Gets the library that this value is tied to.
+    pub fn library(&self) -> &LibraryHandle {
+        &self.library
+    }
+
+    /// Calls `f` with a reference to the value,
+    /// tying the value it returns to the same library.
+    ///
+    /// This means that `RBox`es,`RArc`s,`DynTrait`s,`RObject`s,and function pointers
+    /// returned by calling functions of the library keep it loaded.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use abi_stable::{
+    ///     library::{LibraryPath, RootModule},
+    ///     for_examples::Module_Ref,
+    /// };
+    ///
+    /// let module = unsafe {
+    ///     Module_Ref::load_reloadable_from(LibraryPath::Directory("./plugins".as_ref()))
+    /// }
+    /// .unwrap();
+    ///
+    /// // Copying the root module out of the closure doesn't let it outlive the library,
+    /// // since the copy is tied to it.
+    /// let copy = module.get().with(|module| *module);
+    ///
+    /// // Values that don't point into the library can be
+    /// // assigned to variables outside the closure.
+    /// let mut third = None;
+    /// copy.with(|module| third = module.third());
+    ///
+    /// ```
+    pub fn with<F, R>(&self, f: F) -> LibraryBound<R>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        self.library.bind(f(&self.value))
+    }
+
+    /// Calls `f` with a mutable reference to the value,
+    /// tying the value it returns to the same library.
+    pub fn with_mut<F, R>(&mut self, f: F) -> LibraryBound<R>
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let ret = f(&mut self.value);
+        self.library.bind(ret)
+    }
+
+    /// Transforms the value,keeping it tied to the same library.
+    pub fn map<F, U>(self, f: F) -> LibraryBound<U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        LibraryBound {
+            value: f(self.value),
+            library: self.library,
+        }
+    }
+
+    /// Unwraps the value,no longer keeping the library loaded.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the value doesn't refer to the library
+    /// after it's unloaded.
+    pub unsafe fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: Clone> Clone for LibraryBound<T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            library: self.library.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for LibraryBound<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.value, f)
+    }
+}
+
+//////////////////////////////////////////////////////////////////////
+
+impl<M> ReloadableModule<M>
+where
+    M: RootModule,
+{
+    /// Loads the root module from the library at `where_`.
+    ///
+    /// # Safety
+    ///
+    /// The library can be unloaded while this program is running,
+    /// so values that point into the library must not outlive it.
+    ///
+    /// Values returned by [`LibraryBound::with`] are tied to the library,
+    /// but values that the closures passed to it store elsewhere
+    /// (eg: in a static,or in a variable that the closure captures) are not,
+    /// and neither are threads spawned by the library.
+    ///
+    /// # Errors
+    ///
+    /// This returns the same errors as [`RootModule::load_from`],
+    /// as well as `LibraryError::IoError` if the library could not be copied.
+    ///
+    /// Errors from checking the library after it's loaded are wrapped in a
+    /// `LibraryError::ReloadableLibrary`,
+    /// which keeps the library loaded since those errors can refer to data in it.
+    ///
+    /// With `LibraryPath::SearchPaths`,
+    /// the path of the first library that could be loaded is used for reloading.
+    pub unsafe fn load_from(where_: LibraryPath<'_>) -> Result<Self, LibraryError> {
+        let (path, current) = load_library_with::<M, _, _>(where_, load_reloadable_module::<M>)?;
+        Ok(Self {
+            path,
+            current: RwLock::new(current),
+            generation: AtomicUsize::new(0),
+        })
+    }
+
+    /// Gets the current version of the root module,
+    /// keeping its library loaded while the returned value is alive.
+    pub fn get(&self) -> LibraryBound<M> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Calls `f` with the current version of the root module,
+    /// tying the value it returns to the library,
+    /// as described in [`LibraryBound::with`].
+    pub fn with<F, R>(&self, f: F) -> LibraryBound<R>
+    where
+        F: FnOnce(&M) -> R,
+    {
+        self.current.read().unwrap().with(f)
+    }
+
+    /// Gets a handle to the current version of the library.
+    pub fn library(&self) -> LibraryHandle {
+        self.current.read().unwrap().library.clone()
+    }
+
+    /// The path that the library is loaded from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// How many times the library has been reloaded.
+    pub fn generation(&self) -> usize {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Loads the library again from the same path,
+    /// replacing the root module with the one from the newly loaded library.
+    ///
+    /// This returns a weak handle to the previous library,
+    /// which is unloaded once nothing refers to it.
+    ///
+    /// # Errors
+    ///
+    /// This returns the same errors as [`ReloadableModule::load_from`].
+    /// If an error is returned,the previous root module is kept.
+    pub fn reload(&self) -> Result<WeakLibraryHandle, LibraryError> {
+        let new = load_reloadable_module::<M>(&self.path)?;
+
+        let previous = std::mem::replace(&mut *self.current.write().unwrap(), new);
+        self.generation.fetch_add(1, Ordering::AcqRel);
+
+        let weak = previous.library.downgrade();
+        drop(previous);
+        Ok(weak)
+    }
+}
+
+impl<M> fmt::Debug for ReloadableModule<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReloadableModule")
+            .field("path", &self.path)
+            .field("generation", &self.generation.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+//////////////////////////////////////////////////////////////////////
+
+fn load_reloadable_module<M>(path: &Path) -> Result<LibraryBound<M>, LibraryError>
+where
+    M: RootModule,
+{
+    let library = LibraryHandle::load(path, M::EXPORT_NAME)?;
+    let lib_header = library.inner.lib_header;
+
+    let module = (|| {
+        lib_header.ensure_layout_in_isolation::<M>()?;
+        check_declared_dependencies(M::NAME, lib_header)?;
+        unsafe { install_library_globals(library.raw_library(), M::EXPORT_NAME, lib_header)? };
+
+        // safety: the layout was checked in the code above,
+        unsafe {
+            lib_header
+                .init_root_module_with_unchecked_layout::<M>()?
+                .initialization()
+        }
+    })();
+
+    // The error can refer to data in the library,
+    // so the library is kept loaded until the error is dropped.
+    let module = module.map_err(|err| LibraryError::ReloadableLibrary {
+        library: library.clone(),
+        err: Box::new(err),
+    })?;
+
+    library
+        .inner
+        .root_module_loaded
+        .store(true, Ordering::Release);
+
+    Ok(library.bind(module))
+}
+
+/// Gets a unique path in the temporary directory to copy the library to.
+///
+/// Copying the library is necessary because dynamic loaders return
+/// the already loaded library when loading the same path again.
+fn shadow_copy_path(path: &Path) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let file_name = path
+        .file_name()
+        .map_or_else(Default::default, |x| x.to_string_lossy());
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    env::temp_dir().join(format!(
+        "abi_stable-{}-{}-{}",
+        process::id(),
+        count,
+        file_name
+    ))
+}
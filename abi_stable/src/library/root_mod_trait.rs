@@ -1,7 +1,18 @@
-use super::*;
+use super::{
+    allocation_tracking::register_allocation_tracker,
+    context_loader::context_loader_from_raw_library,
+    dependencies::{
+        check_declared_dependencies, load_with_dependencies, register_loaded_root_module,
+    },
+    globals_installer::install_globals_in_library,
+    shutdown::{register_shutdown_hook, shutdown_registered},
+    *,
+};
 
 use crate::{prefix_type::PrefixRefTrait, utils::leak_value};
 
+use abi_stable_shared::{is_valid_export_name, mangled_named_root_module_loader_name};
+
 /// The root module of a dynamic library,
 /// which may contain other modules,function pointers,and static references.
 ///
@@ -53,6 +64,93 @@ pub trait RootModule: Sized + StableAbi + PrefixRefTrait + 'static {
     /// [`package_version_strings!()`](../macro.package_version_strings.html)
     const VERSION_STRINGS: VersionStrings;
 
+    /// The versions of the library that can be loaded,
+    /// if this is `None`,
+    /// the library must have a version compatible with `VERSION_STRINGS`
+    /// (by the same rules as [`VersionNumber::is_compatible`]).
+    ///
+    /// This replaces the default rule,
+    /// so it can be used to reject a specific version of the library,
+    /// or to accept libraries with a different major version.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use abi_stable::{library::RootModule, StableAbi};
+    /// use abi_stable::sabi_types::{VersionRequirement, VersionStrings};
+    /// #
+    /// # #[repr(C)]
+    /// # #[derive(StableAbi)]
+    /// # #[sabi(kind(Prefix(prefix_ref = Module_Ref)))]
+    /// # pub struct Module {
+    /// #     #[sabi(last_prefix_field)]
+    /// #     pub first: u8,
+    /// # }
+    ///
+    /// impl RootModule for Module_Ref {
+    ///     abi_stable::declare_root_module_statics! {Module_Ref}
+    ///     const BASE_NAME: &'static str = "example_root_module";
+    ///     const NAME: &'static str = "example_root_module";
+    ///     const VERSION_STRINGS: VersionStrings = VersionStrings::new("1.4.0");
+    ///
+    ///     // Version 1.4.2 has a bug,and 2.* versions are compatible with this one.
+    ///     const VERSION_REQUIREMENT: Option<VersionRequirement> =
+    ///         Some(VersionRequirement::new(">=1.4, <3, !=1.4.2"));
+    /// }
+    ///
+    /// ```
+    ///
+    /// [`VersionNumber::is_compatible`]:
+    /// ../sabi_types/version/struct.VersionNumber.html#method.is_compatible
+    const VERSION_REQUIREMENT: Option<VersionRequirement> = None;
+
+    /// The other root modules that this one depends on,
+    /// which are loaded before this one by
+    /// [`load_with_dependencies_from`](#method.load_with_dependencies_from).
+    ///
+    /// These are also stored in the [`LibHeader`] of the library that exports this module,
+    /// and loading this module from a library checks that
+    /// the dependencies in its `LibHeader` were loaded before constructing this module.
+    ///
+    /// [`LibHeader`]: ./struct.LibHeader.html
+    const DEPENDENCIES: &'static [RootModuleDependency] = &[];
+
+    /// The name that the root module is exported with,
+    /// passed to the `#[export_root_module(name = "...")]` attribute,
+    /// if this is `None`,
+    /// the root module exported by a plain `#[export_root_module]` is loaded.
+    ///
+    /// This allows one dynamic library to export multiple root modules,
+    /// each one implementing `RootModule` with the same `BASE_NAME`,
+    /// and whose layouts are checked separately when they're loaded.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use abi_stable::{library::RootModule, sabi_types::VersionStrings, StableAbi};
+    ///
+    /// #[repr(C)]
+    /// #[derive(StableAbi)]
+    /// #[sabi(kind(Prefix(prefix_ref = CodecsMod_Ref)))]
+    /// pub struct CodecsMod {
+    ///     #[sabi(last_prefix_field)]
+    ///     pub encode: extern "C" fn(u32) -> u32,
+    /// }
+    ///
+    /// impl RootModule for CodecsMod_Ref {
+    ///     abi_stable::declare_root_module_statics! {CodecsMod_Ref}
+    ///     const BASE_NAME: &'static str = "media";
+    ///     const NAME: &'static str = "codecs";
+    ///     const VERSION_STRINGS: VersionStrings = abi_stable::package_version_strings!();
+    ///
+    ///     // Exported in the "media" library with
+    ///     // `#[export_root_module(name = "codecs")]`
+    ///     const EXPORT_NAME: Option<&'static str> = Some("codecs");
+    /// }
+    ///
+    /// ```
+    const EXPORT_NAME: Option<&'static str> = None;
+
     /// All the constants of this trait and supertraits.
     ///
     /// It can safely be used as a proxy for the associated constants of this trait.
@@ -154,36 +252,89 @@ pub trait RootModule: Sized + StableAbi + PrefixRefTrait + 'static {
     /// - `LibraryError::IncompatibleVersionNumber`:
     /// If the version number of the library is incompatible.
     ///
+    /// - `LibraryError::ParseVersionRequirementError`:
+    /// If `Self::VERSION_REQUIREMENT` is not a valid version requirement.
+    ///
+    /// - `LibraryError::UnmetVersionRequirement`:
+    /// If the version number of the library doesn't satisfy `Self::VERSION_REQUIREMENT`.
+    ///
     /// - `LibraryError::AbiInstability`:
     /// If the layout of the root module is not the expected one.
     ///
+    /// - `LibraryError::DependencyNotLoaded`/`LibraryError::IncompatibleDependency`:
+    /// If a dependency declared in the `LibHeader` of the library
+    /// was not loaded before,or was loaded with an incompatible version.
+    ///
     /// - `LibraryError::RootModule` :
     /// If the root module initializer returned an error or panicked.
     ///
+    /// - `LibraryError::SearchPathsExhausted`:
+    /// If `where_` is a `LibraryPath::SearchPaths`,
+    /// and none of the directories contains a library that could be opened,
+    /// that exports the root module,
+    /// and that has a compatible abi_stable version and library version.
+    ///
     fn load_from(where_: LibraryPath<'_>) -> Result<Self, LibraryError> {
-        let statics = Self::root_module_statics();
-        statics.root_mod.try_init(|| {
-            let lib = statics.raw_lib.try_init(|| -> Result<_, LibraryError> {
-                let raw_library = load_raw_library::<Self>(where_)?;
-
-                // if the library isn't leaked
-                // it would cause any use of the module to be a use after free.
-                //
-                // By leaking the library
-                // this allows the root module loader to do anything that'd prevent
-                // sound library unloading.
-                Ok(leak_value(raw_library))
-            })?;
-            let items = unsafe { lib_header_from_raw_library(lib)? };
+        Self::load_from_with_options(where_, LoadOptions::new())
+    }
 
-            items.ensure_layout::<Self>()?;
+    /// Loads this module from the path specified by `where_`,
+    /// first loading the dynamic library with `options` if it wasn't already loaded.
+    ///
+    /// `options` only has an effect when the dynamic library is loaded,
+    /// once the root module is loaded,
+    /// this will return the already loaded root module.
+    ///
+    /// Errors are detailed in [`load_from`](#method.load_from),
+    /// `LibraryError::OpenError` records the `options` that the library was loaded with.
+    ///
+    fn load_from_with_options(
+        where_: LibraryPath<'_>,
+        options: LoadOptions,
+    ) -> Result<Self, LibraryError> {
+        load_root_module::<Self, _>(|| load_raw_library::<Self>(where_, options))
+    }
 
-            // safety: the layout was checked in the code above,
-            unsafe {
-                items
-                    .init_root_module_with_unchecked_layout::<Self>()?
-                    .initialization()
-            }
+    /// Loads this module from the path specified by `where_`,
+    /// passing `context` to the function that exports the root module,
+    /// first loading the dynamic library if it wasn't already loaded.
+    ///
+    /// The function that exports the root module must take a `C` parameter,
+    /// as described in the docs for
+    /// [`#[export_root_module]`](../attr.export_root_module.html#host-context).
+    ///
+    /// `context` is only passed to the library when the root module is constructed,
+    /// once the root module is loaded,
+    /// this will return the already loaded root module,
+    /// dropping `context`.
+    ///
+    /// # Errors
+    ///
+    /// On top of the errors that [`load_from`](#method.load_from) returns,
+    /// this returns these errors:
+    ///
+    /// - `LibraryError::ContextNotAccepted`:
+    /// If the function that exports the root module doesn't take a context.
+    ///
+    /// - `LibraryError::AbiInstability`:
+    /// If the layout of the context that the root module takes is not compatible with `C`.
+    ///
+    fn load_from_with_context<C>(where_: LibraryPath<'_>, context: C) -> Result<Self, LibraryError>
+    where
+        C: StableAbi,
+    {
+        let load_raw = || load_raw_library::<Self>(where_, LoadOptions::new());
+
+        load_root_module_with::<Self, _, _>(load_raw, |lib, items| {
+            let loader = unsafe { context_loader_from_raw_library(lib, Self::EXPORT_NAME) }.ok_or(
+                LibraryError::ContextNotAccepted {
+                    module_name: Self::NAME,
+                },
+            )?;
+            loader.ensure_context_layout::<C>()?;
+
+            // safety: the layouts of the root module and the context were checked,
+            unsafe { items.init_root_module_with_context::<Self, C>(loader, context) }
         })
     }
 
@@ -211,6 +362,118 @@ pub trait RootModule: Sized + StableAbi + PrefixRefTrait + 'static {
         Self::load_from(LibraryPath::FullPath(path_))
     }
 
+    /// Loads the [dependencies](#associatedconstant.DEPENDENCIES) of this module,
+    /// and then this module,
+    /// each one from the library with its `BASE_NAME` in `where_`.
+    ///
+    /// Dependencies are loaded with [`load_from`](#method.load_from),
+    /// after all of their own dependencies,
+    /// so every root module is only loaded once.
+    ///
+    /// If `where_` is a `LibraryPath::FullPath`,
+    /// all the root modules are loaded from that path.
+    ///
+    /// # Errors
+    ///
+    /// On top of the errors that [`load_from`](#method.load_from) returns,
+    /// this returns these errors:
+    ///
+    /// - `LibraryError::DependencyCycle`:
+    /// If a root module depends on itself,directly or indirectly.
+    ///
+    /// - `LibraryError::MissingDependency`:
+    /// If a dependency could not be loaded.
+    ///
+    /// - `LibraryError::IncompatibleDependency`:
+    /// If the version of a loaded dependency doesn't satisfy
+    /// the version requirement of a root module.
+    ///
+    fn load_with_dependencies_from(where_: LibraryPath<'_>) -> Result<Self, LibraryError> {
+        load_with_dependencies::<Self>(where_)
+    }
+
+    /// Loads this module from the contents of a dynamic library in memory,
+    /// first loading the dynamic library if it wasn't already loaded.
+    ///
+    /// The library is loaded with [`RawLibrary::load_from_bytes`],
+    /// and then checked the same way as in [`load_from`](#method.load_from).
+    ///
+    /// Once the root module is loaded,
+    /// this will return the already loaded root module.
+    ///
+    /// # Errors
+    ///
+    /// This returns the same errors as [`load_from`](#method.load_from),
+    /// as well as `LibraryError::IoError` if the in-memory file could not be created.
+    ///
+    /// [`RawLibrary::load_from_bytes`]: ./struct.RawLibrary.html#method.load_from_bytes
+    #[cfg(target_os = "linux")]
+    #[cfg_attr(feature = "docsrs", doc(cfg(target_os = "linux")))]
+    fn load_from_bytes(bytes: &[u8]) -> Result<Self, LibraryError> {
+        load_root_module::<Self, _>(|| RawLibrary::load_from_bytes(bytes))
+    }
+
+    /// Loads this module from the path specified by `where_`,
+    /// as a [`ReloadableModule`] that can be reloaded,
+    /// and whose library can be unloaded.
+    ///
+    /// This does not use the statics of this module,
+    /// so it can be called regardless of whether the module was loaded with
+    /// [`load_from`](#method.load_from).
+    ///
+    /// Errors are detailed in [`ReloadableModule::load_from`].
+    ///
+    /// # Safety
+    ///
+    /// This has the same safety requirements as [`ReloadableModule::load_from`].
+    ///
+    /// [`ReloadableModule`]: ./struct.ReloadableModule.html
+    /// [`ReloadableModule::load_from`]: ./struct.ReloadableModule.html#method.load_from
+    unsafe fn load_reloadable_from(
+        where_: LibraryPath<'_>,
+    ) -> Result<ReloadableModule<Self>, LibraryError> {
+        unsafe { ReloadableModule::load_from(where_) }
+    }
+
+    /// Calls the shutdown hook that the root module was exported with,
+    /// with [`#[export_root_module(shutdown = "...")]`
+    /// ](../attr.export_root_module.html#shutdown).
+    ///
+    /// This does nothing if the root module wasn't loaded from a library with
+    /// a `RootModule::load_from*` function,
+    /// if it was exported without a shutdown hook,
+    /// or if the hook was already called (by this function or [`shutdown_all`]).
+    ///
+    /// The library is not unloaded,
+    /// so it's up to the library whether the root module can be used after it's shut down.
+    ///
+    /// # Errors
+    ///
+    /// This returns a `LibraryError::RootModuleShutdown` if the hook
+    /// returned an error or panicked.
+    ///
+    /// [`shutdown_all`]: ./fn.shutdown_all.html
+    fn shutdown() -> Result<(), LibraryError> {
+        let statics = Self::root_module_statics();
+        match (statics.root_mod.get(), statics.lib_header.get()) {
+            (Some(_), Some(lib_header)) => shutdown_registered(lib_header),
+            _ => Ok(()),
+        }
+    }
+
+    /// Gets the statistics of the allocations made by the library that
+    /// the root module was loaded from.
+    ///
+    /// This returns None if the root module wasn't loaded from a library with
+    /// a `RootModule::load_from*` function,
+    /// or if the library was built without the "allocation_tracking" feature.
+    fn allocation_stats() -> Option<AllocationStats> {
+        Self::root_module_statics()
+            .lib_header
+            .get()?
+            .allocation_stats()
+    }
+
     /// Defines behavior that happens once the module is loaded.
     ///
     /// This is ran in the `RootModule::load*` associated functions
@@ -222,19 +485,119 @@ pub trait RootModule: Sized + StableAbi + PrefixRefTrait + 'static {
     }
 }
 
+/// Loads the root module of the library that `load_raw` loads,
+/// storing both in the statics of `M`.
+fn load_root_module<M, F>(load_raw: F) -> Result<M, LibraryError>
+where
+    M: RootModule,
+    F: FnOnce() -> Result<RawLibrary, LibraryError>,
+{
+    load_root_module_with::<M, _, _>(load_raw, |_, items| {
+        // safety: the layout was checked before calling this closure,
+        unsafe { items.init_root_module_with_unchecked_layout::<M>() }
+    })
+}
+
+/// Loads the root module from the library that `load_raw` loads,
+/// constructing it with `init` after checking its layout.
+fn load_root_module_with<M, F, I>(load_raw: F, init: I) -> Result<M, LibraryError>
+where
+    M: RootModule,
+    F: FnOnce() -> Result<RawLibrary, LibraryError>,
+    I: FnOnce(&'static RawLibrary, &'static LibHeader) -> Result<M, LibraryError>,
+{
+    let statics = M::root_module_statics();
+    statics.root_mod.try_init(|| {
+        let lib = statics.raw_lib.try_init(|| -> Result<_, LibraryError> {
+            let raw_library = load_raw()?;
+
+            // if the library isn't leaked
+            // it would cause any use of the module to be a use after free.
+            //
+            // By leaking the library
+            // this allows the root module loader to do anything that'd prevent
+            // sound library unloading.
+            Ok(leak_value(raw_library))
+        })?;
+        let items = unsafe { root_module_header_from_raw_library::<M>(lib)? };
+
+        items.ensure_layout::<M>()?;
+        check_declared_dependencies(M::NAME, items)?;
+        unsafe { install_library_globals(lib, M::EXPORT_NAME, items)? };
+
+        statics.lib_header.init(|| items);
+
+        let module = init(lib, items)?.initialization()?;
+
+        register_loaded_root_module(M::NAME, items.version_strings());
+
+        register_shutdown_hook(items);
+
+        Ok(module)
+    })
+}
+
 /// Loads the raw library at `where_`
-fn load_raw_library<M>(where_: LibraryPath<'_>) -> Result<RawLibrary, LibraryError>
+fn load_raw_library<M>(
+    where_: LibraryPath<'_>,
+    options: LoadOptions,
+) -> Result<RawLibrary, LibraryError>
 where
     M: RootModule,
+{
+    let load_at = |path: &Path| RawLibrary::load_at_with(path, options);
+
+    match where_ {
+        LibraryPath::SearchPaths(search_paths) => {
+            let (_, raw_library) = search_paths.find_library::<M, _, _>(|path| {
+                let raw_library = load_at(path)?;
+                // The header is only used to check that this is a compatible library,
+                // before the library can be unloaded.
+                // The error can't refer to the version string in the library,
+                // since the library is unloaded if this returns an error.
+                unsafe {
+                    root_module_header_from_raw_library::<M>(&raw_library)?
+                        .check_version::<M>()
+                        .map_err(LibraryError::with_owned_version_string)?
+                };
+                Ok(raw_library)
+            })?;
+            Ok(raw_library)
+        }
+        _ => load_library_with::<M, _, _>(where_, load_at).map(|(_, x)| x),
+    }
+}
+
+/// Loads the library that `where_` refers to with `load`,
+/// returning the path that it was loaded from.
+///
+/// With `LibraryPath::SearchPaths`,
+/// `load` is called for the library in each directory until one succeeds.
+pub(super) fn load_library_with<M, T, F>(
+    where_: LibraryPath<'_>,
+    mut load: F,
+) -> Result<(PathBuf, T), LibraryError>
+where
+    M: RootModule,
+    F: FnMut(&Path) -> Result<T, LibraryError>,
 {
     let path = match where_ {
         LibraryPath::Directory(directory) => M::get_library_path(directory),
         LibraryPath::FullPath(full_path) => full_path.to_owned(),
+        LibraryPath::SearchPaths(search_paths) => {
+            return search_paths.find_library::<M, _, _>(load)
+        }
     };
-    RawLibrary::load_at(&path)
+    let x = load(&path)?;
+    Ok((path, x))
 }
 
-/// Gets the LibHeader of a library.
+/// Gets the LibHeader of a library,to load its root module.
+///
+/// This also makes the library forward its panics to the hook set with
+/// [`set_plugin_panic_hook`](./fn.set_plugin_panic_hook.html).
+/// To only inspect the library use [`abi_header_from_raw_library`],
+/// which doesn't run any code in it.
 ///
 /// # Errors
 ///
@@ -252,10 +615,16 @@ where
 /// it will contain dangling `'static` references if the library is dropped before it does.
 ///
 ///
+///
+/// [`abi_header_from_raw_library`]: ./fn.abi_header_from_raw_library.html
 pub unsafe fn lib_header_from_raw_library(
     raw_library: &RawLibrary,
 ) -> Result<&'static LibHeader, LibraryError> {
-    unsafe { abi_header_from_raw_library(raw_library)?.upgrade() }
+    unsafe {
+        let lib_header = lib_header_from_raw_library_with(raw_library, None)?;
+        install_library_globals(raw_library, None, lib_header)?;
+        Ok(lib_header)
+    }
 }
 
 /// Gets the AbiHeaderRef of a library.
@@ -282,6 +651,120 @@ pub unsafe fn abi_header_from_raw_library(
     Ok(header)
 }
 
+/// Gets the LibHeader of the root module that the library exported with
+/// `#[export_root_module(name = "...")]`,passing `name` as the name.
+///
+/// # Errors
+///
+/// On top of the errors that [`lib_header_from_raw_library`] returns,
+/// this returns a `LibraryError::InvalidExportName` if `name` can't be
+/// the name of a root module.
+///
+/// # Safety
+///
+/// The LibHeader is implicitly tied to the lifetime of the library,
+/// it will contain dangling `'static` references if the library is dropped before it does.
+///
+/// [`lib_header_from_raw_library`]: ./fn.lib_header_from_raw_library.html
+pub unsafe fn lib_header_from_raw_library_named(
+    raw_library: &RawLibrary,
+    name: &str,
+) -> Result<&'static LibHeader, LibraryError> {
+    unsafe {
+        let lib_header = lib_header_from_raw_library_with(raw_library, Some(name))?;
+        install_library_globals(raw_library, Some(name), lib_header)?;
+        Ok(lib_header)
+    }
+}
+
+/// Gets the AbiHeaderRef of the root module that the library exported with
+/// `#[export_root_module(name = "...")]`,passing `name` as the name.
+///
+/// # Errors
+///
+/// This will return these errors:
+///
+/// - `LibraryError::InvalidExportName`:
+/// If `name` can't be the name of a root module.
+///
+/// - `LibraryError::GetSymbolError`:
+/// If the root module was not exported.
+///
+/// # Safety
+///
+/// The AbiHeaderRef is implicitly tied to the lifetime of the library,
+/// it will contain dangling `'static` references if the library is dropped before it does.
+///
+pub unsafe fn abi_header_from_raw_library_named(
+    raw_library: &RawLibrary,
+    name: &str,
+) -> Result<AbiHeaderRef, LibraryError> {
+    if !is_valid_export_name(name) {
+        return Err(LibraryError::InvalidExportName {
+            name: name.to_owned(),
+        });
+    }
+
+    let mut mangled = mangled_named_root_module_loader_name(name);
+    mangled.push('\0');
+    let header: AbiHeaderRef = unsafe { *raw_library.get::<AbiHeaderRef>(mangled.as_bytes())? };
+
+    Ok(header)
+}
+
+/// Gets the AbiHeaderRef of the root module exported with `export_name` as its name,
+/// or the unnamed root module if it's `None`.
+pub(super) unsafe fn abi_header_from_raw_library_with(
+    raw_library: &RawLibrary,
+    export_name: Option<&str>,
+) -> Result<AbiHeaderRef, LibraryError> {
+    match export_name {
+        Some(name) => unsafe { abi_header_from_raw_library_named(raw_library, name) },
+        None => unsafe { abi_header_from_raw_library(raw_library) },
+    }
+}
+
+/// Gets the LibHeader of the `M` root module,
+/// which is exported with the `M::EXPORT_NAME` name.
+pub(super) unsafe fn root_module_header_from_raw_library<M>(
+    raw_library: &RawLibrary,
+) -> Result<&'static LibHeader, LibraryError>
+where
+    M: RootModule,
+{
+    unsafe { lib_header_from_raw_library_with(raw_library, M::EXPORT_NAME) }
+}
+
+/// Gets the LibHeader of the root module exported with `export_name` as its name,
+/// or the unnamed root module if it's `None`.
+pub(super) unsafe fn lib_header_from_raw_library_with(
+    raw_library: &RawLibrary,
+    export_name: Option<&str>,
+) -> Result<&'static LibHeader, LibraryError> {
+    unsafe { abi_header_from_raw_library_with(raw_library, export_name)? }.upgrade()
+}
+
+/// Passes the `Globals` of this executable to the library,
+/// and registers the allocation tracker of the root module exported with `export_name`.
+///
+/// This is only called once the root module is going to be loaded,
+/// libraries that are only inspected don't receive the `Globals`.
+///
+/// # Safety
+///
+/// `lib_header` must be the header of the root module exported with `export_name`
+/// from `raw_library`.
+pub(super) unsafe fn install_library_globals(
+    raw_library: &RawLibrary,
+    export_name: Option<&str>,
+    lib_header: &'static LibHeader,
+) -> Result<(), LibraryError> {
+    unsafe {
+        install_globals_in_library(raw_library, export_name)?;
+        register_allocation_tracker(raw_library, export_name, lib_header)
+    }
+}
+
 /// Gets the LibHeader of the library at the path.
 ///
 /// This leaks the underlying dynamic library,
@@ -0,0 +1,180 @@
+use super::*;
+
+use crate::std_types::RVec;
+
+use std::{env, ffi::OsStr, io};
+
+/// The environment variable that [`SearchPaths::with_plugin_path_env`] reads,
+/// a list of directories separated like the `PATH` environment variable
+/// (with `:` on unix,and `;` on windows).
+pub const PLUGIN_PATH_ENV_VAR: &str = "ABI_STABLE_PLUGIN_PATH";
+
+/// An ordered list of directories to search for a dynamic library in,
+/// used with [`LibraryPath::SearchPaths`].
+///
+/// The library is loaded from the first directory that contains a compatible library,
+/// if none does,
+/// a `LibraryError::SearchPathsExhausted` with the error for every directory is returned.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use abi_stable::{
+///     for_examples::Module_Ref,
+///     library::{LibraryPath, RootModule, SearchPaths},
+/// };
+///
+/// let search_paths = SearchPaths::new()
+///     .with_plugin_path_env()
+///     .with_directory("/usr/lib/my_app/plugins")
+///     .with_executable_dir()
+///     .with_target_dirs("../target");
+///
+/// let module = Module_Ref::load_from(LibraryPath::SearchPaths(&search_paths)).unwrap();
+///
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Ord, PartialOrd, Hash)]
+pub struct SearchPaths {
+    directories: Vec<PathBuf>,
+}
+
+impl SearchPaths {
+    /// Constructs an empty list of directories.
+    pub const fn new() -> Self {
+        Self {
+            directories: Vec::new(),
+        }
+    }
+
+    /// Constructs the default list of directories,which is,in order:
+    ///
+    /// - The directories in the `ABI_STABLE_PLUGIN_PATH` environment variable.
+    ///
+    /// - The directory of the current executable.
+    ///
+    /// - The `target/debug` and `target/release` directories,
+    /// relative to the current directory.
+    ///
+    pub fn with_defaults() -> Self {
+        Self::new()
+            .with_plugin_path_env()
+            .with_executable_dir()
+            .with_target_dirs("target")
+    }
+
+    /// Appends a directory to search in.
+    pub fn with_directory<P>(mut self, directory: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.directories.push(directory.into());
+        self
+    }
+
+    /// Appends the directories listed in `paths`,
+    /// separated like in the `PATH` environment variable
+    /// (with `:` on unix,and `;` on windows).
+    ///
+    /// Empty paths in the list are ignored.
+    pub fn with_path_list<V>(mut self, paths: V) -> Self
+    where
+        V: AsRef<OsStr>,
+    {
+        self.directories
+            .extend(env::split_paths(&paths).filter(|p| !p.as_os_str().is_empty()));
+        self
+    }
+
+    /// Appends the directories listed in the `var` environment variable,
+    /// as described in [`with_path_list`](#method.with_path_list).
+    ///
+    /// This does nothing if the environment variable is not set.
+    pub fn with_env_var<K>(self, var: K) -> Self
+    where
+        K: AsRef<OsStr>,
+    {
+        match env::var_os(var) {
+            Some(paths) => self.with_path_list(paths),
+            None => self,
+        }
+    }
+
+    /// Appends the directories listed in the [`ABI_STABLE_PLUGIN_PATH`] environment variable.
+    ///
+    /// [`ABI_STABLE_PLUGIN_PATH`]: ./constant.PLUGIN_PATH_ENV_VAR.html
+    pub fn with_plugin_path_env(self) -> Self {
+        self.with_env_var(PLUGIN_PATH_ENV_VAR)
+    }
+
+    /// Appends the directory that contains the current executable.
+    ///
+    /// This does nothing if the path of the executable can't be determined.
+    pub fn with_executable_dir(mut self) -> Self {
+        let exe_dir = env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_owned));
+        self.directories.extend(exe_dir);
+        self
+    }
+
+    /// Appends the `debug` and `release` subdirectories of the
+    /// `target_dir` cargo target directory.
+    pub fn with_target_dirs<P>(mut self, target_dir: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        let target_dir = target_dir.as_ref();
+        self.directories.push(target_dir.join("debug"));
+        self.directories.push(target_dir.join("release"));
+        self
+    }
+
+    /// The directories that are searched,in order.
+    pub fn directories(&self) -> &[PathBuf] {
+        &self.directories
+    }
+
+    /// Calls `load` with the path of the `M` library in each directory,
+    /// returning the first successfully loaded value along with its path.
+    ///
+    /// Directories that don't contain the library are skipped.
+    ///
+    /// # Errors
+    ///
+    /// If the library could not be loaded from any directory,
+    /// this returns a `LibraryError::SearchPathsExhausted`,
+    /// with a `LibraryError::AtPath` for every directory that was tried.
+    pub(super) fn find_library<M, T, F>(&self, mut load: F) -> Result<(PathBuf, T), LibraryError>
+    where
+        M: RootModule,
+        F: FnMut(&Path) -> Result<T, LibraryError>,
+    {
+        let mut tried = RVec::new();
+
+        for directory in &self.directories {
+            let path = M::get_library_path(directory);
+
+            let res = if path.is_file() {
+                load(&path)
+            } else {
+                Err(LibraryError::IoError {
+                    path: path.clone(),
+                    err: io::Error::new(io::ErrorKind::NotFound, "the library does not exist"),
+                })
+            };
+
+            match res {
+                Ok(x) => return Ok((path, x)),
+                Err(err) => tried.push(LibraryError::AtPath {
+                    path,
+                    err: Box::new(err),
+                }),
+            }
+        }
+
+        Err(LibraryError::SearchPathsExhausted {
+            base_name: M::BASE_NAME,
+            tried,
+        })
+    }
+}
@@ -0,0 +1,241 @@
+use super::*;
+
+use crate::{
+    std_types::{RBoxError, RResult, RVec},
+    utils::{panic_payload_message, FromPanicMessage},
+};
+
+use parking_lot::{const_mutex, Mutex};
+
+use std::ptr;
+
+/// The type of the shutdown hook that
+/// `#[export_root_module(shutdown = "...")]` stores in the [`LibHeader`],
+/// which is called when the root module is shut down.
+///
+/// Shutdown hooks are called by [`RootModule::shutdown`] and [`shutdown_all`].
+///
+/// [`LibHeader`]: ./struct.LibHeader.html
+/// [`RootModule::shutdown`]: ./trait.RootModule.html#method.shutdown
+/// [`shutdown_all`]: ./fn.shutdown_all.html
+pub type ShutdownFn = extern "C" fn() -> RResult<(), RootModuleError>;
+
+//////////////////////////////////////////////////////////////////////
+
+/// For converting the return value of a shutdown function
+/// (passed to `#[export_root_module(shutdown = "...")]`)
+/// to a `Result<(), RBoxError>`.
+pub trait IntoShutdownResult {
+    /// Performs the conversion
+    fn into_shutdown_result(self) -> Result<(), RBoxError>;
+}
+
+impl IntoShutdownResult for () {
+    fn into_shutdown_result(self) -> Result<(), RBoxError> {
+        Ok(())
+    }
+}
+
+impl IntoShutdownResult for Result<(), RBoxError> {
+    fn into_shutdown_result(self) -> Result<(), RBoxError> {
+        self
+    }
+}
+
+impl IntoShutdownResult for RResult<(), RBoxError> {
+    fn into_shutdown_result(self) -> Result<(), RBoxError> {
+        self.into_result()
+    }
+}
+
+#[doc(hidden)]
+pub fn __call_root_module_shutdown<T>(function: fn() -> T) -> RResult<(), RootModuleError>
+where
+    T: IntoShutdownResult,
+{
+    let res = ::std::panic::catch_unwind(|| function().into_shutdown_result());
+    // We turn an unwinding panic into an error value
+    let flattened = match res {
+        Ok(x) => x.map_err(RootModuleError::Returned),
+        Err(payload) => Err(RootModuleError::from_panic_message(panic_payload_message(
+            &*payload,
+        ))),
+    };
+    RResult::from(flattened)
+}
+
+//////////////////////////////////////////////////////////////////////
+
+struct RegisteredHook {
+    lib_header: &'static LibHeader,
+    ran: bool,
+}
+
+/// The shutdown hooks of the root modules that were loaded (and leaked),in load order.
+pub(super) struct ShutdownHooks {
+    hooks: Mutex<Vec<RegisteredHook>>,
+}
+
+impl ShutdownHooks {
+    pub(super) const fn new() -> Self {
+        Self {
+            hooks: const_mutex(Vec::new()),
+        }
+    }
+
+    /// Registers the shutdown hook of a leaked root module,
+    /// so that it's called by `shutdown_all`.
+    ///
+    /// This does nothing if the root module doesn't have a shutdown hook.
+    pub(super) fn register(&self, lib_header: &'static LibHeader) {
+        if lib_header.shutdown_hook().is_none() {
+            return;
+        }
+
+        let mut hooks = self.hooks.lock();
+        // The same library can be loaded by both `RootModule::load_from` and a `PluginSet`.
+        if hooks
+            .iter()
+            .any(|entry| ptr::eq(entry.lib_header, lib_header))
+        {
+            return;
+        }
+        hooks.push(RegisteredHook {
+            lib_header,
+            ran: false,
+        });
+    }
+
+    /// Calls the registered hook of the root module in `lib_header`
+    /// if it wasn't already called.
+    pub(super) fn shutdown(&self, lib_header: &'static LibHeader) -> Result<(), LibraryError> {
+        let entry = {
+            let mut hooks = self.hooks.lock();
+            hooks
+                .iter_mut()
+                .find(|entry| !entry.ran && ptr::eq(entry.lib_header, lib_header))
+                .map(take_registered)
+        };
+
+        // The lock is not held while the hook runs,
+        // so that the hook can shut down other root modules.
+        entry.map_or(Ok(()), run_shutdown_hook)
+    }
+
+    /// Calls all the registered hooks that weren't already called,
+    /// in the reverse order that they were registered.
+    pub(super) fn shutdown_all(&self) -> Result<(), LibraryError> {
+        let mut errors = RVec::new();
+
+        loop {
+            let entry = {
+                let mut hooks = self.hooks.lock();
+                hooks
+                    .iter_mut()
+                    .rev()
+                    .find(|entry| !entry.ran)
+                    .map(take_registered)
+            };
+
+            let lib_header = match entry {
+                Some(x) => x,
+                None => break,
+            };
+
+            if let Err(e) = run_shutdown_hook(lib_header) {
+                errors.push(e);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(LibraryError::Many(errors))
+        }
+    }
+}
+
+fn take_registered(entry: &mut RegisteredHook) -> &'static LibHeader {
+    entry.ran = true;
+    entry.lib_header
+}
+
+static SHUTDOWN_HOOKS: ShutdownHooks = ShutdownHooks::new();
+
+/// Registers the shutdown hook of a leaked root module,so that it's called by `shutdown_all`.
+pub(super) fn register_shutdown_hook(lib_header: &'static LibHeader) {
+    SHUTDOWN_HOOKS.register(lib_header)
+}
+
+/// Calls the registered shutdown hook of the root module in `lib_header`
+/// if it wasn't already called.
+pub(super) fn shutdown_registered(lib_header: &'static LibHeader) -> Result<(), LibraryError> {
+    SHUTDOWN_HOOKS.shutdown(lib_header)
+}
+
+/// Calls the shutdown hook of the root module in `lib_header`,
+/// converting the error it returns into a `LibraryError`.
+///
+/// This does nothing if the root module doesn't have a shutdown hook.
+pub(super) fn run_shutdown_hook(lib_header: &LibHeader) -> Result<(), LibraryError> {
+    let hook = match lib_header.shutdown_hook() {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+
+    // The error is reallocated so that it doesn't point into the library.
+    hook().into_result().map_err(|mut err| {
+        err.reallocate();
+        let consts = lib_header.root_mod_consts();
+        LibraryError::RootModuleShutdown {
+            err,
+            module_name: consts.name().as_str(),
+            version: consts.version_strings(),
+        }
+    })
+}
+
+/// Calls the shutdown hooks of all the root modules that were loaded with
+/// [`RootModule`]`::load_from*` or a [`PluginSet`],
+/// in the reverse order that they were loaded.
+///
+/// Every hook is called at most once,
+/// hooks that were already called by [`RootModule::shutdown`] are skipped.
+///
+/// The root modules are not unloaded,
+/// so it's up to each library whether its root module can be used after it's shut down.
+///
+/// Only the root modules loaded by this copy of abi_stable are shut down,
+/// so root modules that a dynamic library loads have to be shut down by that library.
+///
+/// # Errors
+///
+/// If any hook returned an error or panicked,
+/// this returns a `LibraryError::Many` with a `LibraryError::RootModuleShutdown`
+/// for every hook that failed,
+/// after calling all the other hooks.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use abi_stable::{
+///     for_examples::Module_Ref,
+///     library::{shutdown_all, RootModule},
+/// };
+///
+/// let module = Module_Ref::load_from_directory("./plugins".as_ref()).unwrap();
+///
+/// // ... use the module ...
+///
+/// if let Err(e) = shutdown_all() {
+///     eprintln!("{}", e);
+/// }
+///
+/// ```
+///
+/// [`RootModule`]: ./trait.RootModule.html
+/// [`RootModule::shutdown`]: ./trait.RootModule.html#method.shutdown
+/// [`PluginSet`]: ./struct.PluginSet.html
+pub fn shutdown_all() -> Result<(), LibraryError> {
+    SHUTDOWN_HOOKS.shutdown_all()
+}
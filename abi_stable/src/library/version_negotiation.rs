@@ -0,0 +1,398 @@
+use super::{
+    root_mod_trait::{lib_header_from_raw_library_with, load_library_with},
+    *,
+};
+
+use crate::std_types::{RNone, ROption, RSlice, RVec};
+
+use std::{cmp::Reverse, fmt};
+
+/// A table of the versions of an interface that a library exports,
+/// each one a separate root module.
+///
+/// This is stored in the [`LibHeader`] of the root module exported without a name,
+/// with `#[export_root_module(interface_versions = "...")]`,
+/// and read by [`VersionNegotiator`] to load the highest version that
+/// both the library and the executable support.
+///
+/// # Example
+///
+/// ```rust
+/// use abi_stable::library::{InterfaceVersion, InterfaceVersions};
+///
+/// // Passed to the root module exported without a name,
+/// // with `#[export_root_module(interface_versions = "CODECS")]`
+/// pub const CODECS: InterfaceVersions = InterfaceVersions::new(
+///     "codecs",
+///     &[
+///         // exported with `#[export_root_module(name = "codecs_v1")]`
+///         InterfaceVersion::new(1, "codecs_v1"),
+///         // exported with `#[export_root_module(name = "codecs_v2")]`
+///         InterfaceVersion::new(2, "codecs_v2"),
+///     ],
+/// );
+///
+/// ```
+///
+/// [`LibHeader`]: ./struct.LibHeader.html
+#[repr(C)]
+#[derive(Debug, Copy, Clone, StableAbi)]
+pub struct InterfaceVersions {
+    interface: RStr<'static>,
+    versions: RSlice<'static, InterfaceVersion>,
+}
+
+impl InterfaceVersions {
+    /// Constructs the table from the name of the interface,
+    /// and its versions in any order.
+    pub const fn new(interface: &'static str, versions: &'static [InterfaceVersion]) -> Self {
+        Self {
+            interface: RStr::from_str(interface),
+            versions: RSlice::from_slice(versions),
+        }
+    }
+
+    /// The name of the interface,
+    /// which is passed to [`VersionNegotiator::new`] to load it.
+    ///
+    /// [`VersionNegotiator::new`]: ./struct.VersionNegotiator.html#method.new
+    pub fn interface(&self) -> &'static str {
+        self.interface.as_str()
+    }
+
+    /// The versions of the interface,in the order that they were passed to `new`.
+    pub fn versions(&self) -> &'static [InterfaceVersion] {
+        self.versions.as_slice()
+    }
+}
+
+/// A version of an interface in an [`InterfaceVersions`],
+/// with the name of the root module that implements it.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, StableAbi)]
+pub struct InterfaceVersion {
+    version: u32,
+    export_name: ROption<RStr<'static>>,
+}
+
+impl InterfaceVersion {
+    /// Constructs an `InterfaceVersion` for the root module exported with
+    /// `#[export_root_module(name = "...")]`,passing `export_name` as the name.
+    pub const fn new(version: u32, export_name: &'static str) -> Self {
+        Self {
+            version,
+            export_name: RSome(RStr::from_str(export_name)),
+        }
+    }
+
+    /// Constructs an `InterfaceVersion` for the root module exported
+    /// without a name.
+    pub const fn unnamed(version: u32) -> Self {
+        Self {
+            version,
+            export_name: RNone,
+        }
+    }
+
+    /// The version of the interface,higher versions are preferred.
+    pub const fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// The name that the root module is exported with,
+    /// None for the unnamed root module.
+    pub fn export_name(&self) -> Option<&'static str> {
+        self.export_name.map(|x| x.as_str()).into_option()
+    }
+}
+
+//////////////////////////////////////////////////////////////////////
+
+type FindLibraryFn<T> = fn(
+    LibraryPath<'_>,
+    &mut dyn FnMut(&Path) -> Result<T, LibraryError>,
+) -> Result<(PathBuf, T), LibraryError>;
+
+struct SupportedVersion<T> {
+    version: u32,
+    export_name: Option<&'static str>,
+    load: Box<dyn Fn(&Path) -> Result<T, LibraryError> + Send + Sync>,
+}
+
+/// Loads the highest version of an interface that both a library and
+/// the executable support.
+///
+/// Libraries describe the versions they export with an [`InterfaceVersions`]
+/// in the [`LibHeader`] of the root module exported without a name,
+/// and the executable registers the version number and [`RootModule`] type
+/// of every version it supports with [`version`](#method.version).
+///
+/// A version is only loaded if both the library and the executable
+/// declare it with the same version number and export name.
+/// Versions are tried from the highest to the lowest,
+/// and versions that fail to load
+/// (eg: because their layout is incompatible) are skipped.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use abi_stable::{
+///     library::{LibraryPath, RootModule, VersionNegotiator},
+///     package_version_strings,
+///     sabi_types::VersionStrings,
+///     std_types::RStr,
+///     StableAbi,
+/// };
+///
+/// #[repr(C)]
+/// #[derive(StableAbi)]
+/// #[sabi(kind(Prefix(prefix_ref = CodecsV1_Ref)))]
+/// pub struct CodecsV1 {
+///     #[sabi(last_prefix_field)]
+///     pub decode: extern "C" fn(RStr<'_>) -> u32,
+/// }
+///
+/// impl RootModule for CodecsV1_Ref {
+///     abi_stable::declare_root_module_statics! {CodecsV1_Ref}
+///     const BASE_NAME: &'static str = "codecs";
+///     const NAME: &'static str = "codecs_v1";
+///     const VERSION_STRINGS: VersionStrings = package_version_strings!();
+///     const EXPORT_NAME: Option<&'static str> = Some("codecs_v1");
+/// }
+///
+/// #[repr(C)]
+/// #[derive(StableAbi)]
+/// #[sabi(kind(Prefix(prefix_ref = CodecsV2_Ref)))]
+/// pub struct CodecsV2 {
+///     #[sabi(last_prefix_field)]
+///     pub decode: extern "C" fn(RStr<'_>) -> u64,
+/// }
+///
+/// impl RootModule for CodecsV2_Ref {
+///     abi_stable::declare_root_module_statics! {CodecsV2_Ref}
+///     const BASE_NAME: &'static str = "codecs";
+///     const NAME: &'static str = "codecs_v2";
+///     const VERSION_STRINGS: VersionStrings = package_version_strings!();
+///     const EXPORT_NAME: Option<&'static str> = Some("codecs_v2");
+/// }
+///
+/// enum Codecs {
+///     V1(CodecsV1_Ref),
+///     V2(CodecsV2_Ref),
+/// }
+///
+/// let negotiated = VersionNegotiator::new("codecs")
+///     .version(1, Codecs::V1)
+///     .version(2, Codecs::V2)
+///     .load_from(LibraryPath::Directory("./plugins".as_ref()))
+///     .unwrap();
+///
+/// println!("loaded version {} of the codecs interface", negotiated.version());
+///
+/// let decoded = match negotiated.into_module() {
+///     Codecs::V1(module) => u64::from(module.decode()(RStr::from("100"))),
+///     Codecs::V2(module) => module.decode()(RStr::from("100")),
+/// };
+/// println!("{}", decoded);
+///
+/// ```
+///
+/// [`LibHeader`]: ./struct.LibHeader.html
+/// [`RootModule`]: ./trait.RootModule.html
+pub struct VersionNegotiator<T> {
+    interface: &'static str,
+    find_library: Option<FindLibraryFn<NegotiatedModule<T>>>,
+    supported: Vec<SupportedVersion<T>>,
+}
+
+/// The root module loaded by a [`VersionNegotiator`],
+/// along with the version of the interface that it implements.
+#[derive(Debug, Clone)]
+pub struct NegotiatedModule<T> {
+    path: PathBuf,
+    version: InterfaceVersion,
+    module: T,
+}
+
+impl<T> VersionNegotiator<T> {
+    /// Constructs a `VersionNegotiator` for the interface whose versions are
+    /// in an [`InterfaceVersions`] with `interface` as its name.
+    pub fn new(interface: &'static str) -> Self {
+        Self {
+            interface,
+            find_library: None,
+            supported: Vec::new(),
+        }
+    }
+
+    /// Registers `M` as the root module of the `version` version of the interface,
+    /// converting it into `T` with `f` once it's loaded.
+    ///
+    /// The library must export that version as `M::EXPORT_NAME` for it to be loaded.
+    ///
+    /// The `BASE_NAME` of the first `M` passed to this method is used to
+    /// find the library in directories.
+    pub fn version<M, F>(mut self, version: u32, f: F) -> Self
+    where
+        M: RootModule,
+        F: Fn(M) -> T + Send + Sync + 'static,
+    {
+        if self.find_library.is_none() {
+            self.find_library = Some(find_library::<M, NegotiatedModule<T>>);
+        }
+        self.supported.push(SupportedVersion {
+            version,
+            export_name: M::EXPORT_NAME,
+            load: Box::new(move |path| M::load_from_file(path).map(&f)),
+        });
+        self
+    }
+
+    /// Loads the highest version of the interface that both the library at `where_`
+    /// and this executable support.
+    ///
+    /// The root module is loaded with [`RootModule::load_from_file`],
+    /// which leaks the library.
+    ///
+    /// # Errors
+    ///
+    /// This returns these errors:
+    ///
+    /// - `LibraryError::GetSymbolError`:
+    /// If the library doesn't export a root module without a name.
+    ///
+    /// - `LibraryError::InvalidAbiHeader`:
+    /// If the abi_stable version used by the library is not compatible.
+    ///
+    /// - `LibraryError::NoCompatibleInterfaceVersion`:
+    /// If no version that both the library and this executable support could be loaded,
+    /// with the errors from loading each of them.
+    /// Libraries that don't declare versions of the interface offer none.
+    ///
+    /// As well as the errors from loading the library that
+    /// [`RootModule::load_from`] returns.
+    ///
+    /// [`RootModule::load_from_file`]: ./trait.RootModule.html#method.load_from_file
+    /// [`RootModule::load_from`]: ./trait.RootModule.html#method.load_from
+    pub fn load_from(&self, where_: LibraryPath<'_>) -> Result<NegotiatedModule<T>, LibraryError> {
+        let find_library = match self.find_library {
+            Some(x) => x,
+            None => return Err(self.no_compatible_version(RVec::new(), RVec::new())),
+        };
+        find_library(where_, &mut |path| self.load_at(path)).map(|(_, module)| module)
+    }
+
+    /// Loads the highest version of the interface that both the library at `path`
+    /// and this executable support.
+    ///
+    /// Errors are detailed in [`load_from`](#method.load_from).
+    pub fn load_from_file(&self, path: &Path) -> Result<NegotiatedModule<T>, LibraryError> {
+        self.load_from(LibraryPath::FullPath(path))
+    }
+
+    fn load_at(&self, path: &Path) -> Result<NegotiatedModule<T>, LibraryError> {
+        // The root modules load the library again,
+        // this handle keeps the table of versions alive until they're loaded.
+        let raw_library = RawLibrary::load_at(path)?;
+        let lib_header = unsafe { lib_header_from_raw_library_with(&raw_library, None)? };
+
+        let mut offered = lib_header
+            .interface_versions()
+            .filter(|versions| versions.interface() == self.interface)
+            .map_or_else(Vec::new, |versions| versions.versions().to_vec());
+        offered.sort_by_key(|x| Reverse(x.version));
+
+        let mut errors = RVec::new();
+        for version in &offered {
+            let supported = self.supported.iter().find(|supported| {
+                supported.version == version.version
+                    && supported.export_name == version.export_name()
+            });
+
+            if let Some(supported) = supported {
+                match (supported.load)(path) {
+                    Ok(module) => {
+                        return Ok(NegotiatedModule {
+                            path: path.to_owned(),
+                            version: *version,
+                            module,
+                        })
+                    }
+                    Err(e) => errors.push(e),
+                }
+            }
+        }
+
+        let offered = offered.iter().map(|x| x.version).collect();
+        Err(self.no_compatible_version(offered, errors))
+    }
+
+    fn no_compatible_version(
+        &self,
+        offered: RVec<u32>,
+        errors: RVec<LibraryError>,
+    ) -> LibraryError {
+        LibraryError::NoCompatibleInterfaceVersion {
+            interface: self.interface,
+            offered,
+            errors,
+        }
+    }
+}
+
+impl<T> fmt::Debug for VersionNegotiator<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VersionNegotiator")
+            .field("interface", &self.interface)
+            .field(
+                "supported",
+                &self
+                    .supported
+                    .iter()
+                    .map(|x| (x.version, x.export_name))
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+fn find_library<M, T>(
+    where_: LibraryPath<'_>,
+    load: &mut dyn FnMut(&Path) -> Result<T, LibraryError>,
+) -> Result<(PathBuf, T), LibraryError>
+where
+    M: RootModule,
+{
+    load_library_with::<M, _, _>(where_, load)
+}
+
+impl<T> NegotiatedModule<T> {
+    /// The path that the library was loaded from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The version of the interface that the root module implements.
+    pub const fn version(&self) -> u32 {
+        self.version.version
+    }
+
+    /// The name that the root module is exported with,
+    /// None for the unnamed root module.
+    pub fn export_name(&self) -> Option<&'static str> {
+        self.version.export_name()
+    }
+
+    /// Gets the root module,converted with the function passed to
+    /// [`VersionNegotiator::version`].
+    ///
+    /// [`VersionNegotiator::version`]: ./struct.VersionNegotiator.html#method.version
+    pub const fn module(&self) -> &T {
+        &self.module
+    }
+
+    /// Unwraps the root module.
+    pub fn into_module(self) -> T {
+        self.module
+    }
+}
@@ -191,6 +191,13 @@ macro_rules! check_unerased {
 /// `extern_fn_panic_handling!{no_early_return; <code here> }`,
 /// which *might* be cheaper(this has not been tested yet).
 ///
+/// # Catching panics
+///
+/// `extern_fn_panic_handling!{catch_unwind; <code here> }`
+/// returns an `RErr` with the message of the panic instead of aborting,
+/// for code that evaluates to an `RResult<T, E>`,
+/// where `E` implements [`FromPanicMessage`](./utils/trait.FromPanicMessage.html).
+///
 /// # Example
 ///
 /// ```
@@ -239,6 +246,25 @@ macro_rules! check_unerased {
 ///
 /// ```
 ///
+/// # Example, catch_unwind
+///
+/// ```
+/// use abi_stable::{
+///     extern_fn_panic_handling,
+///     std_types::{RErr, ROk, RResult, RString},
+/// };
+///
+/// pub extern "C" fn divide(l: u32, r: u32) -> RResult<u32, RString> {
+///     extern_fn_panic_handling! {catch_unwind;
+///         ROk(l / r)
+///     }
+/// }
+///
+/// assert_eq!(divide(12, 4), ROk(3));
+/// assert_eq!(divide(1, 0), RErr(RString::from("attempt to divide by zero")));
+///
+/// ```
+///
 /// # Returing in `no_early_return`
 ///
 /// Attempting to do any kind of returning from inside of
@@ -261,6 +287,11 @@ macro_rules! check_unerased {
 ///
 #[macro_export]
 macro_rules! extern_fn_panic_handling {
+    (catch_unwind; $($fn_contents:tt)* ) => (
+        $crate::utils::catch_ffi_unwind(move || {
+            $($fn_contents)*
+        })
+    );
     (no_early_return; $($fn_contents:tt)* ) => ({
         let aborter_guard = {
             use $crate::utils::{AbortBomb,PanicInfo};
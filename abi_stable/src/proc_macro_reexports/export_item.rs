@@ -0,0 +1,77 @@
+/**
+This attribute is used for statics which are exported from an `implementation crate`
+along with their type layout,
+so that they can be accessed with [`RawLibrary::get_checked`]
+after checking that the type of the static is compatible.
+
+This is useful for optional tables of functions (or any other data)
+that don't belong in the root module.
+
+This is applied to statics like this:
+
+```rust
+use abi_stable::{std_types::RStr, StableAbi};
+
+#[abi_stable::export_item(name = "greeting")]
+pub static GREETING: RStr<'static> = RStr::from_str("hello");
+
+#[abi_stable::export_item(name = "codec-table")]
+pub static CODECS: CodecTable = CodecTable { encode, decode };
+
+#[repr(C)]
+#[derive(StableAbi)]
+pub struct CodecTable {
+    pub encode: extern "C" fn(u32) -> u32,
+    pub decode: extern "C" fn(u32) -> u32,
+}
+
+extern "C" fn encode(x: u32) -> u32 {
+    x ^ 0xAAAA
+}
+
+extern "C" fn decode(x: u32) -> u32 {
+    x ^ 0xAAAA
+}
+
+# fn main(){}
+```
+
+The statics can then be accessed from the library that exports them like this:
+
+```rust,no_run
+use abi_stable::{library::RawLibrary, std_types::RStr};
+
+let library = RawLibrary::load_at("./plugins/libplugin.so".as_ref()).unwrap();
+
+let greeting = library.get_checked::<RStr<'static>>("greeting").unwrap();
+assert_eq!(*greeting, "hello");
+```
+
+# Name
+
+The `name = "..."` argument is the name that the item is looked up with,
+which defaults to the name of the static.
+
+Names must only contain ascii alphanumeric characters,`_`,`-`,and `.`.
+
+# Generated code
+
+Exporting an item creates a `#[no_mangle]` static of type [`ExportedItem`],
+whose name is the mangled version of the name of the item,
+with these things:
+
+- The version of `abi_stable` used.
+
+- The type layout of the static,
+for checking that its type is compatible with whatever accesses it.
+
+- A reference to the static.
+
+The type of the static must implement [`StableAbi`](./trait.StableAbi.html).
+
+[`RawLibrary::get_checked`]: ./library/struct.RawLibrary.html#method.get_checked
+[`ExportedItem`]: ./library/struct.ExportedItem.html
+
+*/
+#[doc(inline)]
+pub use abi_stable_derive::export_item;
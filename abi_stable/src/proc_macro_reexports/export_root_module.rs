@@ -71,7 +71,263 @@ for checking that the types are compatible with whatever loads that library.
 
 
 The name used for generated static is the value of 
-[`abi_stable::library::ROOT_MODULE_LOADER_NAME`](./library/constant.ROOT_MODULE_LOADER_NAME.html).
+[`abi_stable::library::ROOT_MODULE_LOADER_NAME`](./library/constant.ROOT_MODULE_LOADER_NAME.html),
+unless a name is passed to the attribute.
+
+It also creates a `#[no_mangle]` static of type
+[`GlobalsInstaller`](./library/struct.GlobalsInstaller.html),
+which the loader uses to make the library forward its panics to the hook set with
+[`set_plugin_panic_hook`](./library/fn.set_plugin_panic_hook.html),
+and its `log` records to the logger of the executable
+(with the "log" feature,see [`external_types::log`](./external_types/log/index.html)),
+and a `#[no_mangle]` static of type
+[`AllocationTracker`](./library/struct.AllocationTracker.html),
+which the loader uses to get the statistics of the allocations that the library made
+(with the "allocation_tracking" feature).
+
+# Name
+
+Libraries can export multiple root modules by passing a name to the attribute,
+as in `#[export_root_module(name = "codecs")]`,
+each named root module has its own header (with its own type layout).
+
+The root module is then loaded by a [`RootModule`] implementor whose
+[`EXPORT_NAME`] constant is `Some("codecs")`.
+
+Names must only contain ascii alphanumeric characters,`_`,`-`,and `.`.
+
+```rust
+use abi_stable::{
+    export_root_module,
+    library::RootModule,
+    prefix_type::PrefixTypeTrait,
+    sabi_types::VersionStrings,
+    StableAbi,
+};
+
+#[export_root_module(name = "codecs")]
+pub fn get_codecs_mod() -> CodecsMod_Ref {
+    CodecsMod { encode }.leak_into_prefix()
+}
+
+#[export_root_module(name = "storage")]
+pub fn get_storage_mod() -> StorageMod_Ref {
+    StorageMod { capacity: 1024 }.leak_into_prefix()
+}
+
+extern "C" fn encode(x: u32) -> u32 {
+    x ^ 0xAAAA
+}
+
+#[repr(C)]
+#[derive(StableAbi)]
+#[sabi(kind(Prefix(prefix_ref = CodecsMod_Ref)))]
+pub struct CodecsMod {
+    #[sabi(last_prefix_field)]
+    pub encode: extern "C" fn(u32) -> u32,
+}
+
+impl RootModule for CodecsMod_Ref {
+    abi_stable::declare_root_module_statics! {CodecsMod_Ref}
+    const BASE_NAME: &'static str = "media";
+    const NAME: &'static str = "codecs";
+    const VERSION_STRINGS: VersionStrings = abi_stable::package_version_strings!();
+    const EXPORT_NAME: Option<&'static str> = Some("codecs");
+}
+
+#[repr(C)]
+#[derive(StableAbi)]
+#[sabi(kind(Prefix(prefix_ref = StorageMod_Ref)))]
+pub struct StorageMod {
+    #[sabi(last_prefix_field)]
+    pub capacity: u64,
+}
+
+impl RootModule for StorageMod_Ref {
+    abi_stable::declare_root_module_statics! {StorageMod_Ref}
+    const BASE_NAME: &'static str = "media";
+    const NAME: &'static str = "storage";
+    const VERSION_STRINGS: VersionStrings = abi_stable::package_version_strings!();
+    const EXPORT_NAME: Option<&'static str> = Some("storage");
+}
+
+# fn main(){}
+```
+
+# Host context
+
+The annotated function can take a single parameter,
+a host context that the library that loads the root module passes to it
+with [`RootModule::load_from_with_context`],
+to give the library access to services of the host (eg: logging,configuration).
+
+The type of the context is declared in the `interface crate`,
+it must implement [`StableAbi`](./trait.StableAbi.html),
+and its layout is checked when the root module is loaded.
+Prefer `'static` types (eg: references to prefix types),
+so that the library can keep the context around.
+
+Root modules exported this way can't be loaded with [`RootModule::load_from`],
+which returns an error when the root module is first loaded.
+
+```rust
+use abi_stable::{
+    export_root_module,
+    library::RootModule,
+    prefix_type::PrefixTypeTrait,
+    sabi_types::VersionStrings,
+    std_types::RStr,
+    StableAbi,
+};
+
+#[export_root_module]
+pub fn get_greeter_mod(host: HostServices_Ref) -> GreeterMod_Ref {
+    host.log()(RStr::from_str("loading the greeter"));
+    GreeterMod { greet }.leak_into_prefix()
+}
+
+extern "C" fn greet(name: RStr<'_>) {
+    println!("Hello, {}!", name);
+}
+
+// This is declared in the interface crate.
+#[repr(C)]
+#[derive(StableAbi)]
+#[sabi(kind(Prefix(prefix_ref = HostServices_Ref)))]
+pub struct HostServices {
+    #[sabi(last_prefix_field)]
+    pub log: extern "C" fn(RStr<'_>),
+}
+
+#[repr(C)]
+#[derive(StableAbi)]
+#[sabi(kind(Prefix(prefix_ref = GreeterMod_Ref)))]
+pub struct GreeterMod {
+    #[sabi(last_prefix_field)]
+    pub greet: extern "C" fn(RStr<'_>),
+}
+
+impl RootModule for GreeterMod_Ref {
+    abi_stable::declare_root_module_statics! {GreeterMod_Ref}
+    const BASE_NAME: &'static str = "greeter";
+    const NAME: &'static str = "greeter";
+    const VERSION_STRINGS: VersionStrings = abi_stable::package_version_strings!();
+}
+
+# fn main(){}
+```
+
+# Shutdown
+
+The `shutdown = "path::to::function"` argument stores a shutdown hook for the root module
+in its [`LibHeader`],
+which is called by [`RootModule::shutdown`] and [`shutdown_all`],
+so that the library can flush its state before the process exits.
+
+The shutdown function takes no arguments and its return type can be one of:
+
+- `()`
+
+- `Result<(), RBoxError>`
+
+- `RResult<(), RBoxError>`
+
+All those types are supported through the [`IntoShutdownResult`] trait.
+
+Panics inside the shutdown function are caught,
+and returned as an error from the function that called the hook.
+
+```rust
+use abi_stable::{
+    export_root_module,
+    library::RootModule,
+    prefix_type::PrefixTypeTrait,
+    sabi_types::VersionStrings,
+    StableAbi,
+};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static SHUT_DOWN: AtomicBool = AtomicBool::new(false);
+
+#[export_root_module(shutdown = "shutdown_logger")]
+pub fn get_logger_mod() -> LoggerMod_Ref {
+    LoggerMod { log }.leak_into_prefix()
+}
+
+fn shutdown_logger() {
+    SHUT_DOWN.store(true, Ordering::SeqCst);
+}
+
+extern "C" fn log(level: u8) {
+    if !SHUT_DOWN.load(Ordering::SeqCst) {
+        println!("log level: {}", level);
+    }
+}
+
+#[repr(C)]
+#[derive(StableAbi)]
+#[sabi(kind(Prefix(prefix_ref = LoggerMod_Ref)))]
+pub struct LoggerMod {
+    #[sabi(last_prefix_field)]
+    pub log: extern "C" fn(u8),
+}
+
+impl RootModule for LoggerMod_Ref {
+    abi_stable::declare_root_module_statics! {LoggerMod_Ref}
+    const BASE_NAME: &'static str = "logger";
+    const NAME: &'static str = "logger";
+    const VERSION_STRINGS: VersionStrings = abi_stable::package_version_strings!();
+}
+
+# fn main(){}
+```
+
+# Interface versions
+
+The `interface_versions = "path::to::CONSTANT"` argument stores an [`InterfaceVersions`]
+constant in the [`LibHeader`] of the root module exported without a name,
+listing the versions of an interface that the library exports as named root modules,
+so that [`VersionNegotiator`] can load the highest version that the executable also supports.
+
+```rust
+use abi_stable::{
+    export_root_module,
+    library::{InterfaceVersion, InterfaceVersions, RootModule},
+    prefix_type::PrefixTypeTrait,
+    sabi_types::VersionStrings,
+    StableAbi,
+};
+
+pub const CODECS: InterfaceVersions =
+    InterfaceVersions::new("codecs", &[InterfaceVersion::unnamed(1)]);
+
+#[export_root_module(interface_versions = "CODECS")]
+pub fn get_codecs_mod() -> CodecsMod_Ref {
+    CodecsMod { decode }.leak_into_prefix()
+}
+
+extern "C" fn decode(x: u32) -> u32 {
+    x / 2
+}
+
+#[repr(C)]
+#[derive(StableAbi)]
+#[sabi(kind(Prefix(prefix_ref = CodecsMod_Ref)))]
+pub struct CodecsMod {
+    #[sabi(last_prefix_field)]
+    pub decode: extern "C" fn(u32) -> u32,
+}
+
+impl RootModule for CodecsMod_Ref {
+    abi_stable::declare_root_module_statics! {CodecsMod_Ref}
+    const BASE_NAME: &'static str = "codecs";
+    const NAME: &'static str = "codecs";
+    const VERSION_STRINGS: VersionStrings = abi_stable::package_version_strings!();
+}
+
+# fn main(){}
+```
 
 # Remove type layout constant
 
@@ -94,6 +350,16 @@ For a more detailed example look in the README in the repository for this crate.
 
 
 [`IntoRootModuleResult`]: ./library/trait.IntoRootModuleResult.html
+[`IntoShutdownResult`]: ./library/trait.IntoShutdownResult.html
+[`InterfaceVersions`]: ./library/struct.InterfaceVersions.html
+[`VersionNegotiator`]: ./library/struct.VersionNegotiator.html
+[`RootModule::shutdown`]: ./library/trait.RootModule.html#method.shutdown
+[`RootModule::load_from`]: ./library/trait.RootModule.html#method.load_from
+[`RootModule::load_from_with_context`]:
+./library/trait.RootModule.html#method.load_from_with_context
+[`shutdown_all`]: ./library/fn.shutdown_all.html
+[`RootModule`]: ./library/trait.RootModule.html
+[`EXPORT_NAME`]: ./library/trait.RootModule.html#associatedconstant.EXPORT_NAME
 [`LateStaticRef`]: ./sabi_types/struct.LateStaticRef.html
 [`LibHeader`]: ./library/struct.LibHeader.html
 
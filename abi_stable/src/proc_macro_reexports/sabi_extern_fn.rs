@@ -93,6 +93,41 @@ assert_eq!(hello(), vec!["hello".into_c(), "world".into()].into_c(),);
 
 ```
 
+# catch_unwind
+
+You can use `#[sabi_extern_fn(catch_unwind)]` to return panics as errors
+instead of aborting the process.
+
+This variant of the attribute requires the function to return an
+`RResult<T, E>`,where `E` implements [`FromPanicMessage`],
+and returns an `RErr` constructed from the panic message if the function panics.
+
+The panic payload is dropped (with `std::mem::forget`) without running its destructor,
+so that panics inside of it don't abort the process.
+
+### Example
+
+```rust
+use abi_stable::{
+    sabi_extern_fn,
+    std_types::{RBoxError, ROk, RResult},
+};
+
+#[sabi_extern_fn(catch_unwind)]
+fn divide(l: u32, r: u32) -> RResult<u32, RBoxError> {
+    ROk(l / r)
+}
+
+assert_eq!(divide(12, 4).unwrap(), 3);
+assert_eq!(
+    divide(1, 0).unwrap_err().to_string(),
+    "attempt to divide by zero",
+);
+
+```
+
+[`FromPanicMessage`]: ./utils/trait.FromPanicMessage.html
+
 
 */
 #[doc(inline)]
@@ -25,5 +25,8 @@ pub use self::{
     rref::RRef,
     rsmallbox::RSmallBox,
     static_ref::StaticRef,
-    version::{ParseVersionError, VersionNumber, VersionStrings},
+    version::{
+        ParseVersionError, ParseVersionRequirementError, VersionNumber, VersionRequirement,
+        VersionStrings,
+    },
 };
@@ -10,6 +10,9 @@ use std::{
 
 use crate::std_types::RStr;
 
+#[cfg(all(test, not(feature = "only_new_tests")))]
+mod tests;
+
 /// The `<major>.<minor>.<patch>` version of a library,
 ///
 /// # Post 1.0 major version
@@ -184,19 +187,26 @@ impl VersionNumber {
     ///
     /// ```
     pub fn new(vn: VersionStrings) -> Result<Self, ParseVersionError> {
-        let mut iter = vn.version.splitn(3, '.');
+        Self::parse_str(vn.version.as_str())
+            .map_err(|(which_field, x)| ParseVersionError::new(vn, which_field, x))
+    }
+
+    /// Parses a version string with the same format as `VersionStrings`,
+    /// returning the field that couldn't be parsed on error.
+    pub(crate) fn parse_str(version: &str) -> Result<Self, (&'static str, ParseIntError)> {
+        let mut iter = version.splitn(3, '.');
 
         VersionNumber {
             major: iter
                 .next()
                 .unwrap_or("")
                 .parse()
-                .map_err(|x| ParseVersionError::new(vn, "major", x))?,
+                .map_err(|x| ("major", x))?,
             minor: iter
                 .next()
                 .unwrap_or("")
                 .parse()
-                .map_err(|x| ParseVersionError::new(vn, "minor", x))?,
+                .map_err(|x| ("minor", x))?,
             patch: iter
                 .next()
                 .unwrap_or("")
@@ -204,7 +214,7 @@ impl VersionNumber {
                 .find(|x| x.key)
                 .map_or("0", |x| x.str)
                 .parse()
-                .map_err(|x| ParseVersionError::new(vn, "patch", x))?,
+                .map_err(|x| ("patch", x))?,
         }
         .piped(Ok)
     }
@@ -357,6 +367,274 @@ impl fmt::Display for VersionStrings {
 ////////////////////////////////////////////////////////////////////////////////
 ////////////////////////////////////////////////////////////////////////////////
 
+/// A requirement on the version number of a library,
+/// made of comma-separated comparisons that must all be satisfied,
+/// eg: `">=1.2, <1.5"`, `"~1.3"`, `"^1.2, !=1.4.2"`.
+///
+/// The versions in the comparisons can omit the minor and patch numbers,
+/// in which case they match any minor/patch number.
+///
+/// These are the supported comparisons:
+///
+/// - `=1.2.3`: exactly `1.2.3`,`=1.2` matches any `1.2.*` version.
+///
+/// - `!=1.2.3`: anything except `1.2.3`,`!=1.2` excludes all `1.2.*` versions.
+///
+/// - `>1.2.3`,`>=1.2.3`,`<1.2.3`,`<=1.2.3`: the usual comparisons,
+/// `>1.2` means `>=1.3.0`,and `<=1.2` means `<1.3.0`.
+///
+/// - `~1.2.3`: at least `1.2.3`,with the same major and minor version.
+/// `~1` allows any `1.*.*` version.
+///
+/// - `^1.2.3`,`1.2.3`: at least `1.2.3`,
+/// without changing the leftmost non-zero number
+/// (`^0.2.3` means `>=0.2.3, <0.3.0`).
+///
+/// - `*`: any version.
+///
+/// The requirement is parsed when it's evaluated,
+/// returning a [`ParseVersionRequirementError`] if it's invalid.
+///
+/// # Example
+///
+/// ```
+/// use abi_stable::sabi_types::{VersionRequirement, VersionStrings};
+///
+/// let requirement = VersionRequirement::new(">=1.2, <1.5, !=1.4.2");
+///
+/// let matches = |version: &'static str| {
+///     let version = VersionStrings::new(version).parsed().unwrap();
+///     requirement.matches(version).unwrap()
+/// };
+///
+/// assert!(!matches("1.1.9"));
+/// assert!(matches("1.2.0"));
+/// assert!(matches("1.4.1"));
+/// assert!(!matches("1.4.2"));
+/// assert!(matches("1.4.3"));
+/// assert!(!matches("1.5.0"));
+///
+/// assert!(VersionRequirement::new(">=1.2, <<1.5").validate().is_err());
+///
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct VersionRequirement {
+    requirement: &'static str,
+}
+
+impl VersionRequirement {
+    /// Constructs a `VersionRequirement` from the text of the requirement.
+    ///
+    /// This does not check whether the requirement is valid,
+    /// that check is done when it is evaluated,
+    /// or with [`validate`](#method.validate).
+    pub const fn new(requirement: &'static str) -> Self {
+        Self { requirement }
+    }
+
+    /// The text of the requirement.
+    pub const fn as_str(&self) -> &'static str {
+        self.requirement
+    }
+
+    /// Checks that the requirement is correctly formatted.
+    ///
+    /// # Errors
+    ///
+    /// This returns a `ParseVersionRequirementError` if the requirement is invalid.
+    pub fn validate(&self) -> Result<(), ParseVersionRequirementError> {
+        self.comparators().try_for_each(|x| x.map(drop))
+    }
+
+    /// Whether `version` satisfies all the comparisons in this requirement.
+    ///
+    /// # Errors
+    ///
+    /// This returns a `ParseVersionRequirementError` if the requirement is invalid.
+    pub fn matches(&self, version: VersionNumber) -> Result<bool, ParseVersionRequirementError> {
+        let mut matches = true;
+        for comparator in self.comparators() {
+            matches &= comparator?.matches(version);
+        }
+        Ok(matches)
+    }
+
+    fn comparators(
+        &self,
+    ) -> impl Iterator<Item = Result<Comparator, ParseVersionRequirementError>> + '_ {
+        let requirement = self.requirement;
+        requirement.split(',').map(move |comparison| {
+            Comparator::parse(comparison.trim()).map_err(|reason| ParseVersionRequirementError {
+                requirement,
+                comparison: comparison.trim().into(),
+                reason,
+            })
+        })
+    }
+}
+
+impl Display for VersionRequirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(self.requirement, f)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ComparisonOp {
+    Exact,
+    NotEqual,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+    Tilde,
+    Caret,
+    Any,
+}
+
+/// A single comparison in a `VersionRequirement`,
+/// where `None` minor/patch numbers match any number.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Comparator {
+    op: ComparisonOp,
+    major: u64,
+    minor: Option<u64>,
+    patch: Option<u64>,
+}
+
+impl Comparator {
+    fn parse(comparison: &str) -> Result<Self, &'static str> {
+        use self::ComparisonOp as CO;
+
+        if comparison.is_empty() {
+            return Err("empty comparison");
+        }
+        if comparison == "*" {
+            return Ok(Self {
+                op: CO::Any,
+                major: 0,
+                minor: None,
+                patch: None,
+            });
+        }
+
+        // Longer operators must come before their prefixes.
+        let (op, version) = [
+            ("!=", CO::NotEqual),
+            (">=", CO::GreaterEq),
+            ("<=", CO::LessEq),
+            (">", CO::Greater),
+            ("<", CO::Less),
+            ("=", CO::Exact),
+            ("~", CO::Tilde),
+            ("^", CO::Caret),
+        ]
+        .iter()
+        .find_map(|&(prefix, op)| comparison.strip_prefix(prefix).map(|rest| (op, rest)))
+        .unwrap_or((CO::Caret, comparison));
+
+        let mut numbers = version.trim().split('.').map(|number| {
+            number
+                .parse::<u32>()
+                .map(u64::from)
+                .map_err(|_| "expected a `major[.minor[.patch]]` version")
+        });
+
+        let major = numbers.next().unwrap_or(Err("expected a version"))?;
+        let minor = numbers.next().transpose()?;
+        let patch = numbers.next().transpose()?;
+        if numbers.next().is_some() {
+            return Err("versions can't have more than 3 numbers");
+        }
+
+        Ok(Self {
+            op,
+            major,
+            minor,
+            patch,
+        })
+    }
+
+    fn matches(&self, version: VersionNumber) -> bool {
+        use self::ComparisonOp as CO;
+
+        let Self {
+            major,
+            minor,
+            patch,
+            ..
+        } = *self;
+        let version = (
+            u64::from(version.major),
+            u64::from(version.minor),
+            u64::from(version.patch),
+        );
+
+        // The range of versions that the numbers of this comparison match.
+        let lower = (major, minor.unwrap_or(0), patch.unwrap_or(0));
+        let upper = match (minor, patch) {
+            (None, _) => (major + 1, 0, 0),
+            (Some(minor), None) => (major, minor + 1, 0),
+            (Some(minor), Some(patch)) => (major, minor, patch + 1),
+        };
+
+        match self.op {
+            CO::Exact => lower <= version && version < upper,
+            CO::NotEqual => !(lower <= version && version < upper),
+            CO::Greater => upper <= version,
+            CO::GreaterEq => lower <= version,
+            CO::Less => version < lower,
+            CO::LessEq => version < upper,
+            CO::Tilde => {
+                let upper = match minor {
+                    Some(minor) => (major, minor + 1, 0),
+                    None => (major + 1, 0, 0),
+                };
+                lower <= version && version < upper
+            }
+            CO::Caret => {
+                let upper = match (major, minor, patch) {
+                    (0, Some(0), Some(patch)) => (0, 0, patch + 1),
+                    (0, Some(minor), _) => (0, minor + 1, 0),
+                    _ => (major + 1, 0, 0),
+                };
+                lower <= version && version < upper
+            }
+            CO::Any => true,
+        }
+    }
+}
+
+/// When a `VersionRequirement` is not correctly formatted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseVersionRequirementError {
+    requirement: &'static str,
+    comparison: String,
+    reason: &'static str,
+}
+
+impl ParseVersionRequirementError {
+    /// Gets back the `VersionRequirement` that could not be parsed.
+    pub const fn requirement(&self) -> VersionRequirement {
+        VersionRequirement::new(self.requirement)
+    }
+}
+
+impl Display for ParseVersionRequirementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "\nInvalid version requirement:'{}'\nerror in the '{}' comparison:{}",
+            self.requirement, self.comparison, self.reason,
+        )
+    }
+}
+
+impl error::Error for ParseVersionRequirementError {}
+
+////////////////////////////////////////////////////////////////////////////////
+////////////////////////////////////////////////////////////////////////////////
+
 /// Instantiates a [`VersionStrings`] with the
 /// major.minor.patch version of the library where it is invoked.
 ///
@@ -396,6 +674,16 @@ impl ParseVersionError {
     pub const fn version_strings(&self) -> VersionStrings {
         self.version_strings
     }
+
+    /// The field of the version string that could not be parsed.
+    pub(crate) const fn which_field(&self) -> &'static str {
+        self.which_field
+    }
+
+    /// The error from parsing the field.
+    pub(crate) const fn parse_error(&self) -> &ParseIntError {
+        &self.parse_error
+    }
 }
 
 impl Display for ParseVersionError {
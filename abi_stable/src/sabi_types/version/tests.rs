@@ -0,0 +1,98 @@
+use super::{VersionNumber, VersionRequirement, VersionStrings};
+
+fn version(version: &'static str) -> VersionNumber {
+    VersionStrings::new(version).parsed().unwrap()
+}
+
+fn assert_matches(
+    requirement: &'static str,
+    matching: &[&'static str],
+    not_matching: &[&'static str],
+) {
+    let requirement = VersionRequirement::new(requirement);
+    for &v in matching {
+        assert!(
+            requirement.matches(version(v)).unwrap(),
+            "'{}' should match '{}'",
+            requirement,
+            v
+        );
+    }
+    for &v in not_matching {
+        assert!(
+            !requirement.matches(version(v)).unwrap(),
+            "'{}' should not match '{}'",
+            requirement,
+            v
+        );
+    }
+}
+
+#[test]
+fn comparison_operators() {
+    assert_matches("=1.2.3", &["1.2.3"], &["1.2.2", "1.2.4", "1.3.3"]);
+    assert_matches("=1.2", &["1.2.0", "1.2.9"], &["1.1.9", "1.3.0"]);
+    assert_matches("!=1.4.2", &["1.4.1", "1.4.3", "2.4.2"], &["1.4.2"]);
+    assert_matches("!=1.4", &["1.3.9", "1.5.0"], &["1.4.0", "1.4.7"]);
+    assert_matches(">1.2.3", &["1.2.4", "2.0.0"], &["1.2.3", "1.0.0"]);
+    assert_matches(">1.2", &["1.3.0"], &["1.2.9"]);
+    assert_matches(">=1.2", &["1.2.0", "3.0.0"], &["1.1.9"]);
+    assert_matches("<1.5", &["1.4.9", "0.1.0"], &["1.5.0"]);
+    assert_matches("<=1.4", &["1.4.9"], &["1.5.0"]);
+    assert_matches("<=1.4.2", &["1.4.2"], &["1.4.3"]);
+    assert_matches("*", &["0.0.0", "1.2.3", "99.0.0"], &[]);
+}
+
+#[test]
+fn tilde_requirements() {
+    assert_matches("~1.3", &["1.3.0", "1.3.9"], &["1.2.9", "1.4.0"]);
+    assert_matches("~1.3.2", &["1.3.2", "1.3.9"], &["1.3.1", "1.4.0"]);
+    assert_matches("~1", &["1.0.0", "1.9.9"], &["0.9.9", "2.0.0"]);
+}
+
+#[test]
+fn caret_requirements() {
+    assert_matches("^1.2.3", &["1.2.3", "1.9.0"], &["1.2.2", "2.0.0"]);
+    assert_matches("1.2.3", &["1.2.3", "1.9.0"], &["1.2.2", "2.0.0"]);
+    assert_matches("^0.2.3", &["0.2.3", "0.2.9"], &["0.2.2", "0.3.0"]);
+    assert_matches("^0.0.3", &["0.0.3"], &["0.0.4", "0.1.0"]);
+    assert_matches("^0.0", &["0.0.0", "0.0.9"], &["0.1.0"]);
+    assert_matches("^0", &["0.0.0", "0.9.9"], &["1.0.0"]);
+}
+
+#[test]
+fn combined_requirements() {
+    assert_matches(
+        ">=1.2, <1.5",
+        &["1.2.0", "1.4.9"],
+        &["1.1.0", "1.5.0", "2.0.0"],
+    );
+    assert_matches(
+        "^1.2, !=1.4.2",
+        &["1.2.0", "1.4.1", "1.9.0"],
+        &["1.4.2", "2.0.0"],
+    );
+    assert_matches(">=1.8, <3", &["1.8.0", "2.5.0"], &["1.7.0", "3.0.0"]);
+}
+
+#[test]
+fn invalid_requirements() {
+    for &requirement in &[
+        "",
+        ">=1.2,",
+        "<<1.5",
+        ">=a.2",
+        "1.2.3.4",
+        "=1..2",
+        ">= 1.2 1.3",
+    ] {
+        let requirement = VersionRequirement::new(requirement);
+        let err = requirement.validate().unwrap_err();
+        assert_eq!(err.requirement(), requirement);
+        assert!(requirement.matches(version("1.2.3")).is_err());
+    }
+
+    for &requirement in &[">= 1.2", " ~1.3 ", "^1.2 , != 1.4.2"] {
+        VersionRequirement::new(requirement).validate().unwrap();
+    }
+}
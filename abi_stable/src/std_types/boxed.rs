@@ -8,7 +8,7 @@ use std::{
     io::{self, BufRead, IoSlice, IoSliceMut, Read, Seek, Write},
     iter::FusedIterator,
     marker::{PhantomData, Unpin},
-    mem::ManuallyDrop,
+    mem::{self, ManuallyDrop},
     ops::DerefMut,
     pin::Pin,
     ptr::{self, NonNull},
@@ -19,6 +19,7 @@ use std::{
 use core_extensions::SelfOps;
 
 use crate::{
+    library::record_reallocation,
     marker_type::NonOwningPhantom,
     pointer_trait::{
         AsMutPtr, AsPtr, CallReferentDrop, CanTransmuteElement, Deallocate, GetPointerKind,
@@ -114,6 +115,7 @@ mod private {
         ///
         /// ```
         pub fn from_box(p: Box<T>) -> RBox<T> {
+            record_reallocation(0, mem::size_of::<T>());
             RBox {
                 data: unsafe { NonNull::new_unchecked(Box::into_raw(p)) },
                 vtable: VTableGetter::<T>::LIB_VTABLE,
@@ -222,6 +224,7 @@ impl<T> RBox<T> {
             if ::std::ptr::eq(this_vtable.0.to_raw_ptr(), other_vtable.0.to_raw_ptr())
                 || this_vtable.type_id()() == other_vtable.type_id()()
             {
+                record_reallocation(mem::size_of::<T>(), 0);
                 Box::from_raw(this.data())
             } else {
                 let ret = Box::new(this.data().read());
@@ -663,6 +666,7 @@ unsafe extern "C" fn destroy_box<T>(
             unsafe { ptr::drop_in_place(ptr); }
         }
         if let Deallocate::Yes = dealloc {
+            record_reallocation(mem::size_of::<T>(), 0);
             unsafe { drop(Box::from_raw(ptr as *mut ManuallyDrop<T>)); }
         }
     }
@@ -17,6 +17,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use core_extensions::SelfOps;
 
 use crate::{
+    library::record_reallocation,
     pointer_trait::CanTransmuteElement,
     prefix_type::WithMetadata,
     sabi_types::RMut,
@@ -164,15 +165,36 @@ mod private {
             F: FnOnce(&mut Vec<T>) -> U,
         {
             let mut old = mem::replace(self, RVec::new()).piped(ManuallyDrop::new);
+            let old_size = old.allocation_size();
             let mut list =
                 unsafe { Vec::<T>::from_raw_parts(old.buffer_mut(), old.len(), old.capacity()) };
             let ret = f(&mut list);
+            let list = RVec::from_vec_untracked(list);
+            record_reallocation(old_size, list.allocation_size());
             unsafe {
-                ptr::write(self, list.into());
+                ptr::write(self, list);
             }
             ret
         }
 
+        /// The size of the buffer in bytes,which is 0 if it's not allocated.
+        pub(super) fn allocation_size(&self) -> usize {
+            self.capacity.wrapping_mul(mem::size_of::<T>())
+        }
+
+        /// Converts a `Vec<T>` into an `RVec<T>`,
+        /// without recording the allocation for allocation tracking.
+        fn from_vec_untracked(this: Vec<T>) -> Self {
+            let mut this = ManuallyDrop::new(this);
+            RVec {
+                vtable: VTableGetter::<T>::LIB_VTABLE,
+                buffer: unsafe { NonNull::new_unchecked(this.as_mut_ptr()) },
+                length: this.len(),
+                capacity: this.capacity(),
+                _marker: PhantomData,
+            }
+        }
+
         /// Gets a raw pointer to the start of this RVec's buffer.
         #[inline(always)]
         pub const fn as_ptr(&self) -> *const T {
@@ -187,14 +209,9 @@ mod private {
     impl_from_rust_repr! {
         impl[T] From<Vec<T>> for RVec<T>{
             fn(this){
-                let mut this = ManuallyDrop::new(this);
-                RVec {
-                    vtable: VTableGetter::<T>::LIB_VTABLE,
-                    buffer: unsafe{ NonNull::new_unchecked(this.as_mut_ptr()) },
-                    length: this.len(),
-                    capacity: this.capacity(),
-                    _marker: PhantomData,
-                }
+                let this = RVec::from_vec_untracked(this);
+                record_reallocation(0, this.allocation_size());
+                this
             }
         }
     }
@@ -514,6 +531,7 @@ impl<T> RVec<T> {
             if ::std::ptr::eq(this_vtable.0.to_raw_ptr(), other_vtable.0.to_raw_ptr())
                 || this_vtable.type_id()() == other_vtable.type_id()()
             {
+                record_reallocation(this.allocation_size(), 0);
                 Vec::from_raw_parts(this.buffer_mut(), this.len(), this.capacity())
             } else {
                 let len = this.length;
@@ -1502,6 +1520,7 @@ struct VecVTable {
 unsafe extern "C" fn destructor_vec<T>(this: RMut<'_, ()>) {
     extern_fn_panic_handling! {no_early_return; unsafe {
         let this = this.transmute_into_mut::<RVec<T>>();
+        record_reallocation(this.allocation_size(), 0);
         drop(Vec::from_raw_parts(
             this.buffer_mut(),
             this.len(),
@@ -22,12 +22,15 @@ use crate::{
     std_types::{RSlice, RStr, UTypeId},
 };
 
+mod c_header;
 mod construction;
 pub mod data_structures;
+mod fingerprint;
 mod iterators;
 mod printing;
 mod shared_vars;
 mod small_types;
+mod snapshot;
 pub mod tagging;
 mod tl_data;
 mod tl_enums;
@@ -40,12 +43,19 @@ mod tl_other;
 mod tl_prefix;
 mod tl_reflection;
 
-pub(crate) use self::iterators::ChainOnce;
+pub(crate) use self::{fingerprint::FingerprintedLayout, iterators::ChainOnce};
 
 pub use self::{
+    c_header::CHeader,
     construction::{ItemInfo, _private_MonoTypeLayoutDerive, _private_TypeLayoutDerive},
+    fingerprint::LayoutFingerprint,
     shared_vars::{MonoSharedVars, SharedVars},
     small_types::{OptionU16, OptionU8, StartLen, StartLenConverter, StartLenRepr},
+    snapshot::{
+        DataSnapshot, EnumSnapshot, FieldSnapshot, FunctionSnapshot, GenericsSnapshot,
+        LayoutOrSnapshot, LayoutSnapshot, NonExhaustiveSnapshot, PrefixTypeSnapshot, TagSnapshot,
+        TypeSnapshot,
+    },
     tagging::Tag,
     tl_data::{GenericTLData, MismatchedTLDataVariant, MonoTLData, TLData, TLDataDiscriminant},
     tl_enums::{
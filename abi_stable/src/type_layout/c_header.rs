@@ -0,0 +1,865 @@
+use super::*;
+
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod tests;
+
+/// A C header declaring the C equivalent of a type,
+/// and of every type that it references.
+///
+/// The header can be used by C and C++ code to use the root module of
+/// an abi_stable dynamic library,and the types that it references.
+///
+/// # Translation
+///
+/// - `repr(C)` structs and unions are translated to C structs and unions.
+///
+/// - `repr(transparent)` types and `Option`s of non-nullable types
+/// are translated to the type that they wrap.
+///
+/// - Fieldless enums are translated to a typedef of the integer type of their discriminant,
+/// and enums with fields are translated to
+/// [the C equivalent of their layout](https://rust-lang.github.io/rfcs/2195-really-tagged-unions.html).
+/// The discriminant of each variant is declared as a `NameOfEnum_NameOfVariant` macro.
+///
+/// - Nonexhaustive enums are translated to a struct with the size and alignment
+/// of their storage,since the variants can change in newer versions.
+///
+/// - Prefix types (modules and vtables) are translated to a struct with all their fields,
+/// and a `NameOfType_WithMetadata` struct that `PrefixRef`s point to,
+/// whose `field_accessibility` field has the `n`th bit set if the `n`th field is accessible.
+///
+/// - Function pointers are translated to C function pointers.
+///
+/// - Zero-sized types are omitted,
+/// and types whose layout can't be inspected are translated to a struct with
+/// the same size and alignment.
+///
+/// Since all sizes and alignments are those of the platform that the layout comes from,
+/// the header asserts that the types have the same size and alignment in C.
+///
+/// Generic types are named after the type with its generic arguments,
+/// adding a numeric suffix to disambiguate types whose names would be equal.
+///
+/// # Example
+///
+/// ```rust
+/// use abi_stable::{
+///     std_types::{RString, RVec},
+///     type_layout::CHeader,
+///     StableAbi,
+/// };
+///
+/// #[repr(C)]
+/// #[derive(StableAbi)]
+/// pub struct Point {
+///     pub x: u32,
+///     pub y: u32,
+/// }
+///
+/// #[repr(C)]
+/// #[derive(StableAbi)]
+/// pub struct Polygon {
+///     pub name: RString,
+///     pub points: RVec<Point>,
+///     pub area: extern "C" fn(&Polygon) -> f64,
+/// }
+///
+/// let header = CHeader::new(Polygon::LAYOUT).to_string();
+///
+/// assert!(header.contains("struct Point {\n    uint32_t x;\n    uint32_t y;\n};"));
+/// assert!(header.contains("double (*area)(const Polygon *param_0);"));
+///
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CHeader {
+    include_guard: String,
+    root_type: String,
+    declarations: Vec<String>,
+    typedefs: Vec<String>,
+    definitions: Vec<String>,
+}
+
+impl CHeader {
+    /// Generates the C header for the type with this layout.
+    pub fn new(layout: &'static TypeLayout) -> Self {
+        let mut gen = Generator {
+            ctypes: HashMap::new(),
+            used_names: HashMap::new(),
+            declarations: Vec::new(),
+            typedefs: Vec::new(),
+            definitions: Vec::new(),
+        };
+
+        let root_type = match gen.ctype_of(layout) {
+            Some(CType::Named(name)) => name,
+            Some(ctype) => {
+                let name = gen.unique_name(layout);
+                let text = format!("typedef {};\n", ctype.declare(&name));
+                let mut dependencies = Vec::new();
+                ctype.value_dependencies(&mut dependencies);
+                gen.define(name.clone(), dependencies, text);
+                name
+            }
+            None => "void".to_string(),
+        };
+
+        Self {
+            include_guard: format!("SABI_{}_H", root_type.to_uppercase()),
+            root_type,
+            declarations: gen.declarations,
+            typedefs: gen.typedefs,
+            definitions: sort_definitions(gen.definitions),
+        }
+    }
+
+    /// The name of the C type that the layout passed to `new` was translated to.
+    pub fn root_type(&self) -> &str {
+        &self.root_type
+    }
+}
+
+impl Display for CHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "/* Generated by abi_stable,declaring `{}` and the types it references. */\n",
+            self.root_type,
+        )?;
+        writeln!(f, "#ifndef {0}\n#define {0}\n", self.include_guard)?;
+        f.write_str(HEADER_PRELUDE)?;
+
+        for declaration in &self.declarations {
+            writeln!(f, "{}", declaration)?;
+        }
+        for definition in self.typedefs.iter().chain(&self.definitions) {
+            writeln!(f, "\n{}", definition.trim_end())?;
+        }
+
+        writeln!(f, "\n#endif /* {} */", self.include_guard)
+    }
+}
+
+const HEADER_PRELUDE: &str = "\
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifndef SABI_STATIC_ASSERT
+#ifdef __cplusplus
+#define SABI_STATIC_ASSERT(cond, msg) static_assert(cond, msg)
+#define SABI_ALIGNOF(T) alignof(T)
+#define SABI_ALIGNAS(N) alignas(N)
+#else
+#define SABI_STATIC_ASSERT(cond, msg) _Static_assert(cond, msg)
+#define SABI_ALIGNOF(T) _Alignof(T)
+#define SABI_ALIGNAS(N) _Alignas(N)
+#endif
+#endif
+
+";
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A C type,used to write the declarator of fields and parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CType {
+    Void,
+    Named(String),
+    Pointer {
+        pointee: Box<CType>,
+        is_const: bool,
+    },
+    Array {
+        element: Box<CType>,
+        len: usize,
+    },
+    Function {
+        returns: Box<CType>,
+        params: Vec<(String, CType)>,
+    },
+}
+
+impl CType {
+    /// Declares `declarator` (usually a field name) with this type.
+    /// Pushes the names of the types that this stores by value.
+    fn value_dependencies(&self, out: &mut Vec<String>) {
+        match self {
+            CType::Named(name) => out.push(name.clone()),
+            CType::Array { element, .. } => element.value_dependencies(out),
+            CType::Void | CType::Pointer { .. } | CType::Function { .. } => {}
+        }
+    }
+
+    fn innermost_element(&self) -> &CType {
+        match self {
+            CType::Array { element, .. } => element.innermost_element(),
+            _ => self,
+        }
+    }
+
+    fn declare(&self, declarator: &str) -> String {
+        fn join(type_: &str, declarator: &str) -> String {
+            if declarator.is_empty() {
+                type_.to_string()
+            } else {
+                format!("{} {}", type_, declarator)
+            }
+        }
+
+        let const_ = |is_const: bool| if is_const { "const " } else { "" };
+
+        match self {
+            CType::Void => join("void", declarator),
+            CType::Named(name) => join(name, declarator),
+            CType::Pointer { pointee, is_const } => match &**pointee {
+                CType::Void | CType::Named(_) => {
+                    let declarator = format!("*{}", declarator);
+                    format!("{}{}", const_(*is_const), pointee.declare(&declarator))
+                }
+                CType::Pointer { .. } => {
+                    let declarator = format!("{}*{}", const_(*is_const), declarator);
+                    pointee.declare(&declarator)
+                }
+                CType::Array { .. } => {
+                    // The const qualifier of an array goes on its elements.
+                    let declaration = pointee.declare(&format!("(*{})", declarator));
+                    match pointee.innermost_element() {
+                        CType::Void | CType::Named(_) => {
+                            format!("{}{}", const_(*is_const), declaration)
+                        }
+                        _ => declaration,
+                    }
+                }
+                CType::Function { .. } => pointee.declare(&format!("(*{})", declarator)),
+            },
+            CType::Array { element, len } => element.declare(&format!("{}[{}]", declarator, len)),
+            CType::Function { returns, params } => {
+                let params = if params.is_empty() {
+                    "void".to_string()
+                } else {
+                    params
+                        .iter()
+                        .map(|(name, ctype)| ctype.declare(name))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                returns.declare(&format!("{}({})", declarator, params))
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+struct Generator {
+    /// The C type of every type that was translated,None for zero-sized types.
+    ctypes: HashMap<TypeKey, Option<CType>>,
+    used_names: HashMap<String, usize>,
+    declarations: Vec<String>,
+    /// Typedefs of integer types,which can be defined before any struct.
+    typedefs: Vec<String>,
+    definitions: Vec<Definition>,
+}
+
+/// The definition of a struct or union.
+struct Definition {
+    name: String,
+    /// The types that the definition stores by value,
+    /// which must be defined before it.
+    value_dependencies: Vec<String>,
+    text: String,
+}
+
+impl Generator {
+    fn ctype_of(&mut self, layout: &'static TypeLayout) -> Option<CType> {
+        let key = type_key(layout);
+        if let Some(ctype) = self.ctypes.get(&key) {
+            return ctype.clone();
+        }
+
+        let ctype = self.compute_ctype(layout);
+        self.ctypes.insert(key, ctype.clone());
+        ctype
+    }
+
+    fn compute_ctype(&mut self, layout: &'static TypeLayout) -> Option<CType> {
+        if layout.size() == 0 {
+            return None;
+        }
+
+        if layout.name() == "PrefixRef" && layout.package() == "abi_stable" {
+            if let Some(prefix) = first_type_param(layout) {
+                let name = self.prefix_type(prefix);
+                return Some(CType::Pointer {
+                    pointee: Box::new(CType::Named(format!("{}_WithMetadata", name))),
+                    is_const: true,
+                });
+            }
+        }
+
+        match (layout.repr_attr(), layout.data()) {
+            (ReprAttr::OptionNonZero, _) => {
+                let wrapped = first_type_param(layout).or_else(|| first_field_layout(layout))?;
+                self.ctype_of(wrapped)
+            }
+            (ReprAttr::Transparent, TLData::Struct { fields }) if !fields.is_empty() => fields
+                .iter()
+                .find(|field| field.layout().size() != 0)
+                .and_then(|field| self.field_ctype(&field)),
+            (_, TLData::Primitive(prim)) => Some(self.primitive(layout, prim)),
+            (_, TLData::Opaque) if layout.name() == "AFunctionPointer" => {
+                // The signature of the function is only known by the field that stores it.
+                Some(CType::Pointer {
+                    pointee: Box::new(CType::Function {
+                        returns: Box::new(CType::Void),
+                        params: Vec::new(),
+                    }),
+                    is_const: false,
+                })
+            }
+            (_, TLData::Struct { fields }) if !fields.is_empty() => {
+                Some(self.struct_or_union(layout, "struct", fields))
+            }
+            (_, TLData::Union { fields }) if !fields.is_empty() => {
+                Some(self.struct_or_union(layout, "union", fields))
+            }
+            (_, TLData::Enum(enum_)) => Some(self.enum_(layout, enum_)),
+            (_, TLData::PrefixType(_)) => Some(CType::Named(self.prefix_type(layout))),
+            _ => Some(self.opaque(layout, "")),
+        }
+    }
+
+    /// Gets the C type of a field,using the signature of the function pointer it stores.
+    fn field_ctype(&mut self, field: &TLField) -> Option<CType> {
+        let layout = field.layout();
+        let functions = field.function_range();
+
+        let fn_layout = match layout.repr_attr() {
+            ReprAttr::OptionNonZero => first_type_param(layout).unwrap_or(layout),
+            _ => layout,
+        };
+
+        if fn_layout.name() == "AFunctionPointer" && functions.len() == 1 {
+            let function = functions.index(0);
+            let returns = self
+                .field_ctype(&function.get_return())
+                .unwrap_or(CType::Void);
+            let params = function
+                .get_params()
+                .enumerate()
+                .filter_map(|(i, param)| {
+                    let ctype = self.field_ctype(&param)?;
+                    let name = if is_c_identifier(param.name()) {
+                        param.name().to_string()
+                    } else {
+                        format!("param_{}", i)
+                    };
+                    Some((name, ctype))
+                })
+                .collect();
+
+            return Some(CType::Pointer {
+                pointee: Box::new(CType::Function {
+                    returns: Box::new(returns),
+                    params,
+                }),
+                is_const: false,
+            });
+        }
+
+        self.ctype_of(layout)
+    }
+
+    fn primitive(&mut self, layout: &'static TypeLayout, prim: TLPrimitive) -> CType {
+        let named = |name: &str| CType::Named(name.to_string());
+        let pointer = |this: &mut Self, is_const: bool| {
+            let pointee = first_type_param(layout)
+                .and_then(|pointee| this.ctype_of(pointee))
+                .unwrap_or(CType::Void);
+            CType::Pointer {
+                pointee: Box::new(pointee),
+                is_const,
+            }
+        };
+
+        match prim {
+            TLPrimitive::U8 => named("uint8_t"),
+            TLPrimitive::I8 => named("int8_t"),
+            TLPrimitive::U16 => named("uint16_t"),
+            TLPrimitive::I16 => named("int16_t"),
+            TLPrimitive::U32 => named("uint32_t"),
+            TLPrimitive::I32 => named("int32_t"),
+            TLPrimitive::U64 => named("uint64_t"),
+            TLPrimitive::I64 => named("int64_t"),
+            TLPrimitive::Usize => named("size_t"),
+            TLPrimitive::Isize => named("ptrdiff_t"),
+            TLPrimitive::F32 => named("float"),
+            TLPrimitive::F64 => named("double"),
+            TLPrimitive::Bool => named("bool"),
+            TLPrimitive::SharedRef | TLPrimitive::ConstPtr => pointer(self, true),
+            TLPrimitive::MutRef | TLPrimitive::MutPtr => pointer(self, false),
+            TLPrimitive::Array => {
+                let element = first_type_param(layout);
+                match element.and_then(|x| Some((x.size(), self.ctype_of(x)?))) {
+                    Some((elem_size, element)) => CType::Array {
+                        element: Box::new(element),
+                        len: layout.size() / elem_size,
+                    },
+                    None => self.opaque(layout, ""),
+                }
+            }
+        }
+    }
+
+    fn struct_or_union(
+        &mut self,
+        layout: &'static TypeLayout,
+        keyword: &str,
+        fields: TLFields,
+    ) -> CType {
+        let name = self.declare_name(layout, keyword);
+
+        let mut body = String::new();
+        let mut dependencies = Vec::new();
+        for field in fields.iter() {
+            self.push_field(&mut body, &mut dependencies, &field, "    ");
+        }
+
+        if body.is_empty() {
+            push_opaque_field(&mut body, layout, "    ");
+        }
+
+        let text = format!(
+            "{keyword} {name} {{\n{body}}};\n{asserts}",
+            keyword = keyword,
+            name = name,
+            body = body,
+            asserts = layout_asserts(&name, layout),
+        );
+        self.define(name.clone(), dependencies, text);
+
+        CType::Named(name)
+    }
+
+    fn enum_(&mut self, layout: &'static TypeLayout, enum_: TLEnum) -> CType {
+        let (tag_type, is_c) = match layout.repr_attr() {
+            ReprAttr::C => ("int", true),
+            ReprAttr::CAndInt(repr) => (discriminant_type(repr), true),
+            ReprAttr::Int(repr) => (discriminant_type(repr), false),
+            _ => return self.opaque(layout, ""),
+        };
+
+        let variant_names = enum_.variant_names_iter().collect::<Vec<&'static str>>();
+
+        let is_fieldless = enum_.field_count.iter().all(|&x| x == 0);
+        let is_exhaustive = enum_.exhaustiveness.is_exhaustive();
+
+        if !is_exhaustive {
+            let comment = format!(
+                "/* A nonexhaustive enum,whose discriminant is a `{}`. */\n",
+                tag_type,
+            );
+            let name = self.opaque_named(layout, &comment);
+            return CType::Named(name);
+        }
+
+        if is_fieldless {
+            let name = self.unique_name(layout);
+            let defines = discriminant_defines(&name, tag_type, &variant_names, &enum_);
+            self.typedefs
+                .push(format!("typedef {} {};\n{}", tag_type, name, defines));
+            CType::Named(name)
+        } else {
+            let keyword = if is_c { "struct" } else { "union" };
+            let name = self.declare_name(layout, keyword);
+
+            let mut variants = String::new();
+            let mut dependencies = Vec::new();
+            let mut fields = enum_.fields.iter();
+            for (variant, &field_count) in variant_names.iter().zip(enum_.field_count.iter()) {
+                let mut variant_fields = String::new();
+                let indentation = if is_c { "            " } else { "        " };
+                if !is_c {
+                    variant_fields.push_str(&format!("{}{} tag;\n", indentation, tag_type));
+                }
+                for field in fields.by_ref().take(usize::from(field_count)) {
+                    self.push_field(&mut variant_fields, &mut dependencies, &field, indentation);
+                }
+                if variant_fields.is_empty() {
+                    continue;
+                }
+                let indentation = &indentation[4..];
+                variants.push_str(&format!(
+                    "{ind}struct {{\n{fields}{ind}}} {variant};\n",
+                    ind = indentation,
+                    fields = variant_fields,
+                    variant = c_identifier(variant),
+                ));
+            }
+
+            let body = if is_c {
+                format!(
+                    "    {} tag;\n    union {{\n{}    }} payload;\n",
+                    tag_type, variants,
+                )
+            } else {
+                variants
+            };
+
+            let text = format!(
+                "{keyword} {name} {{\n{body}}};\n{asserts}{defines}",
+                keyword = keyword,
+                name = name,
+                body = body,
+                asserts = layout_asserts(&name, layout),
+                defines = discriminant_defines(&name, tag_type, &variant_names, &enum_),
+            );
+            self.define(name.clone(), dependencies, text);
+            CType::Named(name)
+        }
+    }
+
+    /// Declares the struct for a prefix type,and the `WithMetadata` struct that wraps it,
+    /// returning the name of the prefix type.
+    fn prefix_type(&mut self, layout: &'static TypeLayout) -> String {
+        if let Some(Some(CType::Named(name))) = self.ctypes.get(&type_key(layout)) {
+            return name.clone();
+        }
+
+        let prefix = match layout.data() {
+            TLData::PrefixType(prefix) => prefix,
+            _ => {
+                return match self.ctype_of(layout) {
+                    Some(CType::Named(name)) => name,
+                    _ => "void".to_string(),
+                }
+            }
+        };
+
+        let name = self.declare_name(layout, "struct");
+        let with_metadata = format!("{}_WithMetadata", name);
+        self.declarations
+            .push(format!("typedef struct {0} {0};", with_metadata));
+
+        let mut body = String::new();
+        let mut dependencies = Vec::new();
+        for (i, field) in prefix.fields.iter().enumerate() {
+            if i == usize::from(prefix.first_suffix_field) {
+                body.push_str(
+                    "    /* The fields below are only accessible if their bit in \
+                     `field_accessibility` is set. */\n",
+                );
+            }
+            self.push_field(&mut body, &mut dependencies, &field, "    ");
+        }
+
+        let text = format!(
+            "/* A prefix type,which can have fields added in minor versions. */\n\
+             struct {name} {{\n{body}}};\n\
+             struct {with_metadata} {{\n    \
+                 uint64_t field_accessibility;\n    \
+                 const void *type_layout;\n    \
+                 {name} value;\n\
+             }};\n",
+            name = name,
+            body = body,
+            with_metadata = with_metadata,
+        );
+        self.define(name.clone(), dependencies, text);
+
+        name
+    }
+
+    fn opaque(&mut self, layout: &'static TypeLayout, comment: &str) -> CType {
+        CType::Named(self.opaque_named(layout, comment))
+    }
+
+    fn opaque_named(&mut self, layout: &'static TypeLayout, comment: &str) -> String {
+        let name = self.declare_name(layout, "struct");
+        let mut body = String::new();
+        push_opaque_field(&mut body, layout, "    ");
+        let text = format!(
+            "{comment}struct {name} {{\n{body}}};\n{asserts}",
+            comment = comment,
+            name = name,
+            body = body,
+            asserts = layout_asserts(&name, layout),
+        );
+        self.define(name.clone(), Vec::new(), text);
+        name
+    }
+
+    fn push_field(
+        &mut self,
+        out: &mut String,
+        dependencies: &mut Vec<String>,
+        field: &TLField,
+        indentation: &str,
+    ) {
+        if let Some(ctype) = self.field_ctype(field) {
+            let declaration = ctype.declare(&c_identifier(field.name()));
+            out.push_str(&format!("{}{};\n", indentation, declaration));
+            ctype.value_dependencies(dependencies);
+        }
+    }
+
+    fn define(&mut self, name: String, value_dependencies: Vec<String>, text: String) {
+        self.definitions.push(Definition {
+            name,
+            value_dependencies,
+            text,
+        });
+    }
+
+    /// Reserves the name of a struct or union,
+    /// forward declaring it so that it can be referenced before it's defined.
+    fn declare_name(&mut self, layout: &'static TypeLayout, keyword: &str) -> String {
+        let name = self.unique_name(layout);
+        self.ctypes
+            .insert(type_key(layout), Some(CType::Named(name.clone())));
+        self.declarations
+            .push(format!("typedef {0} {1} {1};", keyword, name));
+        name
+    }
+
+    fn unique_name(&mut self, layout: &'static TypeLayout) -> String {
+        let name = type_name(layout);
+        let count = self.used_names.entry(name.clone()).or_insert(0);
+        *count += 1;
+        if *count == 1 {
+            name
+        } else {
+            format!("{}_{}", name, count)
+        }
+    }
+}
+
+/// Sorts the definitions so that every type is defined before it's stored by value,
+/// since types can be stored by value in the types that their fields point to.
+fn sort_definitions(definitions: Vec<Definition>) -> Vec<String> {
+    fn visit(
+        index: usize,
+        definitions: &[Definition],
+        indices: &HashMap<&str, usize>,
+        visited: &mut [bool],
+        sorted: &mut Vec<String>,
+    ) {
+        if std::mem::replace(&mut visited[index], true) {
+            return;
+        }
+        let definition = &definitions[index];
+        for dependency in &definition.value_dependencies {
+            if let Some(&dependency) = indices.get(&**dependency) {
+                visit(dependency, definitions, indices, visited, sorted);
+            }
+        }
+        sorted.push(definition.text.clone());
+    }
+
+    let indices = definitions
+        .iter()
+        .enumerate()
+        .map(|(i, definition)| (&*definition.name, i))
+        .collect::<HashMap<&str, usize>>();
+    let mut visited = vec![false; definitions.len()];
+    let mut sorted = Vec::with_capacity(definitions.len());
+    for index in 0..definitions.len() {
+        visit(index, &definitions, &indices, &mut visited, &mut sorted);
+    }
+    sorted
+}
+
+/// Identifies a type.
+///
+/// The size and alignment are included because
+/// all opaque fields have the type id of `()`.
+type TypeKey = (UTypeId, usize, usize);
+
+fn type_key(layout: &'static TypeLayout) -> TypeKey {
+    (layout.get_utypeid(), layout.size(), layout.alignment())
+}
+
+fn first_type_param(layout: &'static TypeLayout) -> Option<&'static TypeLayout> {
+    layout.generics().type_params().first().map(|x| x())
+}
+
+fn first_field_layout(layout: &'static TypeLayout) -> Option<&'static TypeLayout> {
+    let field = layout.get_fields()?.iter().next()?;
+    Some(field.layout())
+}
+
+fn push_opaque_field(out: &mut String, layout: &'static TypeLayout, indentation: &str) {
+    let (element, align) = match layout.alignment() {
+        1 => ("uint8_t", 1),
+        2 => ("uint16_t", 2),
+        4 => ("uint32_t", 4),
+        8 => ("uint64_t", 8),
+        align => ("uint8_t", align),
+    };
+    if element == "uint8_t" && align != 1 {
+        out.push_str(&format!(
+            "{}SABI_ALIGNAS({}) uint8_t _opaque[{}];\n",
+            indentation,
+            align,
+            layout.size(),
+        ));
+    } else {
+        let len = layout.size() / align;
+        out.push_str(&format!("{}{} _opaque[{}];\n", indentation, element, len));
+    }
+}
+
+/// The `#define`s for the discriminants of the variants of an enum.
+fn discriminant_defines(
+    name: &str,
+    tag_type: &str,
+    variant_names: &[&'static str],
+    enum_: &TLEnum,
+) -> String {
+    let mut defines = String::new();
+    for (variant, discriminant) in variant_names
+        .iter()
+        .zip(enum_.discriminants.i128_discriminants())
+    {
+        let suffix = if i32::try_from(discriminant).is_ok() {
+            ""
+        } else if discriminant < 0 {
+            "LL"
+        } else {
+            "ULL"
+        };
+        defines.push_str(&format!(
+            "#define {}_{} (({}){}{})\n",
+            name, variant, tag_type, discriminant, suffix,
+        ));
+    }
+    defines
+}
+
+fn layout_asserts(name: &str, layout: &'static TypeLayout) -> String {
+    format!(
+        "SABI_STATIC_ASSERT(sizeof({0}) == {1}, \"the size of {0} must be {1}\");\n\
+         SABI_STATIC_ASSERT(SABI_ALIGNOF({0}) == {2}, \"the alignment of {0} must be {2}\");\n",
+        name,
+        layout.size(),
+        layout.alignment(),
+    )
+}
+
+const fn discriminant_type(repr: DiscriminantRepr) -> &'static str {
+    match repr {
+        DiscriminantRepr::U8 => "uint8_t",
+        DiscriminantRepr::I8 => "int8_t",
+        DiscriminantRepr::U16 => "uint16_t",
+        DiscriminantRepr::I16 => "int16_t",
+        DiscriminantRepr::U32 => "uint32_t",
+        DiscriminantRepr::I32 => "int32_t",
+        DiscriminantRepr::U64 | DiscriminantRepr::U128 => "uint64_t",
+        DiscriminantRepr::I64 | DiscriminantRepr::I128 => "int64_t",
+        DiscriminantRepr::Usize => "size_t",
+        DiscriminantRepr::Isize => "ptrdiff_t",
+    }
+}
+
+/// The C name of a type,its name with its generic arguments,without lifetimes.
+fn type_name(layout: &'static TypeLayout) -> String {
+    let full_type = layout.full_type().to_string();
+    let mut name = String::with_capacity(full_type.len());
+    let mut chars = full_type.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\'' {
+            // Skipping lifetimes
+            while chars
+                .peek()
+                .map_or(false, |c| c.is_alphanumeric() || *c == '_')
+            {
+                chars.next();
+            }
+        } else if c.is_ascii_alphanumeric() {
+            name.push(c);
+        } else if !name.is_empty() && !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+    while name.ends_with('_') {
+        name.pop();
+    }
+    if name.is_empty() {
+        name.push_str("anonymous");
+    }
+    c_identifier(&name)
+}
+
+fn is_c_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !C_KEYWORDS.contains(&s)
+}
+
+/// Converts a field or variant name to a valid C identifier.
+fn c_identifier(s: &str) -> String {
+    if is_c_identifier(s) {
+        s.to_string()
+    } else if C_KEYWORDS.contains(&s) {
+        format!("{}_", s)
+    } else {
+        let mut out = String::from("_");
+        out.extend(
+            s.chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }),
+        );
+        out
+    }
+}
+
+const C_KEYWORDS: &[&str] = &[
+    "auto",
+    "bool",
+    "break",
+    "case",
+    "char",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extern",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "register",
+    "restrict",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "typedef",
+    "union",
+    "unsigned",
+    "void",
+    "volatile",
+    "while",
+    "class",
+    "delete",
+    "new",
+    "private",
+    "protected",
+    "public",
+    "template",
+    "this",
+    "virtual",
+];
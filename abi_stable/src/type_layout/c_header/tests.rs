@@ -0,0 +1,162 @@
+use super::CHeader;
+
+use crate::{
+    std_types::{ROption, RVec},
+    StableAbi,
+};
+
+#[repr(C)]
+#[derive(StableAbi)]
+pub struct Point {
+    x: u32,
+    y: i16,
+    is_visible: bool,
+}
+
+#[repr(C)]
+#[derive(StableAbi)]
+pub struct Node<'a> {
+    next: Option<&'a Node<'a>>,
+    value: u64,
+}
+
+#[repr(C)]
+#[derive(StableAbi)]
+pub struct Callbacks {
+    transform: extern "C" fn(&Point, *mut u8, usize) -> f64,
+    maybe: Option<unsafe extern "C" fn() -> u8>,
+    pair: [extern "C" fn(); 2],
+}
+
+#[repr(u8)]
+#[derive(StableAbi)]
+pub enum Fieldless {
+    A,
+    B = 10,
+    C,
+}
+
+#[repr(u8)]
+#[derive(StableAbi)]
+pub enum WithFields {
+    Empty,
+    Value(u32),
+}
+
+#[repr(C)]
+#[derive(StableAbi)]
+#[sabi(kind(Prefix))]
+pub struct Module {
+    #[sabi(last_prefix_field)]
+    first: u16,
+    second: u64,
+}
+
+#[repr(C)]
+#[derive(StableAbi)]
+pub struct Containers {
+    bytes: RVec<u8>,
+    words: RVec<u32>,
+    optional: ROption<u16>,
+    module: Module_Ref,
+    #[sabi(unsafe_opaque_field)]
+    opaque: [u32; 3],
+    zero_sized: (),
+}
+
+fn header_of<T: StableAbi>() -> String {
+    CHeader::new(T::LAYOUT).to_string()
+}
+
+fn assert_contains(header: &str, expected: &str) {
+    assert!(
+        header.contains(expected),
+        "expected the header to contain:\n{}\n\nheader:\n{}",
+        expected,
+        header,
+    );
+}
+
+#[test]
+fn structs() {
+    let header = header_of::<Point>();
+
+    assert_contains(&header, "#ifndef SABI_POINT_H\n#define SABI_POINT_H\n");
+    assert_contains(&header, "typedef struct Point Point;");
+    assert_contains(
+        &header,
+        "struct Point {\n    uint32_t x;\n    int16_t y;\n    bool is_visible;\n};",
+    );
+    assert_contains(
+        &header,
+        "SABI_STATIC_ASSERT(sizeof(Point) == 8, \"the size of Point must be 8\");",
+    );
+    assert_contains(&header, "SABI_STATIC_ASSERT(SABI_ALIGNOF(Point) == 4,");
+    assert!(header.trim_end().ends_with("#endif /* SABI_POINT_H */"));
+
+    assert_eq!(CHeader::new(Point::LAYOUT).root_type(), "Point");
+}
+
+#[test]
+fn recursive_types() {
+    let header = header_of::<Node<'static>>();
+
+    assert_contains(
+        &header,
+        "struct Node {\n    const Node *next;\n    uint64_t value;\n};",
+    );
+    assert_eq!(header.matches("struct Node {").count(), 1);
+}
+
+#[test]
+fn function_pointers() {
+    let header = header_of::<Callbacks>();
+
+    assert_contains(
+        &header,
+        "double (*transform)(const Point *param_0, uint8_t *param_1, size_t param_2);",
+    );
+    assert_contains(&header, "uint8_t (*maybe)(void);");
+    assert_contains(&header, "void (*pair[2])(void);");
+}
+
+#[test]
+fn enums() {
+    let header = header_of::<Fieldless>();
+    assert_contains(&header, "typedef uint8_t Fieldless;");
+    assert_contains(&header, "#define Fieldless_A ((uint8_t)0)");
+    assert_contains(&header, "#define Fieldless_B ((uint8_t)10)");
+    assert_contains(&header, "#define Fieldless_C ((uint8_t)11)");
+
+    let header = header_of::<WithFields>();
+    assert_contains(&header, "typedef union WithFields WithFields;");
+    assert_contains(
+        &header,
+        "    struct {\n        uint8_t tag;\n        uint32_t field_0;\n    } Value;",
+    );
+    assert_contains(&header, "#define WithFields_Value ((uint8_t)1)");
+}
+
+#[test]
+fn containers() {
+    let header = header_of::<Containers>();
+
+    assert_contains(&header, "typedef struct RVec RVec;");
+    assert_contains(&header, "typedef struct RVec_2 RVec_2;");
+    assert_contains(&header, "    const uint8_t *buffer;");
+    assert_contains(&header, "    const uint32_t *buffer;");
+    assert_contains(&header, "typedef union ROption ROption;");
+
+    assert_contains(
+        &header,
+        "struct Module_WithMetadata {\n    \
+         uint64_t field_accessibility;\n    \
+         const void *type_layout;\n    \
+         Module value;\n\
+         };",
+    );
+    assert_contains(&header, "    const Module_WithMetadata *module;");
+
+    assert_contains(&header, "    uint32_t _opaque[3];");
+    assert!(!header.contains("zero_sized"), "{}", header);
+}
@@ -0,0 +1,396 @@
+use super::*;
+
+use std::collections::HashMap;
+
+use crate::std_types::RVec;
+
+use self::tagging::{CTVariant, CheckableTag, KeyValue, Primitive};
+
+/// A 128-bit hash of the structure of a `TypeLayout`,
+/// and of the layouts of all the types that it references.
+///
+/// The fingerprint covers everything that `check_layout_compatibility` compares:
+/// the name,package,package version,size,alignment,and `repr` attribute of every type,
+/// their generic parameters,fields,function pointers,enum variants and discriminants,
+/// prefix type fields,and tags.
+/// Const parameters and `ExtraChecks` are covered by their type layouts,
+/// their values can only be compared by running the code that they come with.
+///
+/// It doesn't cover the module path and line where each type is declared,
+/// so fingerprints only change when the layout of a type changes.
+///
+/// Only structured data is hashed,
+/// so the fingerprint of a layout doesn't depend on how abi_stable prints types.
+///
+/// If two layouts have the same fingerprint they were almost certainly
+/// built from the same interface,
+/// which is useful to identify the interface that a dynamic library was built with.
+///
+/// Fingerprints are platform-specific,since they include the size and alignment of types.
+///
+/// # Loading
+///
+/// The fingerprint of the root module of a library is computed the first time that
+/// it's loaded,and stored in its [`LibHeader`]
+/// (it can't be computed at compile time,
+/// because layouts reference each other through function pointers).
+///
+/// When the fingerprint is the same as the one of the expected root module,
+/// the loader doesn't compare the layouts,
+/// it only compares the values of const parameters and `ExtraChecks`,
+/// and combines the prefix types and nonexhaustive enums with the ones
+/// from previously loaded libraries,as the full layout check does.
+///
+/// # Example
+///
+/// ```rust
+/// use abi_stable::{std_types::RString, type_layout::LayoutFingerprint, StableAbi};
+///
+/// #[repr(C)]
+/// #[derive(StableAbi)]
+/// pub struct Config {
+///     pub name: RString,
+///     pub threads: u32,
+/// }
+///
+/// let fingerprint = LayoutFingerprint::new(Config::LAYOUT);
+///
+/// assert_eq!(fingerprint, LayoutFingerprint::new(Config::LAYOUT));
+/// assert_ne!(fingerprint, LayoutFingerprint::new(RString::LAYOUT));
+///
+/// // Fingerprints are displayed as 32 hexadecimal digits
+/// assert_eq!(fingerprint.to_string().len(), 32);
+///
+/// ```
+///
+/// [`LibHeader`]: ../library/struct.LibHeader.html
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, StableAbi)]
+pub struct LayoutFingerprint {
+    // Stored as two u64s because the alignment of u128 depends on the Rust version.
+    high: u64,
+    low: u64,
+}
+
+impl LayoutFingerprint {
+    /// Computes the fingerprint of `layout`.
+    pub fn new(layout: &'static TypeLayout) -> Self {
+        FingerprintedLayout::new(layout).fingerprint
+    }
+
+    /// Constructs a `LayoutFingerprint` from its integer representation.
+    pub const fn from_u128(n: u128) -> Self {
+        LayoutFingerprint {
+            high: (n >> 64) as u64,
+            low: n as u64,
+        }
+    }
+
+    /// Gets the integer representation of this fingerprint.
+    pub const fn to_u128(self) -> u128 {
+        ((self.high as u128) << 64) | (self.low as u128)
+    }
+}
+
+impl Display for LayoutFingerprint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:032x}", self.to_u128())
+    }
+}
+
+/// The fingerprint of a layout,
+/// with the types that the layout checker still checks when the layout
+/// is compared to one with the same fingerprint.
+#[repr(C)]
+#[derive(Debug, StableAbi)]
+pub(crate) struct FingerprintedLayout {
+    pub(crate) layout: &'static TypeLayout,
+    pub(crate) fingerprint: LayoutFingerprint,
+    /// The prefix types,nonexhaustive enums,and types with const parameters or `ExtraChecks`,
+    /// in the order that they were first reached,
+    /// which is the same for all layouts with the same fingerprint.
+    pub(crate) checked_types: RVec<&'static TypeLayout>,
+}
+
+impl FingerprintedLayout {
+    pub(crate) fn new(layout: &'static TypeLayout) -> Self {
+        let mut builder = FingerprintBuilder {
+            indices: HashMap::new(),
+            checked_types: RVec::new(),
+            hasher: Fnv1a128(Fnv1a128::OFFSET_BASIS),
+        };
+        builder.type_(layout);
+        Self {
+            layout,
+            fingerprint: LayoutFingerprint::from_u128(builder.hasher.0),
+            checked_types: builder.checked_types,
+        }
+    }
+}
+
+//////////////////////////////////////////////////////////////////////
+
+/// The 128-bit FNV-1a hash function,
+/// used because its output doesn't depend on the Rust version.
+struct Fnv1a128(u128);
+
+impl Fnv1a128 {
+    const OFFSET_BASIS: u128 = 0x6c62272e_07bb0142_62b82175_6295c58d;
+    const PRIME: u128 = 0x00000000_01000000_00000000_0000013b;
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u128::from(byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.write_bytes(&n.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, n: usize) {
+        self.write_u64(n as u64);
+    }
+
+    fn write_bool(&mut self, b: bool) {
+        self.write_bytes(&[b as u8]);
+    }
+
+    /// Writes a string,followed by a byte that can't appear in utf-8 strings.
+    fn write_text(&mut self, s: &str) {
+        self.write_bytes(s.as_bytes());
+        self.write_bytes(&[0xff]);
+    }
+}
+
+/// What identifies a type in a `FingerprintBuilder`.
+#[derive(PartialEq, Eq, Hash)]
+struct TypeKey {
+    type_id: UTypeId,
+    size: usize,
+    alignment: usize,
+    name: &'static str,
+    mod_path: String,
+    package: &'static str,
+}
+
+struct FingerprintBuilder {
+    /// The order in which each type was first reached.
+    ///
+    /// The size,alignment,name,module path,and package are included because
+    /// opaque fields,`UnsafeIgnoredType`,and `()` all have the type id of `()`.
+    indices: HashMap<TypeKey, usize>,
+    checked_types: RVec<&'static TypeLayout>,
+    hasher: Fnv1a128,
+}
+
+impl FingerprintBuilder {
+    /// Hashes the index of the type,
+    /// and the type itself the first time that it's reached.
+    fn type_(&mut self, layout: &'static TypeLayout) {
+        let key = TypeKey {
+            type_id: layout.get_utypeid(),
+            size: layout.size(),
+            alignment: layout.alignment(),
+            name: layout.name(),
+            mod_path: layout.mod_path().to_string(),
+            package: layout.item_info().package_and_version().0,
+        };
+        let next_index = self.indices.len();
+        let index = *self.indices.entry(key).or_insert(next_index);
+        self.hasher.write_usize(index);
+        if index != next_index {
+            return;
+        }
+
+        let is_checked = match layout.data() {
+            TLData::PrefixType { .. } => true,
+            TLData::Enum(enum_) => !enum_.exhaustiveness.is_exhaustive(),
+            _ => false,
+        };
+        if is_checked
+            || layout.extra_checks().is_some()
+            || !layout.generics().const_params().is_empty()
+        {
+            self.checked_types.push(layout);
+        }
+
+        let (package, version) = layout.item_info().package_and_version();
+        self.hasher.write_text(layout.name());
+        self.hasher.write_text(package);
+        self.hasher.write_text(version);
+        self.hasher.write_usize(layout.size());
+        self.hasher.write_usize(layout.alignment());
+        self.hasher.write_bool(layout.is_nonzero());
+        self.repr_attr(layout.repr_attr());
+
+        let generics = layout.generics();
+        self.hasher.write_usize(generics.lifetime_count());
+        self.hasher.write_usize(generics.type_params().len());
+        for get_layout in generics.type_params() {
+            self.type_(get_layout());
+        }
+        self.hasher.write_usize(generics.const_params().len());
+        for param in generics.const_params() {
+            self.type_(param.layout());
+        }
+
+        self.fields(layout.phantom_fields().iter());
+        self.tag(&layout.tag().to_checkable());
+
+        self.hasher.write_bool(layout.extra_checks().is_some());
+        if let Some(extra_checks) = layout.extra_checks() {
+            self.type_(extra_checks.type_layout());
+            let nested = extra_checks.nested_type_layouts();
+            self.hasher.write_usize(nested.len());
+            for &nested in nested.iter() {
+                self.type_(nested);
+            }
+        }
+
+        self.hasher.write_bytes(&[layout.data_discriminant() as u8]);
+        self.data(layout.data());
+    }
+
+    fn repr_attr(&mut self, repr: ReprAttr) {
+        match repr {
+            ReprAttr::OptionNonZero => self.hasher.write_bytes(&[0]),
+            ReprAttr::Primitive => self.hasher.write_bytes(&[1]),
+            ReprAttr::C => self.hasher.write_bytes(&[2]),
+            ReprAttr::CAndInt(discr) => self.hasher.write_bytes(&[3, discr as u8]),
+            ReprAttr::Transparent => self.hasher.write_bytes(&[4]),
+            ReprAttr::Int(discr) => self.hasher.write_bytes(&[5, discr as u8]),
+            ReprAttr::Packed {
+                alignment_power_of_two,
+            } => self.hasher.write_bytes(&[6, alignment_power_of_two]),
+        }
+    }
+
+    fn tag(&mut self, tag: &CheckableTag) {
+        match tag.variant() {
+            CTVariant::Primitive(prim) => {
+                self.hasher.write_bytes(&[0]);
+                match prim {
+                    Primitive::Null => self.hasher.write_bytes(&[0]),
+                    Primitive::Bool(b) => self.hasher.write_bytes(&[1, *b as u8]),
+                    Primitive::Int(n) => {
+                        self.hasher.write_bytes(&[2]);
+                        self.hasher.write_bytes(&n.to_le_bytes());
+                    }
+                    Primitive::UInt(n) => {
+                        self.hasher.write_bytes(&[3]);
+                        self.hasher.write_u64(*n);
+                    }
+                    Primitive::String_(s) => {
+                        self.hasher.write_bytes(&[4]);
+                        self.hasher.write_text(s.as_str());
+                    }
+                }
+            }
+            CTVariant::Ignored(tag) => {
+                self.hasher.write_bytes(&[1]);
+                self.tag(tag);
+            }
+            CTVariant::Array(tags) => {
+                self.hasher.write_bytes(&[2]);
+                self.hasher.write_usize(tags.len());
+                for tag in tags {
+                    self.tag(tag);
+                }
+            }
+            CTVariant::Set(pairs) => {
+                self.hasher.write_bytes(&[3]);
+                self.tag_pairs(pairs);
+            }
+            CTVariant::Map(pairs) => {
+                self.hasher.write_bytes(&[4]);
+                self.tag_pairs(pairs);
+            }
+        }
+    }
+
+    fn tag_pairs(&mut self, pairs: &[KeyValue<CheckableTag>]) {
+        self.hasher.write_usize(pairs.len());
+        for pair in pairs {
+            self.tag(&pair.key);
+            self.tag(&pair.value);
+        }
+    }
+
+    fn data(&mut self, data: TLData) {
+        match data {
+            TLData::Primitive(prim) => self.hasher.write_bytes(&[prim as u8]),
+            TLData::Opaque => {}
+            TLData::Struct { fields } | TLData::Union { fields } => self.fields(fields.iter()),
+            TLData::Enum(enum_) => {
+                for name in enum_.variant_names_iter() {
+                    self.hasher.write_text(name);
+                }
+                self.hasher.write_usize(enum_.field_count.len());
+                self.hasher.write_bytes(&enum_.field_count);
+                self.fields(enum_.fields.iter());
+                let discriminant_repr = enum_.discriminants.discriminant_repr();
+                self.hasher.write_bytes(&[discriminant_repr as u8]);
+                for discriminant in enum_.discriminants.i128_discriminants() {
+                    self.hasher.write_bytes(&discriminant.to_le_bytes());
+                }
+                let nonexhaustive = enum_.exhaustiveness.as_nonexhaustive();
+                self.hasher.write_bool(nonexhaustive.is_some());
+                if let Some(nonexhaustive) = nonexhaustive {
+                    self.hasher.write_usize(nonexhaustive.original_size());
+                    self.hasher.write_usize(nonexhaustive.original_alignment());
+                }
+            }
+            TLData::PrefixType(prefix) => {
+                let first_suffix_field = usize::from(prefix.first_suffix_field);
+                self.hasher.write_usize(first_suffix_field);
+                for i in 0..prefix.fields.len() {
+                    let is_accessible = prefix.accessible_fields.at(i).is_accessible();
+                    self.hasher.write_bool(is_accessible);
+                }
+                for i in 0..first_suffix_field {
+                    let is_conditional = prefix.conditional_prefix_fields.at(i).is_conditional();
+                    self.hasher.write_bool(is_conditional);
+                }
+                self.fields(prefix.fields.iter());
+            }
+        }
+    }
+
+    fn fields<I>(&mut self, fields: I)
+    where
+        I: IntoIterator<Item = TLField>,
+    {
+        let mut count = 0;
+        for field in fields {
+            self.field(&field);
+            count += 1;
+        }
+        self.hasher.write_usize(count);
+    }
+
+    fn field(&mut self, field: &TLField) {
+        self.hasher.write_text(field.name());
+        self.type_(field.layout());
+        self.lifetime_indices(&field.lifetime_indices());
+
+        let functions = field.function_range();
+        self.hasher.write_usize(functions.len());
+        for function in functions.iter() {
+            self.hasher.write_text(function.name.as_str());
+            self.hasher.write_text(function.bound_lifetimes.as_str());
+            self.fields(function.get_params());
+            self.field(&function.get_return());
+            self.lifetime_indices(&function.paramret_lifetime_indices);
+            self.hasher.write_bool(function.qualifiers().is_unsafe());
+        }
+    }
+
+    fn lifetime_indices(&mut self, indices: &[LifetimeIndexPair]) {
+        self.hasher.write_usize(indices.len());
+        for pair in indices {
+            self.hasher.write_bytes(&[pair.to_u8()]);
+        }
+    }
+}
@@ -0,0 +1,625 @@
+use super::*;
+
+use crate::{
+    std_types::UTypeId,
+    type_layout::tagging::{CTVariant, CheckableTag, Primitive},
+};
+
+use core_extensions::matches;
+
+use serde::{Deserialize, Serialize};
+
+use std::{borrow::Cow, collections::HashMap, convert::TryFrom};
+
+/// An owned snapshot of a `TypeLayout`,
+/// including the layouts of all the types that it references.
+///
+/// Unlike `TypeLayout`,this can be serialized and deserialized with serde
+/// (eg: as JSON),
+/// so that the layout of an interface can be saved and compared
+/// with [`check_layout_compatibility`] later,
+/// without building or loading the dynamic library that the layout came from.
+///
+/// # Limitations
+///
+/// `ExtraChecks` can't be stored in a snapshot,
+/// so the snapshot only records their `Display` output,
+/// and when a snapshot is checked they're only compared for equality.
+///
+/// The prefix types and nonexhaustive enums in a snapshot aren't combined with
+/// the ones from previously checked layouts.
+///
+/// # Example
+///
+/// ```rust
+/// use abi_stable::{
+///     abi_stability::check_layout_compatibility,
+///     std_types::RString,
+///     type_layout::LayoutSnapshot,
+///     StableAbi,
+/// };
+///
+/// #[repr(C)]
+/// #[derive(StableAbi)]
+/// pub struct Config {
+///     pub name: RString,
+///     pub threads: u32,
+/// }
+///
+/// // Saved when the interface was released
+/// let json = serde_json::to_string(&LayoutSnapshot::new(Config::LAYOUT)).unwrap();
+///
+/// // Checked later,possibly by a different build of the interface crate
+/// let released: LayoutSnapshot = serde_json::from_str(&json).unwrap();
+/// check_layout_compatibility(&released, Config::LAYOUT).unwrap();
+///
+/// ```
+///
+/// [`check_layout_compatibility`]: ../abi_stability/fn.check_layout_compatibility.html
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedLayoutSnapshot")]
+pub struct LayoutSnapshot {
+    format_version: u32,
+    /// The snapshotted type is the first one,
+    /// the types that it references are stored once each,
+    /// referenced by their index in this list.
+    types: Vec<TypeSnapshot>,
+}
+
+/// A snapshot of a single type in a [`LayoutSnapshot`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct TypeSnapshot {
+    /// The name of the type.
+    pub name: String,
+    /// The name of the type with its generic parameters(ie:`Foo<'a,u32,RString,1>`).
+    pub full_type: String,
+    /// The package where the type was declared.
+    pub package: String,
+    /// The version of the package where the type was declared.
+    pub package_version: String,
+    /// The path to the module where the type was declared.
+    pub mod_path: String,
+    /// The line where the type was declared.
+    pub line: u32,
+    /// The size of the type.
+    pub size: usize,
+    /// The alignment of the type.
+    pub alignment: usize,
+    /// Whether the type uses non-zero value optimization.
+    pub is_nonzero: bool,
+    /// The `Debug` representation of the `ReprAttr` of the type.
+    pub repr_attr: String,
+    /// The generic parameters of the type.
+    pub generics: GenericsSnapshot,
+    /// The virtual fields that are checked as part of the type.
+    pub phantom_fields: Vec<FieldSnapshot>,
+    /// The `Tag` of the type,in the normalized form used to check compatibility.
+    pub tag: TagSnapshot,
+    /// The `Display` output of the `ExtraChecks` of the type,
+    /// which can't be stored in the snapshot.
+    pub extra_checks: Option<String>,
+    /// Whether the type is a primitive/enum/struct/union and its contents.
+    pub data: DataSnapshot,
+}
+
+/// The generic parameters of a [`TypeSnapshot`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct GenericsSnapshot {
+    /// The names of the lifetime parameters.
+    pub lifetimes: Vec<String>,
+    /// The indices of the type parameters in the [`LayoutSnapshot`].
+    pub type_params: Vec<usize>,
+    /// The `Debug` representation of the const parameters.
+    pub const_params: Vec<String>,
+}
+
+/// A field (or function parameter) in a [`TypeSnapshot`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct FieldSnapshot {
+    /// The name of the field.
+    pub name: String,
+    /// The index of the type of the field in the [`LayoutSnapshot`].
+    pub type_index: usize,
+    /// The lifetimes that the field references.
+    pub lifetime_indices: Vec<String>,
+    /// The function pointers in the type of the field.
+    pub functions: Vec<FunctionSnapshot>,
+}
+
+/// A function pointer in the type of a [`FieldSnapshot`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct FunctionSnapshot {
+    /// The name of the field that the function pointer is used inside of.
+    pub name: String,
+    /// The lifetime parameters declared in the `for<>` of the function.
+    pub bound_lifetimes: Vec<String>,
+    /// The parameters of the function.
+    pub params: Vec<FieldSnapshot>,
+    /// The return type of the function,as a field named `__returns`.
+    pub returns: FieldSnapshot,
+    /// The lifetimes that the parameters and return type reference.
+    pub lifetime_indices: Vec<String>,
+    /// Whether this is an `unsafe` function.
+    pub is_unsafe: bool,
+}
+
+/// The contents of a [`TypeSnapshot`],
+/// every variant corresponds to a `TLData` variant of the same name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum DataSnapshot {
+    /// Types defined in the compiler,with the `Debug` representation of the `TLPrimitive`.
+    Primitive(String),
+    /// A type that can't be inspected.
+    Opaque,
+    /// For structs.
+    Struct {
+        ///
+        fields: Vec<FieldSnapshot>,
+    },
+    /// For unions.
+    Union {
+        ///
+        fields: Vec<FieldSnapshot>,
+    },
+    /// For enums.
+    Enum(EnumSnapshot),
+    /// vtables and modules that can be extended in minor versions.
+    PrefixType(PrefixTypeSnapshot),
+}
+
+/// The contents of an enum in a [`TypeSnapshot`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct EnumSnapshot {
+    /// The names of the variants.
+    pub variant_names: Vec<String>,
+    /// The amount of fields of each variant.
+    pub field_counts: Vec<u8>,
+    /// The fields of all the variants,in declaration order.
+    pub fields: Vec<FieldSnapshot>,
+    /// The `Debug` representation of the `DiscriminantRepr` of the enum.
+    pub discriminant_repr: String,
+    /// The discriminants of the variants.
+    pub discriminants: Vec<i128>,
+    /// The properties of nonexhaustive enums,None if the enum is exhaustive.
+    pub nonexhaustive: Option<NonExhaustiveSnapshot>,
+}
+
+/// The properties of a nonexhaustive enum in an [`EnumSnapshot`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct NonExhaustiveSnapshot {
+    /// The size of the enum that is stored in the `NonExhaustive`.
+    pub original_size: usize,
+    /// The alignment of the enum that is stored in the `NonExhaustive`.
+    pub original_alignment: usize,
+}
+
+/// The contents of a prefix type in a [`TypeSnapshot`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PrefixTypeSnapshot {
+    /// The amount of fields in the prefix.
+    pub prefix_field_count: u8,
+    /// Whether each field is accessible.
+    pub accessible_fields: Vec<bool>,
+    /// Whether each field in the prefix is conditional.
+    pub conditional_prefix_fields: Vec<bool>,
+    /// The fields of the prefix type.
+    pub fields: Vec<FieldSnapshot>,
+}
+
+/// A `Tag` in a [`TypeSnapshot`],
+/// in the normalized form of a `CheckableTag`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum TagSnapshot {
+    ///
+    Null,
+    ///
+    Bool(bool),
+    ///
+    Int(i64),
+    ///
+    UInt(u64),
+    ///
+    String(String),
+    /// A tag that's considered compatible with any other
+    Ignored(Box<TagSnapshot>),
+    ///
+    Array(Vec<TagSnapshot>),
+    /// The elements of a set,sorted.
+    Set(Vec<TagSnapshot>),
+    /// The entries of a map,sorted by key.
+    Map(Vec<(TagSnapshot, TagSnapshot)>),
+}
+
+/// Either a `TypeLayout` or a [`LayoutSnapshot`],
+/// used to pass either one to [`check_layout_compatibility`].
+///
+/// [`check_layout_compatibility`]: ../abi_stability/fn.check_layout_compatibility.html
+#[derive(Debug, Copy, Clone)]
+pub enum LayoutOrSnapshot<'a> {
+    ///
+    Layout(&'static TypeLayout),
+    ///
+    Snapshot(&'a LayoutSnapshot),
+}
+
+//////////////////////////////////////////////////////////////////////
+
+impl LayoutSnapshot {
+    /// The version of the format of serialized snapshots,
+    /// which is increased whenever the format changes incompatibly.
+    pub const FORMAT_VERSION: u32 = 1;
+
+    /// Takes a snapshot of `layout` and all the types that it references.
+    pub fn new(layout: &'static TypeLayout) -> Self {
+        let mut builder = SnapshotBuilder {
+            indices: HashMap::new(),
+            types: Vec::new(),
+        };
+        builder.type_index(layout);
+
+        Self {
+            format_version: Self::FORMAT_VERSION,
+            types: builder
+                .types
+                .into_iter()
+                .map(|x| x.expect("every type is snapshotted after it's reserved"))
+                .collect(),
+        }
+    }
+
+    /// The snapshot of the type that this was constructed from.
+    pub fn root(&self) -> &TypeSnapshot {
+        &self.types[0]
+    }
+
+    /// The snapshots of all the types,the first one is the [`root`](#method.root).
+    ///
+    /// The other types reference each other by their index in this slice.
+    pub fn types(&self) -> &[TypeSnapshot] {
+        &self.types
+    }
+
+    /// Gets the type at `index`.
+    ///
+    /// # Panics
+    ///
+    /// This panics if `index` is out of bounds,
+    /// indices read from the snapshot are always in bounds.
+    pub fn type_at(&self, index: usize) -> &TypeSnapshot {
+        &self.types[index]
+    }
+}
+
+impl From<&'static TypeLayout> for LayoutSnapshot {
+    fn from(layout: &'static TypeLayout) -> Self {
+        Self::new(layout)
+    }
+}
+
+impl<'a> LayoutOrSnapshot<'a> {
+    /// Gets a snapshot,taking it if this is a `TypeLayout`.
+    pub fn to_snapshot(self) -> Cow<'a, LayoutSnapshot> {
+        match self {
+            LayoutOrSnapshot::Layout(layout) => Cow::Owned(LayoutSnapshot::new(layout)),
+            LayoutOrSnapshot::Snapshot(snapshot) => Cow::Borrowed(snapshot),
+        }
+    }
+}
+
+impl From<&'static TypeLayout> for LayoutOrSnapshot<'_> {
+    fn from(layout: &'static TypeLayout) -> Self {
+        LayoutOrSnapshot::Layout(layout)
+    }
+}
+
+impl<'a> From<&'a LayoutSnapshot> for LayoutOrSnapshot<'a> {
+    fn from(snapshot: &'a LayoutSnapshot) -> Self {
+        LayoutOrSnapshot::Snapshot(snapshot)
+    }
+}
+
+impl TypeSnapshot {
+    /// Whether this is a prefix-type(module or vtable).
+    pub const fn is_prefix_kind(&self) -> bool {
+        matches!(self.data, DataSnapshot::PrefixType { .. })
+    }
+
+    /// The fields of the type,None for primitive and opaque types.
+    pub fn fields(&self) -> Option<&[FieldSnapshot]> {
+        match &self.data {
+            DataSnapshot::Primitive(_) | DataSnapshot::Opaque => None,
+            DataSnapshot::Struct { fields } | DataSnapshot::Union { fields } => Some(fields),
+            DataSnapshot::Enum(enum_) => Some(&enum_.fields),
+            DataSnapshot::PrefixType(prefix) => Some(&prefix.fields),
+        }
+    }
+}
+
+impl DataSnapshot {
+    /// Describes whether the type is a primitive/enum/struct/union.
+    pub const fn discriminant(&self) -> TLDataDiscriminant {
+        match self {
+            DataSnapshot::Primitive(_) => TLDataDiscriminant::Primitive,
+            DataSnapshot::Opaque => TLDataDiscriminant::Opaque,
+            DataSnapshot::Struct { .. } => TLDataDiscriminant::Struct,
+            DataSnapshot::Union { .. } => TLDataDiscriminant::Union,
+            DataSnapshot::Enum { .. } => TLDataDiscriminant::Enum,
+            DataSnapshot::PrefixType { .. } => TLDataDiscriminant::PrefixType,
+        }
+    }
+}
+
+impl fmt::Display for TagSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TagSnapshot::Null => f.write_str("null"),
+            TagSnapshot::Bool(x) => fmt::Display::fmt(x, f),
+            TagSnapshot::Int(x) => fmt::Display::fmt(x, f),
+            TagSnapshot::UInt(x) => fmt::Display::fmt(x, f),
+            TagSnapshot::String(x) => fmt::Debug::fmt(x, f),
+            TagSnapshot::Ignored(x) => write!(f, "ignored({})", x),
+            TagSnapshot::Array(list) | TagSnapshot::Set(list) => {
+                let (open, close) = match self {
+                    TagSnapshot::Array(_) => ("[", "]"),
+                    _ => ("{", "}"),
+                };
+                f.write_str(open)?;
+                for (i, elem) in list.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(",")?;
+                    }
+                    fmt::Display::fmt(elem, f)?;
+                }
+                f.write_str(close)
+            }
+            TagSnapshot::Map(entries) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}:{}", key, value)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+//////////////////////////////////////////////////////////////////////
+
+struct SnapshotBuilder {
+    indices: HashMap<UTypeId, usize>,
+    types: Vec<Option<TypeSnapshot>>,
+}
+
+impl SnapshotBuilder {
+    /// Gets the index of the snapshot of `layout`,taking it if it wasn't taken yet.
+    fn type_index(&mut self, layout: &'static TypeLayout) -> usize {
+        let next_index = self.types.len();
+        let index = *self
+            .indices
+            .entry(layout.get_utypeid())
+            .or_insert(next_index);
+
+        if index == next_index {
+            // Reserving the index first so that recursive types reference it.
+            self.types.push(None);
+            let snapshot = self.type_snapshot(layout);
+            self.types[index] = Some(snapshot);
+        }
+        index
+    }
+
+    fn type_snapshot(&mut self, layout: &'static TypeLayout) -> TypeSnapshot {
+        let (package, version) = layout.item_info().package_and_version();
+        let generics = layout.generics();
+
+        TypeSnapshot {
+            name: layout.name().to_string(),
+            full_type: layout.full_type().to_string(),
+            package: package.to_string(),
+            package_version: version.to_string(),
+            mod_path: layout.mod_path().to_string(),
+            line: layout.line(),
+            size: layout.size(),
+            alignment: layout.alignment(),
+            is_nonzero: layout.is_nonzero(),
+            repr_attr: format!("{:?}", layout.repr_attr()),
+            generics: GenericsSnapshot {
+                lifetimes: generics.lifetimes().map(String::from).collect(),
+                type_params: generics
+                    .type_params()
+                    .iter()
+                    .map(|get_layout| self.type_index(get_layout()))
+                    .collect(),
+                const_params: generics
+                    .const_params()
+                    .iter()
+                    .map(|x| format!("{:?}", x))
+                    .collect(),
+            },
+            phantom_fields: self.fields(layout.phantom_fields().iter()),
+            tag: tag_snapshot(&layout.tag().to_checkable()),
+            extra_checks: layout.extra_checks().map(|x| x.to_string()),
+            data: self.data_snapshot(layout.data()),
+        }
+    }
+
+    fn data_snapshot(&mut self, data: TLData) -> DataSnapshot {
+        match data {
+            TLData::Primitive(prim) => DataSnapshot::Primitive(format!("{:?}", prim)),
+            TLData::Opaque => DataSnapshot::Opaque,
+            TLData::Struct { fields } => DataSnapshot::Struct {
+                fields: self.fields(fields.iter()),
+            },
+            TLData::Union { fields } => DataSnapshot::Union {
+                fields: self.fields(fields.iter()),
+            },
+            TLData::Enum(enum_) => DataSnapshot::Enum(EnumSnapshot {
+                variant_names: enum_.variant_names_iter().map(String::from).collect(),
+                field_counts: enum_.field_count.to_vec(),
+                fields: self.fields(enum_.fields.iter()),
+                discriminant_repr: format!("{:?}", enum_.discriminants.discriminant_repr()),
+                discriminants: enum_.discriminants.i128_discriminants(),
+                nonexhaustive: enum_.exhaustiveness.as_nonexhaustive().map(|ne| {
+                    NonExhaustiveSnapshot {
+                        original_size: ne.original_size(),
+                        original_alignment: ne.original_alignment(),
+                    }
+                }),
+            }),
+            TLData::PrefixType(prefix) => DataSnapshot::PrefixType(PrefixTypeSnapshot {
+                prefix_field_count: prefix.first_suffix_field,
+                accessible_fields: (0..prefix.fields.len())
+                    .map(|i| prefix.accessible_fields.at(i).is_accessible())
+                    .collect(),
+                conditional_prefix_fields: (0..usize::from(prefix.first_suffix_field))
+                    .map(|i| prefix.conditional_prefix_fields.at(i).is_conditional())
+                    .collect(),
+                fields: self.fields(prefix.fields.iter()),
+            }),
+        }
+    }
+
+    fn fields<I>(&mut self, fields: I) -> Vec<FieldSnapshot>
+    where
+        I: IntoIterator<Item = TLField>,
+    {
+        fields.into_iter().map(|x| self.field(&x)).collect()
+    }
+
+    fn field(&mut self, field: &TLField) -> FieldSnapshot {
+        FieldSnapshot {
+            name: field.name().to_string(),
+            type_index: self.type_index(field.layout()),
+            lifetime_indices: lifetime_indices(&field.lifetime_indices()),
+            functions: field
+                .function_range()
+                .iter()
+                .map(|func| FunctionSnapshot {
+                    name: func.name.to_string(),
+                    bound_lifetimes: func
+                        .bound_lifetimes
+                        .as_str()
+                        .split(';')
+                        .filter(|x| !x.is_empty())
+                        .map(String::from)
+                        .collect(),
+                    params: self.fields(func.get_params()),
+                    returns: self.field(&func.get_return()),
+                    lifetime_indices: lifetime_indices(&func.paramret_lifetime_indices),
+                    is_unsafe: func.qualifiers().is_unsafe(),
+                })
+                .collect(),
+        }
+    }
+}
+
+fn lifetime_indices(indices: &[LifetimeIndexPair]) -> Vec<String> {
+    indices
+        .iter()
+        .flat_map(|pair| {
+            let (first, second) = pair.both();
+            vec![first.to_string(), second.to_string()]
+        })
+        .collect()
+}
+
+fn tag_snapshot(tag: &CheckableTag) -> TagSnapshot {
+    match tag.variant() {
+        CTVariant::Primitive(Primitive::Null) => TagSnapshot::Null,
+        CTVariant::Primitive(Primitive::Bool(x)) => TagSnapshot::Bool(*x),
+        CTVariant::Primitive(Primitive::Int(x)) => TagSnapshot::Int(*x),
+        CTVariant::Primitive(Primitive::UInt(x)) => TagSnapshot::UInt(*x),
+        CTVariant::Primitive(Primitive::String_(x)) => TagSnapshot::String(x.to_string()),
+        CTVariant::Ignored(x) => TagSnapshot::Ignored(Box::new(tag_snapshot(x))),
+        CTVariant::Array(list) => TagSnapshot::Array(list.iter().map(tag_snapshot).collect()),
+        CTVariant::Set(list) => {
+            TagSnapshot::Set(list.iter().map(|x| tag_snapshot(&x.key)).collect())
+        }
+        CTVariant::Map(list) => TagSnapshot::Map(
+            list.iter()
+                .map(|x| (tag_snapshot(&x.key), tag_snapshot(&x.value)))
+                .collect(),
+        ),
+    }
+}
+
+//////////////////////////////////////////////////////////////////////
+
+/// The deserialized form of a `LayoutSnapshot`,before it's validated.
+#[derive(Deserialize)]
+struct UncheckedLayoutSnapshot {
+    format_version: u32,
+    types: Vec<TypeSnapshot>,
+}
+
+impl TryFrom<UncheckedLayoutSnapshot> for LayoutSnapshot {
+    type Error = String;
+
+    fn try_from(unchecked: UncheckedLayoutSnapshot) -> Result<Self, String> {
+        let UncheckedLayoutSnapshot {
+            format_version,
+            types,
+        } = unchecked;
+
+        if format_version != LayoutSnapshot::FORMAT_VERSION {
+            return Err(format!(
+                "unsupported layout snapshot format version: {},expected {}",
+                format_version,
+                LayoutSnapshot::FORMAT_VERSION,
+            ));
+        }
+        if types.is_empty() {
+            return Err("the layout snapshot has no types".to_string());
+        }
+
+        let check_index = |index: usize| {
+            if index < types.len() {
+                Ok(())
+            } else {
+                Err(format!(
+                    "type index {} is out of bounds in a layout snapshot of {} types",
+                    index,
+                    types.len(),
+                ))
+            }
+        };
+        fn check_fields(
+            fields: &[FieldSnapshot],
+            check_index: &dyn Fn(usize) -> Result<(), String>,
+        ) -> Result<(), String> {
+            for field in fields {
+                check_index(field.type_index)?;
+                for func in &field.functions {
+                    check_fields(&func.params, check_index)?;
+                    check_fields(std::slice::from_ref(&func.returns), check_index)?;
+                }
+            }
+            Ok(())
+        }
+
+        for ty in &types {
+            for &index in &ty.generics.type_params {
+                check_index(index)?;
+            }
+            check_fields(&ty.phantom_fields, &check_index)?;
+            check_fields(ty.fields().unwrap_or(&[]), &check_index)?;
+        }
+
+        Ok(LayoutSnapshot {
+            format_version,
+            types,
+        })
+    }
+}
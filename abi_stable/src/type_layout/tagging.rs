@@ -462,6 +462,10 @@ where
 }
 
 impl CheckableTag {
+    pub(crate) const fn variant(&self) -> &CTVariant {
+        &self.variant
+    }
+
     /// Checks that this `CheckableTag` is compatible with another one,
     /// returning `Ok` if it is compatible, `Err` if it was not.
     pub fn check_compatible(&self, other: &Self) -> Result<(), TagErrors> {
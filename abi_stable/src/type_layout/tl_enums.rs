@@ -241,6 +241,20 @@ macro_rules! declare_tl_discriminants {
                 }
             )*
 
+            /// Gets the values of the discriminants,losslessly converted to `i128`.
+            pub(crate) fn i128_discriminants(&self)->Vec<i128>{
+                match self.inner {
+                    $(
+                        TLDiscrsInner::$variant{discriminants,len}=>unsafe{
+                            std::slice::from_raw_parts(discriminants,len as usize)
+                                .iter()
+                                .map(|&x| x as i128)
+                                .collect()
+                        }
+                    )*
+                }
+            }
+
             /// Gets the type of the discriminant in this `TLDiscriminants`.
             pub const fn discriminant_repr(&self)->DiscriminantRepr{
                 match self.inner {
@@ -473,11 +487,11 @@ impl TLNonExhaustive {
     }
 
     #[inline]
-    const fn original_size(&self) -> usize {
+    pub(crate) const fn original_size(&self) -> usize {
         self.original_size
     }
     #[inline]
-    const fn original_alignment(&self) -> usize {
+    pub(crate) const fn original_alignment(&self) -> usize {
         1_usize << (self.original_alignment_pow2 as u32)
     }
 
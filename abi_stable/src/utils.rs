@@ -1,9 +1,11 @@
 //! Utility functions.
 
 use std::{
+    any::Any,
     cmp::Ord,
     fmt::{self, Debug, Display},
     mem::{self, ManuallyDrop},
+    panic::{self, AssertUnwindSafe},
     ptr::NonNull,
 };
 
@@ -11,7 +13,7 @@ use core_extensions::{strings::LeftPadder, StringExt, TypeIdentity};
 
 use crate::{
     sabi_types::RMut,
-    std_types::{RStr, RString},
+    std_types::{RBoxError, RErr, RResult, RStr, RString},
 };
 
 //////////////////////////////////////
@@ -36,6 +38,75 @@ pub fn ffi_panic_message(info: &'static PanicInfo) -> ! {
     std::process::exit(1);
 }
 
+/// Gets the message of a panic from its payload,
+/// which is `"Box<dyn Any>"` if the payload isn't a string
+/// (the same message that the default panic hook prints).
+pub fn panic_payload_message(payload: &(dyn Any + Send)) -> RString {
+    if let Some(message) = payload.downcast_ref::<&'static str>() {
+        RString::from(*message)
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        RString::from(message.as_str())
+    } else {
+        RString::from("Box<dyn Any>")
+    }
+}
+
+/// For constructing the error of a
+/// [`#[sabi_extern_fn(catch_unwind)]`](../attr.sabi_extern_fn.html#catch_unwind)
+/// function from the message of a panic.
+pub trait FromPanicMessage {
+    /// Constructs the error from the message of the panic.
+    fn from_panic_message(message: RString) -> Self;
+}
+
+impl FromPanicMessage for RString {
+    fn from_panic_message(message: RString) -> Self {
+        message
+    }
+}
+
+impl FromPanicMessage for RBoxError {
+    fn from_panic_message(message: RString) -> Self {
+        RBoxError::from_fmt(&message)
+    }
+}
+
+/// Calls `function`,returning an `RErr` with the message of the panic if it panics.
+///
+/// This is what [`#[sabi_extern_fn(catch_unwind)]`](../attr.sabi_extern_fn.html#catch_unwind)
+/// and `extern_fn_panic_handling!{catch_unwind; ...}` use to catch panics.
+///
+/// # Example
+///
+/// ```rust
+/// use abi_stable::{
+///     std_types::{RErr, ROk, RResult, RString},
+///     utils::catch_ffi_unwind,
+/// };
+///
+/// let res: RResult<u32, RString> = catch_ffi_unwind(|| ROk(3));
+/// assert_eq!(res, ROk(3));
+///
+/// let res: RResult<u32, RString> = catch_ffi_unwind(|| panic!("oh no"));
+/// assert_eq!(res, RErr(RString::from("oh no")));
+/// ```
+pub fn catch_ffi_unwind<F, T, E>(function: F) -> RResult<T, E>
+where
+    F: FnOnce() -> RResult<T, E>,
+    E: FromPanicMessage,
+{
+    match panic::catch_unwind(AssertUnwindSafe(function)) {
+        Ok(x) => x,
+        Err(payload) => {
+            let message = panic_payload_message(&*payload);
+            // Dropping the payload could panic,
+            // which would unwind out of the `extern "C"` function calling this.
+            mem::forget(payload);
+            RErr(E::from_panic_message(message))
+        }
+    }
+}
+
 //////////////////////////////////
 
 /// Only used inside `PhantomData`,
@@ -222,7 +222,7 @@ fn assert_types() {
         },
     ];
 
-    let test_layout = |field_layout, expected_layout| {
+    let test_layout = |field_layout: &'static TypeLayout, expected_layout: &'static TypeLayout| {
         let res = check_layout_compatibility(field_layout, expected_layout);
 
         assert!(
@@ -0,0 +1,143 @@
+use abi_stable::{
+    abi_stability::{
+        abi_checking::check_layout_compatibility, collect_layout_incompatibilities,
+        AbiIncompatibilityKind, AbiIncompatibilityReport,
+    },
+    std_types::RString,
+    type_layout::TypeLayout,
+    StableAbi,
+};
+
+mod v1 {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(StableAbi)]
+    pub struct Inner {
+        pub value: u32,
+    }
+
+    #[repr(C)]
+    #[derive(StableAbi)]
+    pub struct Outer {
+        pub first: u32,
+        pub inner: Inner,
+        pub name: RString,
+    }
+}
+
+mod v2 {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(StableAbi)]
+    pub struct Inner {
+        pub value: u64,
+    }
+
+    #[repr(C)]
+    #[derive(StableAbi)]
+    pub struct Outer {
+        pub renamed: u64,
+        pub inner: Inner,
+        pub name: RString,
+    }
+}
+
+const OUTER_V1: &TypeLayout = <v1::Outer as StableAbi>::LAYOUT;
+const OUTER_V2: &TypeLayout = <v2::Outer as StableAbi>::LAYOUT;
+
+fn kinds(report: &AbiIncompatibilityReport) -> Vec<AbiIncompatibilityKind> {
+    report
+        .incompatibilities()
+        .iter()
+        .map(|x| x.kind())
+        .collect()
+}
+
+#[test]
+fn compatible_layouts() {
+    collect_layout_incompatibilities(OUTER_V1, OUTER_V1).unwrap();
+    collect_layout_incompatibilities(OUTER_V2, OUTER_V2).unwrap();
+}
+
+#[test]
+fn report_of_errors() {
+    let errors = check_layout_compatibility(OUTER_V1, OUTER_V2).unwrap_err();
+    let report = AbiIncompatibilityReport::new(&errors);
+
+    assert_eq!(
+        kinds(&report),
+        errors
+            .flatten_errors()
+            .iter()
+            .map(|x| x.kind())
+            .collect::<Vec<_>>(),
+    );
+    assert_eq!(report.to_string(), errors.to_string());
+    assert!(report.interface().contains("Outer"));
+    assert!(report.implementation().contains("Outer"));
+
+    let inner = report
+        .incompatibilities()
+        .iter()
+        .find(|x| x.field_path() == ["inner", "value"])
+        .unwrap();
+    assert_eq!(inner.kind(), AbiIncompatibilityKind::Name);
+    assert_eq!(inner.type_path().len(), 3);
+    assert_eq!(inner.expected(), Some("u32"));
+    assert_eq!(inner.found(), Some("u64"));
+}
+
+#[test]
+fn collect_all_finds_more() {
+    let errors = check_layout_compatibility(OUTER_V1, OUTER_V2).unwrap_err();
+    let report = AbiIncompatibilityReport::new(&errors);
+    let complete = collect_layout_incompatibilities(OUTER_V1, OUTER_V2).unwrap_err();
+
+    assert!(
+        complete.incompatibilities().len() > report.incompatibilities().len(),
+        "{}\n{}",
+        report,
+        complete,
+    );
+
+    for kind in kinds(&report) {
+        assert!(kinds(&complete).contains(&kind), "{:?}", kind);
+    }
+
+    // The renamed field is only checked when collecting all the incompatibilities.
+    let find_in = |report: &AbiIncompatibilityReport| {
+        report
+            .incompatibilities()
+            .iter()
+            .filter(|x| x.field_path() == ["first"])
+            .map(|x| x.kind())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(find_in(&report), Vec::new());
+    assert!(find_in(&complete).contains(&AbiIncompatibilityKind::Name));
+    assert!(find_in(&complete).contains(&AbiIncompatibilityKind::Size));
+}
+
+#[test]
+fn serialize_report() {
+    let report = collect_layout_incompatibilities(OUTER_V1, OUTER_V2).unwrap_err();
+
+    let json = serde_json::to_string(&report).unwrap();
+    assert!(json.contains("UnexpectedField"), "{}", json);
+
+    let deserialized: AbiIncompatibilityReport = serde_json::from_str(&json).unwrap();
+    assert_eq!(deserialized.interface(), report.interface());
+    assert_eq!(deserialized.implementation(), report.implementation());
+    assert_eq!(deserialized.incompatibilities(), report.incompatibilities());
+
+    let formatted = deserialized.to_string();
+    for incompatibility in deserialized.incompatibilities() {
+        assert!(
+            formatted.contains(&incompatibility.to_string()),
+            "{}",
+            formatted
+        );
+    }
+}
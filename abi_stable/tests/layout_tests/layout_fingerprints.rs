@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+
+use abi_stable::{
+    nonexhaustive_enum::examples::{command_a, command_b},
+    std_types::*,
+    tag,
+    type_layout::{LayoutFingerprint, TypeLayout},
+    DynTrait, StableAbi,
+};
+
+use super::shared_types::{
+    basic_enum, enum_extra_fields_b, extra_variant, gen_more_lts, gen_more_lts_b, mod_5, mod_7,
+    swapped_fields_first,
+};
+
+mod point_a {
+    #[repr(C)]
+    #[derive(abi_stable::StableAbi)]
+    pub struct Point {
+        pub x: u32,
+        pub y: u32,
+    }
+}
+
+mod point_b {
+    // Declared in a different module and line than `point_a::Point`,
+    // which doesn't affect the fingerprint.
+
+    #[repr(C)]
+    #[derive(abi_stable::StableAbi)]
+    pub struct Point {
+        pub x: u32,
+        pub y: u32,
+    }
+}
+
+mod renamed_field {
+    #[repr(C)]
+    #[derive(abi_stable::StableAbi)]
+    pub struct Point {
+        pub x: u32,
+        pub z: u32,
+    }
+}
+
+mod changed_field_type {
+    #[repr(C)]
+    #[derive(abi_stable::StableAbi)]
+    pub struct Point {
+        pub x: u32,
+        pub y: i32,
+    }
+}
+
+mod tagged {
+    use super::tag;
+
+    #[repr(C)]
+    #[derive(abi_stable::StableAbi)]
+    #[sabi(tag = tag!("tagged"))]
+    pub struct Point {
+        pub x: u32,
+        pub y: u32,
+    }
+}
+
+mod discriminant_a {
+    #[repr(u8)]
+    #[derive(abi_stable::StableAbi)]
+    pub enum Enum {
+        A,
+        B,
+    }
+}
+
+mod discriminant_b {
+    #[repr(u8)]
+    #[derive(abi_stable::StableAbi)]
+    pub enum Enum {
+        A,
+        B = 5,
+    }
+}
+
+mod safe_fn {
+    #[repr(C)]
+    #[derive(abi_stable::StableAbi)]
+    pub struct Module {
+        pub function: extern "C" fn(u32) -> u64,
+    }
+}
+
+mod unsafe_fn {
+    #[repr(C)]
+    #[derive(abi_stable::StableAbi)]
+    pub struct Module {
+        pub function: unsafe extern "C" fn(u32) -> u64,
+    }
+}
+
+mod prefix_a {
+    #[repr(C)]
+    #[derive(abi_stable::StableAbi)]
+    #[sabi(kind(Prefix))]
+    pub struct Module {
+        #[sabi(last_prefix_field)]
+        pub first: u8,
+    }
+}
+
+mod prefix_b {
+    #[repr(C)]
+    #[derive(abi_stable::StableAbi)]
+    #[sabi(kind(Prefix))]
+    pub struct Module {
+        #[sabi(last_prefix_field)]
+        pub first: u8,
+        pub second: u16,
+    }
+}
+
+// The types of the `second` fields have the same type id,size,and alignment as `()`,
+// differing only in their names.
+
+mod opaque_field {
+    #[repr(C)]
+    #[derive(abi_stable::StableAbi)]
+    pub struct Pair {
+        pub first: (),
+        #[sabi(unsafe_opaque_field)]
+        pub second: (),
+    }
+}
+
+mod ignored_field {
+    use abi_stable::marker_type::UnsafeIgnoredType;
+
+    #[repr(C)]
+    #[derive(abi_stable::StableAbi)]
+    pub struct Pair {
+        pub first: (),
+        pub second: UnsafeIgnoredType<u8>,
+    }
+}
+
+mod unit_field {
+    #[repr(C)]
+    #[derive(abi_stable::StableAbi)]
+    pub struct Pair {
+        pub first: (),
+        pub second: (),
+    }
+}
+
+#[repr(C)]
+#[derive(StableAbi)]
+pub struct Recursive<'a> {
+    next: Option<&'a Recursive<'a>>,
+    value: u32,
+}
+
+fn fingerprint(layout: &'static TypeLayout) -> LayoutFingerprint {
+    LayoutFingerprint::new(layout)
+}
+
+#[test]
+fn identical_structure() {
+    let fingerprint_a = fingerprint(point_a::Point::LAYOUT);
+    assert_eq!(fingerprint_a, fingerprint(point_a::Point::LAYOUT));
+    assert_eq!(fingerprint_a, fingerprint(point_b::Point::LAYOUT));
+
+    assert_eq!(
+        fingerprint(Recursive::LAYOUT),
+        fingerprint(Recursive::LAYOUT)
+    );
+    assert_eq!(
+        fingerprint(<RVec<RString>>::LAYOUT),
+        fingerprint(<RVec<RString>>::LAYOUT)
+    );
+}
+
+#[test]
+fn different_structure() {
+    let list: Vec<&'static TypeLayout> = vec![
+        point_a::Point::LAYOUT,
+        renamed_field::Point::LAYOUT,
+        changed_field_type::Point::LAYOUT,
+        tagged::Point::LAYOUT,
+        discriminant_a::Enum::LAYOUT,
+        discriminant_b::Enum::LAYOUT,
+        safe_fn::Module::LAYOUT,
+        unsafe_fn::Module::LAYOUT,
+        prefix_a::Module_Ref::LAYOUT,
+        prefix_b::Module_Ref::LAYOUT,
+        Recursive::LAYOUT,
+        basic_enum::Enum::LAYOUT,
+        enum_extra_fields_b::Enum::LAYOUT,
+        extra_variant::Enum::LAYOUT,
+        swapped_fields_first::Rectangle::LAYOUT,
+        <gen_more_lts::Generics<'_, u32>>::LAYOUT,
+        <gen_more_lts_b::Generics<'_>>::LAYOUT,
+        mod_5::Mod::LAYOUT,
+        mod_7::Mod::LAYOUT,
+        <command_a::Foo_NE>::LAYOUT,
+        <command_b::Foo_NE>::LAYOUT,
+        <DynTrait<'static, RBox<()>, ()>>::LAYOUT,
+        <RVec<u8>>::LAYOUT,
+        <RVec<u16>>::LAYOUT,
+        <RSlice<'_, u8>>::LAYOUT,
+        <ROption<u8>>::LAYOUT,
+        <ROption<u16>>::LAYOUT,
+        <RHashMap<RString, u32>>::LAYOUT,
+        <[u8; 2]>::LAYOUT,
+        <[u8; 3]>::LAYOUT,
+        <u32>::LAYOUT,
+        <i32>::LAYOUT,
+        <&u32>::LAYOUT,
+        <*const u32>::LAYOUT,
+        <Option<&u32>>::LAYOUT,
+    ];
+
+    let mut found = HashMap::new();
+    for layout in list {
+        if let Some(prev) = found.insert(fingerprint(layout), layout) {
+            panic!(
+                "`{}` and `{}` have the same fingerprint",
+                prev.full_type(),
+                layout.full_type(),
+            );
+        }
+    }
+}
+
+#[test]
+fn same_type_id_different_names() {
+    let list: Vec<&'static TypeLayout> = vec![
+        opaque_field::Pair::LAYOUT,
+        ignored_field::Pair::LAYOUT,
+        unit_field::Pair::LAYOUT,
+    ];
+
+    for (i, &this) in list.iter().enumerate() {
+        for &other in &list[i + 1..] {
+            assert_ne!(
+                fingerprint(this),
+                fingerprint(other),
+                "\n{:#?}\n{:#?}",
+                this,
+                other
+            );
+        }
+    }
+}
+
+#[test]
+fn display() {
+    let fingerprint = fingerprint(point_a::Point::LAYOUT);
+    let displayed = fingerprint.to_string();
+
+    assert_eq!(displayed.len(), 32);
+    assert_eq!(
+        u128::from_str_radix(&displayed, 16).unwrap(),
+        fingerprint.to_u128()
+    );
+    assert_eq!(
+        LayoutFingerprint::from_u128(fingerprint.to_u128()),
+        fingerprint
+    );
+}
@@ -0,0 +1,334 @@
+use abi_stable::{
+    abi_stability::{
+        check_layout_compatibility, collect_layout_incompatibilities, compare_layouts,
+        AbiIncompatibilityKind, AbiIncompatibilityReport, LayoutExtensionKind,
+    },
+    nonexhaustive_enum::examples::{
+        command_a, command_b, command_h, command_h_mismatched_discriminant,
+    },
+    std_types::{RString, RVec},
+    tag,
+    type_layout::{DataSnapshot, LayoutSnapshot, TypeLayout},
+    StableAbi,
+};
+
+mod v1 {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(StableAbi)]
+    #[sabi(tag = tag!{{ "version" => 1 }})]
+    pub struct Inner {
+        pub value: u32,
+    }
+
+    #[repr(u8)]
+    #[derive(StableAbi)]
+    pub enum Shape {
+        Circle { radius: u32 },
+        Square(u32),
+    }
+
+    #[repr(C)]
+    #[derive(StableAbi)]
+    #[sabi(kind(Prefix))]
+    pub struct Module {
+        #[sabi(last_prefix_field)]
+        pub first: u8,
+        pub callback: extern "C" fn(&Inner, u32) -> u64,
+    }
+
+    #[repr(C)]
+    #[derive(StableAbi)]
+    pub struct Outer<'a> {
+        pub first: u32,
+        pub inner: Inner,
+        pub shape: Shape,
+        pub names: RVec<RString>,
+        pub borrowed: &'a u8,
+        pub module: Module_Ref,
+    }
+}
+
+mod v2 {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(StableAbi)]
+    #[sabi(tag = tag!{{ "version" => 2 }})]
+    pub struct Inner {
+        pub value: u64,
+    }
+
+    #[repr(u16)]
+    #[derive(StableAbi)]
+    pub enum Shape {
+        Circle { radius: u32 },
+        Rectangle(u32, u32),
+    }
+
+    #[repr(C)]
+    #[derive(StableAbi)]
+    #[sabi(kind(Prefix))]
+    pub struct Module {
+        pub first: u8,
+        #[sabi(last_prefix_field)]
+        pub callback: unsafe extern "C" fn(&Inner, u32) -> u64,
+    }
+
+    #[repr(C)]
+    #[derive(StableAbi)]
+    pub struct Outer<'a> {
+        pub renamed: u64,
+        pub inner: Inner,
+        pub shape: Shape,
+        pub names: RVec<RString>,
+        pub borrowed: &'a u8,
+        pub module: Module_Ref,
+    }
+}
+
+mod v1_extended {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(StableAbi)]
+    #[sabi(kind(Prefix))]
+    pub struct Module {
+        #[sabi(last_prefix_field)]
+        pub first: u8,
+        pub callback: extern "C" fn(&v1::Inner, u32) -> u64,
+        pub second: u32,
+        pub third: u64,
+    }
+}
+
+const OUTER_V1: &TypeLayout = <v1::Outer<'static> as StableAbi>::LAYOUT;
+const OUTER_V2: &TypeLayout = <v2::Outer<'static> as StableAbi>::LAYOUT;
+
+fn round_trip(layout: &'static TypeLayout) -> LayoutSnapshot {
+    let snapshot = LayoutSnapshot::new(layout);
+    let json = serde_json::to_string(&snapshot).unwrap();
+    let deserialized: LayoutSnapshot = serde_json::from_str(&json).unwrap();
+    assert_eq!(snapshot, deserialized);
+    deserialized
+}
+
+fn sorted_kinds(report: &AbiIncompatibilityReport) -> Vec<String> {
+    let mut kinds = report
+        .incompatibilities()
+        .iter()
+        .map(|x| format!("{:?}", x.kind()))
+        .collect::<Vec<_>>();
+    kinds.sort();
+    kinds
+}
+
+#[test]
+fn serde_round_trip() {
+    let snapshot = round_trip(OUTER_V1);
+    assert!(snapshot.root().full_type.contains("Outer"));
+    assert!(snapshot.types().len() > 5);
+
+    for layout in [
+        OUTER_V2,
+        <command_h::Foo_NE as StableAbi>::LAYOUT,
+        <RString as StableAbi>::LAYOUT,
+    ] {
+        round_trip(layout);
+    }
+}
+
+#[test]
+fn same_layout_is_compatible() {
+    for layout in [OUTER_V1, OUTER_V2, <command_a::Foo_NE as StableAbi>::LAYOUT] {
+        let snapshot = round_trip(layout);
+        check_layout_compatibility(&snapshot, layout).unwrap();
+        check_layout_compatibility(layout, &snapshot).unwrap();
+        check_layout_compatibility(&snapshot, &snapshot).unwrap();
+    }
+}
+
+#[test]
+fn agrees_with_layout_checking() {
+    let pairs = [
+        (OUTER_V1, OUTER_V2),
+        (OUTER_V2, OUTER_V1),
+        (
+            <command_b::Foo_NE as StableAbi>::LAYOUT,
+            <command_a::Foo_NE as StableAbi>::LAYOUT,
+        ),
+        (
+            <command_h::Foo_NE as StableAbi>::LAYOUT,
+            <command_h_mismatched_discriminant::Foo_NE as StableAbi>::LAYOUT,
+        ),
+    ];
+
+    for (interface, implementation) in pairs {
+        let expected = collect_layout_incompatibilities(interface, implementation).unwrap_err();
+        let snapshot = round_trip(interface);
+
+        let found = collect_layout_incompatibilities(&snapshot, implementation).unwrap_err();
+        assert_eq!(sorted_kinds(&found), sorted_kinds(&expected), "{}", found);
+
+        let found =
+            collect_layout_incompatibilities(interface, &round_trip(implementation)).unwrap_err();
+        assert_eq!(sorted_kinds(&found), sorted_kinds(&expected), "{}", found);
+    }
+
+    // Extending a nonexhaustive enum is compatible.
+    check_layout_compatibility(
+        &round_trip(<command_a::Foo_NE as StableAbi>::LAYOUT),
+        <command_b::Foo_NE as StableAbi>::LAYOUT,
+    )
+    .unwrap();
+}
+
+#[test]
+fn incompatibility_paths() {
+    let report = collect_layout_incompatibilities(&round_trip(OUTER_V1), OUTER_V2).unwrap_err();
+
+    let find = |kind: AbiIncompatibilityKind| {
+        report
+            .incompatibilities()
+            .iter()
+            .find(|x| x.kind() == kind)
+            .unwrap_or_else(|| panic!("no {:?} in:\n{}", kind, report))
+    };
+
+    let name = report
+        .incompatibilities()
+        .iter()
+        .find(|x| x.field_path() == ["first"] && x.type_path().len() == 2)
+        .unwrap();
+    assert_eq!(name.kind(), AbiIncompatibilityKind::Name);
+    assert_eq!(name.expected(), Some("u32"));
+    assert_eq!(name.found(), Some("u64"));
+
+    assert_eq!(
+        find(AbiIncompatibilityKind::TagError).field_path(),
+        ["inner"]
+    );
+    assert_eq!(
+        find(AbiIncompatibilityKind::UnexpectedVariant).field_path(),
+        ["shape"]
+    );
+    let prefix_size = find(AbiIncompatibilityKind::MismatchedPrefixSize);
+    assert_eq!(
+        prefix_size.field_path().first().map(|x| &**x),
+        Some("module")
+    );
+    assert!(find(AbiIncompatibilityKind::UnexpectedField)
+        .found()
+        .unwrap()
+        .starts_with("field_name:renamed\n"));
+
+    let qualifier = find(AbiIncompatibilityKind::FnQualifierMismatch);
+    assert!(
+        qualifier.found().unwrap().starts_with("unsafe fn("),
+        "{}",
+        report
+    );
+}
+
+#[test]
+fn layout_extensions() {
+    let module_v1 = <v1::Module_Ref as StableAbi>::LAYOUT;
+    let module_ext = <v1_extended::Module_Ref as StableAbi>::LAYOUT;
+    let foo_a = <command_a::Foo_NE as StableAbi>::LAYOUT;
+    let foo_b = <command_b::Foo_NE as StableAbi>::LAYOUT;
+
+    let comparisons = [
+        compare_layouts(module_v1, module_ext),
+        compare_layouts(&round_trip(module_v1), module_ext),
+    ];
+    for comparison in &comparisons {
+        assert!(comparison.is_compatible(), "{:?}", comparison);
+        let extensions = comparison.extensions();
+        assert_eq!(extensions.len(), 1, "{:?}", extensions);
+        assert_eq!(extensions[0].kind(), LayoutExtensionKind::PrefixFields);
+        assert_eq!(
+            extensions[0].type_path().last().map(|x| &**x),
+            Some("Module")
+        );
+        assert_eq!(extensions.len(), 1, "{:#?}", extensions);
+    }
+    assert_eq!(comparisons[0], comparisons[1]);
+
+    let comparisons = [
+        compare_layouts(foo_a, foo_b),
+        compare_layouts(foo_a, &round_trip(foo_b)),
+    ];
+    for comparison in &comparisons {
+        assert!(comparison.is_compatible(), "{:?}", comparison);
+        let extensions = comparison.extensions();
+        assert_eq!(extensions.len(), 1, "{:?}", extensions);
+        assert_eq!(
+            extensions[0].kind(),
+            LayoutExtensionKind::NonExhaustiveVariants
+        );
+        assert_eq!(extensions[0].added(), ["C"]);
+        assert_eq!(extensions[0].type_path().last().map(|x| &**x), Some("Foo"));
+    }
+    assert_eq!(comparisons[0], comparisons[1]);
+
+    // The same layouts have no extensions
+    assert!(compare_layouts(module_ext, module_ext)
+        .extensions()
+        .is_empty());
+
+    // Extensions are still found alongside incompatibilities
+    let comparison = compare_layouts(OUTER_V1, <v2::Outer<'static> as StableAbi>::LAYOUT);
+    assert!(!comparison.is_compatible());
+    assert_eq!(
+        comparison
+            .incompatibilities()
+            .unwrap()
+            .incompatibilities()
+            .len(),
+        collect_layout_incompatibilities(OUTER_V1, OUTER_V2)
+            .unwrap_err()
+            .incompatibilities()
+            .len()
+    );
+}
+
+#[test]
+fn prefix_type_snapshots() {
+    let layout = <v1::Module_Ref as StableAbi>::LAYOUT;
+    let snapshot = round_trip(layout);
+    assert!(snapshot
+        .types()
+        .iter()
+        .any(|x| matches!(x.data, DataSnapshot::PrefixType(_))));
+
+    check_layout_compatibility(&snapshot, layout).unwrap();
+
+    let report = check_layout_compatibility(&snapshot, <v2::Module_Ref as StableAbi>::LAYOUT)
+        .unwrap_err()
+        .downcast::<AbiIncompatibilityReport>()
+        .unwrap();
+    assert!(report
+        .incompatibilities()
+        .iter()
+        .any(|x| x.kind() == AbiIncompatibilityKind::MismatchedPrefixSize));
+}
+
+#[test]
+fn invalid_snapshots() {
+    let snapshot = LayoutSnapshot::new(OUTER_V1);
+    let mut json = serde_json::to_value(&snapshot).unwrap();
+
+    json["format_version"] = serde_json::Value::from(LayoutSnapshot::FORMAT_VERSION + 1);
+    serde_json::from_value::<LayoutSnapshot>(json.clone()).unwrap_err();
+    json["format_version"] = serde_json::Value::from(LayoutSnapshot::FORMAT_VERSION);
+    serde_json::from_value::<LayoutSnapshot>(json.clone()).unwrap();
+
+    let type_count = snapshot.types().len();
+    let fields = &mut json["types"][0]["data"]["Struct"]["fields"];
+    fields[0]["type_index"] = serde_json::Value::from(type_count);
+    serde_json::from_value::<LayoutSnapshot>(json.clone()).unwrap_err();
+
+    json["types"] = serde_json::Value::Array(Vec::new());
+    serde_json::from_value::<LayoutSnapshot>(json).unwrap_err();
+}
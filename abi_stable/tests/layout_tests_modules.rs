@@ -42,4 +42,13 @@ mod layout_tests {
 
     #[cfg(all(test, not(feature = "only_new_tests")))]
     mod shared_types;
+
+    #[cfg(all(test, not(feature = "only_new_tests")))]
+    mod incompatibility_report;
+
+    #[cfg(all(test, not(feature = "only_new_tests")))]
+    mod layout_snapshots;
+
+    #[cfg(all(test, not(feature = "only_new_tests")))]
+    mod layout_fingerprints;
 }
@@ -0,0 +1,110 @@
+//! The implementation of the `#[export_item]` attribute.
+
+use super::*;
+
+use crate::parse_utils::parse_str_args;
+
+use as_derive_utils::return_spanned_err;
+
+use syn::{Ident, ItemStatic};
+
+use proc_macro2::Span;
+
+use abi_stable_shared::{is_valid_export_name, mangled_exported_item_name};
+
+#[doc(hidden)]
+pub fn export_item_attr(attr: TokenStream1, item: TokenStream1) -> TokenStream1 {
+    parse_or_compile_err(item, move |item| export_item_inner(attr.into(), item)).into()
+}
+
+#[cfg(test)]
+fn export_item_str(attr: &str, item: &str) -> Result<TokenStream2, syn::Error> {
+    syn::parse_str(item).and_then(move |item| {
+        let attr = syn::parse_str::<TokenStream2>(attr)?;
+        export_item_inner(attr, item)
+    })
+}
+
+fn export_item_inner(attr: TokenStream2, input: ItemStatic) -> Result<TokenStream2, syn::Error> {
+    if let Some(mutability) = &input.mutability {
+        return_spanned_err!(mutability, "Can't export a `static mut`")
+    }
+
+    let name = match &parse_str_args(attr, "export_item", &["name"])?[0] {
+        Some(name) => name.value(),
+        None => input.ident.to_string(),
+    };
+
+    if !is_valid_export_name(&name) {
+        return_spanned_err!(
+            input.ident,
+            "{:?} is not a valid exported item name,\n\
+             it must only contain ascii alphanumeric characters,'_','-',and '.'",
+            name,
+        )
+    }
+
+    let vis = &input.vis;
+    let ident = &input.ident;
+    let ty = &input.ty;
+
+    let export_name = Ident::new(&mangled_exported_item_name(&name), Span::call_site());
+
+    Ok(quote!(
+        #input
+
+        #[no_mangle]
+        #vis static #export_name: ::abi_stable::library::ExportedItem =
+            ::abi_stable::library::ExportedItem::new::<#ty>(&#ident);
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(attr: &str, item: &str) -> String {
+        export_item_str(attr, item)
+            .unwrap()
+            .to_string()
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+    }
+
+    #[test]
+    fn test_output() {
+        let item = "pub static GREETING: RStr<'static> = RStr::from_str(\"hello\");";
+
+        for (attr, name) in [(r#"name = "greeting""#, "greeting"), ("", "GREETING")] {
+            let str_out = output(attr, item);
+            let expected = format!(
+                "pubstatic{}:::abi_stable::library::ExportedItem=\
+                 ::abi_stable::library::ExportedItem::new::<RStr<'static>>(&GREETING);",
+                mangled_exported_item_name(name),
+            );
+            assert!(str_out.contains(&expected), "{}", str_out);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let item = "static FOO: u32 = 0;";
+
+        for (attr, item) in [
+            (r#"name = "hello world""#, item),
+            (r#"name = """#, item),
+            (r#"name = "foo", name = "bar""#, item),
+            (r#"nome = "foo""#, item),
+            ("name", item),
+            ("", "static mut FOO: u32 = 0;"),
+        ] {
+            assert!(
+                export_item_str(attr, item).is_err(),
+                "attr: {}\nitem: {}",
+                attr,
+                item
+            );
+        }
+    }
+}
@@ -8,21 +8,92 @@ use syn::Ident;
 
 use proc_macro2::Span;
 
-use abi_stable_shared::mangled_root_module_loader_name;
+use abi_stable_shared::{
+    is_valid_export_name, mangled_allocation_tracker_name, mangled_context_loader_name,
+    mangled_globals_installer_name, mangled_named_root_module_loader_name,
+    mangled_root_module_loader_name,
+};
+
+use crate::parse_utils::parse_str_args;
 
 #[doc(hidden)]
-pub fn export_root_module_attr(_attr: TokenStream1, item: TokenStream1) -> TokenStream1 {
-    parse_or_compile_err(item, export_root_module_inner).into()
+pub fn export_root_module_attr(attr: TokenStream1, item: TokenStream1) -> TokenStream1 {
+    parse_or_compile_err(item, move |item| {
+        export_root_module_inner(attr.into(), item)
+    })
+    .into()
 }
 
 #[cfg(test)]
 fn export_root_module_str(item: &str) -> Result<TokenStream2, syn::Error> {
-    syn::parse_str(item).and_then(export_root_module_inner)
+    export_root_module_attr_str("", item)
+}
+
+#[cfg(test)]
+fn export_root_module_attr_str(attr: &str, item: &str) -> Result<TokenStream2, syn::Error> {
+    syn::parse_str(item).and_then(move |item| {
+        let attr = syn::parse_str::<TokenStream2>(attr)?;
+        export_root_module_inner(attr, item)
+    })
 }
 
-fn export_root_module_inner(mut input: ItemFn) -> Result<TokenStream2, syn::Error> {
+fn export_root_module_inner(
+    attr: TokenStream2,
+    mut input: ItemFn,
+) -> Result<TokenStream2, syn::Error> {
     let vis = &input.vis;
 
+    let mut args = parse_str_args(
+        attr,
+        "export_root_module",
+        &["name", "shutdown", "interface_versions"],
+    )?
+    .into_iter();
+    let name = args.next().unwrap().map(|name| name.value());
+    let shutdown = args.next().unwrap();
+    let interface_versions = args.next().unwrap();
+
+    if let Some(name) = name.as_ref().filter(|name| !is_valid_export_name(name)) {
+        return_spanned_err!(
+            input.sig.ident,
+            "{:?} is not a valid root module name,\n\
+             it must only contain ascii alphanumeric characters,'_','-',and '.'",
+            name,
+        )
+    }
+
+    let export_name = match &name {
+        Some(name) => mangled_named_root_module_loader_name(name),
+        None => mangled_root_module_loader_name(),
+    };
+
+    let with_shutdown_hook = match shutdown {
+        Some(shutdown) => {
+            let shutdown_fn = shutdown.parse::<syn::Path>()?;
+            quote!(
+                .with_shutdown_hook({
+                    pub extern "C" fn _sabi_erased_shutdown() -> ::abi_stable::std_types::RResult<
+                        (),
+                        ::abi_stable::library::RootModuleError,
+                    > {
+                        ::abi_stable::library::__call_root_module_shutdown(#shutdown_fn)
+                    }
+
+                    _sabi_erased_shutdown
+                })
+            )
+        }
+        None => quote!(),
+    };
+
+    let with_interface_versions = match interface_versions {
+        Some(interface_versions) => {
+            let interface_versions = interface_versions.parse::<syn::Path>()?;
+            quote!(.with_interface_versions(#interface_versions))
+        }
+        None => quote!(),
+    };
+
     let unsafe_no_layout_constant_path =
         syn::parse_str::<syn::Path>("unsafe_no_layout_constant").expect("BUG");
 
@@ -50,7 +121,69 @@ fn export_root_module_inner(mut input: ItemFn) -> Result<TokenStream2, syn::Erro
 
     let original_fn_ident = &input.sig.ident;
 
-    let export_name = Ident::new(&mangled_root_module_loader_name(), Span::call_site());
+    let context_ty = match input.sig.inputs.len() {
+        0 => None,
+        1 => match &input.sig.inputs[0] {
+            syn::FnArg::Typed(arg) => Some(&arg.ty),
+            syn::FnArg::Receiver(receiver) => {
+                return_spanned_err!(receiver, "The root module loader can't take `self`")
+            }
+        },
+        _ => return_spanned_err!(
+            input.sig.inputs,
+            "The root module loader can only take one parameter,the host context",
+        ),
+    };
+
+    let (call_loader, context_loader) = match context_ty {
+        Some(context_ty) => {
+            let context_loader_name = Ident::new(
+                &mangled_context_loader_name(name.as_deref()),
+                Span::call_site(),
+            );
+            let context_loader = quote!(
+                #[no_mangle]
+                #vis static #context_loader_name: ::abi_stable::library::ContextLoader = {
+                    pub unsafe extern "C" fn _sabi_erased_module_with_context(
+                        context: *mut (),
+                    ) -> ::abi_stable::library::RootModuleResult {
+                        unsafe{
+                            ::abi_stable::library::__call_root_module_loader_with_context::<
+                                #context_ty,
+                                _,
+                            >(context, #original_fn_ident)
+                        }
+                    }
+
+                    unsafe{
+                        ::abi_stable::library::ContextLoader::new::<#context_ty>(
+                            _sabi_erased_module_with_context,
+                        )
+                    }
+                };
+            );
+            (
+                quote!(::abi_stable::library::__root_module_requires_context()),
+                context_loader,
+            )
+        }
+        None => (
+            quote!(::abi_stable::library::__call_root_module_loader(#original_fn_ident)),
+            quote!(),
+        ),
+    };
+
+    let export_name = Ident::new(&export_name, Span::call_site());
+
+    let globals_installer_name = Ident::new(
+        &mangled_globals_installer_name(name.as_deref()),
+        Span::call_site(),
+    );
+
+    let allocation_tracker_name = Ident::new(
+        &mangled_allocation_tracker_name(name.as_deref()),
+        Span::call_site(),
+    );
 
     Ok(quote!(
         #input
@@ -59,7 +192,7 @@ fn export_root_module_inner(mut input: ItemFn) -> Result<TokenStream2, syn::Erro
         #vis static #export_name: ::abi_stable::library::LibHeader = {
 
             pub extern "C" fn _sabi_erased_module()-> ::abi_stable::library::RootModuleResult {
-                ::abi_stable::library::__call_root_module_loader(#original_fn_ident)
+                #call_loader
             }
 
             type __SABI_Module = <#ret_ty as ::abi_stable::library::IntoRootModuleResult>::Module;
@@ -69,7 +202,19 @@ fn export_root_module_inner(mut input: ItemFn) -> Result<TokenStream2, syn::Erro
                     ::abi_stable::library::CheckTypeLayout::#check_ty_layout_variant,
                 )
             }
+            #with_shutdown_hook
+            #with_interface_versions
         };
+
+        #[no_mangle]
+        #vis static #globals_installer_name: ::abi_stable::library::GlobalsInstaller =
+            ::abi_stable::library::GlobalsInstaller::new();
+
+        #[no_mangle]
+        #vis static #allocation_tracker_name: ::abi_stable::library::AllocationTracker =
+            ::abi_stable::library::AllocationTracker::new();
+
+        #context_loader
     ))
 }
 
@@ -130,4 +275,142 @@ mod tests {
             assert!(str_out.contains(expected_const));
         }
     }
+
+    #[test]
+    fn test_interface_versions_output() {
+        let item = "pub fn hello()->RString{}";
+
+        let str_out = export_root_module_str(item).unwrap().to_string();
+        assert!(!str_out.contains("with_interface_versions"), "{}", str_out);
+
+        let str_out =
+            export_root_module_attr_str(r#"interface_versions = "codecs::VERSIONS""#, item)
+                .unwrap()
+                .to_string()
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<String>();
+        assert!(
+            str_out.contains(".with_interface_versions(codecs::VERSIONS)"),
+            "{}",
+            str_out
+        );
+    }
+
+    #[test]
+    fn test_shutdown_output() {
+        let item = "pub fn hello()->RString{}";
+
+        let str_out = export_root_module_str(item).unwrap().to_string();
+        assert!(!str_out.contains("with_shutdown_hook"), "{}", str_out);
+
+        for attr in [
+            r#"shutdown = "stop_hello""#,
+            r#"name = "codecs", shutdown = "stop_hello""#,
+        ] {
+            let str_out = export_root_module_attr_str(attr, item)
+                .unwrap()
+                .to_string()
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<String>();
+            assert!(str_out.contains(".with_shutdown_hook("), "{}", str_out);
+            assert!(
+                str_out.contains("__call_root_module_shutdown(stop_hello)"),
+                "{}",
+                str_out
+            );
+        }
+    }
+
+    #[test]
+    fn test_globals_installer_output() {
+        let item = "pub fn hello()->RString{}";
+
+        for (attr, name) in [("", None), (r#"name = "codecs""#, Some("codecs"))] {
+            let str_out = export_root_module_attr_str(attr, item)
+                .unwrap()
+                .to_string()
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<String>();
+            let expected = format!(
+                "pubstatic{}:::abi_stable::library::GlobalsInstaller=\
+                 ::abi_stable::library::GlobalsInstaller::new();",
+                mangled_globals_installer_name(name),
+            );
+            assert!(str_out.contains(&expected), "{}", str_out);
+
+            let expected = format!(
+                "pubstatic{}:::abi_stable::library::AllocationTracker=\
+                 ::abi_stable::library::AllocationTracker::new();",
+                mangled_allocation_tracker_name(name),
+            );
+            assert!(str_out.contains(&expected), "{}", str_out);
+        }
+    }
+
+    #[test]
+    fn test_context_output() {
+        let item = "pub fn hello()->RString{}";
+        let str_out = export_root_module_str(item).unwrap().to_string();
+        assert!(!str_out.contains("ContextLoader"), "{}", str_out);
+
+        for (attr, name) in [("", None), (r#"name = "codecs""#, Some("codecs"))] {
+            let item = "pub fn hello(ctx: HostCtx_Ref)->RString{}";
+            let str_out = export_root_module_attr_str(attr, item)
+                .unwrap()
+                .to_string()
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<String>();
+            let expected = format!(
+                "pubstatic{}:::abi_stable::library::ContextLoader",
+                mangled_context_loader_name(name),
+            );
+            assert!(str_out.contains(&expected), "{}", str_out);
+            assert!(
+                str_out.contains("ContextLoader::new::<HostCtx_Ref>"),
+                "{}",
+                str_out
+            );
+            assert!(
+                str_out.contains("__root_module_requires_context()"),
+                "{}",
+                str_out
+            );
+        }
+
+        for item in [
+            "pub fn hello(ctx: HostCtx_Ref, other: u32)->RString{}",
+            "pub fn hello(self)->RString{}",
+        ] {
+            assert!(export_root_module_str(item).is_err(), "{}", item);
+        }
+    }
+
+    #[test]
+    fn test_named_output() {
+        let item = "pub fn hello()->RString{}";
+
+        let str_out = export_root_module_attr_str(r#"name = "codecs""#, item)
+            .unwrap()
+            .to_string();
+        assert!(
+            str_out.contains(&mangled_named_root_module_loader_name("codecs")),
+            "{}",
+            str_out,
+        );
+        assert!(!str_out.contains(&mangled_root_module_loader_name()));
+
+        for attr in [
+            r#"name = "a codec""#,
+            r#"name = """#,
+            r#"nome = "codecs""#,
+            r#"shutdown = "not a path""#,
+            r#"shutdown = "a", shutdown = "b""#,
+        ] {
+            assert!(export_root_module_attr_str(attr, item).is_err(), "{}", attr);
+        }
+    }
 }
@@ -26,6 +26,11 @@ pub fn export_root_module(attr: TokenStream1, item: TokenStream1) -> TokenStream
     crate::export_root_module_impl::export_root_module_attr(attr, item)
 }
 
+#[proc_macro_attribute]
+pub fn export_item(attr: TokenStream1, item: TokenStream1) -> TokenStream1 {
+    crate::export_item_impl::export_item_attr(attr, item)
+}
+
 #[proc_macro_attribute]
 pub fn sabi_extern_fn(attr: TokenStream1, item: TokenStream1) -> TokenStream1 {
     crate::sabi_extern_fn_impl::sabi_extern_fn(attr, item)
@@ -97,6 +102,7 @@ mod attribute_parsing;
 mod common_tokens;
 mod composite_collections;
 mod concat_and_ranges;
+mod export_item_impl;
 mod export_root_module_impl;
 mod fn_pointer_extractor;
 mod get_static_equivalent;
@@ -1,8 +1,13 @@
 //! Functions for parsing many `syn` types.
 
-use as_derive_utils::ret_err_on_peek;
+use as_derive_utils::{ret_err_on_peek, return_spanned_err};
 
-use syn::{parse, punctuated::Punctuated, token::Add, TypeParamBound};
+use syn::{
+    parse, punctuated::Punctuated, token::Add, Lit, LitStr, Meta, MetaNameValue, Token,
+    TypeParamBound,
+};
+
+use proc_macro2::TokenStream as TokenStream2;
 
 use proc_macro2::Span;
 
@@ -65,3 +70,49 @@ where
         })
     }
 }
+
+/// Parses the optional `param = "..."` arguments of the `#[<attr_name>]` attribute,
+/// returning the value for each parameter in `params`,in the same order.
+pub(crate) fn parse_str_args(
+    attr: TokenStream2,
+    attr_name: &str,
+    params: &[&str],
+) -> Result<Vec<Option<LitStr>>, syn::Error> {
+    use syn::parse::Parser;
+
+    let args = Punctuated::<Meta, Token![,]>::parse_terminated.parse2(attr)?;
+
+    let mut values = vec![None; params.len()];
+    for arg in args {
+        let index = match &arg {
+            Meta::NameValue(MetaNameValue {
+                path,
+                lit: Lit::Str(_),
+                ..
+            }) => params.iter().position(|param| path.is_ident(param)),
+            _ => None,
+        };
+
+        match (index, arg) {
+            (
+                Some(index),
+                Meta::NameValue(MetaNameValue {
+                    path,
+                    lit: Lit::Str(lit),
+                    ..
+                }),
+            ) => {
+                if values[index].is_some() {
+                    return_spanned_err!(
+                        path,
+                        "The `{}` argument can only be passed once",
+                        params[index],
+                    )
+                }
+                values[index] = Some(lit);
+            }
+            (_, arg) => return_spanned_err!(arg, "Unrecognized `#[{}]` parameter", attr_name),
+        }
+    }
+    Ok(values)
+}
@@ -40,6 +40,16 @@ pub(crate) fn convert_to_sabi_extern_fn(with_early_return: WithEarlyReturn, item
         WithEarlyReturn::Yes => None,
     };
 
+    convert_to_extern_fn_with(no_early_return.into_token_stream(), item)
+}
+
+/// Converts a function that returns an `RResult` into an `extern "C" fn`
+/// which returns the message of a panic as an error.
+pub(crate) fn convert_to_catch_unwind_extern_fn(item: &mut ItemFn) {
+    convert_to_extern_fn_with(quote!( catch_unwind; ), item)
+}
+
+fn convert_to_extern_fn_with(mode: TokenStream2, item: &mut ItemFn) {
     item.sig.abi = Some(syn::Abi {
         extern_token: Default::default(),
         name: Some(syn::LitStr::new("C", Span::call_site())),
@@ -49,7 +59,7 @@ pub(crate) fn convert_to_sabi_extern_fn(with_early_return: WithEarlyReturn, item
 
     let x = quote! {
         ::abi_stable::extern_fn_panic_handling!(
-            #no_early_return
+            #mode
 
             #(#statements)*
         )
@@ -61,13 +71,34 @@ pub(crate) fn convert_to_sabi_extern_fn(with_early_return: WithEarlyReturn, item
 }
 
 fn sabi_extern_fn_inner(attr: TokenStream2, mut item: ItemFn) -> Result<TokenStream2, syn::Error> {
-    let with_early_return = match attr.into_iter().next() {
-        Some(TokenTree::Ident(ref ident)) if ident == "no_early_return" => WithEarlyReturn::No,
-        Some(tt) => return_spanned_err!(tt, "Unrecognized `#[sabi_extern_fn]` parameter",),
-        None => WithEarlyReturn::Yes,
-    };
+    let mut with_early_return = WithEarlyReturn::Yes;
+    let mut catch_unwind = false;
+
+    let mut attr = attr.into_iter().peekable();
+    while let Some(tt) = attr.next() {
+        match tt {
+            TokenTree::Ident(ref ident) if ident == "no_early_return" => {
+                with_early_return = WithEarlyReturn::No;
+            }
+            TokenTree::Ident(ref ident) if ident == "catch_unwind" => {
+                catch_unwind = true;
+            }
+            tt => return_spanned_err!(tt, "Unrecognized `#[sabi_extern_fn]` parameter",),
+        }
+        match attr.next() {
+            Some(TokenTree::Punct(ref punct)) if punct.as_char() == ',' => {}
+            Some(tt) => return_spanned_err!(tt, "Expected a `,`"),
+            None => {}
+        }
+    }
 
-    convert_to_sabi_extern_fn(with_early_return, &mut item);
+    if catch_unwind {
+        // Early returns are always supported when catching panics,
+        // since the function body is wrapped in a closure.
+        convert_to_catch_unwind_extern_fn(&mut item);
+    } else {
+        convert_to_sabi_extern_fn(with_early_return, &mut item);
+    }
 
     Ok(item.into_token_stream())
 }
@@ -129,4 +160,47 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_catch_unwind_output() {
+        let item = r##"
+            pub fn hello(x: u32) -> RResult<u32, RBoxError> {
+                let y = x.checked_mul(2)?;
+                ROk(y)
+            }
+        "##;
+        let expected = quote!(
+            pub extern "C" fn hello(x: u32) -> RResult<u32, RBoxError> {
+                ::abi_stable::extern_fn_panic_handling!(
+                    catch_unwind;
+                    let y = x.checked_mul(2)?;
+                    ROk(y)
+                )
+            }
+        );
+
+        for attr in [
+            "catch_unwind",
+            "catch_unwind,",
+            "no_early_return, catch_unwind",
+        ] {
+            assert_eq!(
+                sabi_extern_fn_str(attr, item).unwrap().to_string(),
+                expected.to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let item = "pub fn hello() -> RResult<(), RString> { ROk(()) }";
+
+        for attr in [
+            "catch_panic",
+            "catch_unwind no_early_return",
+            "catch_unwind = true",
+        ] {
+            assert!(sabi_extern_fn_str(attr, item).is_err(), "{}", attr);
+        }
+    }
 }
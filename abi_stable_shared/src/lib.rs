@@ -81,3 +81,128 @@ where
 pub fn mangled_root_module_loader_name() -> String {
     mangle_ident("lib_header", "root module loader")
 }
+
+/// Whether `name` can be used as the name of an item exported with `#[export_item]`,
+/// or of a root module exported with `#[export_root_module]`.
+///
+/// Valid names are non-empty,
+/// and only contain ascii alphanumeric characters,`_`,`-`,and `.`.
+pub fn is_valid_export_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-' | b'.'))
+}
+
+/// Gets the name of the static that contains the LibHeader of the root module
+/// exported with `#[export_root_module(name = "...")]`.
+///
+/// This does not have a trailing `'\0'`,
+/// you need to append it to pass the name to C APIs.
+///
+/// # Panics
+///
+/// This panics if `name` is not a valid name,
+/// as determined by [`is_valid_export_name`].
+pub fn mangled_named_root_module_loader_name(name: &str) -> String {
+    assert!(
+        is_valid_export_name(name),
+        "invalid root module name: {:?}",
+        name,
+    );
+    mangle_ident("named_lib_header", name)
+}
+
+/// Gets the name of the static that contains the loader that takes a host context,
+/// for the root module exported with `export_name` as its name
+/// (`None` for the unnamed root module).
+///
+/// This does not have a trailing `'\0'`,
+/// you need to append it to pass the name to C APIs.
+///
+/// # Panics
+///
+/// This panics if `export_name` is not a valid name,
+/// as determined by [`is_valid_export_name`].
+pub fn mangled_context_loader_name(export_name: Option<&str>) -> String {
+    match export_name {
+        Some(name) => {
+            assert!(
+                is_valid_export_name(name),
+                "invalid root module name: {:?}",
+                name,
+            );
+            mangle_ident("named_context_loader", name)
+        }
+        None => mangle_ident("context_loader", "root module"),
+    }
+}
+
+/// Gets the name of the static that the executable passes its globals to,
+/// exported alongside the root module with `export_name` as its name
+/// (`None` for the unnamed root module).
+///
+/// This does not have a trailing `'\0'`,
+/// you need to append it to pass the name to C APIs.
+///
+/// # Panics
+///
+/// This panics if `export_name` is not a valid name,
+/// as determined by [`is_valid_export_name`].
+pub fn mangled_globals_installer_name(export_name: Option<&str>) -> String {
+    match export_name {
+        Some(name) => {
+            assert!(
+                is_valid_export_name(name),
+                "invalid root module name: {:?}",
+                name,
+            );
+            mangle_ident("named_globals_installer", name)
+        }
+        None => mangle_ident("globals_installer", "root module"),
+    }
+}
+
+/// Gets the name of the static that the executable gets the allocation statistics
+/// of the library from,
+/// exported alongside the root module with `export_name` as its name
+/// (`None` for the unnamed root module).
+///
+/// This does not have a trailing `'\0'`,
+/// you need to append it to pass the name to C APIs.
+///
+/// # Panics
+///
+/// This panics if `export_name` is not a valid name,
+/// as determined by [`is_valid_export_name`].
+pub fn mangled_allocation_tracker_name(export_name: Option<&str>) -> String {
+    match export_name {
+        Some(name) => {
+            assert!(
+                is_valid_export_name(name),
+                "invalid root module name: {:?}",
+                name,
+            );
+            mangle_ident("named_allocation_tracker", name)
+        }
+        None => mangle_ident("allocation_tracker", "root module"),
+    }
+}
+
+/// Gets the name of the static that `#[export_item]` exports for the item named `name`.
+///
+/// This does not have a trailing `'\0'`,
+/// you need to append it to pass the name to C APIs.
+///
+/// # Panics
+///
+/// This panics if `name` is not a valid name,
+/// as determined by [`is_valid_export_name`].
+pub fn mangled_exported_item_name(name: &str) -> String {
+    assert!(
+        is_valid_export_name(name),
+        "invalid exported item name: {:?}",
+        name,
+    );
+    mangle_ident("exported_item", name)
+}
@@ -1,9 +1,9 @@
-use std::{io, sync::Arc};
+use std::{io, path::Path, sync::Arc};
 
 use core_extensions::SelfOps;
 
 use abi_stable::{
-    library::{development_utils::compute_library_path, RootModule},
+    library::{development_utils::compute_library_path, LibraryPath, RootModule},
     std_types::{RArc, RBox, RString, RVec},
 };
 
@@ -18,6 +18,8 @@ fn main() -> io::Result<()> {
 
     run_dynamic_library_tests(mods);
 
+    run_reloadable_library_tests(&library_path);
+
     Ok(())
 }
 
@@ -72,3 +74,40 @@ pub fn run_dynamic_library_tests(mods: TestingMod_Ref) {
     println!("|     tests succeeded!    |");
     println!("'-------------------------'");
 }
+
+/// Tests that a library loaded as a `ReloadableModule` can be reloaded,
+/// and that the previous version is only unloaded after nothing refers to it.
+pub fn run_reloadable_library_tests(library_dir: &Path) {
+    // safety: the values that point into the library are only used inside `with` closures
+    let module =
+        unsafe { TestingMod_Ref::load_reloadable_from(LibraryPath::Directory(library_dir)) }
+            .unwrap_or_else(|e| panic!("{}", e));
+
+    assert_eq!(module.generation(), 0);
+
+    let first = module.get();
+    let mut field_a = 0;
+    first.with(|module| field_a = module.prefix_types_tests().field_a());
+    assert_eq!(field_a, 123);
+
+    // The copy of the root module is tied to the library that it was loaded from.
+    let copied = first.with(|module| *module);
+    assert!(copied.library().ptr_eq(first.library()));
+
+    let previous = module.reload().unwrap_or_else(|e| panic!("{}", e));
+    assert_eq!(module.generation(), 1);
+
+    // `first` keeps the previous version of the library loaded.
+    assert!(!previous.is_unloaded());
+    assert!(!first.library().ptr_eq(&module.library()));
+    let mut field_a = 0;
+    module.with(|module| field_a = module.prefix_types_tests().field_a());
+    assert_eq!(field_a, 123);
+
+    drop(first);
+    assert!(!previous.is_unloaded());
+    drop(copied);
+    assert!(previous.is_unloaded());
+
+    println!("reloadable library tests succeeded");
+}
@@ -1,8 +1,18 @@
 //! This crate is where extra tests which don't belong in examples go.
 
-use testing_interface_1::{get_env_vars, ReturnWhat, TestingMod, TestingMod_Ref};
+use testing_interface_1::{
+    get_env_vars, ContextMod, ContextMod_Ref, DeclaresDependencyMod, DeclaresDependencyMod_Ref,
+    ExtensionTable, ExtraMod, ExtraMod_Ref, HostContext, ProcessMod, ProcessMod_Ref, ReturnWhat,
+    TestingMod, TestingMod_Ref, EXTRA_SHUT_DOWN_VAR, TESTING_INTERFACE_NAME,
+};
 
-use abi_stable::{export_root_module, prefix_type::PrefixTypeTrait, std_types::RBoxError};
+use abi_stable::{
+    export_item, export_root_module,
+    library::{InterfaceVersion, InterfaceVersions},
+    prefix_type::PrefixTypeTrait,
+    sabi_extern_fn,
+    std_types::{RBoxError, ROk, RResult, RStr, RString, RVec},
+};
 
 ///////////////////////////////////////////////////////////////////////////////////
 
@@ -10,7 +20,7 @@ use abi_stable::{export_root_module, prefix_type::PrefixTypeTrait, std_types::RB
 ///
 /// LibHeader is used to check that the layout of `TextOpsMod` in this dynamic library
 /// is compatible with the layout of it in the binary that loads this library.
-#[export_root_module]
+#[export_root_module(interface_versions = "TESTING_INTERFACE")]
 pub fn get_library() -> Result<TestingMod_Ref, RBoxError> {
     let envars = get_env_vars();
 
@@ -26,3 +36,110 @@ pub fn get_library() -> Result<TestingMod_Ref, RBoxError> {
         }
     }
 }
+
+/// A second root module,
+/// which is loaded regardless of what the unnamed root module returns.
+#[export_root_module(name = "extra", shutdown = "shutdown_extra_module")]
+pub fn get_extra_module() -> ExtraMod_Ref {
+    ExtraMod { value: 21 }.leak_into_prefix()
+}
+
+/// A root module that depends on the "extra" root module.
+#[export_root_module(name = "declares_dependency")]
+pub fn get_declares_dependency_module() -> DeclaresDependencyMod_Ref {
+    DeclaresDependencyMod { value: 34 }.leak_into_prefix()
+}
+
+/// A root module whose functions are called in a child process.
+#[export_root_module(name = "process")]
+pub fn get_process_module() -> ProcessMod_Ref {
+    ProcessMod {
+        value: process_value,
+        label: process_label,
+        print: process_print,
+        sleep: process_sleep,
+        abort: process_abort,
+    }
+    .leak_into_prefix()
+}
+
+extern "C" fn process_value() -> u32 {
+    21
+}
+
+extern "C" fn process_label(names: RVec<RString>, value: u32) -> RVec<RString> {
+    names
+        .into_iter()
+        .map(|name| RString::from(format!("{}={}", name, value)))
+        .collect()
+}
+
+extern "C" fn process_print(text: RString) {
+    use std::io::{BufRead, Write};
+
+    print!("{}", text);
+    let _ = std::io::stdout().flush();
+    let _ = std::io::stdin().lock().read_line(&mut String::new());
+}
+
+extern "C" fn process_sleep(millis: u64) {
+    std::thread::sleep(std::time::Duration::from_millis(millis));
+}
+
+extern "C" fn process_abort() {
+    std::process::abort()
+}
+
+/// Exports a root module that's constructed from a host context.
+#[export_root_module(name = "context")]
+pub fn get_context_module(context: HostContext) -> ContextMod_Ref {
+    let value = context.base + context.name.len() as u32;
+    ContextMod { value }.leak_into_prefix()
+}
+
+/// The shutdown hook of the "extra" root module,
+/// which returns an error or panics depending on the `RETURN` environment variable.
+fn shutdown_extra_module() -> Result<(), RBoxError> {
+    match get_env_vars().return_what {
+        ReturnWhat::Ok => {
+            std::env::set_var(EXTRA_SHUT_DOWN_VAR, "1");
+            Ok(())
+        }
+        ReturnWhat::Error => Err(RBoxError::from_fmt("could not shut down")),
+        ReturnWhat::Panic => {
+            panic!()
+        }
+    }
+}
+
+/// Exported separately from the root module,
+/// checked when it's accessed with `RawLibrary::get_checked`.
+#[export_item(name = "extension-table")]
+pub static EXTENSION_TABLE: ExtensionTable = ExtensionTable {
+    multiplier: 3,
+    name: RStr::from_str("triple"),
+    divide,
+    repeat,
+};
+
+/// The versions of the interface named `TESTING_INTERFACE_NAME`,
+/// stored in the `LibHeader` of the root module exported without a name.
+pub const TESTING_INTERFACE: InterfaceVersions = InterfaceVersions::new(
+    TESTING_INTERFACE_NAME,
+    &[
+        InterfaceVersion::unnamed(1),
+        InterfaceVersion::new(3, "missing"),
+        InterfaceVersion::new(2, "extra"),
+    ],
+);
+
+#[sabi_extern_fn(catch_unwind)]
+fn divide(l: u32, r: u32) -> RResult<u32, RBoxError> {
+    log::debug!("dividing {} by {}", l, r);
+    ROk(l / r)
+}
+
+#[sabi_extern_fn]
+fn repeat(text: RStr<'_>, times: u32) -> RString {
+    text.as_str().repeat(times as usize).into()
+}
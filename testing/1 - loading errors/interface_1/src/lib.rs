@@ -4,7 +4,11 @@
 //! These crate test a few of the errors that are returned when loading dynamic libraries
 
 use abi_stable::{
-    library::RootModule, package_version_strings, sabi_types::VersionStrings, StableAbi,
+    library::{RootModule, RootModuleDependency},
+    package_version_strings,
+    sabi_types::{VersionRequirement, VersionStrings},
+    std_types::{RBoxError, RResult, RStr, RString, RVec},
+    StableAbi,
 };
 
 impl RootModule for TestingMod_Ref {
@@ -70,6 +74,262 @@ impl RootModule for NonAbiStableLib_Ref {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+/// This type is used to test that the `VERSION_REQUIREMENT` of a root module is checked.
+#[repr(C)]
+#[derive(StableAbi)]
+#[sabi(kind(Prefix(prefix_ref = ExcludesLibraryVersion_Ref)))]
+pub struct ExcludesLibraryVersion {
+    #[sabi(last_prefix_field)]
+    pub __foo: u64,
+}
+
+impl RootModule for ExcludesLibraryVersion_Ref {
+    abi_stable::declare_root_module_statics! {ExcludesLibraryVersion_Ref}
+
+    const BASE_NAME: &'static str = "testing_1_loading_errors";
+    const NAME: &'static str = "testing_1_loading_errors";
+    const VERSION_STRINGS: VersionStrings = package_version_strings!();
+    const VERSION_REQUIREMENT: Option<VersionRequirement> =
+        Some(VersionRequirement::new("^0.1, !=0.1.0"));
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A root module exported with `#[export_root_module(name = "extra")]`
+/// from the same library as `TestingMod`.
+#[repr(C)]
+#[derive(StableAbi)]
+#[sabi(kind(Prefix(prefix_ref = ExtraMod_Ref)))]
+pub struct ExtraMod {
+    #[sabi(last_prefix_field)]
+    pub value: u32,
+}
+
+impl RootModule for ExtraMod_Ref {
+    abi_stable::declare_root_module_statics! {ExtraMod_Ref}
+
+    const BASE_NAME: &'static str = "testing_1_loading_errors";
+    const NAME: &'static str = "extra";
+    const VERSION_STRINGS: VersionStrings = package_version_strings!();
+    const EXPORT_NAME: Option<&'static str> = Some("extra");
+}
+
+/// A root module exported with `#[export_root_module(name = "process")]`,
+/// whose functions are called in a child process to test `OutOfProcess`.
+#[repr(C)]
+#[derive(StableAbi)]
+#[sabi(kind(Prefix(prefix_ref = ProcessMod_Ref)))]
+pub struct ProcessMod {
+    /// Returns 21.
+    pub value: extern "C" fn() -> u32,
+    /// Appends `=<value>` to every name.
+    pub label: extern "C" fn(RVec<RString>, u32) -> RVec<RString>,
+    /// Prints `text` to stdout without a trailing newline,
+    /// and reads a line from stdin.
+    pub print: extern "C" fn(RString),
+    /// Sleeps for `millis` milliseconds.
+    pub sleep: extern "C" fn(u64),
+    /// Aborts the process.
+    #[sabi(last_prefix_field)]
+    pub abort: extern "C" fn(),
+}
+
+impl RootModule for ProcessMod_Ref {
+    abi_stable::declare_root_module_statics! {ProcessMod_Ref}
+
+    const BASE_NAME: &'static str = "testing_1_loading_errors";
+    const NAME: &'static str = "process";
+    const VERSION_STRINGS: VersionStrings = package_version_strings!();
+    const EXPORT_NAME: Option<&'static str> = Some("process");
+}
+
+/// The environment variable that the shutdown hook of `ExtraMod` sets
+/// when it succeeds.
+pub const EXTRA_SHUT_DOWN_VAR: &str = "TESTING_1_EXTRA_SHUT_DOWN";
+
+/// Used to test that named root modules have their layout checked separately.
+#[repr(C)]
+#[derive(StableAbi)]
+#[sabi(kind(Prefix(prefix_ref = IncompatibleExtraMod_Ref)))]
+pub struct IncompatibleExtraMod {
+    #[sabi(last_prefix_field)]
+    pub value: u64,
+}
+
+impl RootModule for IncompatibleExtraMod_Ref {
+    abi_stable::declare_root_module_statics! {IncompatibleExtraMod_Ref}
+
+    const BASE_NAME: &'static str = "testing_1_loading_errors";
+    const NAME: &'static str = "extra";
+    const VERSION_STRINGS: VersionStrings = package_version_strings!();
+    const EXPORT_NAME: Option<&'static str> = Some("extra");
+}
+
+/// Used to test loading a named root module that the library doesn't export.
+#[repr(C)]
+#[derive(StableAbi)]
+#[sabi(kind(Prefix(prefix_ref = MissingNamedMod_Ref)))]
+pub struct MissingNamedMod {
+    #[sabi(last_prefix_field)]
+    pub value: u32,
+}
+
+impl RootModule for MissingNamedMod_Ref {
+    abi_stable::declare_root_module_statics! {MissingNamedMod_Ref}
+
+    const BASE_NAME: &'static str = "testing_1_loading_errors";
+    const NAME: &'static str = "missing";
+    const VERSION_STRINGS: VersionStrings = package_version_strings!();
+    const EXPORT_NAME: Option<&'static str> = Some("missing");
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// The host context that `ContextMod` is constructed from.
+#[repr(C)]
+#[derive(StableAbi)]
+pub struct HostContext {
+    pub base: u32,
+    pub name: RString,
+}
+
+/// Used to test that the layout of the host context is checked.
+#[repr(C)]
+#[derive(StableAbi)]
+pub struct IncompatibleHostContext {
+    pub base: u64,
+    pub name: RString,
+}
+
+/// A root module whose loader function takes a `HostContext`.
+#[repr(C)]
+#[derive(StableAbi)]
+#[sabi(kind(Prefix(prefix_ref = ContextMod_Ref)))]
+pub struct ContextMod {
+    #[sabi(last_prefix_field)]
+    pub value: u32,
+}
+
+impl RootModule for ContextMod_Ref {
+    abi_stable::declare_root_module_statics! {ContextMod_Ref}
+
+    const BASE_NAME: &'static str = "testing_1_loading_errors";
+    const NAME: &'static str = "context";
+    const VERSION_STRINGS: VersionStrings = package_version_strings!();
+    const EXPORT_NAME: Option<&'static str> = Some("context");
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// The type of the item that the library exports with `#[export_item]`,
+/// to test `RawLibrary::get_checked`.
+#[repr(C)]
+#[derive(StableAbi)]
+pub struct ExtensionTable {
+    pub multiplier: u32,
+    pub name: RStr<'static>,
+    /// Defined with `#[sabi_extern_fn(catch_unwind)]`,
+    /// returning an error when dividing by zero.
+    pub divide: extern "C" fn(u32, u32) -> RResult<u32, RBoxError>,
+    /// Returns a string allocated by the library,
+    /// to test allocation tracking.
+    pub repeat: extern "C" fn(RStr<'_>, u32) -> RString,
+}
+
+/// The name that `ExtensionTable` is exported with.
+pub const EXTENSION_TABLE_NAME: &str = "extension-table";
+
+/// The name of the interface whose versions the library exports,
+/// to test `VersionNegotiator`.
+///
+/// Version 1 is `TestingMod`,version 2 is `ExtraMod`,
+/// and version 3 is `MissingNamedMod`,which the library doesn't export.
+pub const TESTING_INTERFACE_NAME: &str = "testing-interface";
+
+////////////////////////////////////////////////////////////////////////////////
+
+macro_rules! declare_dependent_module {
+    (
+        $(#[$attr:meta])*
+        struct $name:ident, $ref_name:ident;
+        dependencies = [$($dependency:ty => $requirement:literal),* $(,)?]
+    ) => {
+        $(#[$attr])*
+        #[repr(C)]
+        #[derive(StableAbi)]
+        #[sabi(kind(Prefix(prefix_ref = $ref_name)))]
+        pub struct $name {
+            #[sabi(last_prefix_field)]
+            pub __foo: u64,
+        }
+
+        impl RootModule for $ref_name {
+            abi_stable::declare_root_module_statics! {$ref_name}
+
+            const BASE_NAME: &'static str = "testing_1_loading_errors";
+            const NAME: &'static str = stringify!($name);
+            const VERSION_STRINGS: VersionStrings = package_version_strings!();
+            const DEPENDENCIES: &'static [RootModuleDependency] = &[$(
+                RootModuleDependency::new::<$dependency>(VersionRequirement::new($requirement)),
+            )*];
+        }
+    };
+}
+
+declare_dependent_module! {
+    /// Used to test that dependencies are loaded before the root module.
+    struct DependsOnTestingMod, DependsOnTestingMod_Ref;
+    dependencies = [TestingMod_Ref => "0.1.0"]
+}
+
+declare_dependent_module! {
+    /// Used to test that the version of dependencies is checked.
+    struct DependsOnNewerTestingMod, DependsOnNewerTestingMod_Ref;
+    dependencies = [TestingMod_Ref => ">=0.2"]
+}
+
+declare_dependent_module! {
+    /// Used to test that dependencies that can't be loaded are reported.
+    struct DependsOnNonAbiStableLib, DependsOnNonAbiStableLib_Ref;
+    dependencies = [NonAbiStableLib_Ref => "0.1.0"]
+}
+
+declare_dependent_module! {
+    /// Used to test that cyclic dependencies are reported.
+    struct CyclicDependencyA, CyclicDependencyA_Ref;
+    dependencies = [CyclicDependencyB_Ref => "0.1.0"]
+}
+
+declare_dependent_module! {
+    /// Used to test that cyclic dependencies are reported.
+    struct CyclicDependencyB, CyclicDependencyB_Ref;
+    dependencies = [CyclicDependencyA_Ref => "0.1.0"]
+}
+
+/// A root module exported with `#[export_root_module(name = "declares_dependency")]`,
+/// used to test that the dependencies in the `LibHeader` must be loaded before it.
+#[repr(C)]
+#[derive(StableAbi)]
+#[sabi(kind(Prefix(prefix_ref = DeclaresDependencyMod_Ref)))]
+pub struct DeclaresDependencyMod {
+    #[sabi(last_prefix_field)]
+    pub value: u32,
+}
+
+impl RootModule for DeclaresDependencyMod_Ref {
+    abi_stable::declare_root_module_statics! {DeclaresDependencyMod_Ref}
+
+    const BASE_NAME: &'static str = "testing_1_loading_errors";
+    const NAME: &'static str = "declares_dependency";
+    const VERSION_STRINGS: VersionStrings = package_version_strings!();
+    const EXPORT_NAME: Option<&'static str> = Some("declares_dependency");
+    const DEPENDENCIES: &'static [RootModuleDependency] = &[RootModuleDependency::new::<
+        ExtraMod_Ref,
+    >(VersionRequirement::new("0.1"))];
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 /// Parameters for the program passed through environment variables.
 ///
 /// The reason that env vars are used instead of command line arguments is because
@@ -1,16 +1,31 @@
 #![allow(clippy::print_literal)]
 
-use abi_stable::library::{
-    development_utils::compute_library_path, LibraryError, RootModule, RootModuleError,
+use abi_stable::{
+    library::{
+        dependency_load_order, development_utils::compute_library_path,
+        lib_header_from_raw_library_named, load_all, shutdown_all, AbiHeader, LibraryCandidate,
+        LibraryError, LibraryInfo, LibraryPath, LoadOptions, OutOfProcess, PluginSet, RootModule,
+        RootModuleError, SearchPaths,
+    },
+    std_types::{RSome, RString, RVec},
 };
 
 use testing_interface_1::{
-    get_env_vars, NonAbiStableLib_Ref, ReturnWhat, TestingMod_Ref, WithIncompatibleLayout_Ref,
+    get_env_vars, ContextMod_Ref, CyclicDependencyA_Ref, DeclaresDependencyMod_Ref,
+    DependsOnNewerTestingMod_Ref, DependsOnNonAbiStableLib_Ref, DependsOnTestingMod_Ref,
+    ExcludesLibraryVersion_Ref, ExtensionTable, ExtraMod_Ref, HostContext,
+    IncompatibleExtraMod_Ref, IncompatibleHostContext, MissingNamedMod_Ref, NonAbiStableLib_Ref,
+    ProcessMod_Ref, ReturnWhat, TestingMod_Ref, WithIncompatibleLayout_Ref, EXTENSION_TABLE_NAME,
+    EXTRA_SHUT_DOWN_VAR, TESTING_INTERFACE_NAME,
 };
 
-use std::fmt;
+use std::{fmt, path::Path, time::Duration};
 
 fn main() {
+    // Doesn't return in the child processes launched by `run_out_of_process_tests`.
+    process_mod_process().serve_if_child();
+    OutOfProcess::<MissingNamedMod_Ref>::new().serve_if_child();
+
     let target: &std::path::Path = "../../../target/".as_ref();
 
     let envars = get_env_vars();
@@ -24,6 +39,23 @@ fn main() {
         assert!(matches!(err, LibraryError::OpenError { .. }), "{:?}", err,);
     }
 
+    {
+        let options = LoadOptions::new().now().no_delete(true);
+        let err = WithIncompatibleLayout_Ref::load_from_with_options(
+            LibraryPath::Directory("foo/bar/bar".as_ref()),
+            options,
+        )
+        .err()
+        .unwrap();
+        match err {
+            LibraryError::OpenError {
+                options: err_options,
+                ..
+            } => assert_eq!(err_options, options),
+            err => panic!("expected an OpenError, found: {:?}", err),
+        }
+    }
+
     {
         let library_path = compute_library_path::<WithIncompatibleLayout_Ref>(target).unwrap();
         let err = NonAbiStableLib_Ref::load_from_directory(&library_path)
@@ -45,6 +77,9 @@ fn main() {
 
         assert!(matches!(err, LibraryError::AbiInstability(_)), "{:#}", err,);
 
+        let report = err.abi_incompatibility_report().unwrap();
+        assert!(!report.incompatibilities().is_empty(), "{}", report);
+
         // Doing this to make sure that the error formatting is not optimized out.
         let formatted = format!("{0} {0:?}", err);
         println!(
@@ -53,6 +88,25 @@ fn main() {
         );
     }
 
+    {
+        // Reading the info of the library doesn't call the root module loader,
+        // so this works regardless of what the loader does.
+        let library_path = compute_library_path::<TestingMod_Ref>(target).unwrap();
+        let info =
+            LibraryInfo::from_path(&TestingMod_Ref::get_library_path(&library_path)).unwrap();
+
+        assert!(info.is_abi_compatible());
+        assert_eq!(info.name(), Some(TestingMod_Ref::NAME));
+        assert_eq!(info.base_name(), Some(TestingMod_Ref::BASE_NAME));
+        assert_eq!(
+            info.version_strings(),
+            Some(TestingMod_Ref::VERSION_STRINGS)
+        );
+        assert_eq!(info.abi_major(), AbiHeader::VALUE.abi_major);
+        assert_eq!(info.abi_minor(), AbiHeader::VALUE.abi_minor);
+        assert!(info.layout().is_some());
+    }
+
     {
         let library_path = compute_library_path::<TestingMod_Ref>(target).unwrap();
         let res = TestingMod_Ref::load_from_directory(&library_path);
@@ -81,6 +135,10 @@ fn main() {
                         rm_err,
                     );
 
+                    if let RootModuleError::Unwound(message) = rm_err {
+                        assert_eq!(*message, RSome(RString::from("explicit panic")));
+                    }
+
                     print_error_sum(line!(), rm_err);
                 } else {
                     panic!(
@@ -91,6 +149,152 @@ fn main() {
                 print_error_sum(line!(), err);
             }
         }
+    }
+
+    {
+        let library_path = compute_library_path::<TestingMod_Ref>(target).unwrap();
+
+        let mut plugins = PluginSet::<TestingMod_Ref>::new();
+        let err = plugins
+            .load_all(&[
+                TestingMod_Ref::get_library_path(&library_path),
+                "foo/bar/bar".into(),
+                NonAbiStableLib_Ref::get_library_path(&library_path),
+            ])
+            .err()
+            .unwrap();
+
+        let errors = match &err {
+            LibraryError::Many(errors) => errors,
+            _ => panic!("Expected a LibraryError::Many, found:\n{:#?}", err),
+        };
+
+        let expected_errors = match envars.return_what {
+            ReturnWhat::Ok => {
+                assert_eq!(plugins.len(), 1);
+                let plugin = plugins.iter().next().unwrap();
+                assert_eq!(plugin.name(), TestingMod_Ref::NAME);
+                assert_eq!(plugin.module().a(), 5);
+
+                let by_path = plugins
+                    .get(&TestingMod_Ref::get_library_path(&library_path))
+                    .unwrap();
+                assert_eq!(by_path.path(), plugin.path());
+
+                let by_name = plugins
+                    .get_by_name(TestingMod_Ref::NAME)
+                    .collect::<Vec<_>>();
+                assert_eq!(by_name.len(), 1);
+                assert_eq!(by_name[0].path(), plugin.path());
+                assert_eq!(plugins.get_by_name("nonexistent").count(), 0);
+                2
+            }
+            ReturnWhat::Error | ReturnWhat::Panic => {
+                assert!(plugins.is_empty());
+                3
+            }
+        };
+        assert_eq!(errors.len(), expected_errors, "{:#?}", errors);
+        for e in errors {
+            assert!(matches!(e, LibraryError::AtPath { .. }), "{:?}", e);
+        }
+
+        assert!(plugins.get("foo/bar/bar".as_ref()).is_none());
+
+        print_error_sum(line!(), &err);
+    }
+
+    {
+        let library_path = compute_library_path::<NonAbiStableLib_Ref>(target).unwrap();
+        let search_paths = SearchPaths::new()
+            .with_directory("foo/bar/bar")
+            .with_directory(&library_path);
+
+        // Using a PluginSet because the library of `NonAbiStableLib_Ref`
+        // was already loaded into its statics above.
+        let err = PluginSet::<NonAbiStableLib_Ref>::new()
+            .load_from(LibraryPath::SearchPaths(&search_paths))
+            .err()
+            .unwrap();
+
+        let tried = match &err {
+            LibraryError::SearchPathsExhausted { tried, .. } => tried,
+            _ => panic!(
+                "Expected a LibraryError::SearchPathsExhausted, found:\n{:#?}",
+                err
+            ),
+        };
+        assert_eq!(tried.len(), 2, "{:#?}", tried);
+        assert!(
+            matches!(&tried[0], LibraryError::AtPath { err, .. }
+                if matches!(**err, LibraryError::IoError { .. })),
+            "{:?}",
+            tried[0],
+        );
+        assert!(
+            matches!(&tried[1], LibraryError::AtPath { err, .. }
+                if matches!(**err, LibraryError::GetSymbolError { .. })),
+            "{:?}",
+            tried[1],
+        );
+        print_error_sum(line!(), &err);
+    }
+
+    {
+        // PluginSet checks the version of the library before its layout.
+        let library_path = compute_library_path::<ExcludesLibraryVersion_Ref>(target).unwrap();
+        let err = PluginSet::<ExcludesLibraryVersion_Ref>::new()
+            .load_from(LibraryPath::Directory(&library_path))
+            .err()
+            .unwrap();
+
+        match &err {
+            LibraryError::UnmetVersionRequirement {
+                requirement,
+                actual_version,
+                ..
+            } => {
+                assert_eq!(requirement.as_str(), "^0.1, !=0.1.0");
+                assert_eq!(
+                    *actual_version,
+                    TestingMod_Ref::VERSION_STRINGS.parsed().unwrap()
+                );
+            }
+            _ => panic!(
+                "Expected a LibraryError::UnmetVersionRequirement, found:\n{:#?}",
+                err
+            ),
+        }
+        assert!(err.to_string().contains("^0.1, !=0.1.0"), "{}", err);
+        print_error_sum(line!(), &err);
+    }
+
+    run_dependency_tests(target, &envars.return_what);
+
+    run_exported_item_tests(target);
+
+    run_context_tests(target);
+
+    run_named_root_module_tests(target);
+
+    #[cfg(target_os = "linux")]
+    run_load_from_bytes_tests(target, &envars.return_what);
+
+    run_shutdown_tests(target, &envars.return_what);
+
+    run_panic_hook_tests(target);
+
+    run_log_tests(target);
+
+    run_allocation_tracking_tests(target);
+
+    run_version_negotiation_tests(target, &envars.return_what);
+
+    run_out_of_process_tests(target);
+
+    run_load_all_tests(target);
+
+    {
         println!(
             "\n{S}{S}\n\nFinished successfully\n\n{S}{S}\n",
             S = "----------------------------------------",
@@ -98,8 +302,848 @@ fn main() {
     }
 }
 
+fn run_dependency_tests(target: &Path, return_what: &ReturnWhat) {
+    let library_dir = compute_library_path::<TestingMod_Ref>(target).unwrap();
+    let where_ = LibraryPath::Directory(&library_dir);
+
+    let assert_missing_testing_mod = |err: &LibraryError, module_name: &str| match err {
+        LibraryError::MissingDependency {
+            module_name: mn,
+            dependency,
+            err,
+        } => {
+            assert_eq!(*mn, module_name);
+            assert_eq!(*dependency, TestingMod_Ref::NAME);
+            assert!(
+                matches!(**err, LibraryError::RootModule { .. }),
+                "{:?}",
+                err
+            );
+        }
+        _ => panic!(
+            "Expected a LibraryError::MissingDependency, found:\n{:#?}",
+            err
+        ),
+    };
+
+    {
+        let order = dependency_load_order::<DependsOnTestingMod_Ref>().unwrap();
+        let names = order.iter().map(|d| d.name()).collect::<Vec<_>>();
+        assert_eq!(names, [TestingMod_Ref::NAME]);
+
+        let err = DependsOnTestingMod_Ref::load_with_dependencies_from(where_)
+            .err()
+            .unwrap();
+
+        match return_what {
+            ReturnWhat::Ok => {
+                // The dependency was loaded,
+                // and then the root module failed to load because the library
+                // exports a `TestingMod_Ref`.
+                assert!(TestingMod_Ref::get_module().is_some());
+                assert!(matches!(err, LibraryError::AbiInstability(_)), "{:?}", err);
+            }
+            ReturnWhat::Error | ReturnWhat::Panic => {
+                assert_missing_testing_mod(&err, DependsOnTestingMod_Ref::NAME);
+            }
+        }
+        print_error_sum(line!(), &err);
+    }
+
+    {
+        let err = DependsOnNewerTestingMod_Ref::load_with_dependencies_from(where_)
+            .err()
+            .unwrap();
+
+        match return_what {
+            ReturnWhat::Ok => match &err {
+                LibraryError::IncompatibleDependency {
+                    module_name,
+                    dependency,
+                    required,
+                    found,
+                } => {
+                    assert_eq!(*module_name, DependsOnNewerTestingMod_Ref::NAME);
+                    assert_eq!(*dependency, TestingMod_Ref::NAME);
+                    assert_eq!(required.as_str(), ">=0.2");
+                    assert_eq!(*found, TestingMod_Ref::VERSION_STRINGS);
+                }
+                _ => panic!(
+                    "Expected a LibraryError::IncompatibleDependency, found:\n{:#?}",
+                    err
+                ),
+            },
+            ReturnWhat::Error | ReturnWhat::Panic => {
+                assert_missing_testing_mod(&err, DependsOnNewerTestingMod_Ref::NAME);
+            }
+        }
+        print_error_sum(line!(), &err);
+    }
+
+    {
+        let err = DependsOnNonAbiStableLib_Ref::load_with_dependencies_from(where_)
+            .err()
+            .unwrap();
+
+        match &err {
+            LibraryError::MissingDependency {
+                dependency, err, ..
+            } => {
+                assert_eq!(*dependency, NonAbiStableLib_Ref::NAME);
+                assert!(
+                    matches!(**err, LibraryError::GetSymbolError { .. }),
+                    "{:?}",
+                    err
+                );
+            }
+            _ => panic!(
+                "Expected a LibraryError::MissingDependency, found:\n{:#?}",
+                err
+            ),
+        }
+        print_error_sum(line!(), &err);
+    }
+
+    {
+        let err = CyclicDependencyA_Ref::load_with_dependencies_from(where_)
+            .err()
+            .unwrap();
+
+        match &err {
+            LibraryError::DependencyCycle { cycle } => {
+                assert_eq!(
+                    cycle[..],
+                    [
+                        "CyclicDependencyA",
+                        "CyclicDependencyB",
+                        "CyclicDependencyA"
+                    ],
+                );
+            }
+            _ => panic!(
+                "Expected a LibraryError::DependencyCycle, found:\n{:#?}",
+                err
+            ),
+        }
+        assert!(dependency_load_order::<CyclicDependencyA_Ref>().is_err());
+        print_error_sum(line!(), &err);
+    }
+}
+
+fn run_exported_item_tests(target: &Path) {
+    use abi_stable::library::RawLibrary;
+
+    let library_dir = compute_library_path::<TestingMod_Ref>(target).unwrap();
+    let raw_library = RawLibrary::load_at(&TestingMod_Ref::get_library_path(&library_dir)).unwrap();
+
+    {
+        // The item is available regardless of what the root module loader returns.
+        let table = raw_library
+            .get_checked::<ExtensionTable>(EXTENSION_TABLE_NAME)
+            .unwrap();
+        assert_eq!(table.multiplier, 3);
+        assert_eq!(table.name, "triple");
+
+        // Panics inside the library are returned as errors.
+        assert_eq!((table.divide)(12, 4).unwrap(), 3);
+        let err = (table.divide)(1, 0).unwrap_err();
+        assert_eq!(err.to_string(), "attempt to divide by zero");
+    }
+
+    {
+        let err = raw_library
+            .get_checked::<WithIncompatibleLayout_Ref>(EXTENSION_TABLE_NAME)
+            .err()
+            .unwrap();
+        assert!(matches!(err, LibraryError::AbiInstability(_)), "{:?}", err);
+        assert!(err.abi_incompatibility_report().is_some(), "{:?}", err);
+        print_error_sum(line!(), &err);
+    }
+
+    {
+        let err = raw_library
+            .get_checked::<ExtensionTable>("nonexistent")
+            .err()
+            .unwrap();
+        assert!(
+            matches!(err, LibraryError::GetSymbolError { .. }),
+            "{:?}",
+            err
+        );
+    }
+
+    {
+        let err = raw_library
+            .get_checked::<ExtensionTable>("extension table")
+            .err()
+            .unwrap();
+        assert!(
+            matches!(err, LibraryError::InvalidExportName { .. }),
+            "{:?}",
+            err
+        );
+    }
+}
+
+fn run_context_tests(target: &Path) {
+    let library_dir = compute_library_path::<TestingMod_Ref>(target).unwrap();
+    let path = || LibraryPath::Directory(&library_dir);
+    let context = |base: u32| HostContext {
+        base,
+        name: RString::from("host"),
+    };
+
+    {
+        let err = ContextMod_Ref::load_from(path()).err().unwrap();
+        assert!(
+            matches!(
+                err,
+                LibraryError::RootModule {
+                    err: RootModuleError::Returned(_),
+                    ..
+                }
+            ),
+            "{:?}",
+            err
+        );
+        print_error_sum(line!(), &err);
+    }
+
+    {
+        let incompatible = IncompatibleHostContext {
+            base: 0,
+            name: RString::new(),
+        };
+        let err = ContextMod_Ref::load_from_with_context(path(), incompatible)
+            .err()
+            .unwrap();
+        assert!(matches!(err, LibraryError::AbiInstability(_)), "{:?}", err);
+        print_error_sum(line!(), &err);
+    }
+
+    {
+        // Only loaders that take a parameter accept a context.
+        let err = ExtraMod_Ref::load_from_with_context(path(), context(0))
+            .err()
+            .unwrap();
+        assert!(
+            matches!(
+                err,
+                LibraryError::ContextNotAccepted {
+                    module_name: "extra"
+                }
+            ),
+            "{:?}",
+            err
+        );
+        print_error_sum(line!(), &err);
+    }
+
+    let module = ContextMod_Ref::load_from_with_context(path(), context(10)).unwrap();
+    assert_eq!(module.value(), 14);
+
+    // The root module is only constructed once.
+    let module = ContextMod_Ref::load_from_with_context(path(), context(100)).unwrap();
+    assert_eq!(module.value(), 14);
+    assert_eq!(ContextMod_Ref::load_from(path()).unwrap().value(), 14);
+}
+
+fn run_named_root_module_tests(target: &Path) {
+    let library_dir = compute_library_path::<TestingMod_Ref>(target).unwrap();
+
+    {
+        // The "extra" root module is declared as a dependency in the `LibHeader`,
+        // and it's not loaded yet.
+        let err = DeclaresDependencyMod_Ref::load_from_directory(&library_dir)
+            .err()
+            .unwrap();
+        match &err {
+            LibraryError::DependencyNotLoaded {
+                module_name,
+                dependency,
+            } => {
+                assert_eq!(*module_name, DeclaresDependencyMod_Ref::NAME);
+                assert_eq!(*dependency, ExtraMod_Ref::NAME);
+            }
+            _ => panic!(
+                "Expected a LibraryError::DependencyNotLoaded, found:\n{:#?}",
+                err
+            ),
+        }
+        print_error_sum(line!(), &err);
+
+        // The error refers to the name of the dependency in the library,
+        // so the library must stay loaded while the error exists.
+        let err = unsafe {
+            DeclaresDependencyMod_Ref::load_reloadable_from(LibraryPath::Directory(&library_dir))
+        }
+        .err()
+        .unwrap();
+        let weak = match &err {
+            LibraryError::ReloadableLibrary { library, err } => {
+                assert!(
+                    matches!(
+                        **err,
+                        LibraryError::DependencyNotLoaded { dependency, .. }
+                        if dependency == ExtraMod_Ref::NAME
+                    ),
+                    "{:?}",
+                    err
+                );
+                library.downgrade()
+            }
+            _ => panic!(
+                "Expected a LibraryError::ReloadableLibrary, found:\n{:#?}",
+                err
+            ),
+        };
+        assert!(!weak.is_unloaded());
+        let formatted = format!("{0} {0:?}", err);
+        assert!(formatted.contains(ExtraMod_Ref::NAME), "{}", formatted);
+        drop(err);
+        assert!(weak.is_unloaded());
+
+        let module = DeclaresDependencyMod_Ref::load_with_dependencies_from(
+            LibraryPath::Directory(&library_dir),
+        )
+        .unwrap();
+        assert_eq!(module.value(), 34);
+        assert!(ExtraMod_Ref::get_module().is_some());
+
+        let raw_library = DeclaresDependencyMod_Ref::get_raw_library().unwrap();
+        let lib_header =
+            unsafe { lib_header_from_raw_library_named(raw_library, "declares_dependency") }
+                .unwrap();
+        let names = lib_header
+            .dependencies()
+            .iter()
+            .map(|x| x.name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, [ExtraMod_Ref::NAME]);
+    }
+
+    {
+        let module = ExtraMod_Ref::load_from_directory(&library_dir).unwrap();
+        assert_eq!(module.value(), 21);
+    }
+
+    {
+        // The layout of each named root module is checked separately.
+        let err = IncompatibleExtraMod_Ref::load_from_directory(&library_dir)
+            .err()
+            .unwrap();
+        assert!(matches!(err, LibraryError::AbiInstability(_)), "{:?}", err);
+        print_error_sum(line!(), &err);
+    }
+
+    {
+        let err = MissingNamedMod_Ref::load_from_directory(&library_dir)
+            .err()
+            .unwrap();
+        assert!(
+            matches!(err, LibraryError::GetSymbolError { .. }),
+            "{:?}",
+            err
+        );
+    }
+
+    {
+        let path = ExtraMod_Ref::get_library_path(&library_dir);
+        let mut plugins = PluginSet::<ExtraMod_Ref>::new();
+        let plugin = plugins.load_from_file(&path).unwrap();
+        assert_eq!(plugin.name(), ExtraMod_Ref::NAME);
+        assert_eq!(plugin.module().value(), 21);
+
+        let candidate = LibraryCandidate::for_root_module::<ExtraMod_Ref>(&path);
+        assert!(candidate.is_compatible(), "{:?}", candidate);
+        assert_eq!(candidate.name(), Some(ExtraMod_Ref::NAME));
+        assert_eq!(
+            candidate.version(),
+            Some(ExtraMod_Ref::VERSION_STRINGS.version.as_str())
+        );
+
+        // The candidate was unloaded,but the library is still loaded by the `PluginSet`.
+        assert_eq!(plugins.iter().next().unwrap().module().value(), 21);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn run_load_from_bytes_tests(target: &Path, return_what: &ReturnWhat) {
+    use abi_stable::library::{lib_header_from_raw_library, RawLibrary};
+
+    let library_dir = compute_library_path::<TestingMod_Ref>(target).unwrap();
+    let bytes = std::fs::read(TestingMod_Ref::get_library_path(&library_dir)).unwrap();
+
+    {
+        let raw_library = RawLibrary::load_from_bytes(&bytes).unwrap();
+        let lib_header = unsafe { lib_header_from_raw_library(&raw_library) }.unwrap();
+        assert_eq!(
+            LibraryInfo::from_lib_header(lib_header).name(),
+            Some(TestingMod_Ref::NAME)
+        );
+
+        match return_what {
+            ReturnWhat::Ok => {
+                let module = lib_header.init_root_module::<TestingMod_Ref>().unwrap();
+                assert_eq!(module.a(), 5);
+                assert_eq!(module.b(), 8);
+            }
+            ReturnWhat::Error | ReturnWhat::Panic => {
+                let err = lib_header
+                    .init_root_module::<TestingMod_Ref>()
+                    .err()
+                    .unwrap();
+                assert!(matches!(err, LibraryError::RootModule { .. }), "{:?}", err);
+            }
+        }
+
+        // The root module points into the library
+        std::mem::forget(raw_library);
+    }
+
+    {
+        // The library is checked like the ones loaded from files.
+        let err = ExcludesLibraryVersion_Ref::load_from_bytes(&bytes)
+            .err()
+            .unwrap();
+        assert!(matches!(err, LibraryError::AbiInstability(_)), "{:?}", err);
+        print_error_sum(line!(), &err);
+    }
+
+    {
+        let err = RawLibrary::load_from_bytes(b"not a library").err().unwrap();
+        assert!(matches!(err, LibraryError::OpenError { .. }), "{:?}", err);
+    }
+}
+
 fn print_error_sum<E: fmt::Debug + fmt::Display>(line: u32, e: E) {
     let formatted = format!("{0} {0:?}", e);
     let sum = formatted.bytes().map(|x| x as u64).sum::<u64>();
     println!("{}: sum of bytes in the error: {}", line, sum);
 }
+
+fn run_shutdown_tests(target: &Path, return_what: &ReturnWhat) {
+    let library_dir = compute_library_path::<TestingMod_Ref>(target).unwrap();
+    ExtraMod_Ref::load_from_directory(&library_dir).unwrap();
+
+    assert!(std::env::var_os(EXTRA_SHUT_DOWN_VAR).is_none());
+
+    let res = ExtraMod_Ref::shutdown();
+    match (return_what, &res) {
+        (ReturnWhat::Ok, Ok(())) => {
+            assert!(std::env::var_os(EXTRA_SHUT_DOWN_VAR).is_some());
+        }
+        (
+            ReturnWhat::Error,
+            Err(LibraryError::RootModuleShutdown {
+                err: RootModuleError::Returned(err),
+                module_name: "extra",
+                ..
+            }),
+        ) => assert_eq!(err.to_string(), "could not shut down"),
+        (
+            ReturnWhat::Panic,
+            Err(LibraryError::RootModuleShutdown {
+                err: RootModuleError::Unwound(message),
+                module_name: "extra",
+                ..
+            }),
+        ) => assert_eq!(*message, RSome(RString::from("explicit panic"))),
+        _ => panic!(
+            "unexpected shutdown result for {:?}: {:?}",
+            return_what, res
+        ),
+    }
+    if let Err(err) = &res {
+        print_error_sum(line!(), err);
+    }
+
+    // Shutdown hooks are only called once,
+    // even though `ExtraMod_Ref` was also loaded with a `PluginSet`.
+    ExtraMod_Ref::shutdown().unwrap();
+    shutdown_all().unwrap();
+}
+
+fn run_panic_hook_tests(target: &Path) {
+    use abi_stable::library::{lib_header_from_raw_library, set_plugin_panic_hook, RawLibrary};
+    use std::sync::{Arc, Mutex};
+
+    let library_dir = compute_library_path::<TestingMod_Ref>(target).unwrap();
+    let raw_library = RawLibrary::load_at(&TestingMod_Ref::get_library_path(&library_dir)).unwrap();
+    // This passes the panic hook forwarder to the library.
+    unsafe { lib_header_from_raw_library(&raw_library).unwrap() };
+
+    let panics = Arc::new(Mutex::new(Vec::new()));
+    set_plugin_panic_hook({
+        let panics = panics.clone();
+        move |info| {
+            let location = info.location().unwrap();
+            panics.lock().unwrap().push((
+                info.message().to_string(),
+                location.file().to_string(),
+                info.thread_name().map(|x| x.to_string()),
+            ));
+        }
+    });
+
+    let table = raw_library
+        .get_checked::<ExtensionTable>(EXTENSION_TABLE_NAME)
+        .unwrap();
+    (table.divide)(1, 0).unwrap_err();
+
+    let panics = panics.lock().unwrap();
+    match &panics[..] {
+        [(message, file, thread_name)] => {
+            assert_eq!(message, "attempt to divide by zero");
+            assert!(file.contains("impl_1"), "{}", file);
+            assert_eq!(thread_name.as_deref(), Some("main"));
+        }
+        x => panic!("expected one forwarded panic, found: {:?}", x),
+    }
+}
+
+fn run_log_tests(target: &Path) {
+    use abi_stable::{
+        external_types::RMutex,
+        library::{lib_header_from_raw_library, RawLibrary},
+    };
+
+    static RECORDS: RMutex<Vec<String>> = RMutex::new(Vec::new());
+
+    struct HostLogger;
+
+    impl log::Log for HostLogger {
+        fn enabled(&self, _: &log::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record<'_>) {
+            RECORDS.lock().push(format!(
+                "{} {}: {}",
+                record.level(),
+                record.target(),
+                record.args()
+            ));
+        }
+
+        fn flush(&self) {}
+    }
+
+    log::set_logger(&HostLogger).unwrap();
+
+    let library_dir = compute_library_path::<TestingMod_Ref>(target).unwrap();
+    let raw_library = RawLibrary::load_at(&TestingMod_Ref::get_library_path(&library_dir)).unwrap();
+    // This passes the logger to the library.
+    unsafe { lib_header_from_raw_library(&raw_library).unwrap() };
+
+    let table = raw_library
+        .get_checked::<ExtensionTable>(EXTENSION_TABLE_NAME)
+        .unwrap();
+
+    // The max level of the executable applies to the records of the library.
+    log::set_max_level(log::LevelFilter::Info);
+    (table.divide)(1, 3).unwrap();
+    log::set_max_level(log::LevelFilter::Debug);
+    (table.divide)(12, 4).unwrap();
+
+    assert_eq!(
+        *RECORDS.lock(),
+        ["DEBUG testing_1_loading_errors: dividing 12 by 4"]
+    );
+}
+
+fn run_allocation_tracking_tests(target: &Path) {
+    use abi_stable::library::{RawLibrary, ReloadableModule};
+
+    let library_dir = compute_library_path::<TestingMod_Ref>(target).unwrap();
+    let raw_library = RawLibrary::load_at(&TestingMod_Ref::get_library_path(&library_dir)).unwrap();
+    let table = raw_library
+        .get_checked::<ExtensionTable>(EXTENSION_TABLE_NAME)
+        .unwrap();
+
+    {
+        // `ExtraMod_Ref` was loaded from the same library by the previous tests.
+        let before = ExtraMod_Ref::allocation_stats().unwrap();
+
+        let string = (table.repeat)("ab".into(), 3);
+        assert_eq!(string, "ababab");
+
+        let after = ExtraMod_Ref::allocation_stats().unwrap();
+        assert_eq!(after.live_allocations(), before.live_allocations() + 1);
+        assert!(after.live_bytes() >= before.live_bytes() + string.len());
+        assert_eq!(after.total_allocations(), before.total_allocations() + 1);
+
+        drop(string);
+        let dropped = ExtraMod_Ref::allocation_stats().unwrap();
+        assert_eq!(dropped.live_allocations(), before.live_allocations());
+        assert_eq!(dropped.live_bytes(), before.live_bytes());
+    }
+
+    {
+        // A reloadable library is leaked instead of unloaded
+        // while it owns allocations.
+        // safety: the only value that points into the library is `string`,
+        // whose allocation keeps the library loaded.
+        let module = unsafe {
+            ReloadableModule::<ExtraMod_Ref>::load_from(LibraryPath::Directory(&library_dir))
+        }
+        .unwrap();
+        let library = module.library();
+        let string = {
+            let table = library
+                .raw_library()
+                .get_checked::<ExtensionTable>(EXTENSION_TABLE_NAME)
+                .unwrap();
+            (table.repeat)("ab".into(), 2)
+        };
+
+        let stats = library.allocation_stats().unwrap();
+        assert_ne!(stats.live_allocations(), 0);
+
+        let weak = library.downgrade();
+        drop(library);
+        drop(module);
+        assert!(weak.is_unloaded());
+
+        // This calls the destructor in the library,which would crash if it was unloaded.
+        assert_eq!(string, "abab");
+        drop(string);
+    }
+}
+
+fn run_version_negotiation_tests(target: &Path, return_what: &ReturnWhat) {
+    use abi_stable::library::VersionNegotiator;
+
+    enum Testing {
+        V1(TestingMod_Ref),
+        V2(ExtraMod_Ref),
+        V3(MissingNamedMod_Ref),
+    }
+
+    let library_dir = compute_library_path::<TestingMod_Ref>(target).unwrap();
+
+    {
+        // Version 3 is not exported by the library,so version 2 is loaded.
+        let negotiated = VersionNegotiator::new(TESTING_INTERFACE_NAME)
+            .version(1, Testing::V1)
+            .version(3, Testing::V3)
+            .version(2, Testing::V2)
+            .load_from(LibraryPath::Directory(&library_dir))
+            .unwrap();
+        assert_eq!(negotiated.version(), 2);
+        assert_eq!(negotiated.export_name(), Some("extra"));
+        let value = match negotiated.into_module() {
+            Testing::V1(module) => module.a(),
+            Testing::V2(module) => module.value(),
+            Testing::V3(module) => module.value(),
+        };
+        assert_eq!(value, 21);
+    }
+
+    {
+        // Version 2 has an incompatible layout,so version 1 is tried next.
+        let res = VersionNegotiator::new(TESTING_INTERFACE_NAME)
+            .version(1, Some::<TestingMod_Ref>)
+            .version(2, |_: IncompatibleExtraMod_Ref| None)
+            .load_from(LibraryPath::Directory(&library_dir));
+
+        match (return_what, res) {
+            (ReturnWhat::Ok, Ok(negotiated)) => {
+                assert_eq!(negotiated.version(), 1);
+                assert_eq!(negotiated.export_name(), None);
+                assert_eq!(negotiated.into_module().unwrap().a(), 5);
+            }
+            (ReturnWhat::Error | ReturnWhat::Panic, Err(err)) => {
+                match &err {
+                    LibraryError::NoCompatibleInterfaceVersion {
+                        interface,
+                        offered,
+                        errors,
+                    } => {
+                        assert_eq!(*interface, TESTING_INTERFACE_NAME);
+                        assert_eq!(offered[..], [3, 2, 1]);
+                        assert!(
+                            matches!(errors[..], [LibraryError::AbiInstability(_), _]),
+                            "{:#?}",
+                            errors
+                        );
+                    }
+                    x => panic!("unexpected error: {:?}", x),
+                }
+                print_error_sum(line!(), &err);
+            }
+            (_, res) => panic!(
+                "unexpected negotiation result for {:?}: {:?}",
+                return_what,
+                res.map(|x| x.version())
+            ),
+        }
+    }
+
+    {
+        // Versions are matched by their number,not only by their export name.
+        let err = VersionNegotiator::new(TESTING_INTERFACE_NAME)
+            .version(3, Testing::V2)
+            .load_from(LibraryPath::Directory(&library_dir))
+            .err()
+            .unwrap();
+        assert!(
+            matches!(
+                &err,
+                LibraryError::NoCompatibleInterfaceVersion { offered, errors, .. }
+                if offered[..] == [3, 2, 1] && errors.is_empty()
+            ),
+            "{:?}",
+            err
+        );
+    }
+
+    {
+        // The library doesn't declare any versions of this interface.
+        let err = VersionNegotiator::new("nonexistent-interface")
+            .version(1, Testing::V1)
+            .load_from(LibraryPath::Directory(&library_dir))
+            .err()
+            .unwrap();
+        assert!(
+            matches!(
+                &err,
+                LibraryError::NoCompatibleInterfaceVersion { offered, errors, .. }
+                if offered.is_empty() && errors.is_empty()
+            ),
+            "{:?}",
+            err
+        );
+    }
+}
+
+fn process_mod_process() -> OutOfProcess<ProcessMod_Ref> {
+    OutOfProcess::new()
+        .function(ProcessMod_Ref::value)
+        .function(ProcessMod_Ref::label)
+        .function(ProcessMod_Ref::print)
+        .function(ProcessMod_Ref::sleep)
+        .function(ProcessMod_Ref::abort)
+        .timeout(Duration::from_secs(5))
+}
+
+fn run_out_of_process_tests(target: &Path) {
+    let library_dir = compute_library_path::<TestingMod_Ref>(target).unwrap();
+    let where_ = LibraryPath::Directory(&library_dir);
+
+    let module = process_mod_process().spawn(where_).unwrap();
+    assert_eq!(
+        module.path(),
+        ProcessMod_Ref::get_library_path(&library_dir)
+    );
+
+    assert_eq!(module.call(ProcessMod_Ref::value, ()).unwrap(), 21);
+
+    let names: RVec<RString> = vec!["a".into(), "b".into()].into();
+    let labels = module.call(ProcessMod_Ref::label, (names, 8)).unwrap();
+    assert_eq!(labels[..], ["a=8", "b=8"]);
+
+    // The output of the library and reading stdin don't interfere with the calls.
+    module
+        .call(ProcessMod_Ref::print, ("without a newline".into(),))
+        .unwrap();
+    assert_eq!(module.call(ProcessMod_Ref::value, ()).unwrap(), 21);
+
+    let err = module
+        .call(|module: &ProcessMod_Ref| module.value(), ())
+        .unwrap_err();
+    assert!(
+        matches!(&err, LibraryError::OutOfProcess { message, .. } if message.contains("not registered")),
+        "{:?}",
+        err
+    );
+
+    // The child process crashing is returned as an error.
+    let err = module.call(ProcessMod_Ref::abort, ()).unwrap_err();
+    for err in [err, module.call(ProcessMod_Ref::value, ()).unwrap_err()] {
+        match &err {
+            LibraryError::ChildProcessExited {
+                module_name,
+                status,
+                ..
+            } => {
+                assert_eq!(*module_name, ProcessMod_Ref::NAME);
+                assert!(!status.success());
+            }
+            x => panic!("unexpected error: {:?}", x),
+        }
+        print_error_sum(line!(), &err);
+    }
+    drop(module);
+
+    // A child process that doesn't return in time is killed.
+    let module = process_mod_process()
+        .timeout(Duration::from_millis(200))
+        .spawn(where_)
+        .unwrap();
+    let err = module.call(ProcessMod_Ref::sleep, (10_000,)).unwrap_err();
+    assert!(
+        matches!(
+            &err,
+            LibraryError::ChildProcessTimedOut { module_name, timeout, .. }
+            if *module_name == ProcessMod_Ref::NAME && *timeout == Duration::from_millis(200)
+        ),
+        "{:?}",
+        err
+    );
+    let err = module.call(ProcessMod_Ref::value, ()).unwrap_err();
+    assert!(
+        matches!(&err, LibraryError::ChildProcessExited { status, .. } if !status.success()),
+        "{:?}",
+        err
+    );
+    drop(module);
+
+    // The child process reports the errors from loading the root module.
+    let err = OutOfProcess::<MissingNamedMod_Ref>::new()
+        .spawn(where_)
+        .unwrap_err();
+    match &err {
+        LibraryError::OutOfProcess {
+            module_name,
+            message,
+            ..
+        } => {
+            assert_eq!(*module_name, MissingNamedMod_Ref::NAME);
+            assert!(!message.is_empty());
+        }
+        x => panic!("unexpected error: {:?}", x),
+    }
+}
+
+fn run_load_all_tests(target: &Path) {
+    let library_dir = compute_library_path::<TestingMod_Ref>(target).unwrap();
+    let path = ExtraMod_Ref::get_library_path(&library_dir);
+    let missing = library_dir.join("missing_library");
+
+    let (plugins, report) = load_all::<ExtraMod_Ref, _>([&path, &missing, &path]);
+    println!("{}", report);
+
+    assert_eq!(plugins.len(), 1);
+    assert_eq!(plugins.iter().next().unwrap().module().value(), 21);
+    assert!(plugins.get(&path).is_some());
+
+    let libraries = report.libraries();
+    assert_eq!(libraries.len(), 2);
+    assert_eq!(libraries[0].path(), path);
+    assert!(libraries[0].error().is_none());
+    assert_eq!(libraries[1].path(), missing);
+    assert!(
+        matches!(libraries[1].error(), Some(LibraryError::OpenError { .. })),
+        "{:?}",
+        libraries[1].error()
+    );
+
+    let err = report.into_result().unwrap_err();
+    assert!(
+        matches!(&err, LibraryError::Many(errors) if errors.len() == 1),
+        "{:?}",
+        err
+    );
+    print_error_sum(line!(), &err);
+}
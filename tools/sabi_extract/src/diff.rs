@@ -0,0 +1,309 @@
+//! Classifies the changes between the root modules of two builds of a library.
+
+use std::{fmt, fs, path::Path};
+
+use abi_stable::{
+    abi_stability::{
+        compare_layouts, AbiIncompatibility, AbiIncompatibilityKind, LayoutExtension,
+        LayoutExtensionKind,
+    },
+    library::{AbiHeader, LibraryInfo},
+    type_layout::{LayoutFingerprint, LayoutOrSnapshot, LayoutSnapshot, TypeLayout},
+};
+
+use serde::Serialize;
+
+/// The changes between the root modules of two builds of a library.
+#[derive(Debug, Serialize)]
+pub struct LayoutDiff {
+    pub old: LibrarySummary,
+    pub new: LibrarySummary,
+    pub changes: Vec<Change>,
+}
+
+/// Describes one of the compared libraries,or the snapshot of its root module.
+#[derive(Debug, Serialize)]
+pub struct LibrarySummary {
+    pub path: String,
+    /// The name of the library,None for snapshots.
+    pub name: Option<String>,
+    /// The version of the library,None for snapshots.
+    pub version: Option<String>,
+    pub root_module: String,
+    /// The `LayoutFingerprint` of the root module,None for snapshots.
+    pub fingerprint: Option<String>,
+}
+
+/// A change in the layout of a type reachable from the root module.
+#[derive(Debug, Serialize)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub breaking: bool,
+    /// The type that changed.
+    pub type_name: String,
+    /// The fields used to get to the type that changed from the root module.
+    pub field_path: Vec<String>,
+    pub description: String,
+}
+
+/// What kind of change it is.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// Fields were added after the prefix of a prefix type (a module or vtable).
+    PrefixFieldsAdded,
+    /// Variants were added to a nonexhaustive enum.
+    NonExhaustiveVariantsAdded,
+    /// The size of a type changed.
+    Size,
+    /// The alignment of a type changed.
+    Alignment,
+    /// A field was renamed,added,removed,or changed type.
+    Field,
+    /// The variants of an enum changed.
+    Variant,
+    /// Any other change.
+    Other,
+}
+
+/// The layout of the root module of a library,
+/// read from the library itself or from a snapshot saved with the `snapshot` subcommand.
+enum RootLayout {
+    Library(&'static TypeLayout),
+    Snapshot(LayoutSnapshot),
+}
+
+impl LayoutDiff {
+    /// Reads the root module layouts of the libraries or snapshots at both paths,
+    /// and compares them.
+    ///
+    /// Paths ending with `.json` are read as layout snapshots,
+    /// every other path is read as a dynamic library.
+    pub fn from_paths(old: &Path, new: &Path) -> Result<Self, String> {
+        let (old, old_layout) = load_root_layout(old)?;
+        let (new, new_layout) = load_root_layout(new)?;
+
+        let comparison = compare_layouts(old_layout.as_layout(), new_layout.as_layout());
+
+        let mut changes = comparison
+            .extensions()
+            .iter()
+            .map(compatible_change)
+            .collect::<Vec<Change>>();
+
+        if let Some(report) = comparison.incompatibilities() {
+            changes.extend(report.incompatibilities().iter().map(breaking_change));
+        }
+
+        Ok(Self { old, new, changes })
+    }
+
+    /// Whether any of the changes is breaking.
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(|x| x.breaking)
+    }
+}
+
+impl RootLayout {
+    fn as_layout(&self) -> LayoutOrSnapshot<'_> {
+        match self {
+            RootLayout::Library(layout) => LayoutOrSnapshot::Layout(layout),
+            RootLayout::Snapshot(snapshot) => LayoutOrSnapshot::Snapshot(snapshot),
+        }
+    }
+}
+
+fn load_root_layout(path: &Path) -> Result<(LibrarySummary, RootLayout), String> {
+    if path.extension().map_or(false, |x| x == "json") {
+        load_snapshot(path)
+    } else {
+        load_library(path)
+    }
+}
+
+fn load_snapshot(path: &Path) -> Result<(LibrarySummary, RootLayout), String> {
+    let snapshot = fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|json| serde_json::from_str::<LayoutSnapshot>(&json).map_err(|e| e.to_string()))
+        .map_err(|e| {
+            format!(
+                "Could not read the layout snapshot at:\n    {}\n{}",
+                path.display(),
+                e
+            )
+        })?;
+
+    let summary = LibrarySummary {
+        path: path.display().to_string(),
+        name: None,
+        version: None,
+        root_module: snapshot.root().full_type.clone(),
+        fingerprint: None,
+    };
+    Ok((summary, RootLayout::Snapshot(snapshot)))
+}
+
+/// Reads the layout of the root module from the headers of the library,
+/// without running the code that abi_stable runs to load it.
+fn load_library(path: &Path) -> Result<(LibrarySummary, RootLayout), String> {
+    let info = LibraryInfo::from_path(path).map_err(|e| {
+        format!(
+            "Could not load the library at:\n    {}\n{}",
+            path.display(),
+            e
+        )
+    })?;
+
+    if !info.is_abi_compatible() {
+        return Err(format!(
+            "The library uses abi_stable {}.{}, \
+             whose layouts can't be read by this sabi_extract (which uses {}.{}):\n    {}\n\
+             Save a snapshot of its root module with the `snapshot` subcommand of a \
+             sabi_extract that uses the same abi_stable version, \
+             and pass the `.json` snapshot instead.",
+            info.abi_major(),
+            info.abi_minor(),
+            AbiHeader::VALUE.abi_major,
+            AbiHeader::VALUE.abi_minor,
+            path.display(),
+        ));
+    }
+
+    let layout = info.layout().ok_or_else(|| {
+        format!(
+            "The dynamic library does not support reflection:\n    {}",
+            path.display(),
+        )
+    })?;
+
+    let summary = LibrarySummary {
+        path: path.display().to_string(),
+        name: info.name().map(String::from),
+        version: info.version_strings().map(|x| x.to_string()),
+        root_module: layout.full_type().to_string(),
+        fingerprint: Some(LayoutFingerprint::new(layout).to_string()),
+    };
+    Ok((summary, RootLayout::Library(layout)))
+}
+
+fn breaking_change(incompatibility: &AbiIncompatibility) -> Change {
+    use self::AbiIncompatibilityKind as AIK;
+
+    let kind = match incompatibility.kind() {
+        AIK::Size | AIK::MismatchedPrefixSize => ChangeKind::Size,
+        AIK::Alignment => ChangeKind::Alignment,
+        AIK::UnexpectedField
+        | AIK::FieldCountMismatch
+        | AIK::FieldLifetimeMismatch
+        | AIK::Name
+        | AIK::MismatchedPrimitive => ChangeKind::Field,
+        AIK::UnexpectedVariant
+        | AIK::TooManyVariants
+        | AIK::MismatchedExhaustiveness
+        | AIK::EnumDiscriminant
+        | AIK::IncompatibleWithNonExhaustive => ChangeKind::Variant,
+        _ => ChangeKind::Other,
+    };
+
+    let mut description = incompatibility.message().to_string();
+    if let (Some(expected), Some(found)) = (incompatibility.expected(), incompatibility.found()) {
+        description = format!("{} (expected: {}, found: {})", description, expected, found);
+    }
+    if let Some(extra) = incompatibility.extra() {
+        if !description.is_empty() {
+            description.push('\n');
+        }
+        description.push_str(extra);
+    }
+
+    Change {
+        kind,
+        breaking: true,
+        type_name: incompatibility
+            .type_path()
+            .last()
+            .cloned()
+            .unwrap_or_default(),
+        field_path: incompatibility.field_path().to_vec(),
+        description,
+    }
+}
+
+fn compatible_change(extension: &LayoutExtension) -> Change {
+    let (kind, what) = match extension.kind() {
+        LayoutExtensionKind::PrefixFields => (
+            ChangeKind::PrefixFieldsAdded,
+            "Added fields to the prefix type",
+        ),
+        LayoutExtensionKind::NonExhaustiveVariants => (
+            ChangeKind::NonExhaustiveVariantsAdded,
+            "Added variants to the nonexhaustive enum",
+        ),
+        _ => (ChangeKind::Other, "Extended the type"),
+    };
+
+    Change {
+        kind,
+        breaking: false,
+        type_name: extension.type_path().last().cloned().unwrap_or_default(),
+        field_path: extension.field_path().to_vec(),
+        description: format!("{}: {}", what, extension.added().join(", ")),
+    }
+}
+
+impl fmt::Display for LayoutDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Comparing the root modules of:")?;
+        for (which, lib) in [("old", &self.old), ("new", &self.new)] {
+            writeln!(f, "    {}: {}", which, lib.path)?;
+            match (&lib.name, &lib.version) {
+                (Some(name), Some(version)) => {
+                    writeln!(f, "        {} {} ({})", name, version, lib.root_module)?
+                }
+                _ => writeln!(f, "        snapshot of {}", lib.root_module)?,
+            }
+            if let Some(fingerprint) = &lib.fingerprint {
+                writeln!(f, "        layout fingerprint: {}", fingerprint)?;
+            }
+        }
+
+        for (breaking, title) in [(false, "Compatible changes"), (true, "Breaking changes")] {
+            let mut changes = self.changes.iter().filter(|x| x.breaking == breaking);
+            let first = match changes.next() {
+                Some(x) => x,
+                None => continue,
+            };
+            writeln!(f, "\n{}:", title)?;
+            for change in Some(first).into_iter().chain(changes) {
+                fmt::Display::fmt(change, f)?;
+            }
+        }
+
+        let breaking = self.changes.iter().filter(|x| x.breaking).count();
+        writeln!(
+            f,
+            "\nFound {} compatible and {} breaking changes.",
+            self.changes.len() - breaking,
+            breaking,
+        )
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let field_path = if self.field_path.is_empty() {
+            "<root module>".to_string()
+        } else {
+            self.field_path.join(".")
+        };
+        writeln!(
+            f,
+            "    {:?} in `{}` at `{}`:",
+            self.kind, self.type_name, field_path
+        )?;
+        for line in self.description.lines() {
+            writeln!(f, "        {}", line)?;
+        }
+        Ok(())
+    }
+}
@@ -1,15 +1,23 @@
-use std::{fs, path::PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 use abi_stable::{
-    library::lib_header_from_path,
+    library::{AbiHeader, LibraryInfo},
     //abi_stability::check_layout_compatibility,
     reflection::export_module::MRItem,
+    type_layout::{CHeader, LayoutFingerprint, LayoutSnapshot, TypeLayout},
 };
 
 use core_extensions::SelfOps;
 
 use structopt::StructOpt;
 
+mod diff;
+
+use crate::diff::LayoutDiff;
+
 ///////////////////////////////////////////////////////////////////////////////
 
 #[derive(StructOpt)]
@@ -36,6 +44,71 @@ enum Command {
         #[structopt(long = "--compact")]
         compact_json: bool,
     },
+    /// Prints the name,version,abi_stable version,and layout fingerprint of the
+    /// root module of an abi_stable library,
+    /// without running the function that constructs the root module.
+    #[structopt(name = "info")]
+    #[structopt(author = "_")]
+    Info {
+        /// The path to the library.
+        library_path: PathBuf,
+    },
+    /// Saves a snapshot of the layout of the root module of an abi_stable library,
+    /// which the `diff` subcommand can compare with builds that use
+    /// any abi_stable version.
+    #[structopt(name = "snapshot")]
+    #[structopt(author = "_")]
+    Snapshot {
+        /// The path to the library.
+        library_path: PathBuf,
+
+        /// Which file to output the snapshot to.
+        #[structopt(short = "o", long = "out-file")]
+        #[structopt(parse(from_os_str))]
+        output_file: Option<PathBuf>,
+    },
+    /// Compares the root modules of two builds of an abi_stable library,
+    /// classifying every change as compatible or breaking.
+    ///
+    /// Either build can be a snapshot saved with the `snapshot` subcommand
+    /// (a path ending with `.json`),
+    /// which is required for builds that use a different abi_stable version than this program.
+    ///
+    /// The root modules of libraries are read without running the code
+    /// that abi_stable runs to load them.
+    ///
+    /// Exits with status 1 if there are breaking changes,
+    /// and with status 2 if either library couldn't be loaded.
+    #[structopt(name = "diff")]
+    #[structopt(author = "_")]
+    Diff {
+        /// The path to the previous build of the library,or a snapshot of it.
+        old_library: PathBuf,
+
+        /// The path to the new build of the library,or a snapshot of it.
+        new_library: PathBuf,
+
+        /// Whether to output the changes as json.
+        #[structopt(long = "json")]
+        json: bool,
+
+        /// Whether to outputed json is compact
+        #[structopt(long = "compact")]
+        compact_json: bool,
+    },
+    /// Generates a C header declaring the root module of an abi_stable library,
+    /// and every type that it references.
+    #[structopt(name = "cheader")]
+    #[structopt(author = "_")]
+    CHeader {
+        /// The path to the library.
+        library_path: PathBuf,
+
+        /// Which file to output the header to.
+        #[structopt(short = "o", long = "out-file")]
+        #[structopt(parse(from_os_str))]
+        output_file: Option<PathBuf>,
+    },
 }
 
 fn main() {
@@ -76,15 +149,7 @@ version of abi_stable to be loaded successfully.
             output_stdout,
             compact_json,
         } => {
-            let lib_header = lib_header_from_path(library_path.as_ref()).unwrap();
-
-            let layout = lib_header.layout().unwrap_or_else(|| {
-                println!(
-                    "The dynamic library does not support reflection:\n    {}",
-                    library_path.display(),
-                );
-                std::process::exit(1);
-            });
+            let layout = root_module_layout(&library_path);
 
             let root_mod = MRItem::from_type_layout(layout);
 
@@ -107,5 +172,119 @@ version of abi_stable to be loaded successfully.
                 println!("{}", json);
             }
         }
+        Command::Info { library_path } => {
+            let info = LibraryInfo::from_path(library_path.as_ref()).unwrap();
+
+            println!("abi_stable abi: {}.{}", info.abi_major(), info.abi_minor());
+            if !info.is_abi_compatible() {
+                println!(
+                    "The library uses an abi_stable version incompatible with this tool's {}.{}",
+                    AbiHeader::VALUE.abi_major,
+                    AbiHeader::VALUE.abi_minor,
+                );
+                return;
+            }
+            println!("name: {}", info.name().unwrap_or_default());
+            println!("base name: {}", info.base_name().unwrap_or_default());
+            if let Some(version) = info.version_strings() {
+                println!("version: {}", version);
+            }
+            match info.layout() {
+                Some(layout) => {
+                    println!("layout checked: yes, {}", layout.full_type());
+                    println!("layout fingerprint: {}", LayoutFingerprint::new(layout));
+                }
+                None => println!("layout checked: no"),
+            }
+        }
+        Command::Snapshot {
+            library_path,
+            output_file,
+        } => {
+            let layout = root_module_layout(&library_path);
+
+            let json = serde_json::to_string_pretty(&LayoutSnapshot::new(layout)).unwrap();
+
+            match &output_file {
+                Some(output_file) => {
+                    if let Err(e) = fs::write(output_file, json) {
+                        panic!(
+                            "Error writing to file:\n{}\nError:\n{}\n",
+                            output_file.display(),
+                            e,
+                        );
+                    }
+                }
+                None => println!("{}", json),
+            }
+        }
+        Command::Diff {
+            old_library,
+            new_library,
+            json,
+            compact_json,
+        } => {
+            let diff = LayoutDiff::from_paths(&old_library, &new_library).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            });
+
+            if json && compact_json {
+                println!("{}", serde_json::to_string(&diff).unwrap());
+            } else if json {
+                println!("{}", serde_json::to_string_pretty(&diff).unwrap());
+            } else {
+                print!("{}", diff);
+            }
+
+            if diff.is_breaking() {
+                std::process::exit(1);
+            }
+        }
+        Command::CHeader {
+            library_path,
+            output_file,
+        } => {
+            let layout = root_module_layout(&library_path);
+
+            let header = CHeader::new(layout).to_string();
+
+            match &output_file {
+                Some(output_file) => {
+                    if let Err(e) = fs::write(output_file, header) {
+                        panic!(
+                            "Error writing to file:\n{}\nError:\n{}\n",
+                            output_file.display(),
+                            e,
+                        );
+                    }
+                }
+                None => print!("{}", header),
+            }
+        }
     }
 }
+
+/// Gets the layout of the root module of the library at `library_path`,
+/// exiting the process if it can't be read.
+fn root_module_layout(library_path: &Path) -> &'static TypeLayout {
+    let info = LibraryInfo::from_path(library_path).unwrap();
+
+    if !info.is_abi_compatible() {
+        println!(
+            "The dynamic library uses an incompatible abi_stable version ({}.{}):\n    {}",
+            info.abi_major(),
+            info.abi_minor(),
+            library_path.display(),
+        );
+        std::process::exit(1);
+    }
+
+    info.layout().unwrap_or_else(|| {
+        println!(
+            "The dynamic library does not support reflection:\n    {}",
+            library_path.display(),
+        );
+        std::process::exit(1);
+    })
+}